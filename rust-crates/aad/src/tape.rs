@@ -89,6 +89,12 @@ impl Tape {
     }
 }
 
+impl Default for Tape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Represents a single operation (or a variable) in the computation graph.
 pub struct Node {
     /// The partial derivatives of this node with respect to its parents.
@@ -1,4 +1,4 @@
-from typing import Dict, Any, Optional
+from typing import Dict, Any, List, Optional
 import pandas as pd
 import numpy as np
 
@@ -14,8 +14,11 @@ class TollingModel:
     def __init__(self):
         self.gas_curve = None
         self.power_curve = None
+        self.additional_curves = {}
+        self.time_grid = None
         self.model_params = None
         self.unit_params = None
+        self.simulation_config = None
 
     def load_parameters(self, model_params: Any, asset_params: Optional[Any] = None):
         """
@@ -26,9 +29,113 @@ class TollingModel:
         self.model_params = model_params
         self.unit_params = asset_params
 
+    def calibrate_parameters(
+        self,
+        gas_prices: np.ndarray,
+        power_prices: np.ndarray,
+        time_grid: Optional[Any] = None,
+        gas_shape: Optional[np.ndarray] = None,
+        power_shape: Optional[np.ndarray] = None,
+        power_shift: Optional[float] = None,
+    ) -> Any:
+        """
+        Estimate the model parameters from historical gas and power prices by maximum
+        likelihood and load them in place of hand-typed parameters.
+
+        The power prices are measured against `power_shape`, or against a fitted
+        seasonal shape of month, hour and weekend effects if it is omitted; the gas
+        prices against `gas_shape`, if given. A `power_shift` lets negative power
+        prices be fitted and is carried over to the loaded parameters.
+
+        Returns:
+            HistoricalCalibration object with the `estimates`, their `standard_errors`
+            and the `gas_fit` and `power_fit` diagnostics.
+        """
+        calibration = tolling_agreement_valuation.calibrate_historical(
+            np.ascontiguousarray(gas_prices, dtype=np.float64),
+            np.ascontiguousarray(power_prices, dtype=np.float64),
+            time_grid,
+            None
+            if gas_shape is None
+            else np.ascontiguousarray(gas_shape, dtype=np.float64),
+            None
+            if power_shape is None
+            else np.ascontiguousarray(power_shape, dtype=np.float64),
+            power_shift,
+        )
+        self.model_params = tolling_agreement_valuation.ModelParameters(
+            **calibration.estimates, power_shift=power_shift
+        )
+        return calibration
+
+    def calibrate_to_options(
+        self,
+        quotes: List[Any],
+        parameters: Optional[List[str]] = None,
+        num_paths: int = 2000,
+        risk_free_rate: float = 0.0,
+        seed: int = 0,
+    ) -> Any:
+        """
+        Fit the volatilities (by default `sigma_g` and `sigma_p`) of the loaded model
+        parameters to the market prices of options and load the fitted parameters.
+
+        `quotes` are `tolling_agreement_valuation.OptionQuote` objects on monthly gas,
+        power or spark-spread options, exercised against the points of the loaded
+        curves. The options are priced on the same `num_paths` paths throughout the
+        fit, so a fixed `seed` is used by default.
+
+        Returns:
+            ImpliedCalibration object with the fitted `model_params`, the
+            `fitted_values` and the `model_prices`, `market_prices` and `residuals` of
+            the quotes.
+        """
+        if (
+            self.gas_curve is None
+            or self.power_curve is None
+            or self.model_params is None
+        ):
+            raise ValueError(
+                "Curves and Model Parameters must be loaded before calibrating."
+            )
+
+        calibration = tolling_agreement_valuation.calibrate_implied(
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            quotes,
+            parameters if parameters is not None else ["sigma_g", "sigma_p"],
+            num_paths,
+            risk_free_rate,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+        self.model_params = calibration.model_params
+        return calibration
+
+    def load_simulation_config(self, simulation_config: Any):
+        """
+        Load the Monte Carlo configuration used by all subsequent calculations.
+        Expects an instance of `tolling_agreement_valuation.SimulationConfig`.
+        """
+        self.simulation_config = simulation_config
+
+    def load_time_grid(self, time_grid: Any):
+        """
+        Load the calendar time grid of the forward curves.
+        Expects an instance of `tolling_agreement_valuation.TimeGrid` with one point per
+        row of the power curve, e.g. a quarter-hourly grid; the gas curve may instead have
+        one row per local day. Without a grid, the curves are taken to be hourly in UTC,
+        starting at the valuation time, and seasonal volatilities are rejected.
+        """
+        self.time_grid = time_grid
+
     def load_forward_curves(self, curves: Dict[str, pd.DataFrame]):
         """
-        Expects keys: 'gas', 'power'. DataFrames must have a 'price' column.
+        Expects keys: 'gas', 'power' and optionally 'coal' and 'carbon' for the
+        additional assets of the model parameters. DataFrames must have a 'price' column.
         """
         if "gas" not in curves or "power" not in curves:
             raise ValueError("TollingModel requires 'gas' and 'power' forward curves.")
@@ -40,13 +147,23 @@ class TollingModel:
         self.power_curve = np.ascontiguousarray(
             curves["power"]["price"].values, dtype=np.float64
         )
+        self.additional_curves = {
+            asset: np.ascontiguousarray(curve["price"].values, dtype=np.float64)
+            for asset, curve in curves.items()
+            if asset not in ("gas", "power")
+        }
 
     def calculate_daily_profits(
-        self, num_paths: int = 10000, risk_free_rate: float = 0.0
+        self,
+        num_paths: int = 10000,
+        risk_free_rate: float = 0.0,
+        seed: Optional[int] = None,
     ) -> np.ndarray:
         """
         Calculate daily profits for each simulation path.
 
+        Passing the same `seed` reproduces the same profits.
+
         Returns:
             np.ndarray: A (num_paths, num_days) matrix of discounted daily profits
         """
@@ -58,9 +175,116 @@ class TollingModel:
             self.unit_params,
             num_paths,
             risk_free_rate,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
         )
 
-    def get_sample_paths(self, num_paths: int = 100) -> Optional[np.ndarray]:
+    def calculate_profit_statistics(
+        self,
+        num_paths: int = 10000,
+        risk_free_rate: float = 0.0,
+        seed: Optional[int] = None,
+        quantile_levels: Optional[List[float]] = None,
+    ) -> Any:
+        """
+        Calculate profit statistics without storing the simulated paths, so memory
+        stays flat in `num_paths`.
+
+        Passing the same `seed` uses the same paths as `calculate_daily_profits`.
+
+        Returns:
+            ProfitStatistics object containing pv, pv_standard_error,
+            pv_confidence_interval, pv_quantiles and the mean daily profits with their
+            standard errors and confidence intervals.
+        """
+        self._validate_inputs()
+        return tolling_agreement_valuation.calculate_profit_statistics(
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            self.unit_params,
+            num_paths,
+            risk_free_rate,
+            seed,
+            self.simulation_config,
+            quantile_levels,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+
+    def calculate_pv(
+        self,
+        num_paths: int = 10000,
+        risk_free_rate: float = 0.0,
+        seed: Optional[int] = None,
+        control_variates: Optional[List[str]] = None,
+    ) -> Any:
+        """
+        Calculate the present value of the agreement.
+
+        `control_variates` selects controls with known expectations, any of
+        "average_gas_price", "average_power_price" and "spread_option". They tighten
+        the PV at the same path count.
+
+        Returns:
+            PvResult object containing pv, pv_standard_error, pv_confidence_interval,
+            the uncorrected estimate
+            and the variance reduction achieved by the control variates.
+        """
+        self._validate_inputs()
+        return tolling_agreement_valuation.calculate_pv(
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            self.unit_params,
+            num_paths,
+            risk_free_rate,
+            seed,
+            self.simulation_config,
+            control_variates,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+
+    def calculate_pv_adaptive(
+        self,
+        tolerance: float,
+        max_paths: int = 1_000_000,
+        risk_free_rate: float = 0.0,
+        paths_per_step: int = 1000,
+        max_seconds: Optional[float] = None,
+        seed: Optional[int] = None,
+    ) -> Any:
+        """
+        Calculate the present value with as many paths as needed to reach a target
+        standard error `tolerance`, within a path budget and an optional time budget.
+
+        Returns:
+            AdaptivePvResult object containing pv, pv_standard_error, num_paths,
+            stop_reason and the convergence history.
+        """
+        self._validate_inputs()
+        return tolling_agreement_valuation.calculate_pv_adaptive(
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            self.unit_params,
+            tolerance,
+            max_paths,
+            risk_free_rate,
+            paths_per_step,
+            max_seconds,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+
+    def get_sample_paths(
+        self, num_paths: int = 100, seed: Optional[int] = None
+    ) -> Optional[np.ndarray]:
         # Note: sample_prices in Rust only needs model params, not unit params
         if (
             self.gas_curve is None
@@ -72,17 +296,114 @@ class TollingModel:
             )
 
         return tolling_agreement_valuation.sample_prices(
-            self.gas_curve, self.power_curve, self.model_params, num_paths
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            num_paths,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+
+    def get_sample_regimes(
+        self, num_paths: int = 100, seed: Optional[int] = None
+    ) -> Optional[np.ndarray]:
+        """
+        Sample the regimes of the power price of a regime-switching power model.
+
+        Returns:
+            Array of shape (num_paths, num_points) with the index of the regime at every
+            point. With the same `seed`, path i holds the regimes behind path i of
+            `get_sample_paths`.
+        """
+        if (
+            self.gas_curve is None
+            or self.power_curve is None
+            or self.model_params is None
+        ):
+            raise ValueError(
+                "Curves and Model Parameters must be loaded before sampling."
+            )
+
+        return tolling_agreement_valuation.sample_regimes(
+            self.gas_curve,
+            self.power_curve,
+            self.model_params,
+            num_paths,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
+        )
+
+    def get_physical_sample_paths(
+        self,
+        gas_drift: float,
+        lrmc_curve: np.ndarray,
+        num_paths: int = 100,
+        seed: Optional[int] = None,
+    ) -> Optional[np.ndarray]:
+        """
+        Sample real-world price paths under the physical measure, e.g. for risk limits
+        and budgeting, with the volatilities, jumps and correlations of the loaded model
+        parameters.
+
+        Gas grows from today's price, the first row of the gas curve, at `gas_drift` per
+        year. The log power price reverts to `lrmc_curve`, the Long Run Marginal Cost with
+        one row per grid point or per local day, from the first row of the power curve.
+
+        Returns:
+            Array of shape (num_paths, num_assets, num_points), as `get_sample_paths`.
+        """
+        if (
+            self.gas_curve is None
+            or self.power_curve is None
+            or self.model_params is None
+        ):
+            raise ValueError(
+                "Curves and Model Parameters must be loaded before sampling."
+            )
+
+        physical_params = tolling_agreement_valuation.PhysicalParameters(
+            self.model_params,
+            gas_drift,
+            np.ascontiguousarray(lrmc_curve, dtype=np.float64),
+        )
+        return tolling_agreement_valuation.sample_physical_prices(
+            self.gas_curve,
+            self.power_curve,
+            physical_params,
+            num_paths,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
         )
 
     def calculate_greeks(
-        self, num_paths: int = 10000, risk_free_rate: float = 0.0
+        self,
+        num_paths: int = 10000,
+        risk_free_rate: float = 0.0,
+        seed: Optional[int] = None,
     ) -> Any:
         """
         Calculate Greeks (sensitivities) using AAD.
 
+        Passing the same `seed` reproduces the same Greeks and shares the random
+        numbers with `calculate_daily_profits`.
+
         Returns:
-            GreeksResult object containing delta_gas, delta_power, vega_gas, vega_power.
+            GreeksResult object containing pv, delta_gas, delta_power, vega_gas, vega_power.
+            The PV is estimated from the same paths as the Greeks. Every estimate has a
+            matching `*_standard_error` and 95% `*_confidence_interval` attribute.
+            The vegas are arrays with one entry per volatility bucket, named by
+            `vega_gas_buckets` and `vega_power_buckets`. For the Schwartz-Smith gas
+            model the gas vega is split into `short_term` and `long_term`, and for the
+            regime-switching power model the power vega holds one entry per regime.
+            The sensitivities to `v0`, `theta_v` and `xi` of the Heston gas model are
+            in `gas_model_sensitivities`, and the deltas of the additional curves in
+            `delta_additional`, both keyed by name.
         """
         self._validate_inputs()
         return tolling_agreement_valuation.calculate_greeks(
@@ -92,6 +413,10 @@ class TollingModel:
             self.unit_params,
             num_paths,
             risk_free_rate,
+            seed,
+            self.simulation_config,
+            additional_curves=self.additional_curves,
+            time_grid=self.time_grid,
         )
 
     def _validate_inputs(self):
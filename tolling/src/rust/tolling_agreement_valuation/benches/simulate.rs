@@ -0,0 +1,156 @@
+//! Benchmarks of the full-cube simulation.
+//!
+//! `simulate` writes every path straight into a pre-allocated `Array3`. The `stacked`
+//! baseline follows the previous approach of collecting one `Array2` per path and
+//! copying them into the cube afterwards. It understates the old cost, since the
+//! correlated noise of every path is no longer held in a separate array either.
+//!
+//! Besides the wall times measured by criterion, the number and volume of heap
+//! allocations per run are printed for both.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ndarray::{Array1, Array2, Array3, Axis};
+use rayon::prelude::*;
+use tolling_agreement_valuation::{
+    ModelParameters, NoiseSampler, NoiseSource, PriceModel, SimulationConfig, Simulator, TimeGrid,
+};
+
+/// Counts the heap allocations of the benchmark binary.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Three months of hourly points.
+const N_DAYS: usize = 90;
+const N_POINTS: usize = 24 * N_DAYS;
+
+struct Inputs {
+    /// The gas and power forward curves.
+    forward_curves: Vec<Array1<f64>>,
+    time_grid: TimeGrid,
+    model: PriceModel<f64>,
+    noise_source: NoiseSource,
+    config: SimulationConfig,
+}
+
+fn inputs() -> Inputs {
+    let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    Inputs {
+        forward_curves: vec![
+            Array1::from_elem(N_POINTS, 20.0),
+            Array1::from_elem(N_POINTS, 45.0),
+        ],
+        time_grid: TimeGrid::hourly(start, start, N_DAYS, Tz::UTC).unwrap(),
+        model: ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6)
+            .price_model()
+            .unwrap(),
+        noise_source: NoiseSource::new(42),
+        config: SimulationConfig::default(),
+    }
+}
+
+/// Simulates into the pre-allocated cube.
+fn simulate(inputs: &Inputs, num_paths: usize) -> usize {
+    Simulator::simulate(
+        &inputs.forward_curves,
+        &inputs.time_grid,
+        &inputs.model,
+        num_paths,
+        &inputs.noise_source,
+        &inputs.config,
+    )
+    .unwrap()
+    .num_paths()
+}
+
+/// Simulates every path into its own array and stacks them afterwards.
+fn stacked(inputs: &Inputs, num_paths: usize) -> usize {
+    let sampler = NoiseSampler::new(
+        inputs.noise_source,
+        inputs.config,
+        inputs.model.noise_layout(),
+        num_paths,
+        N_POINTS,
+    )
+    .unwrap();
+    let paths: Vec<Array2<f64>> = (0..num_paths)
+        .into_par_iter()
+        .map(|path_idx| {
+            Simulator::simulate_single_path(
+                &inputs.forward_curves,
+                &inputs.time_grid,
+                &inputs.model,
+                &sampler.path_noise(path_idx),
+            )
+        })
+        .collect();
+    let mut prices = Array3::<f64>::zeros((num_paths, 2, N_POINTS));
+    for (i, path) in paths.iter().enumerate() {
+        prices.index_axis_mut(Axis(0), i).assign(path);
+    }
+    prices.len_of(Axis(0))
+}
+
+/// Prints the allocations of one run of `run`.
+fn report_allocations(name: &str, num_paths: usize, run: impl Fn() -> usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    run();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes;
+    println!(
+        "{name}/{num_paths}: {allocations} allocations, {:.1} MiB allocated",
+        bytes as f64 / (1024.0 * 1024.0)
+    );
+}
+
+fn bench_simulate(c: &mut Criterion) {
+    let inputs = inputs();
+    let mut group = c.benchmark_group("simulate");
+    group.sample_size(10);
+    for num_paths in [1_000, 10_000] {
+        report_allocations("pre_allocated", num_paths, || simulate(&inputs, num_paths));
+        report_allocations("stacked", num_paths, || stacked(&inputs, num_paths));
+        group.bench_with_input(
+            BenchmarkId::new("pre_allocated", num_paths),
+            &num_paths,
+            |b, &num_paths| b.iter(|| simulate(&inputs, num_paths)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("stacked", num_paths),
+            &num_paths,
+            |b, &num_paths| b.iter(|| stacked(&inputs, num_paths)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_simulate);
+criterion_main!(benches);
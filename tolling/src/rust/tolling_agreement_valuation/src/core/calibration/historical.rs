@@ -0,0 +1,533 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::calibration::optimizer::{Bfgs, value_and_gradient};
+use crate::core::common::cholesky::solve_positive_definite;
+use crate::core::common::distributions::{normal_cdf, normal_inverse_cdf};
+use crate::core::common::time_grid::TimeGrid;
+
+/// The number of free parameters of the power transition: `kappa`, `sigma_p`,
+/// `lambda_j`, `mu_j`, `sigma_j` and the level.
+const NUM_POWER_PARAMETERS: usize = 6;
+
+/// The number of free parameters of the gas transition: the drift and `sigma_g`.
+const NUM_GAS_PARAMETERS: usize = 2;
+
+/// The multiple of the standard deviation of the diffusion beyond which the recursive
+/// filter of the starting point takes a residual for a jump.
+const JUMP_THRESHOLD: f64 = 3.0;
+
+/// The number of passes of the recursive filter.
+const FILTER_PASSES: usize = 5;
+
+/// The relative size of the steps of the finite differences of the information matrix.
+const HESSIAN_STEP: f64 = 1e-4;
+
+/// Bound on the probability integral transform of a residual, keeping its normal score
+/// finite.
+const PIT_BOUND: f64 = 1e-15;
+
+/// An estimated parameter with its asymptotic standard error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// The estimated value.
+    pub value: f64,
+    /// The standard error of the estimate, NaN if the information matrix is singular.
+    pub standard_error: f64,
+}
+
+/// Goodness-of-fit diagnostics of a price model fitted to a historical series.
+///
+/// The residuals are the normal scores `Phi^{-1}(F(x_{i+1} | x_i))` of the observed
+/// transitions under the fitted transition law `F`. If the model is right they are
+/// independent standard normal, so a mean away from zero, a standard deviation away
+/// from one, skewness, excess kurtosis or autocorrelation point to misspecification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitDiagnostics {
+    /// The number of observed transitions.
+    pub num_observations: usize,
+    /// The maximised log-likelihood of the transitions.
+    pub log_likelihood: f64,
+    /// Akaike's information criterion `2 * k - 2 * ln L` for `k` parameters.
+    pub aic: f64,
+    /// The Bayesian information criterion `k * ln n - 2 * ln L` for `n` transitions.
+    pub bic: f64,
+    /// The mean of the residuals.
+    pub residual_mean: f64,
+    /// The standard deviation of the residuals.
+    pub residual_std: f64,
+    /// The skewness of the residuals.
+    pub residual_skewness: f64,
+    /// The excess kurtosis of the residuals.
+    pub residual_excess_kurtosis: f64,
+    /// The Jarque-Bera statistic of the residuals, chi-squared with two degrees of
+    /// freedom under normality, so values above 5.99 reject it at the 5% level.
+    pub jarque_bera: f64,
+    /// The autocorrelation of consecutive residuals.
+    pub residual_autocorrelation: f64,
+    /// Whether the maximisation of the likelihood converged, i.e. its gradient vanished.
+    pub converged: bool,
+    /// Whether the maximisation of the likelihood stopped because the likelihood no longer
+    /// increased while its gradient had not vanished yet.
+    pub stalled: bool,
+}
+
+impl FitDiagnostics {
+    /// Summarises the `residuals` of a fit of `num_parameters` parameters.
+    fn new(
+        residuals: &[f64],
+        log_likelihood: f64,
+        num_parameters: usize,
+        converged: bool,
+        stalled: bool,
+    ) -> Self {
+        let n = residuals.len() as f64;
+        let mean = residuals.iter().sum::<f64>() / n;
+        let central_moment = |k: i32| residuals.iter().map(|r| (r - mean).powi(k)).sum::<f64>() / n;
+        let variance = central_moment(2);
+        let skewness = central_moment(3) / variance.powf(1.5);
+        let excess_kurtosis = central_moment(4) / variance.powi(2) - 3.0;
+        let autocovariance = residuals
+            .windows(2)
+            .map(|pair| (pair[0] - mean) * (pair[1] - mean))
+            .sum::<f64>()
+            / n;
+        let k = num_parameters as f64;
+        Self {
+            num_observations: residuals.len(),
+            log_likelihood,
+            aic: 2.0 * k - 2.0 * log_likelihood,
+            bic: k * n.ln() - 2.0 * log_likelihood,
+            residual_mean: mean,
+            residual_std: variance.sqrt(),
+            residual_skewness: skewness,
+            residual_excess_kurtosis: excess_kurtosis,
+            jarque_bera: n / 6.0 * (skewness.powi(2) + excess_kurtosis.powi(2) / 4.0),
+            residual_autocorrelation: autocovariance / variance,
+            converged,
+            stalled,
+        }
+    }
+}
+
+/// The parameters of `ModelParameters` estimated from historical prices, with the
+/// nuisance parameters of the fit and its diagnostics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalCalibration {
+    /// Volatility of the gas price.
+    pub sigma_g: Estimate,
+    /// Volatility of the power price.
+    pub sigma_p: Estimate,
+    /// Mean-reversion speed of the power price.
+    pub kappa: Estimate,
+    /// Intensity of the power price jumps.
+    pub lambda_j: Estimate,
+    /// Mean of the log jump sizes.
+    pub mu_j: Estimate,
+    /// Standard deviation of the log jump sizes.
+    pub sigma_j: Estimate,
+    /// Correlation of the gas and power diffusions.
+    pub rho: Estimate,
+    /// The real drift of the gas price per year, relative to its shape, e.g. for
+    /// `ModelParameters::into_physical`.
+    pub gas_drift: Estimate,
+    /// The long-run level of the log power price relative to its shape, without jumps.
+    pub power_level: Estimate,
+    /// The expected number of jumps in the observed power prices.
+    pub expected_num_jumps: f64,
+    /// The fit of the gas prices.
+    pub gas_fit: FitDiagnostics,
+    /// The fit of the power prices.
+    pub power_fit: FitDiagnostics,
+}
+
+impl HistoricalCalibration {
+    /// Returns the estimates of the parameters of `ModelParameters::new`, by name and in
+    /// its order.
+    pub fn estimates(&self) -> [(&'static str, Estimate); 7] {
+        [
+            ("sigma_g", self.sigma_g),
+            ("sigma_p", self.sigma_p),
+            ("kappa", self.kappa),
+            ("lambda_j", self.lambda_j),
+            ("mu_j", self.mu_j),
+            ("sigma_j", self.sigma_j),
+            ("rho", self.rho),
+        ]
+    }
+}
+
+/// The quantities of one observed power transition under given parameters.
+struct PowerTransition<T> {
+    /// The decay of the deviation from the level over the step.
+    decay: T,
+    /// The standard deviation of the diffusion over the step per unit volatility.
+    noise_scale: T,
+    /// The variance of the diffusion over the step.
+    variance: T,
+    /// The probability of a jump in the step.
+    jump_probability: T,
+    /// The change of the log price not explained by the reversion.
+    residual: T,
+}
+
+/// A namespace for the estimation of model parameters from historical prices.
+pub struct HistoricalCalibrator;
+
+impl HistoricalCalibrator {
+    /// Estimates the parameters of the jump-diffusion `ModelParameters` by maximum
+    /// likelihood from deseasonalised log prices.
+    ///
+    /// `gas` and `power` hold the log prices less their forward or seasonal shape at the
+    /// points of `time_grid`, e.g. hourly. Gaps between points, e.g. around missing
+    /// hours, enter through the time steps of the grid.
+    ///
+    /// - Gas is a Brownian motion with drift in the log, whose maximum likelihood
+    ///   estimates are closed-form: `gas_drift` is the drift of the price.
+    /// - Power is the exact Gaussian transition of the OU process of
+    ///   `JumpDiffusionProcessTransformer` around a `power_level`, plus a jump with
+    ///   probability `1 - exp(-lambda_j * dt)` per step drawn from
+    ///   `N(mu_j, sigma_j^2)`. A step with several jumps is counted as one, which is
+    ///   accurate for hourly data. The likelihood of this mixture is maximised by BFGS
+    ///   with AAD gradients, starting from a recursive filter that takes residuals
+    ///   beyond three standard deviations of the diffusion for jumps.
+    /// - `rho` is the correlation of the standardised gas and power diffusion shocks,
+    ///   weighting every step by its posterior probability of not containing a jump
+    ///   and correcting for the decay of the power shock within the step.
+    ///
+    /// The standard errors come from the observed information, the Hessian of the
+    /// log-likelihood by central differences of its AAD gradient; that of `rho` from its
+    /// asymptotic variance `(1 - rho^2)^2 / n`. The volatilities, the reversion and the
+    /// jumps are assumed to be the same under the risk-neutral measure, as usual for
+    /// historical calibration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the series do not have one point per grid point, have fewer
+    /// than three points or are not finite.
+    pub fn calibrate(
+        gas: &Array1<f64>,
+        power: &Array1<f64>,
+        time_grid: &TimeGrid,
+    ) -> Result<HistoricalCalibration> {
+        for (name, series) in [("gas", gas), ("power", power)] {
+            if series.len() != time_grid.len() {
+                bail!(
+                    "The {name} series has {} points, but the time grid has {}.",
+                    series.len(),
+                    time_grid.len()
+                );
+            }
+            if series.iter().any(|x| !x.is_finite()) {
+                bail!("The {name} series must be finite.");
+            }
+        }
+        if time_grid.len() < 3 {
+            bail!("A calibration needs at least three observations.");
+        }
+        let dt: Vec<f64> = (1..time_grid.len())
+            .map(|i| time_grid.time_step(i))
+            .collect();
+
+        // 1. Gas: a Brownian motion with drift in the log.
+        let gas_returns: Vec<f64> = gas.windows(2).into_iter().map(|w| w[1] - w[0]).collect();
+        let n = gas_returns.len() as f64;
+        let total_time: f64 = dt.iter().sum();
+        let log_drift = gas_returns.iter().sum::<f64>() / total_time;
+        let gas_variance = gas_returns
+            .iter()
+            .zip(&dt)
+            .map(|(r, dt)| (r - log_drift * dt).powi(2) / dt)
+            .sum::<f64>()
+            / n;
+        let sigma_g = gas_variance.sqrt();
+        let gas_shocks: Vec<f64> = gas_returns
+            .iter()
+            .zip(&dt)
+            .map(|(r, dt)| (r - log_drift * dt) / (sigma_g * dt.sqrt()))
+            .collect();
+        let gas_log_likelihood = dt
+            .iter()
+            .map(|dt| -0.5 * (2.0 * std::f64::consts::PI * gas_variance * dt).ln())
+            .sum::<f64>()
+            - 0.5 * n;
+        let sigma_g_error = sigma_g / (2.0 * n).sqrt();
+        let log_drift_error = sigma_g / total_time.sqrt();
+
+        // 2. Power: the OU mixture with jumps, by maximum likelihood.
+        let power = power.as_slice().expect("A series is contiguous.");
+        let start = Self::initial_power_parameters(power, &dt);
+        let objective = |u: &[aad::AADVar]| {
+            let theta = Self::natural_parameters(u);
+            -Self::power_log_likelihood(&theta, power, &dt) / aad::AADVar::constant(n)
+        };
+        let minimum = Bfgs::default().minimize(
+            |u| value_and_gradient(objective, u),
+            Self::unconstrained_parameters(&start),
+        );
+        let theta: Vec<f64> = Self::natural_parameters(minimum.point.as_slice().unwrap());
+        let covariance = Self::power_covariance(&theta, power, &dt);
+        let power_error = |k: usize| {
+            covariance
+                .as_ref()
+                .map_or(f64::NAN, |covariance| covariance[[k, k]].sqrt())
+        };
+
+        // 3. The residuals of power, their jump probabilities and the correlation.
+        let mut power_residuals = Vec::with_capacity(dt.len());
+        let mut expected_num_jumps = 0.0;
+        let (mut co_moment, mut weight) = (0.0, 0.0);
+        let (kappa, mu_j, sigma_j) = (theta[0], theta[3], theta[4]);
+        for (i, (&dt, &gas_shock)) in dt.iter().zip(&gas_shocks).enumerate() {
+            let step = Self::power_transition(&theta, power[i], power[i + 1], dt);
+            let jump_variance = step.variance + sigma_j.powi(2);
+            let no_jump_density =
+                (1.0 - step.jump_probability) * normal_density(step.residual, step.variance);
+            let jump_density =
+                step.jump_probability * normal_density(step.residual - mu_j, jump_variance);
+            let jump_posterior = jump_density / (no_jump_density + jump_density);
+            expected_num_jumps += jump_posterior;
+
+            let cdf = (1.0 - step.jump_probability)
+                * normal_cdf(step.residual / step.variance.sqrt())
+                + step.jump_probability * normal_cdf((step.residual - mu_j) / jump_variance.sqrt());
+            power_residuals.push(normal_inverse_cdf(cdf.clamp(PIT_BOUND, 1.0 - PIT_BOUND)));
+
+            // The power shock decays within the step, which lowers its correlation with
+            // the gas shock by `(1 - decay) / (kappa * sqrt(dt) * noise_scale)`.
+            let power_shock = step.residual / step.variance.sqrt();
+            let attenuation = if kappa > 0.0 {
+                (1.0 - step.decay) / (kappa * dt.sqrt() * step.noise_scale)
+            } else {
+                1.0
+            };
+            co_moment += (1.0 - jump_posterior) * gas_shock * power_shock;
+            weight += (1.0 - jump_posterior) * attenuation;
+        }
+        let rho = (co_moment / weight).clamp(-1.0, 1.0);
+        let power_log_likelihood = -minimum.value * n;
+
+        Ok(HistoricalCalibration {
+            sigma_g: Estimate {
+                value: sigma_g,
+                standard_error: sigma_g_error,
+            },
+            sigma_p: Estimate {
+                value: theta[1],
+                standard_error: power_error(1),
+            },
+            kappa: Estimate {
+                value: theta[0],
+                standard_error: power_error(0),
+            },
+            lambda_j: Estimate {
+                value: theta[2],
+                standard_error: power_error(2),
+            },
+            mu_j: Estimate {
+                value: theta[3],
+                standard_error: power_error(3),
+            },
+            sigma_j: Estimate {
+                value: theta[4],
+                standard_error: power_error(4),
+            },
+            rho: Estimate {
+                value: rho,
+                standard_error: (1.0 - rho.powi(2)) / weight.sqrt(),
+            },
+            gas_drift: Estimate {
+                value: log_drift + 0.5 * gas_variance,
+                standard_error: log_drift_error.hypot(sigma_g * sigma_g_error),
+            },
+            power_level: Estimate {
+                value: theta[5],
+                standard_error: power_error(5),
+            },
+            expected_num_jumps,
+            gas_fit: FitDiagnostics::new(
+                &gas_shocks,
+                gas_log_likelihood,
+                NUM_GAS_PARAMETERS,
+                true,
+                false,
+            ),
+            power_fit: FitDiagnostics::new(
+                &power_residuals,
+                power_log_likelihood,
+                NUM_POWER_PARAMETERS,
+                minimum.converged,
+                minimum.stalled,
+            ),
+        })
+    }
+
+    /// Returns the quantities of the transition of the log power price from `x0` to
+    /// `x1` over `dt` years under the parameters `theta`.
+    fn power_transition<T: Float + FromPrimitive>(
+        theta: &[T],
+        x0: f64,
+        x1: f64,
+        dt: f64,
+    ) -> PowerTransition<T> {
+        let (kappa, sigma, lambda, level) = (theta[0], theta[1], theta[2], theta[5]);
+        let two = T::from_f64(2.0).unwrap();
+        let dt_t = T::from_f64(dt).unwrap();
+        let decay = (-kappa * dt_t).exp();
+        let noise_scale = (-(-two * kappa * dt_t).exp_m1() / (two * kappa)).sqrt();
+        let x0 = T::from_f64(x0).unwrap();
+        let x1 = T::from_f64(x1).unwrap();
+        PowerTransition {
+            decay,
+            noise_scale,
+            variance: (sigma * noise_scale).powi(2),
+            jump_probability: -(-lambda * dt_t).exp_m1(),
+            residual: x1 - level - decay * (x0 - level),
+        }
+    }
+
+    /// Returns the log-likelihood of the transitions of the log power price `x` under
+    /// the parameters `theta`.
+    fn power_log_likelihood<T: Float + FromPrimitive>(theta: &[T], x: &[f64], dt: &[f64]) -> T {
+        let (lambda, mu_j, sigma_j) = (theta[2], theta[3], theta[4]);
+        let half = T::from_f64(0.5).unwrap();
+        let log_two_pi = T::from_f64((2.0 * std::f64::consts::PI).ln()).unwrap();
+        let log_density = |residual: T, variance: T| {
+            -half * (log_two_pi + variance.ln()) - half * residual.powi(2) / variance
+        };
+        x.windows(2).zip(dt).fold(T::zero(), |acc, (pair, &dt)| {
+            let step = Self::power_transition(theta, pair[0], pair[1], dt);
+            // `ln(1 - p) = -lambda * dt` for the probability `p` of a jump.
+            let no_jump =
+                -lambda * T::from_f64(dt).unwrap() + log_density(step.residual, step.variance);
+            let jump = step.jump_probability.ln()
+                + log_density(step.residual - mu_j, step.variance + sigma_j.powi(2));
+            // A stable `ln(exp(no_jump) + exp(jump))`.
+            let largest = no_jump.max(jump);
+            acc + largest + ((no_jump - largest).exp() + (jump - largest).exp()).ln()
+        })
+    }
+
+    /// Maps unconstrained coordinates to the power parameters, taking the exponential of
+    /// those that must be positive.
+    fn natural_parameters<T: Float>(u: &[T]) -> Vec<T> {
+        vec![u[0].exp(), u[1].exp(), u[2].exp(), u[3], u[4].exp(), u[5]]
+    }
+
+    /// The inverse of `natural_parameters`.
+    fn unconstrained_parameters(theta: &[f64]) -> Array1<f64> {
+        Array1::from(vec![
+            theta[0].ln(),
+            theta[1].ln(),
+            theta[2].ln(),
+            theta[3],
+            theta[4].ln(),
+            theta[5],
+        ])
+    }
+
+    /// Returns a starting point for the maximisation of the likelihood of the log power
+    /// price `x`.
+    ///
+    /// An AR(1) regression gives the reversion and the level. A recursive filter then
+    /// takes residuals more than `JUMP_THRESHOLD` standard deviations from the mean of
+    /// the remaining residuals for jumps, re-estimating the standard deviation from the
+    /// remaining residuals in every pass; the jumps found give the jump parameters.
+    fn initial_power_parameters(x: &[f64], dt: &[f64]) -> Vec<f64> {
+        let n = dt.len() as f64;
+        let mean_dt = dt.iter().sum::<f64>() / n;
+        let (x0, x1) = (&x[..x.len() - 1], &x[1..]);
+        let mean0 = x0.iter().sum::<f64>() / n;
+        let mean1 = x1.iter().sum::<f64>() / n;
+        let covariance: f64 = x0
+            .iter()
+            .zip(x1)
+            .map(|(a, b)| (a - mean0) * (b - mean1))
+            .sum();
+        let variance: f64 = x0.iter().map(|a| (a - mean0).powi(2)).sum();
+        let slope = if variance > 0.0 {
+            (covariance / variance).clamp(1e-3, 1.0 - 1e-6)
+        } else {
+            1.0 - 1e-6
+        };
+        let intercept = mean1 - slope * mean0;
+        let residuals: Vec<f64> = x0
+            .iter()
+            .zip(x1)
+            .map(|(a, b)| b - intercept - slope * a)
+            .collect();
+
+        let mut is_jump = vec![false; residuals.len()];
+        let mut diffusion_std = 0.0;
+        for _ in 0..FILTER_PASSES {
+            let (centre, std) = mean_and_std(
+                residuals
+                    .iter()
+                    .zip(&is_jump)
+                    .filter(|(_, jump)| !**jump)
+                    .map(|(r, _)| *r),
+            );
+            diffusion_std = std;
+            for (jump, r) in is_jump.iter_mut().zip(&residuals) {
+                *jump = (r - centre).abs() > JUMP_THRESHOLD * std;
+            }
+        }
+        let jumps: Vec<f64> = residuals
+            .iter()
+            .zip(&is_jump)
+            .filter(|(_, jump)| **jump)
+            .map(|(r, _)| *r)
+            .collect();
+        let (jump_mean, jump_std) = if jumps.len() >= 2 {
+            mean_and_std(jumps.iter().copied())
+        } else {
+            (0.0, JUMP_THRESHOLD * diffusion_std)
+        };
+
+        let kappa = -slope.ln() / mean_dt;
+        let noise_scale = (-(-2.0 * kappa * mean_dt).exp_m1() / (2.0 * kappa)).sqrt();
+        vec![
+            kappa,
+            (diffusion_std / noise_scale).max(f64::EPSILON),
+            jumps.len().max(1) as f64 / (n * mean_dt),
+            jump_mean,
+            jump_std.max(diffusion_std).max(f64::EPSILON),
+            intercept / (1.0 - slope),
+        ]
+    }
+
+    /// Returns the covariance of the power estimates `theta`, the inverse of the observed
+    /// information, or `None` if the information is not positive definite.
+    fn power_covariance(theta: &[f64], x: &[f64], dt: &[f64]) -> Option<Array2<f64>> {
+        let gradient = |theta: &Array1<f64>| {
+            value_and_gradient(|t| -Self::power_log_likelihood(t, x, dt), theta).1
+        };
+        let theta = Array1::from(theta.to_vec());
+        let mut information = Array2::zeros((NUM_POWER_PARAMETERS, NUM_POWER_PARAMETERS));
+        for j in 0..NUM_POWER_PARAMETERS {
+            let h = HESSIAN_STEP * theta[j].abs().max(1e-2);
+            let mut up = theta.clone();
+            up[j] += h;
+            let mut down = theta.clone();
+            down[j] -= h;
+            let column = (gradient(&up) - gradient(&down)) / (2.0 * h);
+            information.column_mut(j).assign(&column);
+        }
+        let information = (&information + &information.t()) / 2.0;
+        solve_positive_definite(&information, &Array2::eye(NUM_POWER_PARAMETERS)).ok()
+    }
+}
+
+/// Returns the density of `N(0, variance)` at `x`.
+fn normal_density(x: f64, variance: f64) -> f64 {
+    (-0.5 * x * x / variance).exp() / (2.0 * std::f64::consts::PI * variance).sqrt()
+}
+
+/// Returns the mean and the standard deviation of `values`.
+fn mean_and_std(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / n;
+    let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
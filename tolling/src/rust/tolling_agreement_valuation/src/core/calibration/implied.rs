@@ -0,0 +1,364 @@
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+use ndarray::ArrayView2;
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::model_parameters::{GasModel, ModelParameters, PowerModel};
+use crate::core::parameters::volatility::Volatility;
+
+/// The price an option is written on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionUnderlying {
+    /// The gas price.
+    Gas,
+    /// The power price.
+    Power,
+    /// The spark spread `power - heat_rate * gas` of a unit with the heat rate
+    /// `heat_rate` (MMBtu/MWh), for gas prices in € / MMBtu.
+    SparkSpread {
+        /// The gas burnt per MWh of power (MMBtu/MWh).
+        heat_rate: f64,
+    },
+}
+
+/// Whether an option pays the excess of its underlying over the strike or the shortfall.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    /// Pays `max(underlying - strike, 0)`.
+    Call,
+    /// Pays `max(strike - underlying, 0)`.
+    Put,
+}
+
+/// How an option settles over its delivery period.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Settlement {
+    /// A single option on the average of the underlying over the delivery period, e.g. a
+    /// monthly gas or power option settled against the month's average.
+    #[default]
+    Average,
+    /// A strip of options, one per point of the delivery period, quoted as the average
+    /// payoff, e.g. a month of daily spark-spread options.
+    Strip,
+}
+
+/// The market price of an option, per MWh of its underlying.
+///
+/// The option is exercised against the simulated prices at the points `delivery` of the
+/// time grid, e.g. the hours of a month, and paid at the end of the last day of its
+/// delivery period.
+#[derive(Debug, Clone)]
+pub struct OptionQuote {
+    /// The price the option is written on.
+    pub underlying: OptionUnderlying,
+    /// Whether the option is a call or a put.
+    pub kind: OptionKind,
+    /// How the option settles over its delivery period.
+    pub settlement: Settlement,
+    /// The strike (€ / MWh).
+    pub strike: f64,
+    /// The points of the time grid the option is exercised against.
+    pub delivery: Range<usize>,
+    /// The market price (€ / MWh).
+    pub price: f64,
+    /// The weight of the squared residual of the quote in the objective.
+    pub weight: f64,
+}
+
+impl OptionQuote {
+    /// Creates a quote with a weight of one.
+    pub fn new(
+        underlying: OptionUnderlying,
+        kind: OptionKind,
+        strike: f64,
+        delivery: Range<usize>,
+        price: f64,
+    ) -> Self {
+        Self {
+            underlying,
+            kind,
+            settlement: Settlement::default(),
+            strike,
+            delivery,
+            price,
+            weight: 1.0,
+        }
+    }
+
+    /// Sets how the option settles over its delivery period.
+    pub fn with_settlement(mut self, settlement: Settlement) -> Self {
+        self.settlement = settlement;
+        self
+    }
+
+    /// Sets the weight of the quote, e.g. the inverse of its squared bid-ask spread.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Checks that the quote can be priced on `time_grid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delivery period is empty or exceeds the time grid, or if
+    /// the strike, the price or the weight is not finite or the weight is negative.
+    pub fn check(&self, time_grid: &TimeGrid) -> Result<()> {
+        if self.delivery.is_empty() || self.delivery.end > time_grid.len() {
+            bail!(
+                "The delivery period {:?} of an option is empty or exceeds the {} points of \
+                 the time grid.",
+                self.delivery,
+                time_grid.len()
+            );
+        }
+        if !self.strike.is_finite() || !self.price.is_finite() {
+            bail!("The strike and the price of an option must be finite.");
+        }
+        if !self.weight.is_finite() || self.weight < 0.0 {
+            bail!("The weight of an option must be finite and non-negative.");
+        }
+        Ok(())
+    }
+
+    /// Returns the undiscounted payoff of the option on a simulated path.
+    ///
+    /// `assets` has shape `(num_assets, num_points)`, and `gas` and `power` are the rows
+    /// of the gas and power prices.
+    pub fn payoff<T: Float + FromPrimitive>(
+        &self,
+        assets: &ArrayView2<T>,
+        gas: usize,
+        power: usize,
+    ) -> T {
+        let convert = |x: f64| T::from_f64(x).unwrap();
+        let underlying = |i: usize| match self.underlying {
+            OptionUnderlying::Gas => assets[[gas, i]],
+            OptionUnderlying::Power => assets[[power, i]],
+            OptionUnderlying::SparkSpread { heat_rate } => {
+                assets[[power, i]] - convert(heat_rate) * assets[[gas, i]]
+            }
+        };
+        let exercise = |value: T| {
+            let moneyness = match self.kind {
+                OptionKind::Call => value - convert(self.strike),
+                OptionKind::Put => convert(self.strike) - value,
+            };
+            if moneyness > T::zero() {
+                moneyness
+            } else {
+                T::zero()
+            }
+        };
+
+        let num_points = convert(self.delivery.len() as f64);
+        let points = self.delivery.clone();
+        match self.settlement {
+            Settlement::Average => {
+                exercise(points.map(underlying).fold(T::zero(), |acc, x| acc + x) / num_points)
+            }
+            Settlement::Strip => {
+                points
+                    .map(|i| exercise(underlying(i)))
+                    .fold(T::zero(), |acc, x| acc + x)
+                    / num_points
+            }
+        }
+    }
+
+    /// Returns the time (in years) at which the option is paid: the end of the local day
+    /// of the last point of its delivery period.
+    pub fn payment_time(&self, time_grid: &TimeGrid) -> f64 {
+        time_grid.day_end_time(time_grid.day_of(self.delivery.end - 1))
+    }
+}
+
+/// A parameter of `ModelParameters` fitted to option prices.
+///
+/// The jump intensity `lambda_j` cannot be fitted: the number of jumps on a path does not
+/// change smoothly with it, so its pathwise derivative carries no information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibratedParameter {
+    /// Every bucket of the volatility `sigma_g` of the GBM gas model.
+    SigmaG,
+    /// Every bucket of the volatility `sigma_p` of power.
+    SigmaP,
+    /// The mean-reversion speed `kappa` of power.
+    Kappa,
+    /// The mean `mu_j` of the normal power jumps.
+    MuJ,
+    /// The standard deviation `sigma_j` of the normal power jumps.
+    SigmaJ,
+    /// The correlation `rho` between gas and power.
+    Rho,
+}
+
+/// How a parameter is mapped to the unconstrained coordinate of the optimiser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterDomain {
+    /// Any real number, used as it is.
+    Real,
+    /// A positive number `exp(u)`.
+    Positive,
+    /// A correlation `tanh(u)` in `(-1, 1)`.
+    Correlation,
+}
+
+impl ParameterDomain {
+    /// Returns the unconstrained coordinate of `value`.
+    pub fn to_coordinate(self, value: f64) -> f64 {
+        match self {
+            ParameterDomain::Real => value,
+            ParameterDomain::Positive => value.ln(),
+            ParameterDomain::Correlation => value.atanh(),
+        }
+    }
+
+    /// Returns the parameter of the coordinate `u` and its derivative with respect to
+    /// `u`.
+    pub fn value_at(self, u: f64) -> (f64, f64) {
+        match self {
+            ParameterDomain::Real => (u, 1.0),
+            ParameterDomain::Positive => (u.exp(), u.exp()),
+            ParameterDomain::Correlation => (u.tanh(), 1.0 - u.tanh() * u.tanh()),
+        }
+    }
+}
+
+impl CalibratedParameter {
+    /// Returns the name of the parameter in `ModelParameters`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CalibratedParameter::SigmaG => "sigma_g",
+            CalibratedParameter::SigmaP => "sigma_p",
+            CalibratedParameter::Kappa => "kappa",
+            CalibratedParameter::MuJ => "mu_j",
+            CalibratedParameter::SigmaJ => "sigma_j",
+            CalibratedParameter::Rho => "rho",
+        }
+    }
+
+    /// Returns the domain of the values of the parameter.
+    pub fn domain(self) -> ParameterDomain {
+        match self {
+            CalibratedParameter::MuJ => ParameterDomain::Real,
+            CalibratedParameter::Rho => ParameterDomain::Correlation,
+            _ => ParameterDomain::Positive,
+        }
+    }
+
+    /// Checks that the parameter enters the price model of `params`, so option prices
+    /// depend on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `sigma_g` is fitted for a gas model other than the GBM,
+    /// `sigma_p` or the jump parameters for the regime-switching power model, or the
+    /// jump parameters for a law of the jump sizes replacing `mu_j` and `sigma_j`.
+    pub fn check<T>(self, params: &ModelParameters<T>) -> Result<()> {
+        let regime_switching = matches!(params.power_model, PowerModel::RegimeSwitching { .. });
+        match self {
+            CalibratedParameter::SigmaG if !matches!(params.gas_model, GasModel::Gbm) => {
+                bail!("'sigma_g' is only used by the GBM gas model.")
+            }
+            CalibratedParameter::SigmaP if regime_switching => {
+                bail!("'sigma_p' is not used by the regime-switching power model.")
+            }
+            CalibratedParameter::MuJ | CalibratedParameter::SigmaJ
+                if regime_switching || params.jump_size.is_some() =>
+            {
+                bail!(
+                    "'{}' is only used by the normal jumps of the jump power models.",
+                    self.name()
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns a name for every value of the parameter: the name of the parameter for a
+    /// scalar or a constant volatility, and the name followed by the bucket, e.g.
+    /// `sigma_p[jan]`, for the buckets of a time-dependent volatility.
+    pub fn value_names<T>(self, params: &ModelParameters<T>) -> Vec<String> {
+        match self.volatility(params) {
+            Some(Volatility::Constant(_)) | None => vec![self.name().to_string()],
+            Some(volatility) => volatility
+                .bucket_names()
+                .iter()
+                .map(|bucket| format!("{}[{}]", self.name(), bucket))
+                .collect(),
+        }
+    }
+
+    /// Returns the values of the parameter in `params`, in the order of `value_names`.
+    pub fn values<T>(self, params: &ModelParameters<T>) -> Vec<&T> {
+        match self {
+            CalibratedParameter::SigmaG => params.sigma_g.parameters(),
+            CalibratedParameter::SigmaP => params.sigma_p.parameters(),
+            CalibratedParameter::Kappa => vec![&params.kappa],
+            CalibratedParameter::MuJ => vec![&params.mu_j],
+            CalibratedParameter::SigmaJ => vec![&params.sigma_j],
+            CalibratedParameter::Rho => vec![&params.rho],
+        }
+    }
+
+    /// Returns the values of the parameter in `params` for updating, in the order of
+    /// `value_names`.
+    pub fn values_mut<T>(self, params: &mut ModelParameters<T>) -> Vec<&mut T> {
+        match self {
+            CalibratedParameter::SigmaG => params.sigma_g.parameters_mut(),
+            CalibratedParameter::SigmaP => params.sigma_p.parameters_mut(),
+            CalibratedParameter::Kappa => vec![&mut params.kappa],
+            CalibratedParameter::MuJ => vec![&mut params.mu_j],
+            CalibratedParameter::SigmaJ => vec![&mut params.sigma_j],
+            CalibratedParameter::Rho => vec![&mut params.rho],
+        }
+    }
+
+    /// Returns the volatility of the parameter, if it is one.
+    fn volatility<T>(self, params: &ModelParameters<T>) -> Option<&Volatility<T>> {
+        match self {
+            CalibratedParameter::SigmaG => Some(&params.sigma_g),
+            CalibratedParameter::SigmaP => Some(&params.sigma_p),
+            _ => None,
+        }
+    }
+}
+
+/// The fit of the model to a single option quote.
+#[derive(Debug, Clone, Copy)]
+pub struct QuoteFit {
+    /// The market price of the option.
+    pub market_price: f64,
+    /// The price of the option under the fitted parameters.
+    pub model_price: f64,
+    /// The Monte Carlo standard error of `model_price`.
+    pub model_price_standard_error: f64,
+    /// The difference `model_price - market_price`.
+    pub residual: f64,
+}
+
+/// The result of fitting model parameters to option prices.
+#[derive(Debug, Clone)]
+pub struct ImpliedCalibration {
+    /// The parameters with the fitted values, the others as given.
+    pub model_params: ModelParameters<f64>,
+    /// The name and fitted value of every fitted value, see
+    /// `CalibratedParameter::value_names`.
+    pub fitted_values: Vec<(String, f64)>,
+    /// The fit to every quote, in the order of the quotes.
+    pub quote_fits: Vec<QuoteFit>,
+    /// The root mean square of the residuals of the quotes.
+    pub rmse: f64,
+    /// The number of iterations of the optimiser.
+    pub iterations: usize,
+    /// The largest absolute component of the gradient of the objective at the fit.
+    pub gradient_norm: f64,
+    /// Whether the optimiser converged, i.e. the gradient fell below its tolerance.
+    pub converged: bool,
+    /// Whether the optimiser stopped because the objective no longer decreased while the
+    /// gradient was still above its tolerance.
+    pub stalled: bool,
+}
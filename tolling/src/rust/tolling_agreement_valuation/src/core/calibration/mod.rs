@@ -0,0 +1,4 @@
+pub mod historical;
+pub mod implied;
+pub mod optimizer;
+pub mod seasonal_shape;
@@ -0,0 +1,226 @@
+use aad::{AADVar, backward, clear_tape, get_tape_len};
+use ndarray::{Array1, Array2};
+
+/// Parameter of the sufficient decrease (Armijo) condition of the line search.
+const ARMIJO_SLOPE: f64 = 1e-4;
+
+/// Smallest step of the line search before the minimisation gives up.
+const MIN_STEP: f64 = 1e-12;
+
+/// Returns the value of `f` at `x` and its gradient, computed by a backward AAD pass.
+///
+/// `f` is evaluated once with an `AADVar` per coordinate of `x` on a fresh tape of the
+/// current thread.
+pub fn value_and_gradient<F>(f: F, x: &Array1<f64>) -> (f64, Array1<f64>)
+where
+    F: Fn(&[AADVar]) -> AADVar,
+{
+    clear_tape();
+    let inputs: Vec<AADVar> = x.iter().map(|&value| AADVar::constant(value)).collect();
+    let output = f(&inputs);
+
+    let mut adjoints = vec![0.0; get_tape_len()];
+    adjoints[output.index] = 1.0;
+    backward(&mut adjoints);
+    let gradient = inputs.iter().map(|input| adjoints[input.index]).collect();
+    (output.value, gradient)
+}
+
+/// The point found by a minimisation.
+#[derive(Debug, Clone)]
+pub struct Minimum {
+    /// The point with the smallest value found.
+    pub point: Array1<f64>,
+    /// The value of the objective at `point`.
+    pub value: f64,
+    /// The largest absolute component of the gradient at `point`.
+    pub gradient_norm: f64,
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// Whether the gradient fell below its tolerance.
+    pub converged: bool,
+    /// Whether the value stopped decreasing, or the line search failed, while the gradient
+    /// was still above its tolerance.
+    pub stalled: bool,
+}
+
+/// The quasi-Newton method of Broyden, Fletcher, Goldfarb and Shanno (BFGS) for smooth
+/// unconstrained minimisation.
+///
+/// Every iteration steps along `-H * g`, where `g` is the gradient and `H` approximates
+/// the inverse Hessian, with a backtracking line search for sufficient decrease. `H`
+/// starts as a scaled identity and is updated from the change of the gradient over the
+/// step; it is reset to the identity whenever it stops giving a descent direction.
+/// Points where the objective is not finite are treated as infeasible and backed away
+/// from, so constraints can be imposed by returning infinity or NaN.
+#[derive(Debug, Clone, Copy)]
+pub struct Bfgs {
+    /// The largest number of iterations.
+    pub max_iterations: usize,
+    /// The largest absolute component of the gradient at a minimum.
+    pub gradient_tolerance: f64,
+    /// The relative decrease of the value below which the minimisation stops as stalled
+    /// unless the gradient is below its tolerance.
+    pub value_tolerance: f64,
+}
+
+impl Default for Bfgs {
+    fn default() -> Self {
+        Self {
+            max_iterations: 500,
+            gradient_tolerance: 1e-6,
+            value_tolerance: 1e-12,
+        }
+    }
+}
+
+impl Bfgs {
+    /// Minimises `objective`, which returns the value and the gradient at a point,
+    /// starting from `start`.
+    ///
+    /// The minimum is only reported as converged when the gradient test passes; a
+    /// minimisation that stops because the value no longer decreases is reported as
+    /// stalled instead.
+    pub fn minimize<F>(&self, mut objective: F, start: Array1<f64>) -> Minimum
+    where
+        F: FnMut(&Array1<f64>) -> (f64, Array1<f64>),
+    {
+        let n = start.len();
+        let mut x = start;
+        let (mut value, mut gradient) = objective(&x);
+        let mut inverse_hessian = Array2::<f64>::eye(n);
+        let mut scaled = false;
+        let mut converged = false;
+        let mut stalled = false;
+        let mut iterations = 0;
+
+        while iterations < self.max_iterations {
+            if max_abs(&gradient) <= self.gradient_tolerance {
+                converged = true;
+                break;
+            }
+            iterations += 1;
+
+            let mut direction = -inverse_hessian.dot(&gradient);
+            let mut slope = gradient.dot(&direction);
+            if slope >= 0.0 {
+                inverse_hessian = Array2::eye(n);
+                direction = -&gradient;
+                slope = gradient.dot(&direction);
+            }
+
+            // Backtrack until the value decreases sufficiently.
+            let mut step = 1.0;
+            let (next, next_value, next_gradient) = loop {
+                let candidate = &x + &(step * &direction);
+                let (candidate_value, candidate_gradient) = objective(&candidate);
+                if candidate_value.is_finite()
+                    && candidate_gradient.iter().all(|g| g.is_finite())
+                    && candidate_value <= value + ARMIJO_SLOPE * step * slope
+                {
+                    break (candidate, candidate_value, candidate_gradient);
+                }
+                step *= 0.5;
+                if step < MIN_STEP {
+                    return Minimum {
+                        gradient_norm: max_abs(&gradient),
+                        point: x,
+                        value,
+                        iterations,
+                        converged: false,
+                        stalled: true,
+                    };
+                }
+            };
+
+            let s = &next - &x;
+            let y = &next_gradient - &gradient;
+            let sy = s.dot(&y);
+            if sy > 0.0 {
+                if !scaled {
+                    // Scale the identity to the curvature along the first step.
+                    inverse_hessian *= sy / y.dot(&y);
+                    scaled = true;
+                }
+                // H' = (I - rho * s * y^T) * H * (I - rho * y * s^T) + rho * s * s^T.
+                let rho = 1.0 / sy;
+                let hy = inverse_hessian.dot(&y);
+                let yhy = y.dot(&hy);
+                for i in 0..n {
+                    for j in 0..n {
+                        inverse_hessian[[i, j]] +=
+                            rho * ((1.0 + rho * yhy) * s[i] * s[j] - hy[i] * s[j] - s[i] * hy[j]);
+                    }
+                }
+            }
+
+            let decrease = value - next_value;
+            x = next;
+            value = next_value;
+            gradient = next_gradient;
+            if decrease <= self.value_tolerance * (1.0 + value.abs()) {
+                converged = max_abs(&gradient) <= self.gradient_tolerance;
+                stalled = !converged;
+                break;
+            }
+        }
+
+        Minimum {
+            gradient_norm: max_abs(&gradient),
+            point: x,
+            value,
+            iterations,
+            converged,
+            stalled,
+        }
+    }
+}
+
+/// Returns the largest absolute component of `x`.
+fn max_abs(x: &Array1<f64>) -> f64 {
+    x.iter().fold(0.0, |acc, v| acc.max(v.abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn bfgs_finds_the_minimum_of_the_rosenbrock_function() {
+        let rosenbrock = |x: &[AADVar]| {
+            let one = AADVar::constant(1.0);
+            let hundred = AADVar::constant(100.0);
+            (one - x[0]) * (one - x[0]) + hundred * (x[1] - x[0] * x[0]) * (x[1] - x[0] * x[0])
+        };
+        let minimum =
+            Bfgs::default().minimize(|x| value_and_gradient(rosenbrock, x), array![-1.2, 1.0]);
+
+        assert!(minimum.converged);
+        assert!(!minimum.stalled);
+        assert!(minimum.gradient_norm <= Bfgs::default().gradient_tolerance);
+        assert!((minimum.point[0] - 1.0).abs() < 1e-4);
+        assert!((minimum.point[1] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn bfgs_reports_a_stall_of_the_value_as_not_converged() {
+        // The value of |x|^(1/2) hardly decreases near its kink at zero while the gradient
+        // grows, so only the value tolerance can stop the minimisation.
+        let optimizer = Bfgs {
+            value_tolerance: 1e-3,
+            ..Bfgs::default()
+        };
+        let minimum = optimizer.minimize(
+            |x| {
+                let value = x[0].abs().sqrt();
+                (value, array![0.5 * x[0].signum() / value])
+            },
+            array![1.0],
+        );
+
+        assert!(!minimum.converged);
+        assert!(minimum.stalled);
+        assert!(minimum.gradient_norm > optimizer.gradient_tolerance);
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2};
+
+use crate::core::common::cholesky::solve_positive_definite;
+use crate::core::common::time_grid::{Season, TimeGrid};
+
+/// The number of feasible GLS refits of the shape.
+const GLS_PASSES: usize = 3;
+
+/// Bound on the autocorrelation of the deviations from the shape, below the unit root.
+const MAX_AUTOCORRELATION: f64 = 0.9999;
+
+/// The seasonal shape of a log price series: an additive effect of the local month, the
+/// local hour of the day and the weekend, fitted by least squares.
+///
+/// The log price at point `i` is regressed on an intercept and on indicators of its
+/// month, its hour and whether it falls on a weekend, taken from the `Season` of the
+/// time grid. Only the months and hours that occur in the data get an indicator, with
+/// the first of each as the reference, so a series covering a few months fits as well
+/// as one covering years.
+///
+/// Deviations of power prices from their shape revert slowly, so consecutive points
+/// are strongly autocorrelated and least squares on the levels leaves noisy steps at
+/// e.g. the start and end of weekends, which look like jumps. The shape is therefore
+/// refitted by feasible generalised least squares for deviations following an AR(1),
+/// the discretised OU process: the regression of `y_i - a * y_{i-1}` on
+/// `x_i - a * x_{i-1}`, with the autocorrelation `a` of the residuals of the previous
+/// fit.
+#[derive(Debug, Clone)]
+pub struct SeasonalShape {
+    /// The column of the indicator of every month, if it has one.
+    month_columns: [Option<usize>; 12],
+    /// The column of the indicator of every hour, if it has one.
+    hour_columns: [Option<usize>; 24],
+    /// The column of the weekend indicator, if it has one.
+    weekend_column: Option<usize>,
+    /// The fitted coefficients, the intercept first.
+    coefficients: Array1<f64>,
+}
+
+impl SeasonalShape {
+    /// Fits the shape to `log_prices`, given at every point of `time_grid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the series does not have one point per grid point or has too
+    /// few points to determine the effects.
+    pub fn fit(log_prices: &Array1<f64>, time_grid: &TimeGrid) -> Result<Self> {
+        if log_prices.len() != time_grid.len() {
+            bail!(
+                "A price series has {} points, but the time grid has {}.",
+                log_prices.len(),
+                time_grid.len()
+            );
+        }
+        let seasons: Vec<Season> = (0..time_grid.len()).map(|i| time_grid.season(i)).collect();
+
+        // Give every month and hour but the first a column after the intercept, and the
+        // weekend one if the series has both weekdays and weekends.
+        let mut num_columns = 1;
+        let mut month_columns = [None; 12];
+        let mut hour_columns = [None; 24];
+        let first = seasons[0];
+        for season in &seasons {
+            if season.month != first.month && month_columns[season.month].is_none() {
+                month_columns[season.month] = Some(num_columns);
+                num_columns += 1;
+            }
+            if season.hour != first.hour && hour_columns[season.hour].is_none() {
+                hour_columns[season.hour] = Some(num_columns);
+                num_columns += 1;
+            }
+        }
+        let weekend_column = seasons
+            .iter()
+            .any(|season| season.weekend != first.weekend)
+            .then_some(num_columns);
+        num_columns += usize::from(weekend_column.is_some());
+        let mut shape = Self {
+            month_columns,
+            hour_columns,
+            weekend_column,
+            coefficients: Array1::zeros(num_columns),
+        };
+
+        // Start from least squares on the levels, then refit by feasible GLS with the
+        // autocorrelation of the residuals (Cochrane-Orcutt).
+        let rows: Vec<Vec<usize>> = seasons.iter().map(|season| shape.columns(season)).collect();
+        let level_rows = rows
+            .iter()
+            .zip(log_prices)
+            .map(|(columns, &y)| (columns.iter().map(|&j| (j, 1.0)).collect(), y));
+        shape.coefficients = Self::least_squares(num_columns, level_rows)?;
+        for _ in 0..GLS_PASSES {
+            let residuals: Vec<f64> = rows
+                .iter()
+                .zip(log_prices)
+                .map(|(columns, &y)| {
+                    y - columns.iter().map(|&j| shape.coefficients[j]).sum::<f64>()
+                })
+                .collect();
+            let lagged: f64 = residuals.windows(2).map(|pair| pair[0] * pair[1]).sum();
+            let squares: f64 = residuals[..residuals.len() - 1].iter().map(|r| r * r).sum();
+            let a = if squares > 0.0 {
+                (lagged / squares).clamp(0.0, MAX_AUTOCORRELATION)
+            } else {
+                0.0
+            };
+            let differenced_rows = (1..rows.len()).map(|i| {
+                let mut entries: Vec<(usize, f64)> = rows[i].iter().map(|&j| (j, 1.0)).collect();
+                entries.extend(rows[i - 1].iter().map(|&j| (j, -a)));
+                (entries, log_prices[i] - a * log_prices[i - 1])
+            });
+            shape.coefficients = Self::least_squares(num_columns, differenced_rows)?;
+        }
+        Ok(shape)
+    }
+
+    /// Returns the least squares coefficients of rows given by their non-zero
+    /// `(column, value)` entries and their target, from the normal equations.
+    fn least_squares(
+        num_columns: usize,
+        rows: impl Iterator<Item = (Vec<(usize, f64)>, f64)>,
+    ) -> Result<Array1<f64>> {
+        let mut gram = Array2::zeros((num_columns, num_columns));
+        let mut moments = Array2::zeros((num_columns, 1));
+        for (entries, y) in rows {
+            for &(j, x_j) in &entries {
+                moments[[j, 0]] += x_j * y;
+                for &(k, x_k) in &entries {
+                    gram[[j, k]] += x_j * x_k;
+                }
+            }
+        }
+        let solution = solve_positive_definite(&gram, &moments).map_err(|e| {
+            anyhow::anyhow!("The seasonal shape cannot be fitted to the series: {}", e)
+        })?;
+        Ok(solution.column(0).to_owned())
+    }
+
+    /// Returns the columns of the regressors that are one at a point in `season`.
+    fn columns(&self, season: &Season) -> Vec<usize> {
+        [
+            Some(0),
+            self.month_columns[season.month],
+            self.hour_columns[season.hour],
+            self.weekend_column.filter(|_| season.weekend),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Returns the fitted log price at every point of `time_grid`.
+    pub fn log_levels(&self, time_grid: &TimeGrid) -> Array1<f64> {
+        Array1::from_shape_fn(time_grid.len(), |i| {
+            self.columns(&time_grid.season(i))
+                .iter()
+                .map(|&j| self.coefficients[j])
+                .sum()
+        })
+    }
+}
@@ -0,0 +1,109 @@
+/// Builds Brownian paths from standard normal draws in Brownian bridge order.
+///
+/// The first draw fixes the end point of the path, the second the midpoint, and so on
+/// by recursive bisection. The coarse shape of the path is therefore decided by the
+/// first draws. Combined with a quasi-random sequence, whose first dimensions are the
+/// most evenly distributed, this concentrates the variance of the path functional in
+/// the dimensions where quasi-Monte Carlo works best.
+///
+/// The construction works on an equidistant grid of unit steps and returns normalised
+/// increments, so the output is again a sequence of independent standard normals and
+/// can be fed to the price processes unchanged.
+pub struct BrownianBridge {
+    /// The grid index fixed at each construction step.
+    bridge_index: Vec<usize>,
+    /// The left neighbour (one past the already fixed grid index) at each step.
+    left_index: Vec<usize>,
+    /// The already fixed right neighbour at each step.
+    right_index: Vec<usize>,
+    left_weight: Vec<f64>,
+    right_weight: Vec<f64>,
+    std_dev: Vec<f64>,
+}
+
+impl BrownianBridge {
+    /// Precomputes the construction order for a path of `n_steps` steps.
+    pub fn new(n_steps: usize) -> Self {
+        let mut bridge = BrownianBridge {
+            bridge_index: vec![0; n_steps],
+            left_index: vec![0; n_steps],
+            right_index: vec![0; n_steps],
+            left_weight: vec![0.0; n_steps],
+            right_weight: vec![0.0; n_steps],
+            std_dev: vec![0.0; n_steps],
+        };
+        if n_steps == 0 {
+            return bridge;
+        }
+
+        // Time of grid index `i` is `i + 1`.
+        let t = |i: usize| (i + 1) as f64;
+        let mut is_fixed = vec![false; n_steps];
+
+        is_fixed[n_steps - 1] = true;
+        bridge.bridge_index[0] = n_steps - 1;
+        bridge.std_dev[0] = t(n_steps - 1).sqrt();
+
+        let mut j = 0;
+        for i in 1..n_steps {
+            // Find the next gap `[j, k)` of unfixed grid indices.
+            while is_fixed[j] {
+                j += 1;
+            }
+            let mut k = j;
+            while !is_fixed[k] {
+                k += 1;
+            }
+            // Fix the middle of the gap, conditional on its neighbours.
+            let l = j + ((k - 1 - j) >> 1);
+            is_fixed[l] = true;
+            bridge.bridge_index[i] = l;
+            bridge.left_index[i] = j;
+            bridge.right_index[i] = k;
+
+            let t_left = if j == 0 { 0.0 } else { t(j - 1) };
+            let span = t(k) - t_left;
+            bridge.left_weight[i] = (t(k) - t(l)) / span;
+            bridge.right_weight[i] = (t(l) - t_left) / span;
+            bridge.std_dev[i] = ((t(l) - t_left) * (t(k) - t(l)) / span).sqrt();
+
+            j = k + 1;
+            if j >= n_steps {
+                j = 0;
+            }
+        }
+        bridge
+    }
+
+    /// Transforms `normals`, given in bridge order, into normalised Brownian increments.
+    ///
+    /// The transformation happens in-place.
+    pub fn transform(&self, normals: &mut [f64]) {
+        let n_steps = normals.len();
+        assert_eq!(
+            n_steps,
+            self.bridge_index.len(),
+            "Brownian bridge size mismatch"
+        );
+        if n_steps == 0 {
+            return;
+        }
+
+        let mut path = vec![0.0; n_steps];
+        path[n_steps - 1] = self.std_dev[0] * normals[0];
+        for (i, z) in normals.iter().enumerate().skip(1) {
+            let j = self.left_index[i];
+            let k = self.right_index[i];
+            let l = self.bridge_index[i];
+            let left = if j == 0 { 0.0 } else { path[j - 1] };
+            path[l] =
+                self.left_weight[i] * left + self.right_weight[i] * path[k] + self.std_dev[i] * z;
+        }
+
+        // With unit time steps the increments are standard normal.
+        normals[0] = path[0];
+        for (z, w) in normals[1..].iter_mut().zip(path.windows(2)) {
+            *z = w[1] - w[0];
+        }
+    }
+}
@@ -1,40 +1,109 @@
+use anyhow::{Result, bail};
 use ndarray::Array2;
-use num_traits::{Float, One, Zero};
+use num_traits::{Float, FromPrimitive};
 
-/// Calculates the Cholesky decomposition of a 2x2 correlation matrix.
-///
-/// The correlation matrix is defined as:
-/// ```text
-/// [[1,   rho],
-///  [rho, 1  ]]
-/// ```
+/// Relative tolerance below which a pivot of the decomposition counts as zero.
+const PIVOT_TOLERANCE: f64 = 1e-10;
+
+/// Calculates the Cholesky decomposition of an `n x n` positive semi-definite matrix.
 ///
-/// The resulting lower triangular matrix `L` is:
-/// ```text
-/// [[1,   0              ],
-///  [rho, sqrt(1 - rho^2)]]
-/// ```
+/// Returns the lower triangular matrix `L` with `L * L^T = matrix`, computed with the
+/// Cholesky–Banachiewicz algorithm. Only the lower triangle of `matrix` is read.
 ///
-/// This is used to correlate two series of standard normal random variables.
+/// Singular matrices, e.g. of perfectly correlated assets, are factorised as well: a
+/// pivot that vanishes up to rounding gives a zero column. The function is generic over
+/// `T`, so the factor can be differentiated with respect to the correlations.
 ///
 /// # Arguments
 ///
-/// * `rho`: The correlation coefficient, a value of type `T`.
+/// * `matrix`: A symmetric, positive semi-definite matrix.
 ///
 /// # Returns
 ///
-/// A 2x2 `ndarray::Array2<T>` representing the lower triangular Cholesky factor `L`.
+/// A `Result` containing the lower triangular Cholesky factor `L`.
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if `rho` is outside the mathematical domain of `[-1.0, 1.0]`,
-/// as `(1 - rho^2)` would be negative, causing `sqrt()` to return `NaN` or panic for real numbers.
-pub fn cholesky_2d<T>(rho: T) -> Array2<T>
-where
-    T: Float + One + Zero,
-{
-    let one = T::one();
-    let zero = T::zero();
-    let correlation_term = (one - rho * rho).sqrt();
-    ndarray::arr2(&[[one, zero], [rho, correlation_term]])
+/// Returns an error if `matrix` is not square or not positive semi-definite. Use
+/// `factorise_correlation` to repair such correlation matrices instead.
+pub fn cholesky<T: Float + FromPrimitive>(matrix: &Array2<T>) -> Result<Array2<T>> {
+    let n = matrix.nrows();
+    if matrix.ncols() != n {
+        bail!(
+            "Only square matrices have a Cholesky decomposition, got {:?}.",
+            matrix.dim()
+        );
+    }
+    let scale = matrix
+        .diag()
+        .iter()
+        .fold(T::zero(), |acc, x| acc.max(x.abs()));
+    let tolerance = T::from_f64(PIVOT_TOLERANCE).unwrap() * scale.max(T::one());
+
+    let mut l = Array2::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = matrix[[i, j]];
+            for k in 0..j {
+                sum = sum - l[[i, k]] * l[[j, k]];
+            }
+            if i == j {
+                if sum.is_nan() || sum < -tolerance {
+                    bail!(
+                        "The matrix is not positive semi-definite (pivot {i} is {:?}).",
+                        sum.to_f64()
+                    );
+                }
+                l[[i, i]] = sum.max(T::zero()).sqrt();
+            } else if l[[j, j]] > tolerance.sqrt() {
+                l[[i, j]] = sum / l[[j, j]];
+            } else if sum.abs() > tolerance.sqrt() {
+                // A zero pivot requires the rest of its column to vanish as well.
+                bail!("The matrix is not positive semi-definite (row {i} of singular column {j}).");
+            }
+        }
+    }
+    Ok(l)
+}
+
+/// Solves `matrix * X = rhs` for a symmetric positive definite `matrix` by its Cholesky
+/// decomposition and forward and back substitution, one column of `rhs` at a time.
+///
+/// Passing the identity as `rhs` inverts the matrix, e.g. an information matrix into
+/// the covariance of estimates.
+///
+/// # Errors
+///
+/// Returns an error if `matrix` is not positive definite, including singular matrices,
+/// or if `rhs` does not have a row per row of `matrix`.
+pub fn solve_positive_definite(matrix: &Array2<f64>, rhs: &Array2<f64>) -> Result<Array2<f64>> {
+    let n = matrix.nrows();
+    if rhs.nrows() != n {
+        bail!(
+            "The right-hand side has {} rows, but the matrix has {n}.",
+            rhs.nrows()
+        );
+    }
+    let l = cholesky(matrix)?;
+    let scale = l.diag().iter().fold(0.0_f64, |acc, x| acc.max(*x));
+    if l.diag()
+        .iter()
+        .any(|&pivot| pivot <= PIVOT_TOLERANCE.sqrt() * scale)
+    {
+        bail!("The matrix is singular.");
+    }
+
+    let mut solution = rhs.clone();
+    for mut column in solution.columns_mut() {
+        // Forward substitution with `L`, then back substitution with `L^T`.
+        for i in 0..n {
+            let sum = (0..i).fold(column[i], |acc, k| acc - l[[i, k]] * column[k]);
+            column[i] = sum / l[[i, i]];
+        }
+        for i in (0..n).rev() {
+            let sum = (i + 1..n).fold(column[i], |acc, k| acc - l[[k, i]] * column[k]);
+            column[i] = sum / l[[i, i]];
+        }
+    }
+    Ok(solution)
 }
@@ -0,0 +1,373 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2};
+use num_traits::{Float, FromPrimitive};
+use serde::Deserialize;
+
+use crate::core::common::cholesky::cholesky;
+
+/// Tolerance of the checks that a matrix is symmetric and has a unit diagonal.
+const SHAPE_TOLERANCE: f64 = 1e-12;
+/// Maximum number of sweeps of the Jacobi eigenvalue algorithm.
+const MAX_JACOBI_SWEEPS: usize = 100;
+
+/// How a correlation matrix that is not positive semi-definite is repaired before it is
+/// factorised.
+///
+/// Correlations estimated pairwise, from overlapping histories or by hand, need not form
+/// a valid correlation matrix together. Both repairs return a matrix with a unit
+/// diagonal that is positive semi-definite, and leave valid matrices untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrelationRepair {
+    /// Reject matrices that are not positive semi-definite with an error.
+    #[default]
+    Reject,
+    /// Clip the eigenvalues at `min_eigenvalue` and rescale the result to a unit
+    /// diagonal. Fast and simple, but the result is not the nearest valid matrix.
+    EigenvalueClipping {
+        /// The smallest eigenvalue kept. Zero gives a singular result.
+        min_eigenvalue: f64,
+    },
+    /// Find the nearest correlation matrix in the Frobenius norm by the alternating
+    /// projections of Higham (2002), "Computing the nearest correlation matrix".
+    Higham {
+        /// The maximum number of projection steps.
+        max_iterations: usize,
+        /// The relative change between two steps below which the iteration stops.
+        tolerance: f64,
+    },
+}
+
+impl CorrelationRepair {
+    /// Higham's nearest correlation matrix with settings that converge for the matrix
+    /// sizes used here.
+    pub fn higham() -> Self {
+        CorrelationRepair::Higham {
+            max_iterations: 1000,
+            tolerance: 1e-12,
+        }
+    }
+}
+
+/// The Cholesky factor of a correlation matrix, together with a report of the repair
+/// that was needed to factorise it.
+#[derive(Debug, Clone)]
+pub struct CorrelationFactor<T> {
+    /// The lower triangular Cholesky factor of `correlation`.
+    pub cholesky_l: Array2<T>,
+    /// The matrix that was factorised: the input or, if it was repaired, the repaired
+    /// matrix.
+    pub correlation: Array2<T>,
+    /// `true` if the input was not positive semi-definite and had to be repaired.
+    pub repaired: bool,
+    /// The Frobenius norm of the change to the input, zero if it was not repaired.
+    pub frobenius_distance: T,
+    /// The largest absolute change of a single correlation, zero if it was not repaired.
+    pub max_abs_change: T,
+}
+
+/// Factorises a correlation matrix, repairing it first if it is not positive
+/// semi-definite.
+///
+/// The function is generic over `T`, repair included, so the factor stays
+/// differentiable with respect to the correlations when `T` is an AAD type.
+///
+/// # Arguments
+///
+/// * `correlation`: A symmetric matrix with a unit diagonal and entries in `[-1, 1]`.
+/// * `repair`: What to do if `correlation` is not positive semi-definite.
+///
+/// # Returns
+///
+/// A `Result` containing the `CorrelationFactor`, which reports how far the matrix was
+/// moved by the repair.
+///
+/// # Errors
+///
+/// Returns an error if `correlation` is not a square, symmetric matrix with a unit
+/// diagonal and entries in `[-1, 1]`, or if it is not positive semi-definite and
+/// `repair` is `CorrelationRepair::Reject`.
+pub fn factorise_correlation<T: Float + FromPrimitive>(
+    correlation: &Array2<T>,
+    repair: CorrelationRepair,
+) -> Result<CorrelationFactor<T>> {
+    check_correlation_matrix(correlation)?;
+
+    if let Ok(cholesky_l) = cholesky(correlation) {
+        return Ok(CorrelationFactor {
+            cholesky_l,
+            correlation: correlation.clone(),
+            repaired: false,
+            frobenius_distance: T::zero(),
+            max_abs_change: T::zero(),
+        });
+    }
+
+    let repaired = match repair {
+        CorrelationRepair::Reject => bail!(
+            "The correlation matrix is not positive semi-definite. Choose a correlation \
+             repair to use the nearest valid matrix instead."
+        ),
+        CorrelationRepair::EigenvalueClipping { min_eigenvalue } => {
+            clip_eigenvalues(correlation, T::from_f64(min_eigenvalue).unwrap())
+        }
+        CorrelationRepair::Higham {
+            max_iterations,
+            tolerance,
+        } => {
+            higham_nearest_correlation(correlation, max_iterations, T::from_f64(tolerance).unwrap())
+        }
+    };
+    let change = &repaired - correlation;
+    Ok(CorrelationFactor {
+        cholesky_l: cholesky(&repaired)?,
+        frobenius_distance: frobenius_norm(&change),
+        max_abs_change: change.iter().fold(T::zero(), |acc, x| acc.max(x.abs())),
+        correlation: repaired,
+        repaired: true,
+    })
+}
+
+/// Checks that `correlation` is square and symmetric with a unit diagonal and entries
+/// in `[-1, 1]`.
+fn check_correlation_matrix<T: Float + FromPrimitive>(correlation: &Array2<T>) -> Result<()> {
+    let n = correlation.nrows();
+    if correlation.ncols() != n {
+        bail!(
+            "A correlation matrix must be square, got {:?}.",
+            correlation.dim()
+        );
+    }
+    let tolerance = T::from_f64(SHAPE_TOLERANCE).unwrap();
+    for i in 0..n {
+        if (correlation[[i, i]] - T::one()).abs() > tolerance {
+            bail!("The correlation matrix must have a unit diagonal.");
+        }
+        for j in 0..i {
+            if (correlation[[i, j]] - correlation[[j, i]]).abs() > tolerance {
+                bail!("The correlation matrix must be symmetric.");
+            }
+            if correlation[[i, j]].is_nan() || correlation[[i, j]].abs() > T::one() {
+                bail!("A correlation must lie in [-1, 1].");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Clips the eigenvalues of `matrix` at `min_eigenvalue` and rescales the result to a
+/// unit diagonal.
+fn clip_eigenvalues<T: Float + FromPrimitive>(matrix: &Array2<T>, min_eigenvalue: T) -> Array2<T> {
+    let clipped = project_eigenvalues(matrix, min_eigenvalue);
+    let scale = clipped.diag().mapv(|d| T::one() / d.sqrt());
+    Array2::from_shape_fn(clipped.dim(), |(i, j)| {
+        if i == j {
+            T::one()
+        } else {
+            clipped[[i, j]] * scale[i] * scale[j]
+        }
+    })
+}
+
+/// Computes the nearest correlation matrix to `matrix` in the Frobenius norm.
+///
+/// Alternates between the projection onto the positive semi-definite matrices and the
+/// projection onto the matrices with a unit diagonal, with Dykstra's correction so that
+/// the iteration converges to the nearest point of the intersection rather than to any
+/// point of it. Up to `tolerance`, the final projection onto the unit diagonal leaves
+/// the matrix positive semi-definite; a last eigenvalue clip at zero removes the
+/// remaining rounding so that the result can be factorised.
+fn higham_nearest_correlation<T: Float + FromPrimitive>(
+    matrix: &Array2<T>,
+    max_iterations: usize,
+    tolerance: T,
+) -> Array2<T> {
+    let n = matrix.nrows();
+    let mut correction = Array2::<T>::zeros((n, n));
+    let mut y = matrix.clone();
+    for _ in 0..max_iterations {
+        let r = &y - &correction;
+        let x = project_eigenvalues(&r, T::zero());
+        correction = &x - &r;
+        let previous = y;
+        y = x.clone();
+        y.diag_mut().fill(T::one());
+
+        let change = frobenius_norm(&(&y - &previous)) / frobenius_norm(&y);
+        if change <= tolerance {
+            break;
+        }
+    }
+    clip_eigenvalues(&y, T::zero())
+}
+
+/// Replaces the eigenvalues of the symmetric `matrix` below `min_eigenvalue` by
+/// `min_eigenvalue`.
+fn project_eigenvalues<T: Float + FromPrimitive>(
+    matrix: &Array2<T>,
+    min_eigenvalue: T,
+) -> Array2<T> {
+    let (eigenvalues, eigenvectors) = symmetric_eigen(matrix);
+    let clipped = eigenvalues.mapv(|lambda| lambda.max(min_eigenvalue));
+    let n = matrix.nrows();
+    let mut result = Array2::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let entry = (0..n).fold(T::zero(), |acc, k| {
+                acc + eigenvectors[[i, k]] * clipped[k] * eigenvectors[[j, k]]
+            });
+            result[[i, j]] = entry;
+            result[[j, i]] = entry;
+        }
+    }
+    result
+}
+
+/// Computes the eigenvalues and eigenvectors of a symmetric matrix with the cyclic
+/// Jacobi eigenvalue algorithm.
+///
+/// Returns the eigenvalues and a matrix whose columns are the matching orthonormal
+/// eigenvectors. The algorithm only uses arithmetic and square roots, so it works for
+/// any `Float`, including AAD types.
+fn symmetric_eigen<T: Float + FromPrimitive>(matrix: &Array2<T>) -> (Array1<T>, Array2<T>) {
+    let n = matrix.nrows();
+    let mut a = matrix.clone();
+    let mut v = Array2::<T>::eye(n);
+    let two = T::from_f64(2.0).unwrap();
+    let threshold = T::epsilon() * T::epsilon() * frobenius_norm(matrix).powi(2);
+
+    for _ in 0..MAX_JACOBI_SWEEPS {
+        let off_diagonal = (0..n)
+            .flat_map(|p| (p + 1..n).map(move |q| (p, q)))
+            .fold(T::zero(), |acc, (p, q)| acc + a[[p, q]] * a[[p, q]]);
+        if off_diagonal <= threshold {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[[p, q]] == T::zero() {
+                    continue;
+                }
+                // Rotate in the (p, q) plane to annihilate a[p, q].
+                let theta = (a[[q, q]] - a[[p, p]]) / (two * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + T::one()).sqrt());
+                let c = T::one() / (t * t + T::one()).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+/// The Frobenius norm of `matrix`.
+fn frobenius_norm<T: Float>(matrix: &Array2<T>) -> T {
+    matrix.iter().fold(T::zero(), |acc, x| acc + *x * *x).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// A matrix of pairwise correlations that cannot hold jointly: the first asset is
+    /// strongly correlated with the other two, which are strongly anti-correlated.
+    fn inconsistent() -> Array2<f64> {
+        array![[1.0, 0.9, 0.7], [0.9, 1.0, -0.4], [0.7, -0.4, 1.0]]
+    }
+
+    fn assert_factorises(factor: &CorrelationFactor<f64>) {
+        let product = factor.cholesky_l.dot(&factor.cholesky_l.t());
+        assert!(
+            (&product - &factor.correlation)
+                .iter()
+                .all(|x| x.abs() < 1e-9)
+        );
+        assert!(
+            factor
+                .correlation
+                .diag()
+                .iter()
+                .all(|d| (d - 1.0).abs() < 1e-12)
+        );
+    }
+
+    #[test]
+    fn valid_matrices_are_not_repaired() {
+        let correlation = array![[1.0, 0.6, 0.3], [0.6, 1.0, 0.2], [0.3, 0.2, 1.0]];
+        let factor = factorise_correlation(&correlation, CorrelationRepair::higham()).unwrap();
+
+        assert!(!factor.repaired);
+        assert_eq!(factor.frobenius_distance, 0.0);
+        assert_eq!(factor.correlation, correlation);
+        assert_factorises(&factor);
+        let two_assets =
+            factorise_correlation(&array![[1.0, 0.6], [0.6, 1.0]], CorrelationRepair::Reject)
+                .unwrap();
+        let expected = array![[1.0, 0.0], [0.6, 0.8]];
+        assert!((&two_assets.cholesky_l - &expected).iter().all(|d| d.abs() < 1e-15));
+    }
+
+    #[test]
+    fn invalid_matrices_are_rejected_or_repaired() {
+        assert!(factorise_correlation(&inconsistent(), CorrelationRepair::Reject).is_err());
+
+        let higham = factorise_correlation(&inconsistent(), CorrelationRepair::higham()).unwrap();
+        let clipped = factorise_correlation(
+            &inconsistent(),
+            CorrelationRepair::EigenvalueClipping {
+                min_eigenvalue: 1e-4,
+            },
+        )
+        .unwrap();
+
+        for factor in [&higham, &clipped] {
+            assert!(factor.repaired);
+            assert!(factor.frobenius_distance > 0.0);
+            assert!(factor.max_abs_change <= factor.frobenius_distance);
+            assert_factorises(factor);
+        }
+        // Higham's matrix is the nearest one.
+        assert!(higham.frobenius_distance <= clipped.frobenius_distance);
+    }
+
+    #[test]
+    fn higham_matches_the_published_example() {
+        // Example 5.4 of Higham (2002).
+        let matrix = array![[1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]];
+        let factor = factorise_correlation(&matrix, CorrelationRepair::higham()).unwrap();
+
+        let expected = array![
+            [1.0, 0.7607, 0.1573],
+            [0.7607, 1.0, 0.7607],
+            [0.1573, 0.7607, 1.0]
+        ];
+        assert!(
+            (&factor.correlation - &expected)
+                .iter()
+                .all(|x| x.abs() < 1e-4)
+        );
+    }
+
+    #[test]
+    fn malformed_matrices_are_rejected() {
+        let repair = CorrelationRepair::higham();
+        assert!(factorise_correlation(&array![[1.0, 0.5], [0.4, 1.0]], repair).is_err());
+        assert!(factorise_correlation(&array![[1.0, 1.5], [1.5, 1.0]], repair).is_err());
+        assert!(factorise_correlation(&array![[2.0, 0.5], [0.5, 1.0]], repair).is_err());
+    }
+}
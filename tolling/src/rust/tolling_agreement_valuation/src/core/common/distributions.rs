@@ -0,0 +1,211 @@
+use num_traits::{Float, FromPrimitive};
+
+/// Returns the smallest `k` such that `P(N <= k) >= u` for `N ~ Poisson(mean)`.
+///
+/// Inverting the cumulative distribution function consumes exactly one uniform
+/// random number per draw, whatever the value of `mean`. Bumping the jump intensity
+/// therefore changes the number of jumps but never shifts the random numbers used by
+/// the rest of the path, which is required for common random numbers.
+///
+/// # Arguments
+///
+/// * `u`: A uniform random number in `[0, 1)`.
+/// * `mean`: The mean of the Poisson distribution. Must be non-negative.
+pub fn poisson_inverse_cdf(u: f64, mean: f64) -> u64 {
+    let mut k = 0;
+    let mut probability = (-mean).exp();
+    let mut cumulative = probability;
+
+    // Sequential search from zero. This is cheap because the mean is the expected
+    // number of jumps in a single small time step.
+    while u > cumulative && probability > 0.0 {
+        k += 1;
+        probability *= mean / k as f64;
+        cumulative += probability;
+    }
+    k
+}
+
+/// Returns the quantile function (inverse CDF) of the standard normal distribution.
+///
+/// Uses the rational approximation of Acklam, which has a relative error below
+/// `1.15e-9` on the whole domain. This is used to map uniform (quasi-)random numbers
+/// to normal draws.
+///
+/// # Arguments
+///
+/// * `p`: A probability in the open interval `(0, 1)`.
+pub fn normal_inverse_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p < P_LOW {
+        // Lower tail.
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        // Central region.
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        // Upper tail, by symmetry.
+        -normal_inverse_cdf(1.0 - p)
+    }
+}
+
+/// Returns the cumulative distribution function of the standard normal distribution.
+///
+/// Uses `Phi(x) = 0.5 * erfc(-x / sqrt(2))` with the Chebyshev approximation of the
+/// complementary error function from Numerical Recipes, which has a relative error
+/// below `1.2e-7` everywhere. Only elementary operations are used, so the function is
+/// generic over `T` and can be differentiated with `AADVar`.
+///
+/// # Arguments
+///
+/// * `x`: The point at which the CDF is evaluated.
+pub fn normal_cdf<T: Float + FromPrimitive>(x: T) -> T {
+    const COEFFICIENTS: [f64; 10] = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ];
+    let c = |v: f64| T::from_f64(v).unwrap();
+
+    let z = -x / c(std::f64::consts::SQRT_2);
+    let abs_z = z.abs();
+    let t = T::one() / (T::one() + c(0.5) * abs_z);
+
+    // Horner evaluation of the polynomial in `t`, starting from the highest order.
+    let polynomial = COEFFICIENTS[..9]
+        .iter()
+        .rev()
+        .fold(c(COEFFICIENTS[9]), |acc, &coefficient| {
+            c(coefficient) + t * acc
+        });
+    let erfc_abs = t * (-abs_z * abs_z + polynomial).exp();
+
+    let erfc = if z >= T::zero() {
+        erfc_abs
+    } else {
+        c(2.0) - erfc_abs
+    };
+    c(0.5) * erfc
+}
+
+/// Returns the smallest `k` such that `P(N <= k) >= u` for `N ~ Binomial(n, p)`.
+///
+/// Like `poisson_inverse_cdf`, this consumes exactly one uniform random number per draw.
+///
+/// # Arguments
+///
+/// * `u`: A uniform random number in `[0, 1)`.
+/// * `n`: The number of trials.
+/// * `p`: The success probability of a trial, in `[0, 1]`.
+pub fn binomial_inverse_cdf(u: f64, n: u64, p: f64) -> u64 {
+    if p >= 1.0 {
+        return n;
+    }
+    let mut k = 0;
+    let mut probability = (1.0 - p).powi(n as i32);
+    let mut cumulative = probability;
+    let odds = p / (1.0 - p);
+    while u > cumulative && k < n {
+        probability *= odds * (n - k) as f64 / (k + 1) as f64;
+        k += 1;
+        cumulative += probability;
+    }
+    k
+}
+
+/// Returns the quantile function (inverse CDF) of the Erlang distribution, the sum of
+/// `shape` independent standard exponential variables.
+///
+/// For a single exponential the quantile `-ln(1 - u)` is exact. Otherwise the CDF
+/// `P(X <= x) = 1 - exp(-x) * sum_{k < shape} x^k / k!` is inverted by Newton's method,
+/// safeguarded by bisection, to a relative accuracy of `1e-12`.
+///
+/// # Arguments
+///
+/// * `u`: A uniform random number in `[0, 1)`.
+/// * `shape`: The number of exponential variables. Zero gives zero.
+pub fn erlang_inverse_cdf(u: f64, shape: u64) -> f64 {
+    match shape {
+        0 => return 0.0,
+        1 => return -(-u).ln_1p(),
+        _ => {}
+    }
+    // Returns the CDF and the density at `x`; the density is the last term of the sum.
+    let cdf_and_density = |x: f64| {
+        let mut term = (-x).exp();
+        let mut sum = term;
+        for k in 1..shape {
+            term *= x / k as f64;
+            sum += term;
+        }
+        (1.0 - sum, term)
+    };
+
+    let (mut lower, mut upper) = (0.0, shape as f64);
+    while cdf_and_density(upper).0 < u {
+        lower = upper;
+        upper *= 2.0;
+    }
+    let mut x = 0.5 * (lower + upper);
+    for _ in 0..100 {
+        let (cdf, density) = cdf_and_density(x);
+        if cdf < u {
+            lower = x;
+        } else {
+            upper = x;
+        }
+        let newton = x - (cdf - u) / density;
+        let next = if newton > lower && newton < upper {
+            newton
+        } else {
+            0.5 * (lower + upper)
+        };
+        if (next - x).abs() <= 1e-12 * x {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
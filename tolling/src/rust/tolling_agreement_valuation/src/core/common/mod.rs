@@ -1 +1,10 @@
+pub mod brownian_bridge;
 pub mod cholesky;
+pub mod correlation;
+pub mod distributions;
+pub mod parallel;
+pub mod random;
+pub mod sobol;
+pub mod sobol_direction_numbers;
+pub mod statistics;
+pub mod time_grid;
@@ -0,0 +1,43 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+/// Processes `0..num_items` in parallel in fixed chunks and passes the chunk results to
+/// `consume` in chunk order.
+///
+/// Chunks are `chunk_size` consecutive items (the last one may be shorter). Fixing the
+/// chunk layout, instead of letting rayon decide how to split the work, and consuming the
+/// results in order keeps any reduction done by `consume` independent of the number of
+/// threads. Chunks are run in waves of a few chunks per thread, so at most one wave of
+/// results is held in memory whatever the number of items.
+///
+/// # Arguments
+///
+/// * `num_items`: The number of items to process.
+/// * `chunk_size`: The number of consecutive items processed by one task.
+/// * `map_chunk`: Processes the items in a range and returns the result of the chunk.
+/// * `consume`: Receives the results of all chunks, in chunk order.
+pub fn map_chunks_in_order<R: Send>(
+    num_items: usize,
+    chunk_size: usize,
+    map_chunk: impl Fn(Range<usize>) -> R + Sync,
+    mut consume: impl FnMut(R),
+) {
+    let num_chunks = num_items.div_ceil(chunk_size);
+    let chunks_per_wave = 4 * rayon::current_num_threads();
+
+    let mut wave_start = 0;
+    while wave_start < num_chunks {
+        let wave_end = (wave_start + chunks_per_wave).min(num_chunks);
+        let results: Vec<R> = (wave_start..wave_end)
+            .into_par_iter()
+            .map(|chunk_idx| {
+                let start = chunk_idx * chunk_size;
+                let end = (start + chunk_size).min(num_items);
+                map_chunk(start..end)
+            })
+            .collect();
+        results.into_iter().for_each(&mut consume);
+        wave_start = wave_end;
+    }
+}
@@ -0,0 +1,98 @@
+use ndarray::Array2;
+use ndarray_rand::RandomExt;
+use ndarray_rand::rand::{self, Rng, SeedableRng};
+use ndarray_rand::rand_distr::{Open01, StandardNormal};
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator used for a single Monte Carlo path.
+///
+/// ChaCha is a counter-based generator: a seed selects the key and the stream id
+/// selects an independent, non-overlapping sequence. This lets every path own its
+/// own stream without any shared state between threads.
+pub type PathRng = ChaCha8Rng;
+
+/// Creates the random number generator for the path with index `path_idx`.
+///
+/// The generator depends only on `seed` and `path_idx`, so path `i` draws exactly
+/// the same numbers regardless of the number of rayon threads or the order in which
+/// paths are scheduled.
+///
+/// # Arguments
+///
+/// * `seed`: The master seed of the simulation run.
+/// * `path_idx`: The index of the Monte Carlo path, used as the stream id.
+pub fn path_rng(seed: u64, path_idx: usize) -> PathRng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(path_idx as u64);
+    rng
+}
+
+/// Draws a fresh master seed from the operating system's entropy source.
+///
+/// Used when the caller does not provide an explicit seed, in which case the run
+/// is not reproducible.
+pub fn entropy_seed() -> u64 {
+    rand::rng().random()
+}
+
+/// The random numbers consumed by a single Monte Carlo path.
+///
+/// Holding the draws explicitly, rather than handing an RNG to the processes, makes the
+/// number of draws per path independent of the model parameters. The same path index
+/// therefore sees the same shocks in the base valuation, in the AAD pass and in any
+/// bumped revaluation.
+pub struct PathNoise {
+    /// Independent standard normal draws of shape `(num_normals, num_points)`.
+    pub normals: Array2<f64>,
+    /// Independent uniform draws in `(0, 1)` of shape `(num_uniforms, num_points)`. The
+    /// interval is open so that the mirrored draws `1 - u` of antithetic paths stay valid
+    /// inputs of the inverse CDFs of the processes.
+    pub uniforms: Array2<f64>,
+}
+
+/// A regenerable source of random numbers, keyed by path index.
+///
+/// The source only stores the master seed. The noise of path `i` is regenerated on
+/// demand from its own random stream, so passing the same `NoiseSource` to different
+/// calculations gives them common random numbers without keeping the full noise cube
+/// in memory.
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseSource {
+    seed: u64,
+}
+
+impl NoiseSource {
+    /// Constructs a new `NoiseSource` from a master seed.
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the master seed of the source.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generates the noise of the path with index `path_idx`.
+    ///
+    /// The normal draws are generated first, followed by the uniform draws, so the
+    /// normals of a path do not depend on how many uniforms are requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `path_idx`: The index of the Monte Carlo path.
+    /// * `num_normals`: The number of rows of standard normal draws.
+    /// * `num_uniforms`: The number of rows of uniform draws.
+    /// * `num_points`: The number of time steps in the path.
+    pub fn path_noise(
+        &self,
+        path_idx: usize,
+        num_normals: usize,
+        num_uniforms: usize,
+        num_points: usize,
+    ) -> PathNoise {
+        let mut rng = path_rng(self.seed, path_idx);
+        let normals = Array2::random_using((num_normals, num_points), StandardNormal, &mut rng);
+        let uniforms = Array2::random_using((num_uniforms, num_points), Open01, &mut rng);
+        PathNoise { normals, uniforms }
+    }
+}
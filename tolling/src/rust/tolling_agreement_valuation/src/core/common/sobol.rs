@@ -0,0 +1,354 @@
+use anyhow::{Result, bail};
+use ndarray_rand::rand::Rng;
+
+use crate::core::common::sobol_direction_numbers::INITIAL_DIRECTION_NUMBERS;
+
+/// The maximum dimension of the points: the first dimension plus one per entry of
+/// the table of initial direction numbers.
+pub const MAX_DIMENSIONS: usize = INITIAL_DIRECTION_NUMBERS.len() + 1;
+
+/// The maximum number of bits (and therefore direction numbers) per coordinate.
+const MAX_BITS: usize = 32;
+
+/// A (optionally scrambled) Sobol low-discrepancy sequence in base 2.
+///
+/// Points are generated in Gray code order, which allows random access to the point
+/// with any index. This is required to generate paths in parallel.
+///
+/// Scrambling uses a random linear matrix scramble followed by a random digital shift
+/// (Matoušek). Every scrambled copy of the sequence is again a low-discrepancy
+/// sequence, and each point is uniformly distributed on the unit cube, so independent
+/// scrambles give unbiased estimates whose spread measures the integration error.
+#[derive(Clone)]
+pub struct SobolSequence {
+    num_dims: usize,
+    num_bits: usize,
+    /// The direction numbers, `num_bits` consecutive entries per dimension.
+    directions: Vec<u32>,
+    /// The digital shift of each dimension.
+    shifts: Vec<u32>,
+}
+
+impl SobolSequence {
+    /// Constructs the unscrambled Sobol sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_dims`: The dimension of each point.
+    /// * `max_points`: The number of points that will be requested. Only as many
+    ///   direction numbers as needed to index these points are generated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `num_dims` exceeds `MAX_DIMENSIONS` or `max_points` exceeds
+    /// 2^32.
+    pub fn new(num_dims: usize, max_points: usize) -> Result<Self> {
+        if num_dims > MAX_DIMENSIONS {
+            bail!(
+                "The Sobol sequence supports at most {} dimensions, but {} were requested.",
+                MAX_DIMENSIONS,
+                num_dims
+            );
+        }
+        let num_bits = (usize::BITS - max_points.saturating_sub(1).leading_zeros()).max(1) as usize;
+        if num_bits > MAX_BITS {
+            bail!(
+                "The Sobol sequence supports at most 2^{} points, but {} were requested.",
+                MAX_BITS,
+                max_points
+            );
+        }
+
+        let mut directions = vec![0u32; num_dims * num_bits];
+        let polynomials = primitive_polynomials(num_dims.saturating_sub(1));
+
+        for dim in 0..num_dims {
+            let v = &mut directions[dim * num_bits..(dim + 1) * num_bits];
+            if dim == 0 {
+                // The first dimension is the van der Corput sequence.
+                for (k, v_k) in v.iter_mut().enumerate() {
+                    *v_k = 1 << (31 - k);
+                }
+                continue;
+            }
+
+            let polynomial = polynomials[dim - 1];
+            let degree = (31 - polynomial.leading_zeros()) as usize;
+            let mut m = vec![0u32; num_bits.max(degree)];
+            m[..degree].copy_from_slice(INITIAL_DIRECTION_NUMBERS[dim - 1]);
+
+            // Recurrence m_k = 2 a_1 m_{k-1} ^ 4 a_2 m_{k-2} ^ ... ^ 2^s m_{k-s} ^ m_{k-s}
+            for k in degree..num_bits {
+                let mut value = m[k - degree] ^ (m[k - degree] << degree);
+                for i in 1..degree {
+                    if (polynomial >> (degree - i)) & 1 == 1 {
+                        value ^= m[k - i] << i;
+                    }
+                }
+                m[k] = value;
+            }
+
+            for (k, v_k) in v.iter_mut().enumerate() {
+                *v_k = m[k] << (31 - k);
+            }
+        }
+
+        Ok(SobolSequence {
+            num_dims,
+            num_bits,
+            directions,
+            shifts: vec![0; num_dims],
+        })
+    }
+
+    /// Returns an independently scrambled copy of the sequence.
+    ///
+    /// Each dimension gets its own random lower triangular binary matrix with unit
+    /// diagonal and its own random digital shift.
+    pub fn scrambled<R: Rng + ?Sized>(&self, rng: &mut R) -> Self {
+        let mut scrambled = self.clone();
+        let mut rows = [0u32; MAX_BITS];
+
+        for dim in 0..self.num_dims {
+            // Row `j` of the matrix acts on digit `j` (0 = most significant). Its
+            // diagonal entry is one and the entries for less significant digits are zero.
+            for (j, row) in rows.iter_mut().enumerate() {
+                let diagonal = 1u32 << (31 - j);
+                let upper = !((diagonal - 1) | diagonal);
+                *row = (rng.random::<u32>() & upper) | diagonal;
+            }
+
+            let v = &mut scrambled.directions[dim * self.num_bits..(dim + 1) * self.num_bits];
+            for v_k in v.iter_mut() {
+                let mut value = 0u32;
+                for (j, row) in rows.iter().enumerate() {
+                    if (row & *v_k).count_ones() % 2 == 1 {
+                        value |= 1 << (31 - j);
+                    }
+                }
+                *v_k = value;
+            }
+            scrambled.shifts[dim] = rng.random();
+        }
+        scrambled
+    }
+
+    /// Returns the dimension of the points.
+    pub fn num_dims(&self) -> usize {
+        self.num_dims
+    }
+
+    /// Writes the point with the given index into `out`.
+    ///
+    /// The coordinates lie strictly inside the unit interval, so they can be passed to
+    /// inverse cumulative distribution functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than `num_dims` or if `index` exceeds the number of
+    /// points the sequence was constructed for (rounded up to a power of two).
+    pub fn point(&self, index: usize, out: &mut [f64]) {
+        assert!(index < 1 << self.num_bits, "Sobol point index out of range");
+        let gray = index ^ (index >> 1);
+        let scale = 1.0 / (1u64 << 32) as f64;
+
+        for (dim, x) in out[..self.num_dims].iter_mut().enumerate() {
+            let v = &self.directions[dim * self.num_bits..(dim + 1) * self.num_bits];
+            let mut value = self.shifts[dim];
+            for (k, v_k) in v.iter().enumerate() {
+                if (gray >> k) & 1 == 1 {
+                    value ^= v_k;
+                }
+            }
+            // Centre the point in its cell of width 2^-32 to stay away from 0 and 1.
+            *x = (value as f64 + 0.5) * scale;
+        }
+    }
+}
+
+/// Returns the first `count` primitive polynomials over GF(2), ordered by degree and
+/// then by their binary representation.
+///
+/// A polynomial `x^s + a_1 x^(s-1) + ... + a_(s-1) x + 1` is represented by the integer
+/// whose bit `i` holds the coefficient of `x^i`.
+fn primitive_polynomials(count: usize) -> Vec<u32> {
+    let mut polynomials = Vec::with_capacity(count);
+    let mut degree = 1;
+    while polynomials.len() < count {
+        let order = (1u64 << degree) - 1;
+        let prime_factors = prime_factors(order);
+        // Candidates have the leading and the constant coefficient set.
+        for middle in 0..(1u32 << (degree - 1)).max(1) {
+            if polynomials.len() == count {
+                break;
+            }
+            let polynomial = (1 << degree) | (middle << 1) | 1;
+            if is_primitive(polynomial, degree, order, &prime_factors) {
+                polynomials.push(polynomial);
+            }
+        }
+        degree += 1;
+    }
+    polynomials
+}
+
+/// Checks whether `x` has multiplicative order `2^degree - 1` modulo `polynomial`,
+/// which holds exactly for primitive polynomials.
+fn is_primitive(polynomial: u32, degree: u32, order: u64, prime_factors: &[u64]) -> bool {
+    if pow_x_mod(order, polynomial, degree) != 1 {
+        return false;
+    }
+    prime_factors
+        .iter()
+        .all(|&q| pow_x_mod(order / q, polynomial, degree) != 1)
+}
+
+/// Computes `x^exponent mod polynomial` over GF(2).
+fn pow_x_mod(mut exponent: u64, polynomial: u32, degree: u32) -> u32 {
+    let mul_mod = |a: u32, b: u32| -> u32 {
+        let mut product: u64 = 0;
+        for i in 0..degree {
+            if (b >> i) & 1 == 1 {
+                product ^= (a as u64) << i;
+            }
+        }
+        for i in (degree..2 * degree).rev() {
+            if (product >> i) & 1 == 1 {
+                product ^= (polynomial as u64) << (i - degree);
+            }
+        }
+        product as u32
+    };
+
+    let mut result = 1u32;
+    let mut base = if degree == 1 { 0b10 ^ polynomial } else { 0b10 };
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base);
+        }
+        base = mul_mod(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Returns the distinct prime factors of `n` by trial division.
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            factors.push(p);
+            while n.is_multiple_of(p) {
+                n /= p;
+            }
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitive_polynomials_match_known_table() {
+        // Degrees 1 to 5 in the usual binary representation.
+        let expected = [
+            0b11, 0b111, 0b1011, 0b1101, 0b10011, 0b11001, 0b100101, 0b101001, 0b101111, 0b110111,
+            0b111011, 0b111101,
+        ];
+        assert_eq!(primitive_polynomials(expected.len()), expected);
+    }
+
+    #[test]
+    fn test_first_points_are_stratified() {
+        // Every block of 2^k points of a Sobol sequence puts exactly one point into each
+        // interval [j / 2^k, (j + 1) / 2^k) of every one-dimensional projection.
+        let num_points = 64;
+        let sequence = SobolSequence::new(20, num_points).unwrap();
+        let scrambled = sequence.scrambled(&mut crate::core::common::random::path_rng(1, 0));
+
+        for seq in [&sequence, &scrambled] {
+            let mut point = vec![0.0; seq.num_dims()];
+            let mut counts = vec![vec![0usize; num_points]; seq.num_dims()];
+            for i in 0..num_points {
+                seq.point(i, &mut point);
+                for (dim, x) in point.iter().enumerate() {
+                    counts[dim][(x * num_points as f64) as usize] += 1;
+                }
+            }
+            assert!(counts.iter().flatten().all(|&c| c == 1));
+        }
+    }
+
+    #[test]
+    fn test_initial_direction_numbers_fit_their_polynomials() {
+        let polynomials = primitive_polynomials(INITIAL_DIRECTION_NUMBERS.len());
+        for (initial, polynomial) in INITIAL_DIRECTION_NUMBERS.iter().zip(polynomials) {
+            assert_eq!(initial.len(), (31 - polynomial.leading_zeros()) as usize);
+            // Every m_k is odd and smaller than 2^k.
+            for (k, &m_k) in initial.iter().enumerate() {
+                assert!(m_k % 2 == 1 && m_k < 1 << (k + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_initial_direction_numbers_match_the_published_table() {
+        // Entries of dimensions 2, 7, 14, 20 and 37 of `new-joe-kuo-6.21201`.
+        assert_eq!(INITIAL_DIRECTION_NUMBERS[0], [1]);
+        assert_eq!(INITIAL_DIRECTION_NUMBERS[5], [1, 3, 5, 13]);
+        assert_eq!(INITIAL_DIRECTION_NUMBERS[12], [1, 3, 3, 9, 7, 49]);
+        assert_eq!(INITIAL_DIRECTION_NUMBERS[18], [1, 3, 7, 11, 23, 15, 103]);
+        assert_eq!(INITIAL_DIRECTION_NUMBERS[35], [1, 3, 3, 5, 3, 55, 33]);
+    }
+
+    #[test]
+    fn test_dimensions_are_equidistributed_in_pairs() {
+        // The first 2^10 points of every pair of dimensions put the same number of points
+        // into each box of a 2^a x 2^b partition of the unit square with a + b = 10 - T,
+        // i.e. every two-dimensional projection is a (T, 10, 2)-net. Random odd initial
+        // direction numbers give projections with t = 9.
+        const M: usize = 10;
+        const T: usize = 6;
+        let num_points = 1 << M;
+        let sequence = SobolSequence::new(MAX_DIMENSIONS, num_points).unwrap();
+        let mut point = vec![0.0; MAX_DIMENSIONS];
+        let points: Vec<Vec<f64>> = (0..num_points)
+            .map(|i| {
+                sequence.point(i, &mut point);
+                point.clone()
+            })
+            .collect();
+
+        for i in 0..MAX_DIMENSIONS {
+            for j in 0..i {
+                for a in 0..=M - T {
+                    let b = M - T - a;
+                    let mut counts = [0usize; 1 << (M - T)];
+                    for x in &points {
+                        let cell_i = (x[i] * (1 << a) as f64) as usize;
+                        let cell_j = (x[j] * (1 << b) as f64) as usize;
+                        counts[(cell_i << b) | cell_j] += 1;
+                    }
+                    assert!(
+                        counts.iter().all(|&c| c == 1 << T),
+                        "dimensions {i} and {j}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_more_dimensions_than_the_table_supports() {
+        assert!(SobolSequence::new(MAX_DIMENSIONS, 8).is_ok());
+        assert!(SobolSequence::new(MAX_DIMENSIONS + 1, 8).is_err());
+        assert!(SobolSequence::new(2, usize::MAX).is_err());
+    }
+}
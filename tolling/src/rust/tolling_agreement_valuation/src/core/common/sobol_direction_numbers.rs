@@ -0,0 +1,45 @@
+/// Initial direction numbers `m_1, ..., m_s` of every dimension after the first, in the
+/// order of the primitive polynomials generated by `sobol::primitive_polynomials`. The
+/// sequence supports one dimension more than the table has entries.
+///
+/// The entries are those of the `new-joe-kuo-6.21201` table of Joe and Kuo ("Constructing
+/// Sobol sequences with better two-dimensional projections", SIAM J. Sci. Comput. 30,
+/// 2008) for all primitive polynomials up to degree 7.
+pub const INITIAL_DIRECTION_NUMBERS: [&[u32]; 36] = [
+    &[1],
+    &[1, 3],
+    &[1, 3, 1],
+    &[1, 1, 1],
+    &[1, 1, 3, 3],
+    &[1, 3, 5, 13],
+    &[1, 1, 5, 5, 17],
+    &[1, 1, 5, 5, 5],
+    &[1, 1, 7, 11, 19],
+    &[1, 1, 5, 1, 1],
+    &[1, 1, 1, 3, 11],
+    &[1, 3, 5, 5, 31],
+    &[1, 3, 3, 9, 7, 49],
+    &[1, 1, 1, 15, 21, 21],
+    &[1, 3, 1, 13, 27, 49],
+    &[1, 1, 1, 15, 7, 5],
+    &[1, 3, 1, 15, 13, 25],
+    &[1, 1, 5, 5, 19, 61],
+    &[1, 3, 7, 11, 23, 15, 103],
+    &[1, 3, 7, 13, 13, 15, 69],
+    &[1, 1, 3, 13, 7, 35, 63],
+    &[1, 3, 5, 9, 1, 25, 53],
+    &[1, 3, 1, 13, 9, 35, 107],
+    &[1, 3, 1, 5, 27, 61, 31],
+    &[1, 1, 5, 11, 19, 41, 61],
+    &[1, 3, 5, 3, 3, 13, 69],
+    &[1, 1, 7, 13, 1, 19, 1],
+    &[1, 3, 7, 5, 13, 19, 59],
+    &[1, 1, 3, 9, 25, 29, 41],
+    &[1, 3, 5, 13, 23, 1, 55],
+    &[1, 3, 7, 3, 13, 59, 17],
+    &[1, 3, 1, 3, 5, 53, 69],
+    &[1, 1, 5, 5, 23, 33, 13],
+    &[1, 1, 7, 7, 1, 61, 123],
+    &[1, 1, 7, 9, 13, 61, 49],
+    &[1, 3, 3, 5, 3, 55, 33],
+];
@@ -0,0 +1,588 @@
+use std::collections::BTreeMap;
+
+use ndarray::{Array1, ArrayView1, Zip};
+
+/// Calculates the standard error of the mean of `values`, where the values are grouped
+/// into independent batches.
+///
+/// Values within a batch may be dependent (e.g. the two paths of an antithetic pair, or
+/// all paths of one scrambled quasi-random replicate), but batches must be independent.
+/// The estimator treats the batch totals as the independent observations:
+///
+/// `SE^2 = B / (B - 1) * sum_b (S_b - mean * n_b)^2 / N^2`
+///
+/// where `S_b` and `n_b` are the sum and size of batch `b`, `B` is the number of
+/// batches and `N` the number of values. With one value per batch this reduces to the
+/// usual `s / sqrt(N)`.
+///
+/// # Arguments
+///
+/// * `values`: The per path values, in path order.
+/// * `batch_of`: Maps a path index to its batch index in `0..num_batches`.
+/// * `num_batches`: The number of batches.
+///
+/// # Returns
+///
+/// The standard error, or `NaN` if fewer than two batches are available.
+pub fn batch_standard_error(
+    values: &[f64],
+    batch_of: impl Fn(usize) -> usize,
+    num_batches: usize,
+) -> f64 {
+    if num_batches < 2 || values.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut batch_sums = vec![0.0; num_batches];
+    let mut batch_sizes = vec![0usize; num_batches];
+    for (path_idx, value) in values.iter().enumerate() {
+        let batch = batch_of(path_idx);
+        batch_sums[batch] += value;
+        batch_sizes[batch] += 1;
+    }
+
+    let n = values.len() as f64;
+    let mean = batch_sums.iter().sum::<f64>() / n;
+    let sum_sq: f64 = batch_sums
+        .iter()
+        .zip(batch_sizes.iter())
+        .map(|(sum, &size)| (sum - mean * size as f64).powi(2))
+        .sum();
+
+    let b = num_batches as f64;
+    (b / (b - 1.0) * sum_sq).sqrt() / n
+}
+
+/// Returns the 97.5% quantile of Student's t distribution with `dof` degrees of freedom,
+/// i.e. the multiplier of the standard error in a two-sided 95% confidence interval.
+///
+/// The intervals are built from the independent batches of a run, of which there can be
+/// few (e.g. eight scrambled quasi-random replicates), so the t distribution is used
+/// instead of the normal one. One and two degrees of freedom are exact; above that the
+/// Cornish-Fisher expansion around the normal quantile is accurate to about `1e-4`.
+pub fn student_t_quantile_975(dof: usize) -> f64 {
+    const Z: f64 = 1.959_963_984_540_054;
+    match dof {
+        0 => f64::NAN,
+        1 => 12.706_204_736_174_7,
+        2 => 4.302_652_729_749_46,
+        _ => {
+            let nu = dof as f64;
+            let z3 = Z.powi(3);
+            let z5 = Z.powi(5);
+            let z7 = Z.powi(7);
+            let z9 = Z.powi(9);
+            Z + (z3 + Z) / (4.0 * nu)
+                + (5.0 * z5 + 16.0 * z3 + 3.0 * Z) / (96.0 * nu.powi(2))
+                + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * Z) / (384.0 * nu.powi(3))
+                + (79.0 * z9 + 776.0 * z7 + 1482.0 * z5 - 1920.0 * z3 - 945.0 * Z)
+                    / (92160.0 * nu.powi(4))
+        }
+    }
+}
+
+/// A two-sided confidence interval `[lower, upper]`.
+///
+/// `T` is `f64` for scalar estimates and `Array1<f64>` for per-bucket estimates such as
+/// the deltas, in which case the interval is element-wise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceInterval<T> {
+    /// The lower bound of the interval.
+    pub lower: T,
+    /// The upper bound of the interval.
+    pub upper: T,
+}
+
+impl ConfidenceInterval<f64> {
+    /// The 95% confidence interval of an estimate from `num_batches` independent batches.
+    pub fn from_standard_error(estimate: f64, standard_error: f64, num_batches: usize) -> Self {
+        let half_width = student_t_quantile_975(num_batches.saturating_sub(1)) * standard_error;
+        Self {
+            lower: estimate - half_width,
+            upper: estimate + half_width,
+        }
+    }
+}
+
+impl ConfidenceInterval<Array1<f64>> {
+    /// The element-wise 95% confidence interval of an estimate from `num_batches`
+    /// independent batches.
+    pub fn from_standard_error(
+        estimate: ArrayView1<f64>,
+        standard_error: ArrayView1<f64>,
+        num_batches: usize,
+    ) -> Self {
+        let half_width = &standard_error * student_t_quantile_975(num_batches.saturating_sub(1));
+        Self {
+            lower: &estimate - &half_width,
+            upper: &estimate + &half_width,
+        }
+    }
+}
+
+/// Describes how the samples of a run are grouped into independent batches.
+///
+/// The samples are split, in order, into streams of `stream_len` consecutive samples
+/// (the last stream may be shorter). Without interleaving every stream is a batch of its
+/// own; with `num_interleaved` batches the streams are dealt out to the batches in turn.
+/// This covers single paths, antithetic pairs and quasi-random replicates, and answers
+/// every question in constant time and memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLayout {
+    num_values: usize,
+    stream_len: usize,
+    num_interleaved: Option<usize>,
+}
+
+impl BatchLayout {
+    /// Creates the layout of `num_values` samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `num_values`: The total number of samples of the run.
+    /// * `stream_len`: The number of consecutive samples sharing a stream. At least one.
+    /// * `num_interleaved`: The number of batches the streams are dealt out to, or `None`
+    ///   if every stream is a batch.
+    pub fn new(num_values: usize, stream_len: usize, num_interleaved: Option<usize>) -> Self {
+        Self {
+            num_values,
+            stream_len: stream_len.max(1),
+            num_interleaved: num_interleaved.map(|n| n.max(1)),
+        }
+    }
+
+    /// The number of streams.
+    fn num_streams(&self) -> usize {
+        self.num_values.div_ceil(self.stream_len)
+    }
+
+    /// Returns the batch of sample `idx`.
+    pub fn batch_of(&self, idx: usize) -> usize {
+        let stream = idx / self.stream_len;
+        match self.num_interleaved {
+            Some(num_interleaved) => stream % num_interleaved,
+            None => stream,
+        }
+    }
+
+    /// Returns the number of non-empty batches.
+    pub fn num_batches(&self) -> usize {
+        let num_streams = self.num_streams();
+        match self.num_interleaved {
+            Some(num_interleaved) => num_interleaved.min(num_streams),
+            None => num_streams,
+        }
+    }
+
+    /// Returns the number of samples in batch `batch`.
+    pub fn batch_size(&self, batch: usize) -> usize {
+        let num_streams = self.num_streams();
+        let (step, num_batch_streams) = match self.num_interleaved {
+            Some(num_interleaved) if batch < num_streams => {
+                (num_interleaved, (num_streams - batch).div_ceil(num_interleaved))
+            }
+            None if batch < num_streams => (1, 1),
+            _ => return 0,
+        };
+        // Every stream is full except possibly the last one of the run.
+        let last_stream = batch + (num_batch_streams - 1) * step;
+        let last_stream_len = self.num_values - (num_streams - 1) * self.stream_len;
+        let last_len = if last_stream == num_streams - 1 {
+            last_stream_len
+        } else {
+            self.stream_len
+        };
+        (num_batch_streams - 1) * self.stream_len + last_len
+    }
+}
+
+/// Streaming first and second moments of vector-valued samples grouped into batches.
+///
+/// This computes the same mean and standard error as `batch_standard_error`, element by
+/// element, without keeping the samples. Samples are added one at a time with the batch
+/// they belong to; the partial sum of a batch is kept until all of its samples have
+/// arrived, and is then folded into the moments. Memory therefore grows with the number
+/// of incomplete batches, not with the number of samples.
+///
+/// Accumulators for disjoint sets of samples can be combined with `merge`, so the
+/// samples can be processed in parallel. The moments are stored relative to the running
+/// mean, which avoids the cancellation of the textbook `sum(x^2) - n * mean^2`. The
+/// result depends on the order of the merges, so callers that need reproducible results
+/// must merge in a fixed order.
+#[derive(Debug, Clone)]
+pub struct BatchMoments {
+    /// The batches of the samples passed to `add`, if samples are added one at a time.
+    layout: Option<BatchLayout>,
+    /// Partial sums and sample counts of the batches that are not complete yet.
+    open_batches: BTreeMap<usize, (Array1<f64>, usize)>,
+    /// The number of complete batches.
+    num_batches: usize,
+    /// The number of samples in the complete batches.
+    num_values: usize,
+    /// The mean of the samples in the complete batches.
+    mean: Array1<f64>,
+    /// `sum_b (S_b - mean * n_b)^2` over the complete batches.
+    sum_sq_deviations: Array1<f64>,
+    /// `sum_b (S_b - mean * n_b) * n_b` over the complete batches.
+    sum_size_deviations: Array1<f64>,
+    /// `sum_b n_b^2` over the complete batches.
+    sum_sq_sizes: f64,
+}
+
+impl BatchMoments {
+    /// Creates an empty accumulator for samples of length `dim`, to which complete batches
+    /// are added with `add_batch`.
+    pub fn new(dim: usize) -> Self {
+        Self {
+            layout: None,
+            open_batches: BTreeMap::new(),
+            num_batches: 0,
+            num_values: 0,
+            mean: Array1::zeros(dim),
+            sum_sq_deviations: Array1::zeros(dim),
+            sum_size_deviations: Array1::zeros(dim),
+            sum_sq_sizes: 0.0,
+        }
+    }
+
+    /// Creates an empty accumulator for samples of length `dim`, to which single samples
+    /// are added with `add`. `layout` tells which batch a sample belongs to and when a
+    /// batch is complete.
+    pub fn with_layout(dim: usize, layout: BatchLayout) -> Self {
+        Self {
+            layout: Some(layout),
+            ..Self::new(dim)
+        }
+    }
+
+    /// Adds the sample with index `idx` in the layout of the accumulator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the accumulator was created without a layout.
+    pub fn add(&mut self, idx: usize, value: ArrayView1<f64>) {
+        let layout = self.layout.expect("samples can only be added one at a time with a layout");
+        self.add_partial_batch(layout.batch_of(idx), value, 1);
+    }
+
+    /// Combines the samples of `other` into `self`.
+    ///
+    /// Both accumulators must have been created with the same layout and must hold
+    /// disjoint samples.
+    pub fn merge(&mut self, other: BatchMoments) {
+        self.merge_complete(
+            other.num_batches,
+            other.num_values,
+            &other.mean,
+            &other.sum_sq_deviations,
+            &other.sum_size_deviations,
+            other.sum_sq_sizes,
+        );
+        for (batch, (sum, count)) in other.open_batches {
+            self.add_partial_batch(batch, sum.view(), count);
+        }
+    }
+
+    /// Adds a complete batch of `batch_size` samples whose sum is `batch_sum`.
+    ///
+    /// Unlike `add`, this does not consult the batch sizes, which is useful when the
+    /// number of samples per batch is only known once a run has ended.
+    pub fn add_batch(&mut self, batch_sum: ArrayView1<f64>, batch_size: usize) {
+        if batch_size == 0 {
+            return;
+        }
+        let batch_mean = &batch_sum / batch_size as f64;
+        let zeros = Array1::zeros(batch_mean.raw_dim());
+        self.merge_complete(
+            1,
+            batch_size,
+            &batch_mean,
+            &zeros,
+            &zeros,
+            (batch_size as f64).powi(2),
+        );
+    }
+
+    /// The number of complete batches.
+    pub fn num_batches(&self) -> usize {
+        self.num_batches
+    }
+
+    /// The element-wise mean of the samples in the complete batches.
+    pub fn mean(&self) -> &Array1<f64> {
+        &self.mean
+    }
+
+    /// The element-wise standard error of `mean`, see `batch_standard_error`.
+    ///
+    /// Elements are `NaN` if fewer than two batches are complete.
+    pub fn standard_error(&self) -> Array1<f64> {
+        if self.num_batches < 2 {
+            return Array1::from_elem(self.mean.raw_dim(), f64::NAN);
+        }
+        let b = self.num_batches as f64;
+        let n = self.num_values as f64;
+        self.sum_sq_deviations
+            .mapv(|sum_sq| (b / (b - 1.0) * sum_sq).sqrt() / n)
+    }
+
+    /// The element-wise 95% confidence interval of `mean`.
+    pub fn confidence_interval(&self) -> ConfidenceInterval<Array1<f64>> {
+        ConfidenceInterval::<Array1<f64>>::from_standard_error(
+            self.mean.view(),
+            self.standard_error().view(),
+            self.num_batches,
+        )
+    }
+
+    /// Adds the sum of `count` samples of batch `batch`, closing the batch once all of
+    /// its samples have arrived.
+    fn add_partial_batch(&mut self, batch: usize, sum: ArrayView1<f64>, count: usize) {
+        let (batch_sum, batch_count) = self
+            .open_batches
+            .entry(batch)
+            .or_insert_with(|| (Array1::zeros(sum.raw_dim()), 0));
+        *batch_sum += &sum;
+        *batch_count += count;
+
+        let layout = self.layout.expect("partial batches can only be completed with a layout");
+        if *batch_count == layout.batch_size(batch) {
+            let (batch_sum, batch_count) = self.open_batches.remove(&batch).unwrap();
+            self.add_batch(batch_sum.view(), batch_count);
+        }
+    }
+
+    /// Combines the moments of a set of complete batches into `self`.
+    ///
+    /// Moving the reference of the deviations from a mean `m_a` to the combined mean `m`
+    /// uses, with `d = m_a - m`:
+    ///
+    /// - `sum (S_b - m * n_b)^2 = sum (S_b - m_a * n_b)^2 + 2 * d * sum (S_b - m_a * n_b) * n_b + d^2 * sum n_b^2`
+    /// - `sum (S_b - m * n_b) * n_b = sum (S_b - m_a * n_b) * n_b + d * sum n_b^2`
+    fn merge_complete(
+        &mut self,
+        num_batches: usize,
+        num_values: usize,
+        mean: &Array1<f64>,
+        sum_sq_deviations: &Array1<f64>,
+        sum_size_deviations: &Array1<f64>,
+        sum_sq_sizes: f64,
+    ) {
+        if num_batches == 0 {
+            return;
+        }
+        let total = (self.num_values + num_values) as f64;
+        let weight_self = self.num_values as f64 / total;
+        let weight_other = num_values as f64 / total;
+        let (self_sq_sizes, other_sq_sizes) = (self.sum_sq_sizes, sum_sq_sizes);
+
+        Zip::from(&mut self.mean)
+            .and(&mut self.sum_sq_deviations)
+            .and(&mut self.sum_size_deviations)
+            .and(mean)
+            .and(sum_sq_deviations)
+            .and(sum_size_deviations)
+            .for_each(|m_self, q_self, p_self, &m_other, &q_other, &p_other| {
+                let m = weight_self * *m_self + weight_other * m_other;
+                let d_self = *m_self - m;
+                let d_other = m_other - m;
+                *q_self = *q_self
+                    + 2.0 * d_self * *p_self
+                    + d_self * d_self * self_sq_sizes
+                    + q_other
+                    + 2.0 * d_other * p_other
+                    + d_other * d_other * other_sq_sizes;
+                *p_self = *p_self + d_self * self_sq_sizes + p_other + d_other * other_sq_sizes;
+                *m_self = m;
+            });
+
+        self.num_batches += num_batches;
+        self.num_values += num_values;
+        self.sum_sq_sizes += sum_sq_sizes;
+    }
+}
+
+/// A streaming estimate of a quantile using the P² algorithm of Jain and Chlamtac (1985).
+///
+/// The estimator keeps five markers: the minimum, the maximum, the target quantile and
+/// two quantiles half-way to the extremes. Each observation moves the marker positions,
+/// and marker heights are adjusted with a piecewise-parabolic interpolation. Memory and
+/// work per observation are constant, at the price of an approximation error that
+/// shrinks as observations accumulate. Until five observations have been seen the exact
+/// sample quantile is returned.
+#[derive(Debug, Clone)]
+pub struct StreamingQuantile {
+    probability: f64,
+    /// The number of observations so far.
+    count: usize,
+    /// Marker heights.
+    heights: [f64; 5],
+    /// Actual marker positions (zero-based ranks).
+    positions: [f64; 5],
+    /// Desired marker positions.
+    desired: [f64; 5],
+    /// Increments of the desired positions per observation.
+    increments: [f64; 5],
+}
+
+impl StreamingQuantile {
+    /// Creates an estimator of the quantile at `probability`, which must lie in `(0, 1)`.
+    pub fn new(probability: f64) -> Self {
+        let p = probability;
+        Self {
+            probability,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [0.0, 1.0, 2.0, 3.0, 4.0],
+            desired: [0.0, 2.0 * p, 4.0 * p, 2.0 + 2.0 * p, 4.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    /// The probability of the estimated quantile.
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// Adds an observation.
+    pub fn add(&mut self, x: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Find the cell containing `x`, extending the extremes if needed.
+        let cell = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (0..4).rfind(|&i| self.heights[i] <= x).unwrap_or(0)
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        // Move the three middle markers towards their desired positions.
+        for i in 1..4 {
+            let offset = self.desired[i] - self.positions[i];
+            let room_above = self.positions[i + 1] - self.positions[i];
+            let room_below = self.positions[i - 1] - self.positions[i];
+            if (offset >= 1.0 && room_above > 1.0) || (offset <= -1.0 && room_below < -1.0) {
+                let step = offset.signum();
+                let parabolic = self.parabolic(i, step);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, step)
+                    };
+                self.positions[i] += step;
+            }
+        }
+    }
+
+    /// The current estimate of the quantile, or `NaN` if there are no observations.
+    pub fn estimate(&self) -> f64 {
+        match self.count {
+            0 => f64::NAN,
+            1..=4 => {
+                // Exact sample quantile with linear interpolation between order statistics.
+                let mut sorted = self.heights[..self.count].to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let rank = self.probability * (self.count - 1) as f64;
+                let lower = rank.floor() as usize;
+                let upper = rank.ceil() as usize;
+                sorted[lower] + (rank - lower as f64) * (sorted[upper] - sorted[lower])
+            }
+            _ => self.heights[2],
+        }
+    }
+
+    /// The piecewise-parabolic prediction of the height of marker `i` moved by `step`.
+    fn parabolic(&self, i: usize, step: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + step) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - step) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    /// The linear prediction of the height of marker `i` moved by `step`.
+    fn linear(&self, i: usize, step: f64) -> f64 {
+        let j = if step > 0.0 { i + 1 } else { i - 1 };
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step * (q[j] - q[i]) / (n[j] - n[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr1;
+
+    #[test]
+    fn batch_moments_match_batch_standard_error() {
+        // Pairs of consecutive values form a batch, as for antithetic paths.
+        let values: Vec<f64> = (0..40).map(|i| ((i * 37 % 11) as f64).sin() * 3.0 + 5.0).collect();
+        let layout = BatchLayout::new(values.len(), 2, None);
+        let batch_of = |i: usize| i / 2;
+
+        // Accumulate the values in two interleaved halves and merge them.
+        let mut first = BatchMoments::with_layout(1, layout);
+        let mut second = BatchMoments::with_layout(1, layout);
+        for (i, &value) in values.iter().enumerate() {
+            let moments = if i % 3 == 0 { &mut first } else { &mut second };
+            moments.add(i, arr1(&[value]).view());
+        }
+        first.merge(second);
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let standard_error = batch_standard_error(&values, batch_of, 20);
+        assert_eq!(first.num_batches(), 20);
+        assert!((first.mean()[0] - mean).abs() < 1e-12);
+        assert!((first.standard_error()[0] - standard_error).abs() < 1e-12);
+    }
+
+    #[test]
+    fn batch_layout_counts_interleaved_streams() {
+        // 11 values in pairs, dealt out to 4 batches: streams 0..6 of which the last
+        // holds a single value.
+        let layout = BatchLayout::new(11, 2, Some(4));
+        let mut sizes = vec![0; layout.num_batches()];
+        for idx in 0..11 {
+            sizes[layout.batch_of(idx)] += 1;
+        }
+        let expected: Vec<usize> = (0..layout.num_batches()).map(|b| layout.batch_size(b)).collect();
+        assert_eq!(sizes, expected);
+        assert_eq!(sizes, vec![4, 3, 2, 2]);
+    }
+
+    #[test]
+    fn streaming_quantile_approximates_the_sample_quantile() {
+        // A deterministic, well-mixed sequence of uniform values in [0, 1).
+        let values: Vec<f64> = (0..10_000u64)
+            .map(|i| (i.wrapping_mul(2_654_435_761) % 10_007) as f64 / 10_007.0)
+            .collect();
+        for probability in [0.05, 0.5, 0.95] {
+            let mut quantile = StreamingQuantile::new(probability);
+            values.iter().for_each(|&x| quantile.add(x));
+            assert!((quantile.estimate() - probability).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn student_t_quantile_matches_tables() {
+        assert!((student_t_quantile_975(7) - 2.364_624).abs() < 1e-4);
+        assert!((student_t_quantile_975(30) - 2.042_272).abs() < 1e-4);
+    }
+}
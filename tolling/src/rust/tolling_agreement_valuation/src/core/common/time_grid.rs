@@ -0,0 +1,429 @@
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use ndarray::Array1;
+
+/// The number of seconds in a year of the ACT/365 Fixed day count.
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// The calendar time grid of a valuation: the delivery periods of the forward curves.
+///
+/// Every point of the grid is the start of a delivery period, given as a UTC timestamp.
+/// A period lasts until the next point, the last one until the end of the grid, so the
+/// grid can be quarter-hourly, half-hourly, hourly or of any other resolution. The
+/// grid converts the timestamps into year fractions from the valuation time (ACT/365
+/// Fixed), which the price processes use as their time axis, and groups the points into
+/// the days of a local time zone, which the profit calculator values and discounts.
+///
+/// Local days need not have 24 hourly points: in a zone with daylight saving time the
+/// day the clocks go forward has 23 and the day they go back 25 (or 92 and 100
+/// quarter-hours).
+///
+/// Every point also carries its local `Season`, which seasonal volatilities depend on.
+///
+/// Forward curves may be given at the resolution of the grid or with one point per
+/// local day, as is usual for gas; `align_curve` maps them onto the grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeGrid {
+    valuation_time: DateTime<Utc>,
+    time_zone: Tz,
+    timestamps: Vec<DateTime<Utc>>,
+    end: DateTime<Utc>,
+    /// The year fraction of every point from the valuation time.
+    times: Array1<f64>,
+    /// The length of every delivery period in hours.
+    durations: Array1<f64>,
+    /// The local calendar position of every point.
+    seasons: Vec<Season>,
+    /// The local date of every day of the grid.
+    days: Vec<NaiveDate>,
+    /// The index of the first point of every day, followed by the number of points.
+    day_starts: Vec<usize>,
+    /// The year fraction of the end of every day, i.e. of the following local midnight.
+    day_end_times: Vec<f64>,
+}
+
+impl TimeGrid {
+    /// Constructs a grid from the start of every delivery period.
+    ///
+    /// # Arguments
+    ///
+    /// * `valuation_time`: The time the year fractions are measured from.
+    /// * `timestamps`: The start of every delivery period, in increasing order.
+    /// * `end`: The end of the last delivery period.
+    /// * `time_zone`: The time zone whose local days group the points.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no points, if the points are not strictly
+    /// increasing, if the first point lies before the valuation time or if `end` does
+    /// not lie after the last point.
+    pub fn new(
+        valuation_time: DateTime<Utc>,
+        timestamps: Vec<DateTime<Utc>>,
+        end: DateTime<Utc>,
+        time_zone: Tz,
+    ) -> Result<Self> {
+        let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) else {
+            bail!("A time grid needs at least one point.");
+        };
+        if first < valuation_time {
+            bail!("The time grid starts at {first}, before the valuation time {valuation_time}.");
+        }
+        if timestamps.windows(2).any(|pair| pair[0] >= pair[1]) {
+            bail!("The points of a time grid must be strictly increasing.");
+        }
+        if end <= last {
+            bail!("The time grid ends at {end}, before its last point {last}.");
+        }
+
+        let year_fraction = |timestamp: DateTime<Utc>| {
+            (timestamp - valuation_time).num_milliseconds() as f64 / 1000.0 / SECONDS_PER_YEAR
+        };
+        let times = timestamps.iter().map(|&t| year_fraction(t)).collect();
+        let durations = timestamps
+            .iter()
+            .zip(timestamps.iter().skip(1).chain([&end]))
+            .map(|(start, next)| (*next - *start).num_milliseconds() as f64 / 3_600_000.0)
+            .collect();
+
+        let mut days = Vec::new();
+        let mut day_starts = Vec::new();
+        let mut seasons = Vec::with_capacity(timestamps.len());
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            let local = timestamp.with_timezone(&time_zone);
+            seasons.push(Season {
+                month: local.month0() as usize,
+                hour: local.hour() as usize,
+                weekend: matches!(local.weekday(), Weekday::Sat | Weekday::Sun),
+            });
+            let date = local.date_naive();
+            if days.last() != Some(&date) {
+                days.push(date);
+                day_starts.push(i);
+            }
+        }
+        day_starts.push(timestamps.len());
+        let day_end_times = days
+            .iter()
+            .map(|date| {
+                let next = date.succ_opt().expect("The date lies within the calendar.");
+                Ok(year_fraction(local_midnight(next, time_zone)?))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            valuation_time,
+            time_zone,
+            timestamps,
+            end,
+            times,
+            durations,
+            seasons,
+            days,
+            day_starts,
+            day_end_times,
+        })
+    }
+
+    /// Constructs an hourly grid covering `num_days` whole local days from `start_date`.
+    ///
+    /// The valuation time is the local midnight starting `valuation_date`. The points
+    /// are one hour apart in UTC, so days with a daylight saving time change have 23 or
+    /// 25 points.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `num_days` is zero or if `start_date` lies before
+    /// `valuation_date`.
+    pub fn hourly(
+        valuation_date: NaiveDate,
+        start_date: NaiveDate,
+        num_days: usize,
+        time_zone: Tz,
+    ) -> Result<Self> {
+        Self::regular(
+            valuation_date,
+            start_date,
+            num_days,
+            TimeDelta::hours(1),
+            time_zone,
+        )
+    }
+
+    /// Constructs a grid of delivery periods of length `period` covering `num_days`
+    /// whole local days from `start_date`, e.g. 15 minutes for quarter-hourly products.
+    ///
+    /// The valuation time is the local midnight starting `valuation_date`. The points are
+    /// `period` apart in UTC. If `period` does not divide a day, the last period is
+    /// shortened to end at the last local midnight.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `num_days` is zero, if `period` is not positive or if
+    /// `start_date` lies before `valuation_date`.
+    pub fn regular(
+        valuation_date: NaiveDate,
+        start_date: NaiveDate,
+        num_days: usize,
+        period: TimeDelta,
+        time_zone: Tz,
+    ) -> Result<Self> {
+        if num_days == 0 {
+            bail!("A time grid needs at least one day.");
+        }
+        if period <= TimeDelta::zero() {
+            bail!("The delivery periods of a time grid must have a positive length.");
+        }
+        let start = local_midnight(start_date, time_zone)?;
+        let end_date = start_date
+            .checked_add_days(chrono::Days::new(num_days as u64))
+            .ok_or_else(|| anyhow::anyhow!("The time grid ends outside the calendar."))?;
+        let end = local_midnight(end_date, time_zone)?;
+        let timestamps = (0..)
+            .map(|k| start + period * k)
+            .take_while(|&timestamp| timestamp < end)
+            .collect();
+        Self::new(
+            local_midnight(valuation_date, time_zone)?,
+            timestamps,
+            end,
+            time_zone,
+        )
+    }
+
+    /// Returns the number of points.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Returns `true` if the grid has no points, which a constructed grid never has.
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Returns the time the year fractions are measured from.
+    pub fn valuation_time(&self) -> DateTime<Utc> {
+        self.valuation_time
+    }
+
+    /// Returns the time zone of the local days.
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone
+    }
+
+    /// Returns the start of every delivery period.
+    pub fn timestamps(&self) -> &[DateTime<Utc>] {
+        &self.timestamps
+    }
+
+    /// Returns the end of the last delivery period.
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    /// Returns the year fraction of every point from the valuation time.
+    pub fn times(&self) -> &Array1<f64> {
+        &self.times
+    }
+
+    /// Returns the length of the delivery period of point `i` in hours.
+    pub fn duration(&self, i: usize) -> f64 {
+        self.durations[i]
+    }
+
+    /// Returns the local calendar position of point `i`.
+    pub fn season(&self, i: usize) -> Season {
+        self.seasons[i]
+    }
+
+    /// Returns the year fraction between point `i` and its predecessor, or between the
+    /// valuation time and the first point for `i = 0`.
+    pub fn time_step(&self, i: usize) -> f64 {
+        if i == 0 {
+            self.times[0]
+        } else {
+            self.times[i] - self.times[i - 1]
+        }
+    }
+
+    /// Returns the number of local days with at least one point.
+    pub fn num_days(&self) -> usize {
+        self.days.len()
+    }
+
+    /// Returns the local date of every day.
+    pub fn days(&self) -> &[NaiveDate] {
+        &self.days
+    }
+
+    /// Returns the indices of the points of day `day`.
+    pub fn day_points(&self, day: usize) -> Range<usize> {
+        self.day_starts[day]..self.day_starts[day + 1]
+    }
+
+    /// Returns the index of the day point `i` belongs to.
+    pub fn day_of(&self, i: usize) -> usize {
+        self.day_starts.partition_point(|&start| start <= i) - 1
+    }
+
+    /// Returns the year fraction of the end of day `day`, at which its cash flow is
+    /// paid.
+    pub fn day_end_time(&self, day: usize) -> f64 {
+        self.day_end_times[day]
+    }
+
+    /// Returns the index of the curve point that applies to grid point `i`, for a curve
+    /// of `curve_len` points given either per grid point or per local day.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `curve_len` is neither the number of points nor the number of days.
+    pub fn curve_index(&self, curve_len: usize, i: usize) -> usize {
+        if curve_len == self.len() {
+            i
+        } else if curve_len == self.num_days() {
+            self.day_of(i)
+        } else {
+            panic!("A curve of {curve_len} points does not fit the time grid.")
+        }
+    }
+
+    /// Maps a forward curve onto the points of the grid.
+    ///
+    /// A curve with one point per grid point is returned as it is. A curve with one point
+    /// per local day, e.g. a daily gas curve under a quarter-hourly power curve, applies
+    /// its price to every point of the day.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve has neither one point per grid point nor one per
+    /// day.
+    pub fn align_curve<T: Clone>(&self, curve: &Array1<T>) -> Result<Array1<T>> {
+        let curve_len = curve.len();
+        if curve_len != self.len() && curve_len != self.num_days() {
+            bail!(
+                "A forward curve has {curve_len} points, but the time grid has {} points on \
+                 {} days.",
+                self.len(),
+                self.num_days()
+            );
+        }
+        Ok(Array1::from_shape_fn(self.len(), |i| {
+            curve[self.curve_index(curve_len, i)].clone()
+        }))
+    }
+}
+
+/// The position of a point in the local calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Season {
+    /// The local month, from 0 for January to 11 for December.
+    pub month: usize,
+    /// The local hour of the day, from 0 to 23.
+    pub hour: usize,
+    /// Whether the point falls on a Saturday or Sunday.
+    pub weekend: bool,
+}
+
+/// Returns the start of the local day `date` in `time_zone`.
+///
+/// If the clocks go back at midnight, the earlier of the two midnights is used. If they
+/// go forward across midnight, the day starts at the first valid time after it.
+fn local_midnight(date: NaiveDate, time_zone: Tz) -> Result<DateTime<Utc>> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    (0..=2)
+        .find_map(|hour| {
+            time_zone
+                .from_local_datetime(&(midnight + TimeDelta::hours(hour)))
+                .earliest()
+        })
+        .map(|local| local.with_timezone(&Utc))
+        .ok_or_else(|| anyhow::anyhow!("The start of {date} in {time_zone} does not exist."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn daylight_saving_days_have_23_and_25_hours() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let spring = TimeGrid::hourly(date(2025, 3, 29), date(2025, 3, 29), 3, berlin).unwrap();
+        let autumn = TimeGrid::hourly(date(2025, 10, 25), date(2025, 10, 25), 3, berlin).unwrap();
+
+        let hours = |grid: &TimeGrid| {
+            (0..grid.num_days())
+                .map(|day| grid.day_points(day).len())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hours(&spring), [24, 23, 24]);
+        assert_eq!(hours(&autumn), [24, 25, 24]);
+        assert_eq!(autumn.len(), 73);
+        assert_eq!(autumn.day_of(48), 1);
+        assert_eq!(autumn.day_of(49), 2);
+
+        // Year fractions count elapsed time, so the day of 25 hours is longer.
+        let day_length = |grid: &TimeGrid, day| {
+            (grid.day_end_time(day) - grid.day_end_time(day - 1)) * 365.0 * 24.0
+        };
+        assert!((day_length(&spring, 1) - 23.0).abs() < 1e-9);
+        assert!((day_length(&autumn, 1) - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn year_fractions_are_measured_from_the_valuation_time() {
+        let grid = TimeGrid::hourly(date(2025, 1, 1), date(2025, 7, 2), 1, Tz::UTC).unwrap();
+
+        assert!((grid.times()[0] - 182.0 / 365.0).abs() < 1e-12);
+        assert!((grid.time_step(0) - 182.0 / 365.0).abs() < 1e-12);
+        assert!((grid.time_step(1) - 1.0 / 8760.0).abs() < 1e-12);
+        assert!((grid.day_end_time(0) - 183.0 / 365.0).abs() < 1e-12);
+        assert!(TimeGrid::hourly(date(2025, 7, 2), date(2025, 1, 1), 1, Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn quarter_hourly_grids_have_quarter_hour_periods() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let quarter_hour = TimeDelta::minutes(15);
+        let grid = TimeGrid::regular(
+            date(2025, 3, 29),
+            date(2025, 3, 29),
+            2,
+            quarter_hour,
+            berlin,
+        )
+        .unwrap();
+
+        assert_eq!(grid.len(), 96 + 92);
+        assert_eq!(grid.day_points(1).len(), 92);
+        assert!(grid.durations.iter().all(|&hours| hours == 0.25));
+        assert!((grid.time_step(1) - 0.25 / 8760.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn daily_curves_are_aligned_to_the_grid() {
+        let grid = TimeGrid::regular(
+            date(2025, 1, 1),
+            date(2025, 1, 1),
+            2,
+            TimeDelta::minutes(30),
+            Tz::UTC,
+        )
+        .unwrap();
+        let daily = ndarray::array![20.0, 22.0];
+
+        let aligned = grid.align_curve(&daily).unwrap();
+        assert_eq!(aligned.len(), 96);
+        assert!(aligned.iter().take(48).all(|&price| price == 20.0));
+        assert!(aligned.iter().skip(48).all(|&price| price == 22.0));
+        assert_eq!(grid.align_curve(&aligned).unwrap(), aligned);
+        assert!(grid.align_curve(&ndarray::array![1.0, 2.0, 3.0]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod valuation;
+pub mod calibration;
 pub mod parameters;
 pub mod processes;
 pub mod services;
@@ -1,5 +1,9 @@
 pub mod model_parameters;
+pub mod physical_parameters;
 pub mod unit_parameters;
+pub mod volatility;
 
-pub use model_parameters::ModelParameters;
-pub use unit_parameters::UnitParameter;
+pub use model_parameters::{GasModel, ModelParameters, PowerModel};
+pub use physical_parameters::PhysicalParameters;
+pub use unit_parameters::{Dispatch, UnitParameter};
+pub use volatility::{SeasonalVolatility, Volatility};
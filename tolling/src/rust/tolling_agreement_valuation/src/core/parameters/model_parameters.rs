@@ -1,19 +1,56 @@
+use anyhow::{Result, bail};
+use ndarray::Array2;
+use num_traits::{Float, FromPrimitive};
 use serde::Deserialize;
 
+use crate::core::common::correlation::CorrelationRepair;
+use crate::core::parameters::volatility::Volatility;
+use crate::core::processes::geometric_brownian_motion_transformer::GeometricBrownianMotionTransformer;
+use crate::core::processes::heston_process_transformer::{
+    HestonProcessTransformer, VarianceScheme,
+};
+use crate::core::processes::jump_diffusion_process_transformer::{
+    JumpDiffusionProcessTransformer, OuScheme,
+};
+use crate::core::processes::jump_size::JumpSize;
+use crate::core::processes::price_process::PriceProcess;
+use crate::core::processes::regime_switching_process_transformer::{
+    Regime, RegimeSwitchingProcessTransformer,
+};
+use crate::core::processes::schwartz_smith_process_transformer::SchwartzSmithProcessTransformer;
+use crate::core::processes::shifted_process_transformer::ShiftedProcessTransformer;
+use crate::core::processes::spike_process_transformer::SpikeProcessTransformer;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::price_model::PriceModel;
+
 /// Contains the parameters for the stochastic models used in the simulation.
 ///
 /// This struct holds the parameters that define the behavior of the gas and power
 /// price processes. It is generic over the number type `T`, allowing it to be used
 /// with both standard floats (`f64`) for simulation and `AADVar` for greeks calculation.
+///
+/// The volatilities of gas and power may be constant or time-dependent, e.g. a seasonal
+/// shape by month, hour of the day and weekday (see `Volatility`).
+///
+/// The gas price follows a GBM by default, the two-factor model of Schwartz and Smith or
+/// a stochastic-volatility model (see `GasModel`).
+///
+/// The power price follows a mean-reverting jump diffusion by default, a two-factor
+/// model with a separate spike factor or a regime-switching model (see `PowerModel`).
+/// Its jumps are normal with mean `mu_j` and standard deviation `sigma_j` unless another
+/// `JumpSize` is set. A `power_shift` lets any power model produce negative prices.
+///
+/// Further assets, such as coal for dark spreads and carbon allowances for clean
+/// spreads, can be added with `with_asset` and correlated with `with_correlation`.
 #[derive(Debug, Deserialize, Clone)]
 pub struct ModelParameters<T> {
     /// Volatility of the gas price process (in percent).
-    pub sigma_g: T,
+    pub sigma_g: Volatility<T>,
     /// Volatility of the power price process (in percent).
-    pub sigma_p: T,
-    /// Mean reversion speed of the power price process (unitless).
+    pub sigma_p: Volatility<T>,
+    /// Mean reversion speed of the power price process (1 / year).
     pub kappa: T,
-    /// The average number of jumps per day in the power price (jumps / day).
+    /// The average number of jumps per year in the power price (jumps / year).
     pub lambda_j: T,
     /// The mean size of a jump in the power price (€ / MWh).
     pub mu_j: T,
@@ -21,10 +58,174 @@ pub struct ModelParameters<T> {
     pub sigma_j: T,
     /// The correlation between the gas and power price processes.
     pub rho: T,
+    /// Assets simulated besides gas and power, each as a GBM around its forward curve
+    /// with the given volatility, in the order they are simulated.
+    #[serde(default)]
+    pub additional_assets: Vec<(Asset, T)>,
+    /// Correlations between the Brownian shocks of pairs of assets other than gas and
+    /// power, whose correlation is `rho`. Pairs that are not listed are uncorrelated.
+    #[serde(default)]
+    pub correlations: Vec<(Asset, Asset, T)>,
+    /// How the correlation matrix is repaired if the correlations are not jointly
+    /// consistent. By default such a matrix is rejected.
+    #[serde(default)]
+    pub correlation_repair: CorrelationRepair,
+    /// The discretisation of the mean-reverting power factor. Exact by default.
+    #[serde(default)]
+    pub ou_scheme: OuScheme,
+    /// The model of the power price. A mean-reverting jump diffusion by default.
+    #[serde(default)]
+    pub power_model: PowerModel<T>,
+    /// The model of the gas price. A GBM with volatility `sigma_g` by default.
+    #[serde(default)]
+    pub gas_model: GasModel<T>,
+    /// The shift of the power price (€ / MWh), if any. The power model then describes the
+    /// price plus the shift, which makes e.g. the lognormal jump diffusion shifted
+    /// lognormal and lets the price fall to `-power_shift` (see
+    /// `ShiftedProcessTransformer`).
+    #[serde(default)]
+    pub power_shift: Option<T>,
+    /// The law of the power price jumps, replacing the normal law given by `mu_j` and
+    /// `sigma_j`, if any.
+    #[serde(default)]
+    pub jump_size: Option<JumpSize<T>>,
+}
+
+/// The stochastic model of the power price around its forward curve.
+///
+/// The jump models share the volatility `sigma_p`, the mean-reversion speed `kappa` and
+/// the jump parameters of `ModelParameters`, and differ in how the jumps revert. The
+/// regime-switching model uses `kappa` only.
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerModel<T> {
+    /// A single OU factor carrying both diffusion and jumps, so spikes revert at `kappa`
+    /// (see `JumpDiffusionProcessTransformer`).
+    #[default]
+    JumpDiffusion,
+    /// A diffusive base factor reverting at `kappa` and a separate spike factor carrying
+    /// the jumps, which reverts at `kappa_spike` (see `SpikeProcessTransformer`).
+    TwoFactorSpike {
+        /// Mean-reversion speed of the spike factor (1 / year).
+        kappa_spike: T,
+    },
+    /// A mean-reverting factor whose level and volatility switch between regimes, e.g.
+    /// a normal and a scarcity regime, without jumps (see
+    /// `RegimeSwitchingProcessTransformer`).
+    RegimeSwitching {
+        /// The level and volatility of every regime, the first of which holds at the
+        /// valuation time.
+        regimes: Vec<Regime<T>>,
+        /// The rates per year of switching from the regime of the row to the regime of
+        /// the column. Every row sums to zero.
+        transition_rates: Vec<Vec<f64>>,
+    },
+}
+
+impl<T> PowerModel<T> {
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> PowerModel<U> {
+        match self {
+            PowerModel::JumpDiffusion => PowerModel::JumpDiffusion,
+            PowerModel::TwoFactorSpike { kappa_spike } => PowerModel::TwoFactorSpike {
+                kappa_spike: f(kappa_spike),
+            },
+            PowerModel::RegimeSwitching {
+                regimes,
+                transition_rates,
+            } => PowerModel::RegimeSwitching {
+                regimes: regimes
+                    .iter()
+                    .map(|regime| Regime {
+                        level: f(&regime.level),
+                        sigma: f(&regime.sigma),
+                    })
+                    .collect(),
+                transition_rates: transition_rates.clone(),
+            },
+        }
+    }
+}
+
+/// The stochastic model of the gas price around its forward curve.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GasModel<T> {
+    /// A GBM with volatility `sigma_g`, which has a flat volatility term structure (see
+    /// `GeometricBrownianMotionTransformer`).
+    #[default]
+    Gbm,
+    /// A mean-reverting short-term factor and a persistent long-term factor, replacing
+    /// `sigma_g` (see `SchwartzSmithProcessTransformer`). The shocks of the long-term
+    /// factor are those correlated with power by `rho`.
+    SchwartzSmith {
+        /// Mean-reversion speed of the short-term factor (1 / year).
+        kappa_short: T,
+        /// Volatility of the short-term factor.
+        sigma_short: T,
+        /// Volatility of the long-term factor.
+        sigma_long: T,
+        /// The correlation between the shocks of the two factors.
+        rho_short_long: T,
+    },
+    /// A stochastic variance following a CIR process, replacing `sigma_g` (see
+    /// `HestonProcessTransformer`). The shocks of the price are those correlated with
+    /// power by `rho`.
+    Heston {
+        /// The variance at the valuation time.
+        v0: T,
+        /// Mean-reversion speed of the variance (1 / year).
+        kappa_v: T,
+        /// The long-run mean of the variance.
+        theta_v: T,
+        /// The volatility of the variance (vol-of-vol).
+        xi: T,
+        /// The correlation between the shocks of the price and of the variance.
+        rho_sv: T,
+        /// The discretisation of the variance. Full truncation by default.
+        #[serde(default)]
+        scheme: VarianceScheme,
+    },
+}
+
+impl<T> GasModel<T> {
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> GasModel<U> {
+        match self {
+            GasModel::Gbm => GasModel::Gbm,
+            GasModel::SchwartzSmith {
+                kappa_short,
+                sigma_short,
+                sigma_long,
+                rho_short_long,
+            } => GasModel::SchwartzSmith {
+                kappa_short: f(kappa_short),
+                sigma_short: f(sigma_short),
+                sigma_long: f(sigma_long),
+                rho_short_long: f(rho_short_long),
+            },
+            GasModel::Heston {
+                v0,
+                kappa_v,
+                theta_v,
+                xi,
+                rho_sv,
+                scheme,
+            } => GasModel::Heston {
+                v0: f(v0),
+                kappa_v: f(kappa_v),
+                theta_v: f(theta_v),
+                xi: f(xi),
+                rho_sv: f(rho_sv),
+                scheme: *scheme,
+            },
+        }
+    }
 }
 
 impl<T> ModelParameters<T> {
-    /// Constructs a new `ModelParameters` instance.
+    /// Constructs a new `ModelParameters` instance for gas and power only, with constant
+    /// volatilities.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         sigma_g: T,
@@ -36,13 +237,421 @@ impl<T> ModelParameters<T> {
         rho: T,
     ) -> Self {
         ModelParameters {
-            sigma_g,
-            sigma_p,
+            sigma_g: Volatility::Constant(sigma_g),
+            sigma_p: Volatility::Constant(sigma_p),
             kappa,
             lambda_j,
             mu_j,
             sigma_j,
             rho,
+            additional_assets: Vec::new(),
+            correlations: Vec::new(),
+            correlation_repair: CorrelationRepair::default(),
+            ou_scheme: OuScheme::default(),
+            power_model: PowerModel::default(),
+            gas_model: GasModel::default(),
+            power_shift: None,
+            jump_size: None,
+        }
+    }
+
+    /// Sets the volatility of the gas price, e.g. to a seasonal shape.
+    pub fn with_gas_volatility(mut self, sigma_g: Volatility<T>) -> Self {
+        self.sigma_g = sigma_g;
+        self
+    }
+
+    /// Sets the volatility of the power price, e.g. to a seasonal shape.
+    pub fn with_power_volatility(mut self, sigma_p: Volatility<T>) -> Self {
+        self.sigma_p = sigma_p;
+        self
+    }
+
+    /// Adds an asset simulated as a GBM with volatility `sigma`.
+    pub fn with_asset(mut self, asset: Asset, sigma: T) -> Self {
+        self.additional_assets.push((asset, sigma));
+        self
+    }
+
+    /// Sets the correlation between the Brownian shocks of `first` and `second`.
+    pub fn with_correlation(mut self, first: Asset, second: Asset, rho: T) -> Self {
+        self.correlations.push((first, second, rho));
+        self
+    }
+
+    /// Sets how the correlation matrix is repaired if it is not positive semi-definite.
+    pub fn with_correlation_repair(mut self, repair: CorrelationRepair) -> Self {
+        self.correlation_repair = repair;
+        self
+    }
+
+    /// Sets the discretisation of the mean-reverting power factor.
+    pub fn with_ou_scheme(mut self, scheme: OuScheme) -> Self {
+        self.ou_scheme = scheme;
+        self
+    }
+
+    /// Sets the model of the power price.
+    pub fn with_power_model(mut self, power_model: PowerModel<T>) -> Self {
+        self.power_model = power_model;
+        self
+    }
+
+    /// Shifts the power price by `shift`, so that it can fall to `-shift`.
+    pub fn with_power_shift(mut self, shift: T) -> Self {
+        self.power_shift = Some(shift);
+        self
+    }
+
+    /// Sets the model of the gas price.
+    pub fn with_gas_model(mut self, gas_model: GasModel<T>) -> Self {
+        self.gas_model = gas_model;
+        self
+    }
+
+    /// Sets the law of the power price jumps, replacing the normal law given by `mu_j`
+    /// and `sigma_j`.
+    pub fn with_jump_size(mut self, jump_size: JumpSize<T>) -> Self {
+        self.jump_size = Some(jump_size);
+        self
+    }
+
+    /// Returns the simulated assets, in simulation order: gas, power and then the
+    /// additional assets.
+    pub fn assets(&self) -> Vec<Asset> {
+        [Asset::Gas, Asset::Power]
+            .into_iter()
+            .chain(self.additional_assets.iter().map(|(asset, _)| *asset))
+            .collect()
+    }
+
+    /// Returns `true` if the gas or power volatility is seasonal, so that the simulation
+    /// depends on the calendar dates of the time grid.
+    pub fn is_seasonal(&self) -> bool {
+        self.sigma_g.is_seasonal() || self.sigma_p.is_seasonal()
+    }
+
+    /// Returns the volatility parameters of the gas model, in the order of
+    /// `gas_volatility_buckets`: the parameters of `sigma_g` for a GBM and the short- and
+    /// long-term volatilities for the Schwartz-Smith model. The stochastic variance of
+    /// the Heston model has no volatility parameters, see `gas_model_parameters`.
+    pub fn gas_volatility_parameters(&self) -> Vec<&T> {
+        match &self.gas_model {
+            GasModel::Gbm => self.sigma_g.parameters(),
+            GasModel::SchwartzSmith {
+                sigma_short,
+                sigma_long,
+                ..
+            } => vec![sigma_short, sigma_long],
+            GasModel::Heston { .. } => vec![],
+        }
+    }
+
+    /// Returns a name for every parameter of `gas_volatility_parameters`: the bucket
+    /// names of `sigma_g` for a GBM and `short_term` and `long_term` for the
+    /// Schwartz-Smith model.
+    pub fn gas_volatility_buckets(&self) -> Vec<String> {
+        match &self.gas_model {
+            GasModel::Gbm => self.sigma_g.bucket_names(),
+            GasModel::SchwartzSmith { .. } => {
+                vec!["short_term".to_string(), "long_term".to_string()]
+            }
+            GasModel::Heston { .. } => vec![],
+        }
+    }
+
+    /// Returns the parameters of the gas model whose sensitivities are reported besides
+    /// the vegas, in the order of `gas_model_parameter_names`: the initial variance, the
+    /// mean variance and the vol-of-vol of the Heston model, and none otherwise.
+    pub fn gas_model_parameters(&self) -> Vec<&T> {
+        match &self.gas_model {
+            GasModel::Gbm | GasModel::SchwartzSmith { .. } => vec![],
+            GasModel::Heston { v0, theta_v, xi, .. } => vec![v0, theta_v, xi],
+        }
+    }
+
+    /// Returns the name of every parameter of `gas_model_parameters`: `v0`, `theta_v` and
+    /// `xi` for the Heston model.
+    pub fn gas_model_parameter_names(&self) -> Vec<String> {
+        match &self.gas_model {
+            GasModel::Gbm | GasModel::SchwartzSmith { .. } => vec![],
+            GasModel::Heston { .. } => ["v0", "theta_v", "xi"].map(String::from).to_vec(),
+        }
+    }
+
+    /// Returns the volatility parameters of the power model, in the order of
+    /// `power_volatility_buckets`: the parameters of `sigma_p` for the jump models and
+    /// the volatility of every regime for the regime-switching model.
+    pub fn power_volatility_parameters(&self) -> Vec<&T> {
+        match &self.power_model {
+            PowerModel::JumpDiffusion | PowerModel::TwoFactorSpike { .. } => {
+                self.sigma_p.parameters()
+            }
+            PowerModel::RegimeSwitching { regimes, .. } => {
+                regimes.iter().map(|regime| &regime.sigma).collect()
+            }
+        }
+    }
+
+    /// Returns a name for every parameter of `power_volatility_parameters`: the bucket
+    /// names of `sigma_p` for the jump models and `regime_0`, `regime_1`, ... for the
+    /// regime-switching model.
+    pub fn power_volatility_buckets(&self) -> Vec<String> {
+        match &self.power_model {
+            PowerModel::JumpDiffusion | PowerModel::TwoFactorSpike { .. } => {
+                self.sigma_p.bucket_names()
+            }
+            PowerModel::RegimeSwitching { regimes, .. } => {
+                (0..regimes.len()).map(|i| format!("regime_{i}")).collect()
+            }
+        }
+    }
+
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> ModelParameters<U> {
+        ModelParameters {
+            sigma_g: self.sigma_g.map(&f),
+            sigma_p: self.sigma_p.map(&f),
+            kappa: f(&self.kappa),
+            lambda_j: f(&self.lambda_j),
+            mu_j: f(&self.mu_j),
+            sigma_j: f(&self.sigma_j),
+            rho: f(&self.rho),
+            additional_assets: self
+                .additional_assets
+                .iter()
+                .map(|(asset, sigma)| (*asset, f(sigma)))
+                .collect(),
+            correlations: self
+                .correlations
+                .iter()
+                .map(|(first, second, rho)| (*first, *second, f(rho)))
+                .collect(),
+            correlation_repair: self.correlation_repair,
+            ou_scheme: self.ou_scheme,
+            power_model: self.power_model.map(&f),
+            gas_model: self.gas_model.map(&f),
+            power_shift: self.power_shift.as_ref().map(&f),
+            jump_size: self.jump_size.as_ref().map(|jump_size| jump_size.map(&f)),
+        }
+    }
+}
+
+impl<T: Float + FromPrimitive + Send + Sync + 'static> ModelParameters<T> {
+    /// Returns the correlation matrix of the Brownian shocks of all assets, with rows
+    /// and columns in the order of `assets`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a correlation refers to an asset that is not simulated,
+    /// correlates an asset with itself or is given more than once.
+    pub fn correlation_matrix(&self) -> Result<Array2<T>> {
+        let assets = self.assets();
+        let mut correlation = Array2::eye(assets.len());
+        correlation[[0, 1]] = self.rho;
+        correlation[[1, 0]] = self.rho;
+
+        let mut is_set = Array2::from_elem(correlation.dim(), false);
+        is_set[[0, 1]] = true;
+        is_set[[1, 0]] = true;
+        for &(first, second, rho) in &self.correlations {
+            let index_of = |asset: Asset| match assets.iter().position(|&a| a == asset) {
+                Some(idx) => Ok(idx),
+                None => bail!(
+                    "A correlation refers to '{}', which is not simulated.",
+                    asset.name()
+                ),
+            };
+            let (i, j) = (index_of(first)?, index_of(second)?);
+            if i == j {
+                bail!("An asset cannot be correlated with itself.");
+            }
+            if is_set[[i, j]] {
+                bail!(
+                    "The correlation of '{}' and '{}' is given more than once.",
+                    first.name(),
+                    second.name()
+                );
+            }
+            correlation[[i, j]] = rho;
+            correlation[[j, i]] = rho;
+            is_set[[i, j]] = true;
+            is_set[[j, i]] = true;
+        }
+        Ok(correlation)
+    }
+
+    /// Returns the law of the power price jumps: `jump_size` if set, and normal jumps with
+    /// mean `mu_j` and standard deviation `sigma_j` otherwise.
+    pub fn jump_size_law(&self) -> JumpSize<T> {
+        self.jump_size.unwrap_or(JumpSize::Normal {
+            mu: self.mu_j,
+            sigma: self.sigma_j,
+        })
+    }
+
+    /// Returns the process of the gas price selected by `gas_model`, with the short-term
+    /// factor of the Schwartz-Smith model discretised by `ou_scheme`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the correlation of the Schwartz-Smith factors is outside
+    /// `[-1, 1]`, or if the Heston variance has a negative start, a mean-reversion speed,
+    /// mean or vol-of-vol that is not positive, or a correlation outside `[-1, 1]`. The
+    /// condition of the QE martingale correction depends on the steps and is checked
+    /// against the time grid, see `HestonProcessTransformer`.
+    pub fn gas_process(&self) -> Result<Box<dyn PriceProcess<T>>> {
+        Ok(match self.gas_model {
+            GasModel::Gbm => {
+                Box::new(GeometricBrownianMotionTransformer::new(self.sigma_g.clone()))
+            }
+            GasModel::SchwartzSmith {
+                kappa_short,
+                sigma_short,
+                sigma_long,
+                rho_short_long,
+            } => {
+                if rho_short_long.abs() > T::one() {
+                    bail!(
+                        "The correlation of the short- and long-term gas factors must be in \
+                         [-1, 1]."
+                    );
+                }
+                Box::new(
+                    SchwartzSmithProcessTransformer::new(
+                        kappa_short,
+                        sigma_short,
+                        sigma_long,
+                        rho_short_long,
+                    )
+                    .with_scheme(self.ou_scheme),
+                )
+            }
+            GasModel::Heston {
+                v0,
+                kappa_v,
+                theta_v,
+                xi,
+                rho_sv,
+                scheme,
+            } => {
+                if v0 < T::zero() {
+                    bail!("The initial variance of gas must not be negative.");
+                }
+                if kappa_v <= T::zero() || theta_v <= T::zero() || xi <= T::zero() {
+                    bail!(
+                        "The mean-reversion speed, the mean and the vol-of-vol of the gas \
+                         variance must be positive."
+                    );
+                }
+                if rho_sv.abs() > T::one() {
+                    bail!("The correlation of the gas price and its variance must be in [-1, 1].");
+                }
+                Box::new(
+                    HestonProcessTransformer::new(v0, kappa_v, theta_v, xi, rho_sv)
+                        .with_scheme(scheme),
+                )
+            }
+        })
+    }
+
+    /// Returns the process of the power price selected by `power_model`, discretised by
+    /// `ou_scheme` and shifted by `power_shift`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the law of the jumps is invalid (see `JumpSize::check`), if
+    /// the regimes are inconsistent (see `RegimeSwitchingProcessTransformer::new`) or if
+    /// the shift is negative.
+    pub fn power_process(&self) -> Result<Box<dyn PriceProcess<T>>> {
+        let process = self.unshifted_power_process()?;
+        Ok(match self.power_shift {
+            Some(shift) if shift < T::zero() => {
+                bail!("The shift of the power price must not be negative.")
+            }
+            Some(shift) => Box::new(ShiftedProcessTransformer::new(process, shift)),
+            None => process,
+        })
+    }
+
+    /// Returns the process of the power price selected by `power_model`, see
+    /// `power_process`.
+    fn unshifted_power_process(&self) -> Result<Box<dyn PriceProcess<T>>> {
+        self.jump_size_law().check()?;
+        Ok(match &self.power_model {
+            PowerModel::JumpDiffusion => Box::new(
+                JumpDiffusionProcessTransformer::new(
+                    self.sigma_p.clone(),
+                    self.kappa,
+                    self.lambda_j,
+                    self.jump_size_law(),
+                )
+                .with_scheme(self.ou_scheme),
+            ),
+            PowerModel::TwoFactorSpike { kappa_spike } => Box::new(
+                SpikeProcessTransformer::new(
+                    self.sigma_p.clone(),
+                    self.kappa,
+                    *kappa_spike,
+                    self.lambda_j,
+                    self.jump_size_law(),
+                )
+                .with_scheme(self.ou_scheme),
+            ),
+            PowerModel::RegimeSwitching {
+                regimes,
+                transition_rates,
+            } => {
+                let n = transition_rates.len();
+                if transition_rates.iter().any(|row| row.len() != n) {
+                    bail!("The transition rates of the regimes must form a square matrix.");
+                }
+                let rates = Array2::from_shape_fn((n, n), |(i, j)| transition_rates[i][j]);
+                Box::new(
+                    RegimeSwitchingProcessTransformer::new(regimes.clone(), rates, self.kappa)?
+                        .with_scheme(self.ou_scheme),
+                )
+            }
+        })
+    }
+
+    /// Returns the price model defined by the parameters: the process of `gas_process`
+    /// for gas, the process of `power_process` for power and a GBM for every additional
+    /// asset, correlated by `correlation_matrix` after the repair selected by
+    /// `correlation_repair`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gas or power process is invalid (see `gas_process` and
+    /// `power_process`) or if the
+    /// assets or correlations are inconsistent, see `correlation_matrix` and
+    /// `PriceModel::new`.
+    pub fn price_model(&self) -> Result<PriceModel<T>> {
+        self.price_model_with(self.gas_process()?, self.power_process()?)
+    }
+
+    /// Returns the price model of the parameters with the given processes of gas and
+    /// power, e.g. their real-world processes (see `PhysicalParameters`) or user-defined
+    /// ones, which can be valued with `calculate_pv_with_model`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the assets or correlations are inconsistent, see
+    /// `correlation_matrix` and `PriceModel::new`.
+    pub fn price_model_with(
+        &self,
+        gas: Box<dyn PriceProcess<T>>,
+        power: Box<dyn PriceProcess<T>>,
+    ) -> Result<PriceModel<T>> {
+        let mut processes: Vec<(Asset, Box<dyn PriceProcess<T>>)> =
+            vec![(Asset::Gas, gas), (Asset::Power, power)];
+        for &(asset, sigma) in &self.additional_assets {
+            processes.push((asset, Box::new(GeometricBrownianMotionTransformer::new(sigma))));
         }
+        PriceModel::new(
+            processes,
+            &self.correlation_matrix()?,
+            self.correlation_repair,
+        )
     }
 }
@@ -0,0 +1,159 @@
+use anyhow::{Result, bail};
+use ndarray::Array1;
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::model_parameters::{ModelParameters, PowerModel};
+use crate::core::processes::physical_power_process_transformer::PhysicalPowerProcessTransformer;
+use crate::core::simulator::price_model::PriceModel;
+
+/// The parameters of the real-world dynamics under the physical measure `P`, used to
+/// generate scenarios for risk limits and budgeting rather than for valuation.
+///
+/// Under `P` gas grows from today's price at its real drift `gas_drift` instead of
+/// following its forward curve, and the log power price reverts to the Long Run Marginal
+/// Cost `lrmc_curve` (see `PhysicalPowerProcessTransformer`). The volatilities,
+/// correlations and jumps are those of the wrapped `ModelParameters`; further assets
+/// keep following their forward curves.
+///
+/// The parameters are built by `ModelParameters::into_physical`, which consumes the
+/// risk-neutral parameters, and cannot be turned back. A set of parameters therefore
+/// belongs to one measure, and the valuation services, which take `ModelParameters`,
+/// cannot be run on real-world dynamics by mistake.
+#[derive(Debug, Clone)]
+pub struct PhysicalParameters<T> {
+    model_params: ModelParameters<T>,
+    gas_drift: T,
+    lrmc_curve: Array1<T>,
+}
+
+impl<T> ModelParameters<T> {
+    /// Turns the parameters into real-world parameters with the real drift `gas_drift`
+    /// of gas per year and the Long Run Marginal Cost `lrmc_curve` of power (€ / MWh),
+    /// given per point of the time grid or per local day.
+    pub fn into_physical(self, gas_drift: T, lrmc_curve: Array1<T>) -> PhysicalParameters<T> {
+        PhysicalParameters {
+            model_params: self,
+            gas_drift,
+            lrmc_curve,
+        }
+    }
+}
+
+impl<T> PhysicalParameters<T> {
+    /// Returns the real drift of gas per year.
+    pub fn gas_drift(&self) -> &T {
+        &self.gas_drift
+    }
+
+    /// Returns the Long Run Marginal Cost of power.
+    pub fn lrmc_curve(&self) -> &Array1<T> {
+        &self.lrmc_curve
+    }
+
+    /// Returns `true` if the gas or power volatility is seasonal.
+    pub fn is_seasonal(&self) -> bool {
+        self.model_params.is_seasonal()
+    }
+}
+
+impl<T: Float + FromPrimitive + Send + Sync + 'static> PhysicalParameters<T> {
+    /// Checks that the real-world dynamics of the power model are defined.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless power follows the jump diffusion without a shift, the only
+    /// power model with real-world dynamics.
+    pub fn check(&self) -> Result<()> {
+        if self.model_params.power_model != PowerModel::JumpDiffusion {
+            bail!("Real-world dynamics are only defined for the jump-diffusion power model.");
+        }
+        if self.model_params.power_shift.is_some() {
+            bail!("Real-world dynamics are not defined for a shifted power price.");
+        }
+        Ok(())
+    }
+
+    /// Returns the real-world price model on `time_grid`: the gas process of the
+    /// parameters, the power process reverting to the LRMC and a GBM for every
+    /// additional asset, correlated as under `Q`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the power model has no real-world dynamics (see `check`), if
+    /// the LRMC does not fit `time_grid` or is not positive, or if the parameters do not
+    /// define a valid price model (see `ModelParameters::price_model`).
+    pub fn price_model(&self, time_grid: &TimeGrid) -> Result<PriceModel<T>> {
+        self.check()?;
+        let params = &self.model_params;
+        params.jump_size_law().check()?;
+        let lrmc = time_grid
+            .align_curve(&self.lrmc_curve)
+            .map_err(|e| anyhow::anyhow!("The long run marginal cost does not fit: {}", e))?;
+        let power = PhysicalPowerProcessTransformer::new(
+            params.sigma_p.clone(),
+            params.kappa,
+            params.lambda_j,
+            params.jump_size_law(),
+            &lrmc,
+        )?
+        .with_scheme(params.ou_scheme);
+        params.price_model_with(params.gas_process()?, Box::new(power))
+    }
+
+    /// Returns the real-world mean `G_0 * exp(gas_drift * t)` of the gas price at every
+    /// point of `time_grid`, starting from the first point of `gas_curve`, taken as
+    /// today's price. The gas process simulates around this curve instead of the
+    /// forward curve.
+    pub fn expected_gas_curve(&self, gas_curve: &Array1<T>, time_grid: &TimeGrid) -> Array1<T> {
+        let spot = gas_curve[0];
+        time_grid
+            .times()
+            .mapv(|t| spot * (self.gas_drift * T::from_f64(t).unwrap()).exp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use crate::core::services::sample_paths::{SamplePhysicalPathsArgs, sample_physical_paths};
+    use crate::core::simulator::asset::Asset;
+    use crate::core::simulator::simulation_config::SimulationConfig;
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+
+    #[test]
+    fn gas_grows_at_its_real_drift_and_measures_do_not_mix() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::regular(start, start, 90, TimeDelta::days(1), Tz::UTC).unwrap();
+        let params = ModelParameters::new(0.3, 0.5, 50.0, 5.0, 0.1, 0.2, 0.6);
+        let physical = params
+            .clone()
+            .into_physical(0.5, Array1::from_elem(90, 60.0));
+        let num_paths = 4_000;
+        let args = SamplePhysicalPathsArgs {
+            // Gas forwards are in backwardation, but real-world gas grows from today.
+            gas_curve: Array1::linspace(30.0, 20.0, 90),
+            power_curve: Array1::from_elem(90, 80.0),
+            additional_curves: Vec::new(),
+            time_grid: time_grid.clone(),
+            physical_params: physical,
+            num_paths,
+            noise_source: NoiseSource::new(23),
+            simulation_config: SimulationConfig::default(),
+        };
+        let result = sample_physical_paths(args).unwrap();
+
+        let gas = result.asset_data(Asset::Gas).unwrap();
+        let last = gas.column(89);
+        let expected = 30.0 * (0.5 * time_grid.times()[89]).exp();
+        let standard_error = last.std(1.0) / (num_paths as f64).sqrt();
+        assert!((last.mean().unwrap() - expected).abs() < 4.0 * standard_error);
+
+        let shifted = params
+            .with_power_shift(50.0)
+            .into_physical(0.5, Array1::from_elem(90, 60.0));
+        assert!(shifted.price_model(&time_grid).is_err());
+    }
+}
@@ -1,29 +1,103 @@
+use num_traits::Zero;
 use serde::Deserialize;
 
+use crate::core::simulator::asset::Asset;
+
+/// How a unit that is started for a day is dispatched over the points of the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Dispatch {
+    /// The unit runs through every point of the day once started, even in the points
+    /// where it sells at a loss, as in the optimal dispatch problem of the model
+    /// description.
+    #[default]
+    FullDay,
+    /// The unit runs only in the points whose spread is positive and stands idle in the
+    /// others, e.g. when the power price turns negative, without paying for a further
+    /// start.
+    Hourly,
+}
+
 /// Represents the operational and cost parameters of a single power generation unit.
 ///
 /// This struct is generic over the number type `T`, allowing it to be used
 /// with both standard floats (`f64`) for simulation and `AADVar` for greeks calculation.
+///
+/// The unit earns the clean spread `power - heat_rate * fuel - emission_factor * carbon`
+/// per MWh: the clean spark spread for a gas-fired unit and the clean dark spread for a
+/// coal-fired one.
 #[derive(Debug, Deserialize, Clone)]
 pub struct UnitParameter<T> {
-    /// The efficiency of the unit in converting fuel (gas) to electricity.
-    /// Units: MWh / MMBtu
+    /// The efficiency of the unit in converting fuel to electricity.
+    /// Units: MMBtu / MWh
     pub heat_rate: T,
-    /// The maximum power output of the unit.
-    /// Units: MWh
+    /// The maximum power output of the unit. Running through a delivery period, the
+    /// unit produces `capacity` times the length of the period in hours.
+    /// Units: MW
     pub capacity: T,
     /// The fixed cost incurred each time the unit is started.
     /// Units: €
     pub start_up_costs: T,
+    /// The fuel the unit burns, `Asset::Gas` or `Asset::Coal`.
+    #[serde(default = "default_fuel")]
+    pub fuel: Asset,
+    /// The carbon emitted per unit of power produced. Zero if carbon costs are ignored.
+    /// Units: tCO2 / MWh
+    #[serde(default)]
+    pub emission_factor: T,
+    /// How the unit is dispatched over the points of a day it is started.
+    #[serde(default)]
+    pub dispatch: Dispatch,
 }
 
-impl<T> UnitParameter<T> {
-    /// Constructs a new `UnitParameter` instance.
+/// The fuel of a unit unless specified otherwise.
+fn default_fuel() -> Asset {
+    Asset::Gas
+}
+
+impl<T: Zero> UnitParameter<T> {
+    /// Constructs a new gas-fired `UnitParameter` instance without carbon costs.
     pub fn new(heat_rate: T, capacity: T, start_up_costs: T) -> Self {
         UnitParameter {
             heat_rate,
             capacity,
             start_up_costs,
+            fuel: default_fuel(),
+            emission_factor: T::zero(),
+            dispatch: Dispatch::default(),
+        }
+    }
+}
+
+impl<T> UnitParameter<T> {
+    /// Sets the fuel the unit burns.
+    pub fn with_fuel(mut self, fuel: Asset) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Sets the carbon emitted per MWh of power, so that the unit pays for carbon
+    /// allowances.
+    pub fn with_emission_factor(mut self, emission_factor: T) -> Self {
+        self.emission_factor = emission_factor;
+        self
+    }
+
+    /// Sets how the unit is dispatched over the points of a day it is started.
+    pub fn with_dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> UnitParameter<U> {
+        UnitParameter {
+            heat_rate: f(&self.heat_rate),
+            capacity: f(&self.capacity),
+            start_up_costs: f(&self.start_up_costs),
+            fuel: self.fuel,
+            emission_factor: f(&self.emission_factor),
+            dispatch: self.dispatch,
         }
     }
 }
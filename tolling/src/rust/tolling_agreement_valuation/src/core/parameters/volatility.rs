@@ -0,0 +1,228 @@
+use anyhow::{Result, bail};
+use num_traits::Float;
+use serde::Deserialize;
+
+use crate::core::common::time_grid::TimeGrid;
+
+/// The abbreviations of the months, used to name the buckets of a seasonal volatility.
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// The volatility of a price process, constant or varying over the points of the time grid.
+///
+/// The volatility of a point applies to the step of the process that reaches it. Every
+/// variant is described by a list of parameters, its buckets, whose sensitivities are
+/// reported as the vegas of the process.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Volatility<T> {
+    /// The same volatility at every point.
+    Constant(T),
+    /// A volatility curve with one point per point of the time grid or one per local day.
+    Curve(Vec<T>),
+    /// A volatility that depends on the local month, hour of the day and day type.
+    Seasonal(SeasonalVolatility<T>),
+}
+
+/// A seasonal volatility shape: a level per calendar month, scaled by an optional factor
+/// per hour of the day and an optional factor on weekends.
+///
+/// The volatility at a point in local month `m` and hour `h` is
+/// `monthly[m] * hourly[h] * weekend`, where the weekend factor only applies on Saturdays
+/// and Sundays. Hourly factors separate e.g. the peak and off-peak hours of power.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SeasonalVolatility<T> {
+    /// The volatility of every calendar month, January first.
+    pub monthly: Vec<T>,
+    /// The factor of every local hour of the day, midnight first. Empty for no hourly
+    /// shape.
+    #[serde(default = "Vec::new")]
+    pub hourly: Vec<T>,
+    /// The factor applied on weekends, if any.
+    pub weekend: Option<T>,
+}
+
+impl<T> Volatility<T> {
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Volatility<U> {
+        match self {
+            Volatility::Constant(sigma) => Volatility::Constant(f(sigma)),
+            Volatility::Curve(curve) => Volatility::Curve(curve.iter().map(f).collect()),
+            Volatility::Seasonal(seasonal) => Volatility::Seasonal(SeasonalVolatility {
+                monthly: seasonal.monthly.iter().map(&f).collect(),
+                hourly: seasonal.hourly.iter().map(&f).collect(),
+                weekend: seasonal.weekend.as_ref().map(&f),
+            }),
+        }
+    }
+
+    /// Returns `true` if the volatility depends on the calendar date of the points.
+    pub fn is_seasonal(&self) -> bool {
+        matches!(self, Volatility::Seasonal(_))
+    }
+
+    /// Returns the parameters of the volatility, in the order of `bucket_names`.
+    pub fn parameters(&self) -> Vec<&T> {
+        match self {
+            Volatility::Constant(sigma) => vec![sigma],
+            Volatility::Curve(curve) => curve.iter().collect(),
+            Volatility::Seasonal(seasonal) => seasonal
+                .monthly
+                .iter()
+                .chain(&seasonal.hourly)
+                .chain(&seasonal.weekend)
+                .collect(),
+        }
+    }
+
+    /// Returns the parameters of the volatility for updating, e.g. by a calibration, in
+    /// the order of `bucket_names`.
+    pub fn parameters_mut(&mut self) -> Vec<&mut T> {
+        match self {
+            Volatility::Constant(sigma) => vec![sigma],
+            Volatility::Curve(curve) => curve.iter_mut().collect(),
+            Volatility::Seasonal(seasonal) => seasonal
+                .monthly
+                .iter_mut()
+                .chain(&mut seasonal.hourly)
+                .chain(&mut seasonal.weekend)
+                .collect(),
+        }
+    }
+
+    /// Returns a name for every parameter: `sigma` for a constant volatility, the index
+    /// of the point for a curve, and the month (`jan`), hour (`h07`) or `weekend` for a
+    /// seasonal shape.
+    pub fn bucket_names(&self) -> Vec<String> {
+        match self {
+            Volatility::Constant(_) => vec!["sigma".to_string()],
+            Volatility::Curve(curve) => (0..curve.len()).map(|i| i.to_string()).collect(),
+            Volatility::Seasonal(seasonal) => MONTHS
+                .iter()
+                .take(seasonal.monthly.len())
+                .map(|month| month.to_string())
+                .chain((0..seasonal.hourly.len()).map(|hour| format!("h{hour:02}")))
+                .chain(seasonal.weekend.iter().map(|_| "weekend".to_string()))
+                .collect(),
+        }
+    }
+
+    /// Checks that the volatility is defined at every point of `time_grid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a curve has neither one point per grid point nor one per day,
+    /// or if a seasonal shape does not have 12 months and either none or 24 hours.
+    pub fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        match self {
+            Volatility::Constant(_) => {}
+            Volatility::Curve(curve) => {
+                if curve.len() != time_grid.len() && curve.len() != time_grid.num_days() {
+                    bail!(
+                        "A volatility curve has {} points, but the time grid has {} points on \
+                         {} days.",
+                        curve.len(),
+                        time_grid.len(),
+                        time_grid.num_days()
+                    );
+                }
+            }
+            Volatility::Seasonal(seasonal) => {
+                if seasonal.monthly.len() != 12 {
+                    bail!(
+                        "A seasonal volatility needs 12 monthly levels, got {}.",
+                        seasonal.monthly.len()
+                    );
+                }
+                if !seasonal.hourly.is_empty() && seasonal.hourly.len() != 24 {
+                    bail!(
+                        "A seasonal volatility needs no or 24 hourly factors, got {}.",
+                        seasonal.hourly.len()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float> Volatility<T> {
+    /// Returns the volatility at point `i` of `time_grid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the volatility does not fit the grid. Call `check_time_grid` first to
+    /// report this as an error instead.
+    #[inline(always)]
+    pub fn at(&self, time_grid: &TimeGrid, i: usize) -> T {
+        match self {
+            Volatility::Constant(sigma) => *sigma,
+            Volatility::Curve(curve) => curve[time_grid.curve_index(curve.len(), i)],
+            Volatility::Seasonal(seasonal) => {
+                let season = time_grid.season(i);
+                let mut sigma = seasonal.monthly[season.month];
+                if let Some(factor) = seasonal.hourly.get(season.hour) {
+                    sigma = sigma * *factor;
+                }
+                match seasonal.weekend {
+                    Some(factor) if season.weekend => sigma * factor,
+                    _ => sigma,
+                }
+            }
+        }
+    }
+}
+
+impl<T> From<T> for Volatility<T> {
+    fn from(sigma: T) -> Self {
+        Volatility::Constant(sigma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn seasonal_volatility_follows_the_local_calendar() {
+        // Friday 31 January to Sunday 2 February 2025, hourly in Berlin (UTC+1).
+        let start = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 3, chrono_tz::Europe::Berlin).unwrap();
+        let mut monthly = vec![0.5; 12];
+        monthly[1] = 0.75;
+        let volatility = Volatility::Seasonal(SeasonalVolatility {
+            monthly,
+            hourly: (0..24)
+                .map(|hour| if hour == 18 { 1.5 } else { 1.0 })
+                .collect(),
+            weekend: Some(0.5),
+        });
+        volatility.check_time_grid(&time_grid).unwrap();
+
+        assert_eq!(volatility.at(&time_grid, 0), 0.5);
+        assert_eq!(volatility.at(&time_grid, 18), 0.75);
+        assert_eq!(volatility.at(&time_grid, 24), 0.375);
+        assert_eq!(volatility.at(&time_grid, 24 + 18), 0.5625);
+        assert_eq!(volatility.parameters().len(), 12 + 24 + 1);
+        assert_eq!(volatility.bucket_names()[1], "feb");
+        assert_eq!(volatility.bucket_names()[12 + 18], "h18");
+    }
+
+    #[test]
+    fn curves_must_fit_the_time_grid() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 2, Tz::UTC).unwrap();
+
+        let daily = Volatility::Curve(vec![0.3, 0.6]);
+        daily.check_time_grid(&time_grid).unwrap();
+        assert_eq!(daily.at(&time_grid, 30), 0.6);
+        assert!(
+            Volatility::Curve(vec![0.3; 5])
+                .check_time_grid(&time_grid)
+                .is_err()
+        );
+    }
+}
@@ -1,68 +1,115 @@
+use anyhow::Result;
 use ndarray::{Array1, ArrayViewMut1};
 use num_traits::{Float, FromPrimitive};
 
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::Volatility;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
 /// A stateless transformer that converts a path of standard normal random numbers
 /// into a path following a Geometric Brownian Motion (GBM) process.
 ///
 /// The transformation is based on the closed-form solution to the GBM
 /// stochastic differential equation (SDE):
 ///
-/// `dX_t = mu(t) * X_t * dt + sigma * X_t * dW_t`
+/// `dX_t = mu(t) * X_t * dt + sigma(t) * X_t * dW_t`
+///
+/// Where the drift `mu(t)` is derived from the forward curve `F(t)` and the volatility
+/// `sigma(t)` may vary over time, e.g. with the season.
 ///
-/// Where the drift `mu(t)` is derived from the forward curve `F(t)`.
-pub struct GeometricBrownianMotionTransformer;
+/// As a `PriceProcess`, the transformer holds its volatility `sigma`.
+#[derive(Debug, Clone)]
+pub struct GeometricBrownianMotionTransformer<T> {
+    /// The volatility of the process.
+    pub sigma: Volatility<T>,
+}
+
+impl<T: Float + FromPrimitive> GeometricBrownianMotionTransformer<T> {
+    /// Constructs a GBM process with volatility `sigma`, constant or time-dependent.
+    pub fn new(sigma: impl Into<Volatility<T>>) -> Self {
+        Self {
+            sigma: sigma.into(),
+        }
+    }
 
-impl GeometricBrownianMotionTransformer {
     /// Transforms a path of random noise in-place to follow a GBM process.
     ///
     /// This function implements the closed-form solution for a GBM process driven
     /// by a forward curve `F(t)`:
     ///
-    /// `X_t = F(t) * exp(-0.5 * sigma^2 * t + sigma * W_t)`
+    /// `X_t = F(t) * exp(-0.5 * int_0^t sigma(s)^2 ds + int_0^t sigma(s) dW_s)`
     ///
-    /// Where `W_t` is the cumulative Wiener process at time `t`.
+    /// Where `W` is the Wiener process and `t` the year fraction of the point from the
+    /// valuation time. The volatility is piecewise constant, taking the value of a point
+    /// on the step that reaches it, so the solution is exact for any spacing of the points.
     ///
     /// # Arguments
     ///
     /// * `f`: A reference to an `Array1` representing the forward curve `F(t)`.
-    /// * `sigma`: The volatility of the process.
-    /// * `n_points`: The number of time steps in the path.
+    /// * `sigma`: The volatility of the process at every point.
+    /// * `time_grid`: The time of every point of the path.
     /// * `path`: A mutable view of an `Array1` containing the standard normal noise.
-    ///           The transformation happens in-place, and this buffer is overwritten
-    ///           with the resulting GBM path.
+    ///   The transformation happens in-place, and this buffer is overwritten
+    ///   with the resulting GBM path.
     #[inline(always)]
-    pub fn transform_path_to_gbm<T: Float + FromPrimitive>(
+    pub fn transform_path_to_gbm(
         f: &Array1<T>,
-        sigma: T,
-        n_points: usize,
+        sigma: &Volatility<T>,
+        time_grid: &TimeGrid,
         mut path: ArrayViewMut1<T>,
     ) {
-        let dt = T::one() / T::from_usize(n_points - 1).unwrap();
-        let dt_sqrt = dt.sqrt();
         let half = T::from_f64(0.5).unwrap();
 
-        // Pre-calculate the constant part of the drift term in the exponent.
-        let drift_term = -half * sigma.powi(2);
-
-        // `w_t` will accumulate the Wiener process `W_t = sum(Z_i * sqrt(dt))`.
+        // `w_t` will accumulate the stochastic integral `sum(sigma_i * Z_i * sqrt(dt_i))`
+        // and `variance` its variance `sum(sigma_i^2 * dt_i)`.
         // The input `path` contains the standard normal variables `Z_i`.
         let mut w_t = T::zero();
+        let mut variance = T::zero();
 
-        // At t=0, the noise is 0.0, so exp(...) is 1.0. The price is just the forward price.
-        // We set this explicitly as the loop starts from i=1.
-        path[0] = f[0];
-
-        // Iterate through the time steps to calculate the price at each point.
-        for i in 1..n_points {
-            // The Wiener process W_t is the cumulative sum of the scaled noise.
+        // Iterate through the time steps to calculate the price at each point. The first
+        // step runs from the valuation time to the first point, so a path starting at
+        // the valuation time starts at the forward price.
+        for i in 0..path.len() {
             // path[i] currently holds the random shock Z_i for this step.
-            w_t = w_t + path[i] * dt_sqrt;
-
-            let t_i = T::from_usize(i).unwrap() * dt;
-            let f_i = f[i];
+            let dt = time_grid.time_step(i);
+            let sigma_i = sigma.at(time_grid, i);
+            w_t = w_t + sigma_i * path[i] * T::from_f64(dt.sqrt()).unwrap();
+            variance = variance + sigma_i.powi(2) * T::from_f64(dt).unwrap();
 
             // Apply the closed-form solution. The original noise in path[i] is overwritten.
-            path[i] = f_i * (drift_term * t_i + sigma * w_t).exp();
+            path[i] = f[i] * (-half * variance + w_t).exp();
         }
     }
+
+    /// Returns the expected value `E[X_t]` of the simulated process at every point.
+    ///
+    /// The exponent `-0.5 * int sigma^2 ds + int sigma dW` has expectation one after
+    /// exponentiation, so the discretised process reproduces the forward curve exactly:
+    /// `E[X_t] = F(t)`. Control variates rely on this to use the simulated prices as
+    /// controls with a known mean.
+    pub fn expected_path(f: &Array1<T>) -> Array1<T> {
+        f.clone()
+    }
+}
+
+impl<T: Float + FromPrimitive + Send + Sync> PriceProcess<T>
+    for GeometricBrownianMotionTransformer<T>
+{
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        path: ArrayViewMut1<T>,
+        _noise: ProcessNoise<'_>,
+    ) {
+        Self::transform_path_to_gbm(forward_curve, &self.sigma, time_grid, path);
+    }
+
+    fn expected_path(&self, forward_curve: &Array1<T>, _time_grid: &TimeGrid) -> Array1<T> {
+        Self::expected_path(forward_curve)
+    }
+
+    fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        self.sigma.check_time_grid(time_grid)
+    }
 }
@@ -1,8 +1,75 @@
+use anyhow::Result;
 use ndarray::{Array1, ArrayViewMut1};
-use ndarray_rand::rand::prelude::ThreadRng;
-use ndarray_rand::rand::Rng;
-use ndarray_rand::rand_distr::{Distribution, Poisson, StandardNormal};
 use num_traits::{Float, FromPrimitive};
+use serde::Deserialize;
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::Volatility;
+use crate::core::processes::jump_size::JumpSize;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+/// The discretisation of the Ornstein-Uhlenbeck component `V_t` on the steps of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OuScheme {
+    /// The exact Gaussian transition: over a step `dt`, `V` decays by `exp(-kappa * dt)`
+    /// and receives a shock with the exact conditional variance
+    /// `sigma^2 * (1 - exp(-2 * kappa * dt)) / (2 * kappa)`. Unbiased for any step.
+    #[default]
+    Exact,
+    /// The Euler-Maruyama scheme: `V` decays by `1 - kappa * dt` and receives a shock
+    /// with variance `sigma^2 * dt`. Biased unless `kappa * dt` is small, and unstable
+    /// for `kappa * dt > 2`.
+    Euler,
+}
+
+/// The coefficients of one step of the OU component, `V' = decay * V + noise_scale *
+/// sigma * Z - drift_scale * c + J`, for a standard normal `Z`, a drift `c` per year and
+/// the jumps `J` of the step.
+#[derive(Debug, Clone, Copy)]
+pub struct OuStep<T> {
+    /// The factor by which `V` decays over the step.
+    pub decay: T,
+    /// The standard deviation of the diffusion shock of the step per unit volatility.
+    pub noise_scale: T,
+    /// The integral of the decay over the step, which weights a constant drift.
+    pub drift_scale: T,
+}
+
+impl OuScheme {
+    /// Returns the coefficients of a step of length `dt` (in years) with mean-reversion
+    /// speed `kappa`. Without mean reversion both schemes coincide.
+    pub fn step<T: Float + FromPrimitive>(self, kappa: T, dt: f64) -> OuStep<T> {
+        if dt == 0.0 {
+            // A point at the valuation time; this also keeps `sqrt` away from zero, where
+            // its derivative is infinite.
+            return OuStep {
+                decay: T::one(),
+                noise_scale: T::zero(),
+                drift_scale: T::zero(),
+            };
+        }
+        let dt_t = T::from_f64(dt).unwrap();
+        match self {
+            OuScheme::Exact if !kappa.is_zero() => {
+                // `exp_m1` keeps `1 - exp(-x)` accurate for slow reversion.
+                let two = T::from_f64(2.0).unwrap();
+                let decay = (-kappa * dt_t).exp();
+                let variance = -(-two * kappa * dt_t).exp_m1() / (two * kappa);
+                OuStep {
+                    decay,
+                    noise_scale: variance.sqrt(),
+                    drift_scale: -(-kappa * dt_t).exp_m1() / kappa,
+                }
+            }
+            OuScheme::Exact | OuScheme::Euler => OuStep {
+                decay: T::one() - kappa * dt_t,
+                noise_scale: T::from_f64(dt.sqrt()).unwrap(),
+                drift_scale: dt_t,
+            },
+        }
+    }
+}
 
 /// A stateless transformer that converts a path of standard normal random numbers
 /// into a path following a Mean-Reverting Jump Diffusion (MRJD) process.
@@ -11,133 +78,379 @@ use num_traits::{Float, FromPrimitive};
 /// deterministic forward curve and `V_t` is a stochastic component. `V_t` follows
 /// an Ornstein-Uhlenbeck process with jumps, governed by the SDE:
 ///
-/// `dV_t = -kappa * V_t * dt + sigma * dW_t + J * dN_t - lambda * (E[exp(J)] - 1) * dt`
+/// `dV_t = -kappa * V_t * dt + sigma(t) * dW_t + J * dN_t - lambda * (E[exp(J)] - 1) * dt`
 ///
 /// Where:
 /// - `kappa` is the mean-reversion speed.
-/// - `sigma` is the volatility of the OU process.
+/// - `sigma(t)` is the volatility of the OU process, constant or time-dependent. Over a
+///   step it takes the value of the point the step reaches.
 /// - `dW_t` is the Wiener process component.
-/// - `J` is the jump size, `J ~ N(mu_j, sigma_j^2)`.
+/// - `J` is the jump size, normal by default or exponential (see `JumpSize`).
 /// - `dN_t` is a Poisson process with intensity `lambda`.
 /// - The final term is a drift correction to ensure the process is a martingale.
-pub struct JumpDiffusionProcessTransformer;
+///
+/// `V_t` is discretised on the steps of the time grid by the exact Gaussian transition of
+/// the OU process, or optionally by Euler-Maruyama (see `OuScheme`). In both schemes the
+/// jumps of a step are added at its end.
+///
+/// As a `PriceProcess`, the transformer holds its parameters and reads the jump count
+/// and jump size draws from the rows of noise its `JumpSize` requests.
+#[derive(Debug, Clone)]
+pub struct JumpDiffusionProcessTransformer<T> {
+    /// Volatility of the OU process.
+    pub sigma: Volatility<T>,
+    /// Mean-reversion speed.
+    pub kappa: T,
+    /// Intensity of the Poisson process for jumps.
+    pub lambda_j: T,
+    /// The law of the jump sizes.
+    pub jump_size: JumpSize<T>,
+    /// The discretisation of the OU component.
+    pub scheme: OuScheme,
+}
+
+impl<T: Float + FromPrimitive> JumpDiffusionProcessTransformer<T> {
+    /// Constructs a MRJD process from its parameters, discretised exactly.
+    pub fn new(
+        sigma: impl Into<Volatility<T>>,
+        kappa: T,
+        lambda_j: T,
+        jump_size: JumpSize<T>,
+    ) -> Self {
+        Self {
+            sigma: sigma.into(),
+            kappa,
+            lambda_j,
+            jump_size,
+            scheme: OuScheme::default(),
+        }
+    }
+
+    /// Sets the discretisation of the OU component.
+    pub fn with_scheme(mut self, scheme: OuScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
 
-impl JumpDiffusionProcessTransformer {
     /// Transforms a path of random noise in-place to follow the full JDP process.
     ///
     /// This is the main entry point for the transformation. It performs two major steps:
     /// 1. It first transforms the path of standard normal noise into an Ornstein-Uhlenbeck
-    ///    process with jumps (`V_t`) using an Euler-Maruyama discretization scheme.
+    ///    process with jumps (`V_t`) using the discretization `scheme` on the steps of
+    ///    `time_grid`, starting from `V = 0` at the valuation time.
     /// 2. It then applies the final transformation `X_t = F(t) * exp(V_t)` to get the
     ///    final price path.
     ///
     /// # Arguments
     ///
     /// * `f`: The forward curve `F(t)`.
-    /// * `sigma_p`: Volatility of the OU process.
+    /// * `sigma_p`: Volatility of the OU process at every point.
     /// * `kappa`: Mean-reversion speed.
     /// * `lambda_j`: Intensity of the Poisson process for jumps.
-    /// * `mu_j`: Mean of the jump size distribution.
-    /// * `sigma_j`: Standard deviation of the jump size distribution.
+    /// * `jump_size`: The law of the jump sizes.
+    /// * `scheme`: The discretization of the OU process.
+    /// * `time_grid`: The time of every point of the path.
     /// * `path`: A mutable view of the standard normal noise, which is overwritten in-place.
-    /// * `rng`: A mutable reference to a random number generator.
+    /// * `noise`: The draws deciding the number and size of the jumps in each time step,
+    ///   see `JumpSize::compound_jump`.
     #[inline(always)]
-    pub fn transform_path_to_jdp<T: Float + FromPrimitive>(
+    #[allow(clippy::too_many_arguments)]
+    pub fn transform_path_to_jdp(
         f: &Array1<T>,
-        sigma_p: T,
+        sigma_p: &Volatility<T>,
         kappa: T,
         lambda_j: T,
-        mu_j: T,
-        sigma_j: T,
+        jump_size: &JumpSize<T>,
+        scheme: OuScheme,
+        time_grid: &TimeGrid,
         mut path: ArrayViewMut1<T>,
-        rng: &mut ThreadRng,
+        noise: ProcessNoise<'_>,
     ) {
-        let n_points = path.len();
-        let dt_val = 1.0 / n_points as f64;
-        let dt = T::from_f64(dt_val).unwrap();
-        let dt_sqrt = dt.sqrt();
-
         // This term corrects the drift to ensure the process is a martingale.
-        // It accounts for the expected value of the log-normal jump size.
-        let half = T::from_f64(0.5).unwrap();
-        let drift_term = (mu_j + half * sigma_j.powi(2)).exp() - T::one();
-        let jump_drift_correction = lambda_j * dt * drift_term;
-
-        // The Poisson distribution determines the number of jumps in a time step `dt`.
-        // Its parameter (lambda * dt) must be f64.
-        let lambda_f64 = lambda_j.to_f64().unwrap();
-        let poisson_dist = Poisson::new(lambda_f64 * dt_val).unwrap();
+        // It accounts for the expected value of `exp(J)`, per year.
+        let jump_compensator = jump_size.compensator(lambda_j);
 
         // First, transform the noise into the OU process `V_t`.
         Self::transform_path_to_ou(
             sigma_p,
             kappa,
-            mu_j,
-            sigma_j,
-            dt,
-            dt_sqrt,
-            jump_drift_correction,
-            &poisson_dist,
+            lambda_j,
+            jump_size,
+            jump_compensator,
+            scheme,
+            time_grid,
             path.view_mut(),
-            rng,
+            noise,
         );
 
         // Finally, map the OU process `V_t` to the final price process `X_t = F(t) * exp(V_t)`.
-        for i in 0..n_points {
+        for i in 0..path.len() {
             path[i] = f[i] * path[i].exp();
         }
     }
 
-    /// Simulates the Ornstein-Uhlenbeck with jumps process `V_t` using Euler-Maruyama.
+    /// Returns the expected value `E[X_t]` of the discretised process at every point.
+    ///
+    /// Both schemes of `transform_path_to_jdp` are linear in the shocks, so
+    /// `V_t = sum_{k <= t} b_k * xi_k`, where `b_k` is the product of the decays `a_m`
+    /// of the steps `m` after `k` up to `t`, and the step shocks
+    /// `xi_k = sigma_k * s_k * Z + J_k - c * d_k` are independent. `a_k`, `s_k` and `d_k`
+    /// are the coefficients of the step (see `OuStep`), `J_k` is the compound Poisson
+    /// jump of the step and `c` the jump compensator per year. The moment generating
+    /// functions of the shocks give `E[X_t] = F(t) * exp(sum_{k <= t} g_k(b_k))` with
+    ///
+    /// `g_k(b) = 0.5 * b^2 * sigma_k^2 * s_k^2 + lambda * dt_k * (E[exp(b * J)] - 1) - b * c * d_k`
+    ///
+    /// The drift correction compensates the jumps only to first order and the diffusion
+    /// has no convexity correction, so the expectation is close to, but not exactly, the
+    /// forward curve. Control variates use this exact value as the known mean of the
+    /// simulated power prices. The steps of a calendar grid need not be equal, so the
+    /// sum is evaluated for every point, at a cost quadratic in the number of points.
+    ///
+    /// # Arguments
+    ///
+    /// The same model parameters as `transform_path_to_jdp`, with the forward curve `f`
+    /// determining the number of points.
+    #[allow(clippy::too_many_arguments)]
+    pub fn expected_path(
+        f: &Array1<T>,
+        sigma_p: &Volatility<T>,
+        kappa: T,
+        lambda_j: T,
+        jump_size: &JumpSize<T>,
+        scheme: OuScheme,
+        time_grid: &TimeGrid,
+    ) -> Array1<T> {
+        let half = T::from_f64(0.5).unwrap();
+        let jump_compensator = jump_size.compensator(lambda_j);
+        let steps: Vec<(T, T, OuStep<T>)> = (0..f.len())
+            .map(|i| {
+                let dt = time_grid.time_step(i);
+                let sigma = sigma_p.at(time_grid, i);
+                (T::from_f64(dt).unwrap(), sigma, scheme.step(kappa, dt))
+            })
+            .collect();
+
+        Array1::from_shape_fn(f.len(), |t| {
+            let mut log_mean = T::zero();
+            // The weight of the shock of step `k` in `V_t`, starting from the last step.
+            let mut b = T::one();
+            for &(dt, sigma, step) in steps[..=t].iter().rev() {
+                let diffusion = half * (b * sigma * step.noise_scale).powi(2);
+                let jumps = lambda_j * dt * (jump_size.mgf(b) - T::one());
+                log_mean = log_mean + diffusion + jumps - b * jump_compensator * step.drift_scale;
+                b = b * step.decay;
+            }
+            f[t] * log_mean.exp()
+        })
+    }
+
+    /// Simulates the Ornstein-Uhlenbeck with jumps process `V_t` using `scheme`.
     ///
     /// This function overwrites the input `path` in-place.
-    fn transform_path_to_ou<T: Float + FromPrimitive>(
-        sigma_p: T,
+    #[allow(clippy::too_many_arguments)]
+    fn transform_path_to_ou(
+        sigma_p: &Volatility<T>,
         kappa: T,
-        mu_j: T,
-        sigma_j: T,
-        dt: T,
-        dt_sqrt: T,
-        jump_drift_correction: T,
-        poisson_dist: &Poisson<f64>,
+        lambda_j: T,
+        jump_size: &JumpSize<T>,
+        jump_compensator: T,
+        scheme: OuScheme,
+        time_grid: &TimeGrid,
         mut path: ArrayViewMut1<T>,
-        rng: &mut ThreadRng,
+        noise: ProcessNoise<'_>,
     ) {
-        let n_points = path.len();
-        // The OU process `V_t` starts at 0 at time 0.
-        path[0] = T::zero();
-        for t in 1..n_points {
-            let p = path[t - 1];
+        // The Poisson distribution determines the number of jumps in a time step `dt`.
+        // Its parameter (lambda * dt) must be f64.
+        let lambda_f64 = lambda_j.to_f64().unwrap();
+
+        // The OU process `V_t` starts at 0 at the valuation time.
+        let mut v = T::zero();
+        for t in 0..path.len() {
+            let dt_val = time_grid.time_step(t);
+            let step = scheme.step(kappa, dt_val);
             // The input `path` contains the standard normal variable Z_t for the Wiener process.
-            let dw = path[t] * dt_sqrt;
-            let jump_val = Self::sample_jump(rng, poisson_dist, mu_j, sigma_j);
+            let dw = path[t] * step.noise_scale;
+            let jump_val = jump_size.compound_jump(&noise, t, lambda_f64 * dt_val);
 
-            // Euler-Maruyama step for the SDE of V_t.
-            let dx = -kappa * p * dt + sigma_p * dw + jump_val - jump_drift_correction;
-            path[t] = p + dx;
+            // Step of the SDE of V_t: decay, diffusion, jumps and drift correction.
+            let diffusion = sigma_p.at(time_grid, t) * dw;
+            v = step.decay * v + diffusion + jump_val - jump_compensator * step.drift_scale;
+            path[t] = v;
         }
     }
+}
 
-    /// Samples a single jump value for a given time step.
-    ///
-    /// The number of jumps is drawn from a Poisson distribution. If one or more jumps
-    /// occur, their total size is drawn from a Normal distribution, scaled by the
-    /// number of jumps.
-    #[inline(always)]
-    fn sample_jump<T: Float + FromPrimitive>(rng: &mut ThreadRng, poisson: &Poisson<f64>, mu: T, sigma: T) -> T {
-        let n_jumps = poisson.sample(rng);
-
-        if n_jumps > 0.0 {
-            // If jumps occur, their total size is the sum of `n_jumps` IID normal variables.
-            // Sum of N normals: N*mu, N*sigma^2 variance.
-            let n_jumps_t = T::from_f64(n_jumps).unwrap();
-            let z: f64 = rng.sample(StandardNormal);
-            let z_t = T::from_f64(z).unwrap();
-
-            (mu * n_jumps_t) + (z_t * (sigma * n_jumps_t.sqrt()))
-        } else {
-            // No jumps in this time step.
-            T::zero()
+impl<T> PriceProcess<T> for JumpDiffusionProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_normals(&self) -> usize {
+        self.jump_size.num_normals()
+    }
+
+    fn num_uniforms(&self) -> usize {
+        self.jump_size.num_uniforms()
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        Self::transform_path_to_jdp(
+            forward_curve,
+            &self.sigma,
+            self.kappa,
+            self.lambda_j,
+            &self.jump_size,
+            self.scheme,
+            time_grid,
+            path,
+            noise,
+        );
+    }
+
+    fn expected_path(&self, forward_curve: &Array1<T>, time_grid: &TimeGrid) -> Array1<T> {
+        Self::expected_path(
+            forward_curve,
+            &self.sigma,
+            self.kappa,
+            self.lambda_j,
+            &self.jump_size,
+            self.scheme,
+            time_grid,
+        )
+    }
+
+    fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        self.sigma.check_time_grid(time_grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::{NoiseSource, PathNoise};
+    use aad::{AADVar, backward, clear_tape};
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+    use ndarray::{Array2, s};
+
+    /// A grid of `num_days` daily points, valued at its start.
+    fn daily_grid(num_days: usize) -> TimeGrid {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        TimeGrid::regular(start, start, num_days, TimeDelta::days(1), Tz::UTC).unwrap()
+    }
+
+    /// The jump draws of a path whose first row of normals drives the diffusion.
+    fn jump_noise(noise: &PathNoise) -> ProcessNoise<'_> {
+        ProcessNoise {
+            normals: noise.normals.slice(s![1.., ..]),
+            uniforms: noise.uniforms.view(),
+        }
+    }
+
+    /// Normal jumps with mean `mu` and standard deviation `sigma`.
+    fn normal_jumps<T>(mu: T, sigma: T) -> JumpSize<T> {
+        JumpSize::Normal { mu, sigma }
+    }
+
+    /// Simulates `num_paths` jump-free log prices `ln(X_t / F_t) = V_t`.
+    fn log_prices(
+        scheme: OuScheme,
+        sigma: f64,
+        kappa: f64,
+        time_grid: &TimeGrid,
+        num_paths: usize,
+    ) -> Array2<f64> {
+        let n_points = time_grid.len();
+        let f = Array1::ones(n_points);
+        let source = NoiseSource::new(17);
+        let mut paths = Array2::zeros((num_paths, n_points));
+        for (path_idx, mut path) in paths.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(path_idx, 2, 1, n_points);
+            path.assign(&noise.normals.row(0));
+            JumpDiffusionProcessTransformer::transform_path_to_jdp(
+                &f,
+                &Volatility::Constant(sigma),
+                kappa,
+                0.0,
+                &normal_jumps(0.0, 0.0),
+                scheme,
+                time_grid,
+                path.view_mut(),
+                jump_noise(&noise),
+            );
+            path.mapv_inplace(f64::ln);
+        }
+        paths
+    }
+
+    #[test]
+    fn exact_scheme_matches_the_ou_variance_for_fast_reversion() {
+        // Daily steps with `kappa * dt = 0.55`, where Euler overstates the variance.
+        let (sigma, kappa, num_paths) = (1.5, 200.0, 20_000);
+        let time_grid = daily_grid(20);
+        let ou_variance = |t: f64| sigma.powi(2) * (1.0 - (-2.0 * kappa * t).exp()) / (2.0 * kappa);
+        let sample_variance = |paths: &Array2<f64>, i: usize| paths.column(i).var(1.0);
+        // The relative standard error of a sample variance is `sqrt(2 / num_paths)`.
+        let tolerance = 4.0 * (2.0 / num_paths as f64).sqrt();
+
+        let exact = log_prices(OuScheme::Exact, sigma, kappa, &time_grid, num_paths);
+        let euler = log_prices(OuScheme::Euler, sigma, kappa, &time_grid, num_paths);
+        for i in [1, 2, 19] {
+            let expected = ou_variance(time_grid.times()[i]);
+            assert!((sample_variance(&exact, i) / expected - 1.0).abs() < tolerance);
         }
+        assert!(sample_variance(&euler, 19) / ou_variance(time_grid.times()[19]) > 1.1);
+    }
+
+    #[test]
+    fn exact_scheme_differentiates_with_respect_to_kappa() {
+        let time_grid = daily_grid(5);
+        let noise = NoiseSource::new(3).path_noise(0, 2, 1, time_grid.len());
+        let simulate = |kappa: f64| {
+            let f = Array1::from_elem(time_grid.len(), 50.0);
+            let mut path = noise.normals.row(0).to_owned();
+            JumpDiffusionProcessTransformer::transform_path_to_jdp(
+                &f,
+                &Volatility::Constant(0.8),
+                kappa,
+                10.0,
+                &normal_jumps(0.2, 0.1),
+                OuScheme::Exact,
+                &time_grid,
+                path.view_mut(),
+                jump_noise(&noise),
+            );
+            path[4]
+        };
+
+        clear_tape();
+        let kappa = AADVar::constant(30.0);
+        let f = Array1::from_elem(time_grid.len(), AADVar::constant(50.0));
+        let mut path = noise.normals.row(0).mapv(AADVar::constant);
+        JumpDiffusionProcessTransformer::transform_path_to_jdp(
+            &f,
+            &Volatility::Constant(AADVar::constant(0.8)),
+            kappa,
+            AADVar::constant(10.0),
+            &normal_jumps(AADVar::constant(0.2), AADVar::constant(0.1)),
+            OuScheme::Exact,
+            &time_grid,
+            path.view_mut(),
+            jump_noise(&noise),
+        );
+        let mut adjoints = vec![0.0; aad::get_tape_len()];
+        adjoints[path[4].index] = 1.0;
+        backward(&mut adjoints);
+
+        let bump = 1e-5;
+        let finite_difference = (simulate(30.0 + bump) - simulate(30.0 - bump)) / (2.0 * bump);
+        assert!((path[4].value - simulate(30.0)).abs() < 1e-12);
+        assert!((adjoints[kappa.index] - finite_difference).abs() < 1e-6 * finite_difference.abs());
     }
 }
@@ -0,0 +1,239 @@
+use anyhow::{Result, bail};
+use ndarray::s;
+use num_traits::{Float, FromPrimitive};
+use serde::Deserialize;
+
+use crate::core::common::distributions::{
+    binomial_inverse_cdf, erlang_inverse_cdf, poisson_inverse_cdf,
+};
+use crate::core::processes::price_process::ProcessNoise;
+
+/// The row of `ProcessNoise::uniforms` holding the jump count draws. The draws of the
+/// jump sizes follow in the further rows.
+const JUMP_COUNT_ROW: usize = 0;
+
+/// The law of the size `J` of a single jump of the log price.
+///
+/// Each law has a moment generating function `E[exp(b * J)]` in closed form, which gives
+/// the compensator `lambda * (E[exp(J)] - 1)` that keeps a jump process a martingale.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JumpSize<T> {
+    /// Normal jumps `J ~ N(mu, sigma^2)`, symmetric around `mu`.
+    Normal {
+        /// The mean of a jump.
+        mu: T,
+        /// The standard deviation of a jump.
+        sigma: T,
+    },
+    /// Double-exponential jumps (Kou): with probability `p_up` a jump is up and
+    /// exponential with rate `eta_up`, otherwise it is down and exponential with rate
+    /// `eta_down`. Captures asymmetric up-spikes and sudden drops, e.g. towards negative
+    /// prices, with heavier tails than normal jumps.
+    DoubleExponential {
+        /// The probability of an up jump.
+        p_up: T,
+        /// The rate of the up jumps, whose mean is `1 / eta_up`. Must exceed 1.
+        eta_up: T,
+        /// The rate of the down jumps, whose mean is `1 / eta_down`.
+        eta_down: T,
+    },
+    /// Up-only spikes `J ~ Exp(eta)`, with mean `1 / eta`.
+    Exponential {
+        /// The rate of the jumps. Must exceed 1.
+        eta: T,
+    },
+}
+
+impl<T> JumpSize<T> {
+    /// Converts every parameter with `f`, e.g. to register the parameters on the AAD tape.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> JumpSize<U> {
+        match self {
+            JumpSize::Normal { mu, sigma } => JumpSize::Normal {
+                mu: f(mu),
+                sigma: f(sigma),
+            },
+            JumpSize::DoubleExponential {
+                p_up,
+                eta_up,
+                eta_down,
+            } => JumpSize::DoubleExponential {
+                p_up: f(p_up),
+                eta_up: f(eta_up),
+                eta_down: f(eta_down),
+            },
+            JumpSize::Exponential { eta } => JumpSize::Exponential { eta: f(eta) },
+        }
+    }
+
+    /// The number of rows of standard normal draws the jumps of a path need.
+    pub fn num_normals(&self) -> usize {
+        match self {
+            JumpSize::Normal { .. } => 1,
+            JumpSize::DoubleExponential { .. } | JumpSize::Exponential { .. } => 0,
+        }
+    }
+
+    /// The number of rows of uniform draws the jumps of a path need: one for the jump
+    /// count and the rest for the jump sizes.
+    pub fn num_uniforms(&self) -> usize {
+        match self {
+            JumpSize::Normal { .. } => 1,
+            // The number of up jumps and the sums of the up and of the down jumps.
+            JumpSize::DoubleExponential { .. } => 4,
+            JumpSize::Exponential { .. } => 2,
+        }
+    }
+}
+
+impl<T: Float + FromPrimitive> JumpSize<T> {
+    /// Checks that the parameters define a law with `E[exp(J)] < infinity`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a standard deviation is negative, a probability is outside
+    /// `[0, 1]`, a rate of down jumps is not positive or a rate of up jumps does not
+    /// exceed 1.
+    pub fn check(&self) -> Result<()> {
+        let value = |x: &T| x.to_f64().unwrap();
+        match self {
+            JumpSize::Normal { sigma, .. } => {
+                if value(sigma) < 0.0 {
+                    bail!("The standard deviation of normal jumps must not be negative.");
+                }
+            }
+            JumpSize::DoubleExponential {
+                p_up,
+                eta_up,
+                eta_down,
+            } => {
+                if !(0.0..=1.0).contains(&value(p_up)) {
+                    bail!("The probability of an up jump must be in [0, 1].");
+                }
+                if value(eta_up) <= 1.0 {
+                    bail!("The rate of up jumps must exceed 1 for E[exp(J)] to be finite.");
+                }
+                if value(eta_down) <= 0.0 {
+                    bail!("The rate of down jumps must be positive.");
+                }
+            }
+            JumpSize::Exponential { eta } => {
+                if value(eta) <= 1.0 {
+                    bail!(
+                        "The rate of exponential jumps must exceed 1 for E[exp(J)] to be finite."
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the moment generating function `E[exp(b * J)]` of a single jump.
+    ///
+    /// For exponential jumps it is finite for `b < eta_up` (and `b > -eta_down`), which
+    /// `check` ensures for `|b| <= 1`.
+    pub fn mgf(&self, b: T) -> T {
+        let half = T::from_f64(0.5).unwrap();
+        match *self {
+            JumpSize::Normal { mu, sigma } => (b * mu + half * (b * sigma).powi(2)).exp(),
+            JumpSize::DoubleExponential {
+                p_up,
+                eta_up,
+                eta_down,
+            } => p_up * eta_up / (eta_up - b) + (T::one() - p_up) * eta_down / (eta_down + b),
+            JumpSize::Exponential { eta } => eta / (eta - b),
+        }
+    }
+
+    /// Returns the compensator `lambda * (E[exp(J)] - 1)` of jumps with intensity
+    /// `lambda`, the drift per year that offsets their expected effect on the price.
+    pub fn compensator(&self, lambda: T) -> T {
+        lambda * (self.mgf(T::one()) - T::one())
+    }
+
+    /// Returns the total size of the jumps in step `t` of a path.
+    ///
+    /// The number of jumps is drawn from a Poisson distribution with mean `jump_mean` by
+    /// inverting its CDF at a uniform draw. The sum of their sizes is then drawn from its
+    /// exact law by inversion: a single normal draw for normal jumps, and Erlang
+    /// quantiles for exponential jumps, after splitting double-exponential jumps into up
+    /// and down jumps by a binomial draw. The sizes stay pathwise differentiable in the
+    /// parameters of the law, while the probability of an up jump and the intensity
+    /// only move the discrete jump counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `noise`: The draws of the jumps, as requested by `num_normals` and
+    ///   `num_uniforms`.
+    /// * `t`: The step of the path.
+    /// * `jump_mean`: The expected number of jumps in the step, `lambda * dt`.
+    pub fn compound_jump(&self, noise: &ProcessNoise<'_>, t: usize, jump_mean: f64) -> T {
+        let n_jumps = poisson_inverse_cdf(noise.uniforms[[JUMP_COUNT_ROW, t]], jump_mean);
+        if n_jumps == 0 {
+            return T::zero();
+        }
+        let uniforms = noise.uniforms.slice(s![JUMP_COUNT_ROW + 1.., t]);
+        let n_jumps_t = T::from_u64(n_jumps).unwrap();
+        let erlang = |u: f64, n: u64| T::from_f64(erlang_inverse_cdf(u, n)).unwrap();
+        match *self {
+            JumpSize::Normal { mu, sigma } => {
+                // The sum of `n` IID normal jumps is normal with mean `n * mu` and
+                // variance `n * sigma^2`.
+                let z = T::from_f64(noise.normals[[0, t]]).unwrap();
+                mu * n_jumps_t + z * (sigma * n_jumps_t.sqrt())
+            }
+            JumpSize::DoubleExponential {
+                p_up,
+                eta_up,
+                eta_down,
+            } => {
+                let n_up = binomial_inverse_cdf(uniforms[0], n_jumps, p_up.to_f64().unwrap());
+                erlang(uniforms[1], n_up) / eta_up - erlang(uniforms[2], n_jumps - n_up) / eta_down
+            }
+            JumpSize::Exponential { eta } => erlang(uniforms[0], n_jumps) / eta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+
+    #[test]
+    fn compound_jumps_match_the_moment_generating_function() {
+        let num_draws = 200_000;
+        let jump_mean = 1.5;
+        let laws = [
+            JumpSize::Normal {
+                mu: 0.2,
+                sigma: 0.3,
+            },
+            JumpSize::DoubleExponential {
+                p_up: 0.7,
+                eta_up: 4.0,
+                eta_down: 3.0,
+            },
+            JumpSize::Exponential { eta: 5.0 },
+        ];
+        for law in laws {
+            let noise =
+                NoiseSource::new(5).path_noise(0, law.num_normals(), law.num_uniforms(), num_draws);
+            let process_noise = ProcessNoise {
+                normals: noise.normals.view(),
+                uniforms: noise.uniforms.view(),
+            };
+            let samples: Vec<f64> = (0..num_draws)
+                .map(|t| law.compound_jump(&process_noise, t, jump_mean).exp())
+                .collect();
+            let mean = samples.iter().sum::<f64>() / num_draws as f64;
+            let variance =
+                samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (num_draws - 1) as f64;
+
+            // A compound Poisson sum has `E[exp(sum J)] = exp(mean * (E[exp(J)] - 1))`.
+            let expected = (jump_mean * (law.mgf(1.0) - 1.0)).exp();
+            let standard_error = (variance / num_draws as f64).sqrt();
+            assert!((mean - expected).abs() < 4.0 * standard_error, "{law:?}");
+        }
+    }
+}
@@ -1,2 +1,10 @@
 pub mod geometric_brownian_motion_transformer;
+pub mod heston_process_transformer;
 pub mod jump_diffusion_process_transformer;
+pub mod jump_size;
+pub mod physical_power_process_transformer;
+pub mod price_process;
+pub mod regime_switching_process_transformer;
+pub mod schwartz_smith_process_transformer;
+pub mod shifted_process_transformer;
+pub mod spike_process_transformer;
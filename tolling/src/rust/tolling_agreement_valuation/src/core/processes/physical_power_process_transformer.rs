@@ -0,0 +1,231 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::Volatility;
+use crate::core::processes::jump_diffusion_process_transformer::{OuScheme, OuStep};
+use crate::core::processes::jump_size::JumpSize;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+/// A power price process under the physical measure `P`, whose log price reverts to the
+/// Long Run Marginal Cost (LRMC) instead of following the forward curve.
+///
+/// The log price follows
+///
+/// `d(ln P_t) = kappa * (theta_LRMC(t) - ln P_t) * dt + sigma(t) * dW_t + J * dN_t`
+///
+/// where `theta_LRMC(t)` is the log of the LRMC and `J` the size of the jumps of a
+/// Poisson process `N` with intensity `lambda`, drawn from a `JumpSize`. The jumps are
+/// not compensated, since real-world prices need not be martingales.
+///
+/// The log price starts from the first point of the forward curve, taken as today's
+/// spot price, and is discretised on the steps of the time grid by `OuScheme`, with the
+/// LRMC held at its value at the end of each step. The rest of the forward curve is
+/// ignored.
+///
+/// As a `PriceProcess`, the transformer reads the jump draws from the rows of noise its
+/// `JumpSize` requests, like `JumpDiffusionProcessTransformer`, so the same noise drives
+/// the risk-neutral and the real-world prices.
+#[derive(Debug, Clone)]
+pub struct PhysicalPowerProcessTransformer<T> {
+    /// Volatility of the log price.
+    pub sigma: Volatility<T>,
+    /// Mean-reversion speed towards the LRMC.
+    pub kappa: T,
+    /// Intensity of the Poisson process for jumps.
+    pub lambda_j: T,
+    /// The law of the jump sizes.
+    pub jump_size: JumpSize<T>,
+    /// The log of the LRMC at every point of the time grid.
+    pub log_lrmc: Array1<T>,
+    /// The discretisation of the log price.
+    pub scheme: OuScheme,
+}
+
+impl<T: Float + FromPrimitive> PhysicalPowerProcessTransformer<T> {
+    /// Constructs a real-world power process reverting to `lrmc`, given at every point of
+    /// the time grid, discretised exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the LRMC is not positive at every point.
+    pub fn new(
+        sigma: impl Into<Volatility<T>>,
+        kappa: T,
+        lambda_j: T,
+        jump_size: JumpSize<T>,
+        lrmc: &Array1<T>,
+    ) -> Result<Self> {
+        if lrmc.iter().any(|&level| level <= T::zero()) {
+            bail!("The long run marginal cost must be positive at every point.");
+        }
+        Ok(Self {
+            sigma: sigma.into(),
+            kappa,
+            lambda_j,
+            jump_size,
+            log_lrmc: lrmc.mapv(T::ln),
+            scheme: OuScheme::default(),
+        })
+    }
+
+    /// Sets the discretisation of the log price.
+    pub fn with_scheme(mut self, scheme: OuScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Returns the step reaching every point of `time_grid`.
+    fn steps(&self, time_grid: &TimeGrid, num_points: usize) -> Vec<OuStep<T>> {
+        (0..num_points)
+            .map(|i| self.scheme.step(self.kappa, time_grid.time_step(i)))
+            .collect()
+    }
+
+    /// Returns the part of the log price that does not depend on the shocks: the start
+    /// decayed towards the LRMC.
+    fn log_levels(&self, spot: T, steps: &[OuStep<T>]) -> Vec<T> {
+        let mut level = spot.ln();
+        steps
+            .iter()
+            .zip(&self.log_lrmc)
+            .map(|(step, &log_lrmc)| {
+                level = step.decay * level + self.kappa * step.drift_scale * log_lrmc;
+                level
+            })
+            .collect()
+    }
+}
+
+impl<T> PriceProcess<T> for PhysicalPowerProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_normals(&self) -> usize {
+        self.jump_size.num_normals()
+    }
+
+    fn num_uniforms(&self) -> usize {
+        self.jump_size.num_uniforms()
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        mut path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        let steps = self.steps(time_grid, path.len());
+        let levels = self.log_levels(forward_curve[0], &steps);
+        // The parameter of the Poisson distribution of the jump count must be f64.
+        let lambda_f64 = self.lambda_j.to_f64().unwrap();
+
+        // The deviation of the log price from its level, driven by the shocks.
+        let mut deviation = T::zero();
+        for (t, step) in steps.iter().enumerate() {
+            // The input `path` contains the standard normal shock of the log price.
+            let diffusion = self.sigma.at(time_grid, t) * step.noise_scale * path[t];
+            let jump = self
+                .jump_size
+                .compound_jump(&noise, t, lambda_f64 * time_grid.time_step(t));
+            deviation = step.decay * deviation + diffusion + jump;
+            path[t] = (levels[t] + deviation).exp();
+        }
+    }
+
+    /// Returns the real-world mean of the price at every point.
+    ///
+    /// The deviation from the level is `sum_{k <= t} b_k * xi_k`, where `b_k` is the
+    /// product of the decays of the steps after `k` and the step shocks `xi_k` are
+    /// independent. Its moment generating function gives `E[P_t] = exp(level_t + sum_{k <= t}
+    /// (0.5 * b_k^2 * sigma_k^2 * s_k^2 + lambda * dt_k * (E[exp(b_k * J)] - 1)))`, at a
+    /// cost quadratic in the number of points.
+    fn expected_path(&self, forward_curve: &Array1<T>, time_grid: &TimeGrid) -> Array1<T> {
+        let half = T::from_f64(0.5).unwrap();
+        let steps = self.steps(time_grid, forward_curve.len());
+        let levels = self.log_levels(forward_curve[0], &steps);
+        Array1::from_shape_fn(forward_curve.len(), |t| {
+            let mut log_mean = levels[t];
+            // The weight of the shock of step `k` at point `t`, starting from the last step.
+            let mut b = T::one();
+            for k in (0..=t).rev() {
+                let dt = T::from_f64(time_grid.time_step(k)).unwrap();
+                let shock = self.sigma.at(time_grid, k) * steps[k].noise_scale;
+                log_mean = log_mean
+                    + half * (b * shock).powi(2)
+                    + self.lambda_j * dt * (self.jump_size.mgf(b) - T::one());
+                b = b * steps[k].decay;
+            }
+            log_mean.exp()
+        })
+    }
+
+    fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        if self.log_lrmc.len() != time_grid.len() {
+            bail!(
+                "The long run marginal cost has {} points, but the time grid has {}.",
+                self.log_lrmc.len(),
+                time_grid.len()
+            );
+        }
+        self.sigma.check_time_grid(time_grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+    use ndarray::{Array2, s};
+
+    #[test]
+    fn prices_revert_to_the_long_run_marginal_cost() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::regular(start, start, 60, TimeDelta::days(1), Tz::UTC).unwrap();
+        let n_points = time_grid.len();
+        let lrmc = Array1::from_elem(n_points, 60.0);
+        let jump_size = JumpSize::Normal {
+            mu: 0.1,
+            sigma: 0.2,
+        };
+        let process =
+            PhysicalPowerProcessTransformer::new(0.8, 50.0, 10.0, jump_size, &lrmc).unwrap();
+        // Today's spot price is far below the LRMC.
+        let f = Array1::from_elem(n_points, 20.0);
+        let num_paths = 10_000;
+        let source = NoiseSource::new(61);
+
+        let mut prices = Array2::zeros((num_paths, n_points));
+        for (path_idx, mut path) in prices.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(
+                path_idx,
+                1 + process.num_normals(),
+                process.num_uniforms(),
+                n_points,
+            );
+            path.assign(&noise.normals.row(0));
+            let process_noise = ProcessNoise {
+                normals: noise.normals.slice(s![1.., ..]),
+                uniforms: noise.uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), process_noise);
+        }
+
+        let expected = process.expected_path(&f, &time_grid);
+        assert!((expected[0] - 20.0).abs() < 1e-9);
+        for t in [5, n_points - 1] {
+            let column = prices.column(t);
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!((column.mean().unwrap() - expected[t]).abs() < 4.0 * standard_error);
+        }
+        // After many reversion times the median price is close to the LRMC.
+        let mut last: Vec<f64> = prices.column(n_points - 1).to_vec();
+        last.sort_by(f64::total_cmp);
+        let median = last[num_paths / 2];
+        assert!((median / 60.0).ln().abs() < 0.1);
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use ndarray::{Array1, ArrayView2, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+
+/// The random numbers a `PriceProcess` consumes on top of its diffusion shocks.
+///
+/// Instead of drawing from a random number generator, a process reads its draws from
+/// the noise of the path (see `PathNoise`), which keeps common random numbers and
+/// quasi-Monte Carlo intact whatever the process. Each process receives only its own
+/// rows, as many as it requests through `PriceProcess::num_normals` and
+/// `PriceProcess::num_uniforms`.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessNoise<'a> {
+    /// Independent standard normal draws of shape `(num_normals, num_points)`.
+    pub normals: ArrayView2<'a, f64>,
+    /// Independent uniform draws in `[0, 1)` of shape `(num_uniforms, num_points)`.
+    pub uniforms: ArrayView2<'a, f64>,
+}
+
+/// A stochastic model for the price of a single asset around its forward curve.
+///
+/// The simulator correlates the Brownian shocks of all assets and hands every process
+/// the standard normal shocks of its asset, together with any further draws it needs
+/// (e.g. for jumps). The process turns them into a price path in-place.
+///
+/// Processes run on the calendar time of a `TimeGrid`. A path starts at the valuation
+/// time, so its first step reaches the first point of the grid.
+///
+/// The trait is generic over the number type `T`, so a process holding `AADVar`
+/// parameters can be differentiated with respect to them.
+pub trait PriceProcess<T: Float + FromPrimitive>: Send + Sync {
+    /// The number of rows of standard normal draws the process needs besides its
+    /// correlated diffusion shocks.
+    fn num_normals(&self) -> usize {
+        0
+    }
+
+    /// The number of rows of uniform draws the process needs.
+    fn num_uniforms(&self) -> usize {
+        0
+    }
+
+    /// Transforms a path of correlated standard normal shocks in-place into prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `forward_curve`: The forward curve `F(t)` of the asset.
+    /// * `time_grid`: The time of every point of the forward curve.
+    /// * `path`: The correlated standard normal shocks of the asset, one per point. The
+    ///   buffer is overwritten with the simulated prices.
+    /// * `noise`: The further draws of the process, as requested by `num_normals` and
+    ///   `num_uniforms`.
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    );
+
+    /// Returns the expected price `E[X_t]` of the simulated process at every point.
+    ///
+    /// This is the mean of the discretised process as simulated by `transform_path`,
+    /// which need not equal the forward curve exactly.
+    fn expected_path(&self, forward_curve: &Array1<T>, time_grid: &TimeGrid) -> Array1<T>;
+
+    /// Checks that the parameters of the process are defined at every point of
+    /// `time_grid`, e.g. that a volatility curve has the length of the grid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process cannot run on `time_grid`.
+    fn check_time_grid(&self, _time_grid: &TimeGrid) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the regime of every point of the path driven by `noise` for a process
+    /// that switches between regimes, and `None` for any other process.
+    ///
+    /// The regimes depend only on the further draws of the process, so they are those
+    /// of the prices `transform_path` produces from the same noise.
+    fn regime_path(&self, _time_grid: &TimeGrid, _noise: ProcessNoise<'_>) -> Option<Vec<usize>> {
+        None
+    }
+}
@@ -0,0 +1,325 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+use serde::Deserialize;
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::processes::jump_diffusion_process_transformer::OuScheme;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+/// The row of `ProcessNoise::uniforms` holding the regime transition draws.
+const TRANSITION_ROW: usize = 0;
+/// The number of terms of the Taylor series of the matrix exponential.
+const EXPM_TERMS: usize = 16;
+
+/// A regime of a `RegimeSwitchingProcessTransformer`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Regime<T> {
+    /// The level of the log price in the regime, relative to the other regimes. A
+    /// scarcity regime has a higher level than the normal regime.
+    pub level: T,
+    /// The volatility of the mean-reverting factor in the regime.
+    pub sigma: T,
+}
+
+/// A power price process that switches between regimes, e.g. a normal and a scarcity
+/// regime, each with its own price level and volatility.
+///
+/// The regime `R_t` is a continuous-time Markov chain with the transition rate matrix
+/// (generator) `Q`, whose entry `Q[i][j]` is the rate per year of switching from regime
+/// `i` to regime `j` and whose rows sum to zero. Over a step `dt` it moves with the
+/// transition probabilities `exp(Q * dt)`, so the chain is exact on any time grid. The
+/// price is
+///
+/// `X_t = F(t) * exp(V_t - 0.5 * v_t) * exp(l(R_t)) / E[exp(l(R_t))]`
+///
+/// with `dV_t = -kappa * V_t * dt + sigma(R_t) * dW_t`, where `l(i)` and `sigma(i)` are
+/// the level and volatility of regime `i` and `v_t` is the variance of `V_t` given the
+/// regimes of the path. Both factors have mean 1 given the regimes, so the process is a
+/// martingale: `E[X_t] = F(t)`.
+///
+/// The chain starts in the first regime at the valuation time. Over a step, the regime
+/// switches first and its volatility drives the step. `V_t` is discretised by `OuScheme`.
+///
+/// As a `PriceProcess`, the transformer reads its regime transitions from one row of
+/// uniforms. The regimes of a path can be recovered from the same noise with
+/// `regime_path`.
+#[derive(Debug, Clone)]
+pub struct RegimeSwitchingProcessTransformer<T> {
+    /// The regimes, the first of which holds at the valuation time.
+    pub regimes: Vec<Regime<T>>,
+    /// The transition rate matrix `Q` of the regimes (1 / year).
+    pub transition_rates: Array2<f64>,
+    /// Mean-reversion speed of the factor `V_t`.
+    pub kappa: T,
+    /// The discretisation of `V_t`.
+    pub scheme: OuScheme,
+}
+
+impl<T: Float + FromPrimitive> RegimeSwitchingProcessTransformer<T> {
+    /// Constructs a regime-switching process from its regimes and their transition rate
+    /// matrix, discretised exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no regimes, if `transition_rates` is not a square
+    /// matrix with one row per regime, or if it is not a generator: off-diagonal rates
+    /// must not be negative and every row must sum to zero.
+    pub fn new(regimes: Vec<Regime<T>>, transition_rates: Array2<f64>, kappa: T) -> Result<Self> {
+        let n = regimes.len();
+        if n == 0 {
+            bail!("A regime-switching process needs at least one regime.");
+        }
+        if transition_rates.dim() != (n, n) {
+            bail!(
+                "The transition rates of {n} regimes must be {n} x {n}, got {:?}.",
+                transition_rates.dim()
+            );
+        }
+        for (i, row) in transition_rates.rows().into_iter().enumerate() {
+            if row
+                .iter()
+                .enumerate()
+                .any(|(j, &rate)| j != i && rate < 0.0)
+            {
+                bail!("The transition rates out of regime {i} must not be negative.");
+            }
+            if row.sum().abs() > 1e-9 * row[i].abs().max(1.0) {
+                bail!("The transition rates of regime {i} must sum to zero.");
+            }
+        }
+        Ok(Self {
+            regimes,
+            transition_rates,
+            kappa,
+            scheme: OuScheme::default(),
+        })
+    }
+
+    /// Sets the discretisation of the mean-reverting factor.
+    pub fn with_scheme(mut self, scheme: OuScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Returns the transition probability matrix `exp(Q * dt)` of a step of `dt` years.
+    ///
+    /// The exponential is computed by scaling and squaring: the Taylor series is
+    /// evaluated for `Q * dt / 2^s`, whose norm is at most 0.5, and squared `s` times.
+    pub fn transition_matrix(&self, dt: f64) -> Array2<f64> {
+        let n = self.regimes.len();
+        let generator = &self.transition_rates * dt;
+        let norm = generator
+            .rows()
+            .into_iter()
+            .map(|row| row.iter().map(|x| x.abs()).sum::<f64>())
+            .fold(0.0, f64::max);
+        let squarings = if norm > 0.5 {
+            (norm / 0.5).log2().ceil() as i32
+        } else {
+            0
+        };
+        let scaled = generator / 2f64.powi(squarings);
+
+        let mut term = Array2::<f64>::eye(n);
+        let mut matrix = Array2::<f64>::eye(n);
+        for k in 1..=EXPM_TERMS {
+            term = term.dot(&scaled) / k as f64;
+            matrix += &term;
+        }
+        for _ in 0..squarings {
+            matrix = matrix.dot(&matrix);
+        }
+        matrix
+    }
+
+    /// Returns the transition probability matrix of every step of `time_grid`.
+    ///
+    /// Calendar grids have few distinct step lengths, so every matrix is computed once.
+    fn step_transitions(&self, time_grid: &TimeGrid, num_points: usize) -> Vec<Array2<f64>> {
+        let mut computed: Vec<(f64, Array2<f64>)> = Vec::new();
+        (0..num_points)
+            .map(|t| {
+                let dt = time_grid.time_step(t);
+                match computed.iter().find(|(step, _)| *step == dt) {
+                    Some((_, matrix)) => matrix.clone(),
+                    None => {
+                        let matrix = self.transition_matrix(dt);
+                        computed.push((dt, matrix.clone()));
+                        matrix
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Simulates the regimes of a path from its transition draws.
+    fn simulate_regimes(
+        &self,
+        transitions: &[Array2<f64>],
+        noise: &ProcessNoise<'_>,
+    ) -> Vec<usize> {
+        let last = self.regimes.len() - 1;
+        let mut regime = 0;
+        transitions
+            .iter()
+            .enumerate()
+            .map(|(t, matrix)| {
+                // Invert the CDF of the row of the current regime at the uniform draw.
+                let u = noise.uniforms[[TRANSITION_ROW, t]];
+                let mut cumulative = 0.0;
+                regime = matrix
+                    .row(regime)
+                    .iter()
+                    .position(|&p| {
+                        cumulative += p;
+                        u < cumulative
+                    })
+                    .unwrap_or(last);
+                regime
+            })
+            .collect()
+    }
+
+    /// Returns `ln E[exp(l(R_t))]` at every point, from the distribution of the regimes
+    /// propagated through the transition matrices of the steps.
+    fn log_mean_levels(&self, transitions: &[Array2<f64>]) -> Vec<T> {
+        let mut distribution = Array1::<f64>::zeros(self.regimes.len());
+        distribution[0] = 1.0;
+        transitions
+            .iter()
+            .map(|matrix| {
+                distribution = distribution.dot(matrix);
+                distribution
+                    .iter()
+                    .zip(&self.regimes)
+                    .fold(T::zero(), |mean, (&p, regime)| {
+                        mean + T::from_f64(p).unwrap() * regime.level.exp()
+                    })
+                    .ln()
+            })
+            .collect()
+    }
+}
+
+impl<T> PriceProcess<T> for RegimeSwitchingProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_uniforms(&self) -> usize {
+        1
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        mut path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        let transitions = self.step_transitions(time_grid, path.len());
+        let regimes = self.simulate_regimes(&transitions, &noise);
+        let log_mean_levels = self.log_mean_levels(&transitions);
+        let half = T::from_f64(0.5).unwrap();
+
+        // The factor `V_t` and its variance given the regimes, both zero at the valuation
+        // time.
+        let (mut v, mut variance) = (T::zero(), T::zero());
+        for t in 0..path.len() {
+            let regime = &self.regimes[regimes[t]];
+            let step = self.scheme.step(self.kappa, time_grid.time_step(t));
+            // The input `path` contains the standard normal shock of the factor.
+            let shock = regime.sigma * step.noise_scale;
+            v = step.decay * v + shock * path[t];
+            variance = step.decay.powi(2) * variance + shock.powi(2);
+            let log_price = v - half * variance + regime.level - log_mean_levels[t];
+            path[t] = forward_curve[t] * log_price.exp();
+        }
+    }
+
+    /// The process is a martingale, so its mean is the forward curve.
+    fn expected_path(&self, forward_curve: &Array1<T>, _time_grid: &TimeGrid) -> Array1<T> {
+        forward_curve.clone()
+    }
+
+    fn regime_path(&self, time_grid: &TimeGrid, noise: ProcessNoise<'_>) -> Option<Vec<usize>> {
+        let transitions = self.step_transitions(time_grid, noise.uniforms.ncols());
+        Some(self.simulate_regimes(&transitions, &noise))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::{array, s};
+
+    /// A normal and a scarcity regime, left at 50 and 2000 times a year.
+    fn two_regimes() -> RegimeSwitchingProcessTransformer<f64> {
+        let regimes = vec![
+            Regime {
+                level: 0.0,
+                sigma: 0.5,
+            },
+            Regime {
+                level: 1.2,
+                sigma: 2.0,
+            },
+        ];
+        let rates = array![[-50.0, 50.0], [2000.0, -2000.0]];
+        RegimeSwitchingProcessTransformer::new(regimes, rates, 20.0).unwrap()
+    }
+
+    #[test]
+    fn transition_matrix_matches_the_two_state_chain() {
+        let process = two_regimes();
+        let (a, b) = (50.0, 2000.0);
+        for dt in [1.0 / 8760.0, 1.0 / 365.0, 0.25] {
+            let matrix = process.transition_matrix(dt);
+            let to_scarcity = a / (a + b) * (1.0 - (-(a + b) * dt).exp());
+            assert!((matrix[[0, 1]] - to_scarcity).abs() < 1e-12);
+            assert!((matrix.row(1).sum() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn prices_are_martingales_and_regimes_are_retrievable() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 2, Tz::UTC).unwrap();
+        let n_points = time_grid.len();
+        let num_paths = 20_000;
+        let process = two_regimes();
+        let f = Array1::from_elem(n_points, 1.0);
+        let source = NoiseSource::new(29);
+
+        let mut prices = ndarray::Array2::zeros((num_paths, n_points));
+        let mut time_in_scarcity = 0;
+        for (path_idx, mut path) in prices.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(
+                path_idx,
+                1 + process.num_normals(),
+                process.num_uniforms(),
+                n_points,
+            );
+            path.assign(&noise.normals.row(0));
+            let process_noise = ProcessNoise {
+                normals: noise.normals.slice(s![1.., ..]),
+                uniforms: noise.uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), process_noise);
+            let regimes = process.regime_path(&time_grid, process_noise).unwrap();
+            time_in_scarcity += regimes.iter().filter(|&&regime| regime == 1).count();
+        }
+
+        for t in [1, 24, n_points - 1] {
+            let column = prices.column(t);
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!((column.mean().unwrap() - 1.0).abs() < 4.0 * standard_error);
+        }
+        // The chain quickly reaches its stationary share of scarcity, 50 / 2050.
+        let share = time_in_scarcity as f64 / (num_paths * n_points) as f64;
+        assert!((share - 50.0 / 2050.0).abs() < 0.005);
+    }
+}
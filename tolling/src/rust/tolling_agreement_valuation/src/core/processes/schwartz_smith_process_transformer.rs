@@ -0,0 +1,162 @@
+use ndarray::{Array1, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::processes::jump_diffusion_process_transformer::OuScheme;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+/// The two-factor model of Schwartz and Smith, with a mean-reverting short-term factor
+/// and a persistent long-term factor, around a forward curve.
+///
+/// The price is modelled as `X_t = F(t) * exp(chi_t + xi_t - m_t)`, where
+///
+/// `dchi_t = -kappa_short * chi_t * dt + sigma_short * dW^chi_t`
+///
+/// `dxi_t = sigma_long * dW^xi_t`
+///
+/// and the two Brownian motions have correlation `rho_short_long`. Short-term deviations
+/// revert at `kappa_short`, while long-term shocks persist. The volatility of the price
+/// thereby falls from that of `chi + xi` for near deliveries towards `sigma_long` for
+/// distant ones, instead of the flat term structure of a GBM.
+///
+/// Both factors start at zero at the valuation time. The short-term factor is
+/// discretised on the steps of the time grid by `OuScheme` and the long-term factor
+/// exactly. The deterministic correction `m_t = 0.5 * Var[chi_t + xi_t]`, computed
+/// exactly for the discretised factors, makes the process a martingale: `E[X_t] = F(t)`.
+///
+/// As a `PriceProcess`, the long-term factor is driven by the correlated diffusion
+/// shocks of the asset, so `rho` and the correlations of `ModelParameters` apply to it.
+/// The short-term factor mixes them with one further row of normals to reach
+/// `rho_short_long`.
+#[derive(Debug, Clone)]
+pub struct SchwartzSmithProcessTransformer<T> {
+    /// Mean-reversion speed of the short-term factor.
+    pub kappa_short: T,
+    /// Volatility of the short-term factor.
+    pub sigma_short: T,
+    /// Volatility of the long-term factor.
+    pub sigma_long: T,
+    /// The correlation between the shocks of the two factors.
+    pub rho_short_long: T,
+    /// The discretisation of the short-term factor.
+    pub scheme: OuScheme,
+}
+
+impl<T: Float + FromPrimitive> SchwartzSmithProcessTransformer<T> {
+    /// Constructs a Schwartz-Smith process from its parameters, with the short-term
+    /// factor discretised exactly.
+    pub fn new(kappa_short: T, sigma_short: T, sigma_long: T, rho_short_long: T) -> Self {
+        Self {
+            kappa_short,
+            sigma_short,
+            sigma_long,
+            rho_short_long,
+            scheme: OuScheme::default(),
+        }
+    }
+
+    /// Sets the discretisation of the short-term factor.
+    pub fn with_scheme(mut self, scheme: OuScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+}
+
+impl<T> PriceProcess<T> for SchwartzSmithProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_normals(&self) -> usize {
+        1
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        mut path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        let half = T::from_f64(0.5).unwrap();
+        let two = T::from_f64(2.0).unwrap();
+        let rho = self.rho_short_long;
+        let independent = (T::one() - rho.powi(2)).sqrt();
+
+        let (mut short, mut long) = (T::zero(), T::zero());
+        // The variances of the two factors and their covariance.
+        let (mut var_short, mut var_long, mut covariance) = (T::zero(), T::zero(), T::zero());
+        for t in 0..path.len() {
+            let dt = time_grid.time_step(t);
+            let step = self.scheme.step(self.kappa_short, dt);
+            let shock_short = self.sigma_short * step.noise_scale;
+            let shock_long = self.sigma_long * T::from_f64(dt.sqrt()).unwrap();
+
+            // The input `path` contains the correlated shock of the long-term factor.
+            let z_long = path[t];
+            let z_independent = T::from_f64(noise.normals[[0, t]]).unwrap();
+            let z_short = rho * z_long + independent * z_independent;
+            short = step.decay * short + shock_short * z_short;
+            long = long + shock_long * z_long;
+
+            var_short = step.decay.powi(2) * var_short + shock_short.powi(2);
+            var_long = var_long + shock_long.powi(2);
+            covariance = step.decay * covariance + rho * shock_short * shock_long;
+            let variance = var_short + var_long + two * covariance;
+
+            path[t] = forward_curve[t] * (short + long - half * variance).exp();
+        }
+    }
+
+    /// The correction makes the process a martingale, so its mean is the forward curve.
+    fn expected_path(&self, forward_curve: &Array1<T>, _time_grid: &TimeGrid) -> Array1<T> {
+        forward_curve.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+    use ndarray::{Array2, s};
+
+    #[test]
+    fn prices_are_martingales_with_a_falling_volatility_term_structure() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::regular(start, start, 365, TimeDelta::days(1), Tz::UTC).unwrap();
+        let n_points = time_grid.len();
+        let num_paths = 5_000;
+        let (kappa_short, sigma_short, sigma_long) = (8.0, 0.8, 0.2);
+        let process =
+            SchwartzSmithProcessTransformer::new(kappa_short, sigma_short, sigma_long, -0.3);
+        let f = Array1::from_elem(n_points, 1.0);
+        let source = NoiseSource::new(31);
+
+        let mut prices = Array2::zeros((num_paths, n_points));
+        for (path_idx, mut path) in prices.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(path_idx, 2, 0, n_points);
+            path.assign(&noise.normals.row(0));
+            let process_noise = ProcessNoise {
+                normals: noise.normals.slice(s![1.., ..]),
+                uniforms: noise.uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), process_noise);
+        }
+
+        for t in [30, 180, n_points - 1] {
+            let column = prices.column(t);
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!((column.mean().unwrap() - 1.0).abs() < 4.0 * standard_error);
+        }
+
+        // The volatility of the log price per unit of time falls towards `sigma_long`.
+        let log_volatility = |t: usize| {
+            let years = time_grid.times()[t];
+            prices.column(t).mapv(f64::ln).std(1.0) / years.sqrt()
+        };
+        let (near, far) = (log_volatility(30), log_volatility(n_points - 1));
+        assert!(near > far);
+        assert!((far - sigma_long).abs() < 0.1);
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use ndarray::{Array1, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+/// A price process shifted by a constant, so that a lognormal model can produce negative
+/// prices.
+///
+/// The price is modelled as `X_t = Y_t - shift`, where `Y` follows the inner process
+/// around the shifted forward curve `F(t) + shift`. A lognormal `Y` thereby turns into a
+/// shifted-lognormal `X`, bounded below by `-shift` instead of zero, e.g. to capture the
+/// negative power prices of sunny, windy weekends.
+///
+/// The shift moves the forward curve and the price by the same amount, so the mean of
+/// the price is that of the inner process less the shift: a martingale inner process
+/// gives `E[X_t] = F(t)`. The shifted forward curve should be positive at every point,
+/// i.e. the shift should exceed the most negative forward price.
+pub struct ShiftedProcessTransformer<T> {
+    /// The process of the shifted price `Y`.
+    pub inner: Box<dyn PriceProcess<T>>,
+    /// The amount by which the price is shifted.
+    pub shift: T,
+}
+
+impl<T: Float + FromPrimitive> ShiftedProcessTransformer<T> {
+    /// Shifts the prices of `inner` by `shift`.
+    pub fn new(inner: Box<dyn PriceProcess<T>>, shift: T) -> Self {
+        Self { inner, shift }
+    }
+
+    /// Returns the forward curve of the shifted price `Y`.
+    fn shifted_curve(&self, forward_curve: &Array1<T>) -> Array1<T> {
+        forward_curve.mapv(|f| f + self.shift)
+    }
+}
+
+impl<T> PriceProcess<T> for ShiftedProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_normals(&self) -> usize {
+        self.inner.num_normals()
+    }
+
+    fn num_uniforms(&self) -> usize {
+        self.inner.num_uniforms()
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        mut path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        let shifted_curve = self.shifted_curve(forward_curve);
+        self.inner
+            .transform_path(&shifted_curve, time_grid, path.view_mut(), noise);
+        path.mapv_inplace(|y| y - self.shift);
+    }
+
+    fn expected_path(&self, forward_curve: &Array1<T>, time_grid: &TimeGrid) -> Array1<T> {
+        let shifted_curve = self.shifted_curve(forward_curve);
+        self.inner
+            .expected_path(&shifted_curve, time_grid)
+            .mapv(|y| y - self.shift)
+    }
+
+    fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        self.inner.check_time_grid(time_grid)
+    }
+
+    fn regime_path(&self, time_grid: &TimeGrid, noise: ProcessNoise<'_>) -> Option<Vec<usize>> {
+        self.inner.regime_path(time_grid, noise)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use crate::core::processes::jump_diffusion_process_transformer::JumpDiffusionProcessTransformer;
+    use crate::core::processes::jump_size::JumpSize;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::{Array2, s};
+
+    #[test]
+    fn shifted_prices_go_negative_and_stay_martingales() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 2, Tz::UTC).unwrap();
+        let n_points = time_grid.len();
+        let num_paths = 10_000;
+        let jumps = JumpSize::Normal {
+            mu: -0.2,
+            sigma: 0.3,
+        };
+        let inner = JumpDiffusionProcessTransformer::new(1.5, 20.0, 200.0, jumps);
+        let shift = 60.0;
+        let process = ShiftedProcessTransformer::new(Box::new(inner), shift);
+        let f = Array1::from_elem(n_points, 5.0);
+        let source = NoiseSource::new(41);
+
+        let mut prices = Array2::zeros((num_paths, n_points));
+        for (path_idx, mut path) in prices.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(
+                path_idx,
+                1 + process.num_normals(),
+                process.num_uniforms(),
+                n_points,
+            );
+            path.assign(&noise.normals.row(0));
+            let process_noise = ProcessNoise {
+                normals: noise.normals.slice(s![1.., ..]),
+                uniforms: noise.uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), process_noise);
+        }
+
+        assert!(prices.iter().any(|&price| price < 0.0));
+        assert!(prices.iter().all(|&price| price > -shift));
+        let expected = process.expected_path(&f, &time_grid);
+        for t in [12, n_points - 1] {
+            let column = prices.column(t);
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!((column.mean().unwrap() - expected[t]).abs() < 4.0 * standard_error);
+        }
+    }
+}
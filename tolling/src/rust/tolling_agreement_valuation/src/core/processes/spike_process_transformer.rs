@@ -0,0 +1,260 @@
+use anyhow::Result;
+use ndarray::{Array1, ArrayViewMut1};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::Volatility;
+use crate::core::processes::jump_diffusion_process_transformer::{OuScheme, OuStep};
+use crate::core::processes::jump_size::JumpSize;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+/// The weight below which the jumps of an earlier step are taken to have decayed out of
+/// the spike factor when computing the martingale correction.
+const SPIKE_WEIGHT_CUTOFF: f64 = 1e-12;
+
+/// A power price process with a slow diffusive base factor and a fast-reverting spike
+/// factor that carries the jumps.
+///
+/// The price is modelled as `X_t = F(t) * exp(B_t + S_t - m_t)`, where
+///
+/// `dB_t = -kappa * B_t * dt + sigma(t) * dW_t`
+///
+/// `dS_t = -kappa_spike * S_t * dt + J * dN_t`
+///
+/// and `J` is the size of the jumps of a Poisson process `N` with intensity `lambda`,
+/// drawn from a `JumpSize`. Spikes thereby revert at their own speed `kappa_spike`, typically
+/// within hours, while ordinary deviations revert at `kappa`.
+///
+/// Both factors start at zero at the valuation time and are discretised on the steps of
+/// the time grid by `OuScheme`, with the jumps of a step added at its end. The two
+/// factors are independent, so the deterministic correction
+/// `m_t = ln E[exp(B_t)] + ln E[exp(S_t)]`, computed exactly for the discretised factors,
+/// makes the process a martingale: `E[X_t] = F(t)`. The jumps of a step enter `S_t` with
+/// the product `b` of the spike decays of the later steps, and contribute
+/// `lambda * dt * (E[exp(b * J)] - 1)` to the correction; steps
+/// with `|b| < 1e-12` are dropped. The correction is therefore cheap for fast spike
+/// reversion, but its cost grows quadratically in the number of points as
+/// `kappa_spike` approaches zero.
+///
+/// As a `PriceProcess`, the transformer reads the jump count and jump size draws from
+/// the rows of noise its `JumpSize` requests, like `JumpDiffusionProcessTransformer`.
+#[derive(Debug, Clone)]
+pub struct SpikeProcessTransformer<T> {
+    /// Volatility of the base factor.
+    pub sigma: Volatility<T>,
+    /// Mean-reversion speed of the base factor.
+    pub kappa: T,
+    /// Mean-reversion speed of the spike factor.
+    pub kappa_spike: T,
+    /// Intensity of the Poisson process for jumps.
+    pub lambda_j: T,
+    /// The law of the jump sizes.
+    pub jump_size: JumpSize<T>,
+    /// The discretisation of both factors.
+    pub scheme: OuScheme,
+}
+
+impl<T: Float + FromPrimitive> SpikeProcessTransformer<T> {
+    /// Constructs a two-factor spike process from its parameters, discretised exactly.
+    pub fn new(
+        sigma: impl Into<Volatility<T>>,
+        kappa: T,
+        kappa_spike: T,
+        lambda_j: T,
+        jump_size: JumpSize<T>,
+    ) -> Self {
+        Self {
+            sigma: sigma.into(),
+            kappa,
+            kappa_spike,
+            lambda_j,
+            jump_size,
+            scheme: OuScheme::default(),
+        }
+    }
+
+    /// Sets the discretisation of both factors.
+    pub fn with_scheme(mut self, scheme: OuScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Returns the step of the base factor and the step of the spike factor reaching
+    /// every point of `time_grid`.
+    fn steps(&self, time_grid: &TimeGrid, num_points: usize) -> Vec<(OuStep<T>, OuStep<T>)> {
+        (0..num_points)
+            .map(|i| {
+                let dt = time_grid.time_step(i);
+                (
+                    self.scheme.step(self.kappa, dt),
+                    self.scheme.step(self.kappa_spike, dt),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the martingale correction `m_t` of every point, see the type documentation.
+    fn log_corrections(&self, time_grid: &TimeGrid, steps: &[(OuStep<T>, OuStep<T>)]) -> Vec<T> {
+        let half = T::from_f64(0.5).unwrap();
+        let mut corrections = Vec::with_capacity(steps.len());
+        // The variance of the base factor, which is Gaussian.
+        let mut base_variance = T::zero();
+        for (t, (base, _)) in steps.iter().enumerate() {
+            let shock = self.sigma.at(time_grid, t) * base.noise_scale;
+            base_variance = base.decay.powi(2) * base_variance + shock.powi(2);
+
+            let mut spike = T::zero();
+            // The weight of the jumps of step `k` in `S_t`, starting from the last step.
+            let mut b = T::one();
+            for k in (0..=t).rev() {
+                if b.abs().to_f64().unwrap() < SPIKE_WEIGHT_CUTOFF {
+                    break;
+                }
+                let dt = T::from_f64(time_grid.time_step(k)).unwrap();
+                spike = spike + self.lambda_j * dt * (self.jump_size.mgf(b) - T::one());
+                b = b * steps[k].1.decay;
+            }
+            corrections.push(half * base_variance + spike);
+        }
+        corrections
+    }
+}
+
+impl<T> PriceProcess<T> for SpikeProcessTransformer<T>
+where
+    T: Float + FromPrimitive + Send + Sync,
+{
+    fn num_normals(&self) -> usize {
+        self.jump_size.num_normals()
+    }
+
+    fn num_uniforms(&self) -> usize {
+        self.jump_size.num_uniforms()
+    }
+
+    fn transform_path(
+        &self,
+        forward_curve: &Array1<T>,
+        time_grid: &TimeGrid,
+        mut path: ArrayViewMut1<T>,
+        noise: ProcessNoise<'_>,
+    ) {
+        let steps = self.steps(time_grid, path.len());
+        let corrections = self.log_corrections(time_grid, &steps);
+        // The parameter of the Poisson distribution of the jump count must be f64.
+        let lambda_f64 = self.lambda_j.to_f64().unwrap();
+
+        let (mut base_factor, mut spike_factor) = (T::zero(), T::zero());
+        for (t, (base, spike)) in steps.iter().enumerate() {
+            // The input `path` contains the standard normal shock of the base factor.
+            let diffusion = self.sigma.at(time_grid, t) * base.noise_scale * path[t];
+            base_factor = base.decay * base_factor + diffusion;
+            let jump = self
+                .jump_size
+                .compound_jump(&noise, t, lambda_f64 * time_grid.time_step(t));
+            spike_factor = spike.decay * spike_factor + jump;
+            path[t] = forward_curve[t] * (base_factor + spike_factor - corrections[t]).exp();
+        }
+    }
+
+    /// The correction makes the process a martingale, so its mean is the forward curve.
+    fn expected_path(&self, forward_curve: &Array1<T>, _time_grid: &TimeGrid) -> Array1<T> {
+        forward_curve.clone()
+    }
+
+    fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        self.sigma.check_time_grid(time_grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::Array2;
+
+    /// An hourly grid of `num_days` days, valued at its start.
+    fn hourly_grid(num_days: usize) -> TimeGrid {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        TimeGrid::hourly(start, start, num_days, Tz::UTC).unwrap()
+    }
+
+    #[test]
+    fn spikes_revert_at_their_own_speed() {
+        let time_grid = hourly_grid(1);
+        let n_points = time_grid.len();
+        let (kappa, kappa_spike) = (5.0, 2000.0);
+        let process = SpikeProcessTransformer::new(
+            0.0,
+            kappa,
+            kappa_spike,
+            50.0,
+            JumpSize::Normal {
+                mu: 1.0,
+                sigma: 0.0,
+            },
+        );
+        let f = Array1::from_elem(n_points, 40.0);
+        let normals = Array2::zeros((1, n_points));
+        let simulate = |uniforms: &Array2<f64>| {
+            let mut path = Array1::zeros(n_points);
+            let noise = ProcessNoise {
+                normals: normals.view(),
+                uniforms: uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), noise);
+            path
+        };
+
+        // A jump in the step reaching the second point and none anywhere else.
+        let calm = simulate(&Array2::zeros((1, n_points)));
+        let mut uniforms = Array2::zeros((1, n_points));
+        uniforms[[0, 1]] = 0.999;
+        let spiked = simulate(&uniforms);
+
+        let spike = (&spiked / &calm).mapv(f64::ln);
+        assert!(spike[1] >= 1.0);
+        let decay = (-kappa_spike * time_grid.time_step(2)).exp();
+        for t in 2..n_points {
+            assert!((spike[t] / spike[t - 1] - decay).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn prices_are_martingales() {
+        let time_grid = hourly_grid(1);
+        let n_points = time_grid.len();
+        let num_paths = 10_000;
+        let jump_size = JumpSize::DoubleExponential {
+            p_up: 0.8,
+            eta_up: 3.0,
+            eta_down: 2.0,
+        };
+        let process = SpikeProcessTransformer::new(0.6, 5.0, 300.0, 400.0, jump_size);
+        let f = Array1::from_elem(n_points, 1.0);
+        let source = NoiseSource::new(23);
+
+        let mut prices = Array2::zeros((num_paths, n_points));
+        for (path_idx, mut path) in prices.rows_mut().into_iter().enumerate() {
+            let noise = source.path_noise(
+                path_idx,
+                1 + process.num_normals(),
+                process.num_uniforms(),
+                n_points,
+            );
+            path.assign(&noise.normals.row(0));
+            let process_noise = ProcessNoise {
+                normals: noise.normals.slice(ndarray::s![1.., ..]),
+                uniforms: noise.uniforms.view(),
+            };
+            process.transform_path(&f, &time_grid, path.view_mut(), process_noise);
+        }
+
+        for t in [6, 12, n_points - 1] {
+            let column = prices.column(t);
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!((column.mean().unwrap() - 1.0).abs() < 4.0 * standard_error);
+        }
+    }
+}
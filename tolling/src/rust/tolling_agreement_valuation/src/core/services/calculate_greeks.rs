@@ -1,17 +1,106 @@
+use std::ops::Range;
+
 use aad::{backward, clear_tape, get_tape_len, AADVar};
-use ndarray::Array1;
+use ndarray::{s, Array1};
 use num_traits::Zero;
-use rayon::prelude::*;
 
-use crate::core::common::cholesky::cholesky_2d;
+use crate::core::common::parallel::map_chunks_in_order;
+use crate::core::common::random::NoiseSource;
+use crate::core::common::statistics::{BatchMoments, ConfidenceInterval};
+use crate::core::common::time_grid::TimeGrid;
 use crate::core::parameters::{ModelParameters, UnitParameter};
-use crate::core::simulator::simulate_prices::{Simulator, TollingAssetIndex};
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::simulator::simulation_config::SimulationConfig;
 use crate::core::valuation::profit_and_loss::ProfitCalculator;
 use anyhow::Result;
 
-/// A tuple representing the greeks calculated for a single path.
-/// Contents are: (delta_gas, delta_power, vega_gas, vega_power).
-type PathGreeks = (Array1<f64>, Array1<f64>, f64, f64);
+/// The value and greeks of a single path, laid out as
+/// `[pv, vega_gas..., vega_power..., gas_model..., delta_gas..., delta_power...,
+/// delta_additional...]`, see `GreeksLayout`.
+///
+/// A flat layout lets the first and second moments of all quantities be accumulated by
+/// a single `BatchMoments`.
+type PathGreeks = Array1<f64>;
+
+/// Position of the present value in `PathGreeks`.
+const PV_IDX: usize = 0;
+
+/// The positions of the bucketed greeks in `PathGreeks`, which follow the present value.
+///
+/// There is one vega per bucket of a volatility (see `Volatility::parameters`), one
+/// sensitivity per parameter of `ModelParameters::gas_model_parameters` and one delta per
+/// point of an input curve, e.g. per day for a daily gas curve. The deltas of the
+/// additional assets follow in the order of `CalculateGreeksArgs::additional_curves`.
+#[derive(Debug, Clone)]
+struct GreeksLayout {
+    num_vega_gas: usize,
+    num_vega_power: usize,
+    num_gas_model: usize,
+    num_delta_gas: usize,
+    num_delta_power: usize,
+    num_delta_additional: Vec<usize>,
+}
+
+impl GreeksLayout {
+    fn new(args: &CalculateGreeksArgs) -> Self {
+        Self {
+            num_vega_gas: args.model_params.gas_volatility_parameters().len(),
+            num_vega_power: args.model_params.power_volatility_parameters().len(),
+            num_gas_model: args.model_params.gas_model_parameters().len(),
+            num_delta_gas: args.gas_curve.len(),
+            num_delta_power: args.power_curve.len(),
+            num_delta_additional: args
+                .additional_curves
+                .iter()
+                .map(|(_, curve)| curve.len())
+                .collect(),
+        }
+    }
+
+    fn vega_gas(&self) -> Range<usize> {
+        let start = PV_IDX + 1;
+        start..start + self.num_vega_gas
+    }
+
+    fn vega_power(&self) -> Range<usize> {
+        let start = self.vega_gas().end;
+        start..start + self.num_vega_power
+    }
+
+    fn gas_model(&self) -> Range<usize> {
+        let start = self.vega_power().end;
+        start..start + self.num_gas_model
+    }
+
+    fn delta_gas(&self) -> Range<usize> {
+        let start = self.gas_model().end;
+        start..start + self.num_delta_gas
+    }
+
+    fn delta_power(&self) -> Range<usize> {
+        let start = self.delta_gas().end;
+        start..start + self.num_delta_power
+    }
+
+    /// The deltas of the `k`-th additional curve.
+    fn delta_additional(&self, k: usize) -> Range<usize> {
+        let start = self.delta_power().end + self.num_delta_additional[..k].iter().sum::<usize>();
+        start..start + self.num_delta_additional[k]
+    }
+
+    fn len(&self) -> usize {
+        self.delta_power().end + self.num_delta_additional.iter().sum::<usize>()
+    }
+}
+
+/// Number of consecutive paths whose greeks are accumulated sequentially before the
+/// partial moments are combined. Fixing the chunk layout (instead of letting rayon decide
+/// how to split the work) keeps the floating point summation order, and therefore the
+/// result, independent of the number of threads.
+const REDUCTION_CHUNK_SIZE: usize = 64;
 
 /// Calculates the Greeks (sensitivities) of the tolling agreement value.
 ///
@@ -23,48 +112,137 @@ type PathGreeks = (Array1<f64>, Array1<f64>, f64, f64);
 /// 1.  **Map**: For each path, `calculate_greeks_for_path` is called. This function performs
 ///     the full simulation and valuation for one path using `AADVar` types and runs the
 ///     backward AAD pass to get the path-specific gradients.
-/// 2.  **Reduce**: The gradients from all paths are summed together.
+/// 2.  **Reduce**: The first and second moments of the path values and gradients are
+///     accumulated, first within fixed chunks of `REDUCTION_CHUNK_SIZE` paths and then
+///     across chunks, always in chunk order.
 ///
-/// Finally, the summed gradients are averaged to produce the final reported Greeks.
+/// The means are the reported Greeks; the second moments give their standard errors and
+/// 95% confidence intervals. The present value is averaged over the same paths, so the PV
+/// and the Greeks are estimated from one common sample.
 ///
 /// # Arguments
 ///
 /// * `args`: A reference to `CalculateGreeksArgs` containing all necessary input parameters.
+///
+/// Deltas are reported for the curve of every simulated asset, those of the additional
+/// assets in `GreeksResult::delta_additional`.
 pub fn calculate_greeks(args: &CalculateGreeksArgs) -> Result<GreeksResult> {
     let num_paths = args.num_paths;
+    // Validates the model and the curves once; every path rebuilds both on its own tape.
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    ProfitCalculator::check_assets(&args.unit_params, price_model.assets())?;
+    let sampler = NoiseSampler::new(
+        args.noise_source,
+        args.simulation_config,
+        price_model.noise_layout(),
+        num_paths,
+        args.time_grid.len(),
+    )?;
+
+    // The standard errors account for dependent paths (antithetic pairs, quasi-random
+    // replicates) by working with independent batches of paths.
+    let layout = sampler.batch_layout(num_paths);
+    let num_values = GreeksLayout::new(args).len();
+    let mut moments = BatchMoments::with_layout(num_values, layout);
 
-    // Use a parallel map-reduce approach.
     // Each task runs `calculate_greeks_for_path`, which handles the AAD tape locally,
     // ensuring thread safety.
-    let (total_delta_gas, total_delta_power, total_vega_gas, total_vega_power) = (0..num_paths)
-        .into_par_iter()
-        .map(|_| calculate_greeks_for_path(args))
-        .reduce(
-            || {
-                // Identity for the reduction: zero-filled arrays and zero scalars.
-                (
-                    Array1::zeros(args.gas_curve.raw_dim()),
-                    Array1::zeros(args.power_curve.raw_dim()),
-                    0.0,
-                    0.0,
-                )
-            },
-            // The reduction operation: element-wise sum for arrays and standard sum for scalars.
-            |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3),
-        );
+    map_chunks_in_order(
+        num_paths,
+        REDUCTION_CHUNK_SIZE,
+        |paths| {
+            let mut chunk_moments = BatchMoments::with_layout(num_values, layout);
+            for path_idx in paths {
+                let greeks = calculate_greeks_for_path(args, &forward_curves, &sampler, path_idx);
+                chunk_moments.add(path_idx, greeks.view());
+            }
+            chunk_moments
+        },
+        |chunk_moments| moments.merge(chunk_moments),
+    );
 
-    // Average the gradients by the number of paths.
-    let num_paths_f64 = num_paths as f64;
-    let delta_gas = total_delta_gas / num_paths_f64;
-    let delta_power = total_delta_power / num_paths_f64;
-    let vega_gas = total_vega_gas / num_paths_f64;
-    let vega_power = total_vega_power / num_paths_f64;
+    let mean = moments.mean();
+    let standard_error = moments.standard_error();
+    let confidence_interval = moments.confidence_interval();
+    let layout = GreeksLayout::new(args);
+    let scalar_interval = |idx: usize| ConfidenceInterval {
+        lower: confidence_interval.lower[idx],
+        upper: confidence_interval.upper[idx],
+    };
+    let buckets = |range: Range<usize>| {
+        let slice = s![range];
+        (
+            mean.slice(slice).to_owned(),
+            standard_error.slice(slice).to_owned(),
+            ConfidenceInterval {
+                lower: confidence_interval.lower.slice(slice).to_owned(),
+                upper: confidence_interval.upper.slice(slice).to_owned(),
+            },
+        )
+    };
+    let (delta_gas, delta_gas_standard_error, delta_gas_confidence_interval) =
+        buckets(layout.delta_gas());
+    let (delta_power, delta_power_standard_error, delta_power_confidence_interval) =
+        buckets(layout.delta_power());
+    let (vega_gas, vega_gas_standard_error, vega_gas_confidence_interval) =
+        buckets(layout.vega_gas());
+    let (vega_power, vega_power_standard_error, vega_power_confidence_interval) =
+        buckets(layout.vega_power());
+    let gas_model_sensitivities = args
+        .model_params
+        .gas_model_parameter_names()
+        .into_iter()
+        .zip(layout.gas_model())
+        .map(|(name, idx)| ParameterSensitivity {
+            name,
+            sensitivity: mean[idx],
+            standard_error: standard_error[idx],
+            confidence_interval: scalar_interval(idx),
+        })
+        .collect();
+    let delta_additional = args
+        .additional_curves
+        .iter()
+        .enumerate()
+        .map(|(k, (asset, _))| {
+            let (delta, standard_error, confidence_interval) =
+                buckets(layout.delta_additional(k));
+            AssetDelta {
+                asset: *asset,
+                delta,
+                standard_error,
+                confidence_interval,
+            }
+        })
+        .collect();
 
     let greeks = GreeksResult {
+        pv: mean[PV_IDX],
+        pv_standard_error: standard_error[PV_IDX],
+        pv_confidence_interval: scalar_interval(PV_IDX),
         delta_gas,
+        delta_gas_standard_error,
+        delta_gas_confidence_interval,
         delta_power,
+        delta_power_standard_error,
+        delta_power_confidence_interval,
+        delta_additional,
         vega_gas,
+        vega_gas_standard_error,
+        vega_gas_confidence_interval,
+        vega_gas_buckets: args.model_params.gas_volatility_buckets(),
         vega_power,
+        vega_power_standard_error,
+        vega_power_confidence_interval,
+        vega_power_buckets: args.model_params.power_volatility_buckets(),
+        gas_model_sensitivities,
     };
     Ok(greeks)
 }
@@ -74,61 +252,58 @@ pub fn calculate_greeks(args: &CalculateGreeksArgs) -> Result<GreeksResult> {
 /// This function encapsulates the entire logic for one Monte Carlo path in the AAD context:
 /// 1.  Clears the thread-local AAD tape.
 /// 2.  Promotes all input parameters from `f64` to `AADVar`.
-/// 3.  Simulates the price paths for gas and power.
+/// 3.  Simulates the price paths of all assets.
 /// 4.  Calculates the total discounted profit (NPV) for the path.
 /// 5.  Triggers the backward AAD pass to compute adjoints (gradients).
 /// 6.  Extracts the relevant gradients (Deltas and Vegas) from the tape.
-fn calculate_greeks_for_path(args: &CalculateGreeksArgs) -> PathGreeks {
+///
+/// The random numbers are the noise of path `path_idx` from `sampler`, i.e. the same
+/// draws `Simulator::simulate` uses for that path. Valuations sharing a noise source and
+/// configuration therefore differ only through their inputs.
+///
+/// `forward_curves` are the curves of all assets in the order of the price model, aligned
+/// to the time grid by `calculate_greeks`. The deltas of the grid points are summed onto
+/// the points of the input curves, so a daily curve gets one delta per day.
+fn calculate_greeks_for_path(
+    args: &CalculateGreeksArgs,
+    forward_curves: &[Array1<f64>],
+    sampler: &NoiseSampler,
+    path_idx: usize,
+) -> PathGreeks {
     // 1. AAD Tape Management: Start with a fresh, empty tape for this thread.
     clear_tape();
 
     // 2. AAD Variable Initialization: Register all inputs as constants on the tape.
-    let gas_curve_aad = args.gas_curve.mapv(AADVar::constant);
-    let power_curve_aad = args.power_curve.mapv(AADVar::constant);
-
-    let model_params_aad = ModelParameters::new(
-        AADVar::constant(args.model_params.sigma_g),
-        AADVar::constant(args.model_params.sigma_p),
-        AADVar::constant(args.model_params.kappa),
-        AADVar::constant(args.model_params.lambda_j),
-        AADVar::constant(args.model_params.mu_j),
-        AADVar::constant(args.model_params.sigma_j),
-        AADVar::constant(args.model_params.rho),
-    );
+    let curves_aad: Vec<Array1<AADVar>> = forward_curves
+        .iter()
+        .map(|curve| curve.mapv(AADVar::constant))
+        .collect();
+
+    let model_params_aad = args.model_params.map(|&x| AADVar::constant(x));
 
     let unit_params_aad: Vec<UnitParameter<AADVar>> = args
         .unit_params
         .iter()
-        .map(|p| {
-            UnitParameter::new(
-                AADVar::constant(p.heat_rate),
-                AADVar::constant(p.capacity),
-                AADVar::constant(p.start_up_costs),
-            )
-        })
+        .map(|p| p.map(|&x| AADVar::constant(x)))
         .collect();
 
     let risk_free_rate_aad = AADVar::constant(args.risk_free_rate);
 
     // 3. Simulation using AAD variables.
-    let l = cholesky_2d(model_params_aad.rho);
-    let assets = Simulator::simulate_single_path(
-        &gas_curve_aad,
-        &power_curve_aad,
-        &model_params_aad,
-        &l,
-    );
+    let price_model = model_params_aad
+        .price_model()
+        .expect("The model was validated by `calculate_greeks`.");
+    let noise = sampler.path_noise(path_idx);
+    let assets =
+        Simulator::simulate_single_path(&curves_aad, &args.time_grid, &price_model, &noise);
 
     // 4. Valuation: Calculate the profit for the simulated path.
-    let n_points = gas_curve_aad.len();
-    let path_gas = assets.row(TollingAssetIndex::Gas.idx());
-    let path_power = assets.row(TollingAssetIndex::Power.idx());
     let daily_profits = ProfitCalculator::calculate_single_path(
-        &path_gas,
-        &path_power,
+        &assets.view(),
+        price_model.assets(),
+        &args.time_grid,
         &unit_params_aad,
         risk_free_rate_aad,
-        n_points / 24, // Assuming hourly resolution
     );
 
     // 5. Aggregation: Sum daily profits to get the total NPV for the path.
@@ -144,25 +319,41 @@ fn calculate_greeks_for_path(args: &CalculateGreeksArgs) -> PathGreeks {
 
     // 7. Gradient Extraction: Read the computed derivatives from the adjoints vector.
     // The index of each AADVar points to its location in the adjoints vector.
-    let mut local_delta_gas = Array1::<f64>::zeros(gas_curve_aad.raw_dim());
-    let mut local_delta_power = Array1::<f64>::zeros(power_curve_aad.raw_dim());
-
-    for (i, point) in gas_curve_aad.iter().enumerate() {
-        local_delta_gas[i] = adjoints[point.index];
-    }
-    for (i, point) in power_curve_aad.iter().enumerate() {
-        local_delta_power[i] = adjoints[point.index];
+    let layout = GreeksLayout::new(args);
+    let mut path_greeks = PathGreeks::zeros(layout.len());
+    path_greeks[PV_IDX] = total_value.value;
+    for (parameters, range) in [
+        (model_params_aad.gas_volatility_parameters(), layout.vega_gas()),
+        (model_params_aad.power_volatility_parameters(), layout.vega_power()),
+        (model_params_aad.gas_model_parameters(), layout.gas_model()),
+    ] {
+        for (idx, parameter) in range.zip(parameters) {
+            path_greeks[idx] = adjoints[parameter.index];
+        }
     }
 
-    let local_vega_gas = adjoints[model_params_aad.sigma_g.index];
-    let local_vega_power = adjoints[model_params_aad.sigma_p.index];
+    // Models built from `ModelParameters` always contain gas and power, and the
+    // additional assets were validated by `calculate_greeks`.
+    let curve_of = |asset| &curves_aad[price_model.index_of(asset).unwrap()];
+    let additional = args
+        .additional_curves
+        .iter()
+        .enumerate()
+        .map(|(k, (asset, _))| (*asset, layout.delta_additional(k)));
+    for (asset, range) in [
+        (Asset::Gas, layout.delta_gas()),
+        (Asset::Power, layout.delta_power()),
+    ]
+    .into_iter()
+    .chain(additional)
+    {
+        for (i, point) in curve_of(asset).iter().enumerate() {
+            let bucket = args.time_grid.curve_index(range.len(), i);
+            path_greeks[range.start + bucket] += adjoints[point.index];
+        }
+    }
 
-    (
-        local_delta_gas,
-        local_delta_power,
-        local_vega_gas,
-        local_vega_power,
-    )
+    path_greeks
 }
 
 /// Arguments required for the `calculate_greeks` function.
@@ -171,6 +362,10 @@ pub struct CalculateGreeksArgs {
     pub gas_curve: Array1<f64>,
     /// The forward curve for power prices.
     pub power_curve: Array1<f64>,
+    /// The forward curves of the additional assets of `model_params`, e.g. carbon.
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    /// The calendar time of every point of the forward curves.
+    pub time_grid: TimeGrid,
     /// Parameters for the stochastic models.
     pub model_params: ModelParameters<f64>,
     /// Parameters defining the power generation units.
@@ -179,16 +374,325 @@ pub struct CalculateGreeksArgs {
     pub num_paths: usize,
     /// The annual risk-free rate for discounting.
     pub risk_free_rate: f64,
+    /// The source of random numbers for the simulation paths.
+    pub noise_source: NoiseSource,
+    /// The variance reduction techniques applied to the random numbers.
+    pub simulation_config: SimulationConfig,
 }
 
 /// Holds the results of the greeks calculation.
+///
+/// Every estimate comes with its Monte Carlo (or randomised quasi-Monte Carlo) standard
+/// error and a 95% confidence interval. Delta and vega intervals are per bucket and do not
+/// hold jointly for the whole curve.
 pub struct GreeksResult {
-    /// Delta with respect to the gas forward curve.
+    /// The present value, estimated from the same paths as the Greeks.
+    pub pv: f64,
+    /// The standard error of `pv`.
+    pub pv_standard_error: f64,
+    /// The 95% confidence interval of `pv`.
+    pub pv_confidence_interval: ConfidenceInterval<f64>,
+    /// Delta with respect to every point of the gas forward curve.
     pub delta_gas: Array1<f64>,
-    /// Delta with respect to the power forward curve.
+    /// The standard error of every bucket of `delta_gas`.
+    pub delta_gas_standard_error: Array1<f64>,
+    /// The 95% confidence interval of every bucket of `delta_gas`.
+    pub delta_gas_confidence_interval: ConfidenceInterval<Array1<f64>>,
+    /// Delta with respect to every point of the power forward curve.
     pub delta_power: Array1<f64>,
-    /// Vega with respect to the gas price volatility (`sigma_g`).
-    pub vega_gas: f64,
-    /// Vega with respect to the power price volatility (`sigma_p`).
-    pub vega_power: f64,
+    /// The standard error of every bucket of `delta_power`.
+    pub delta_power_standard_error: Array1<f64>,
+    /// The 95% confidence interval of every bucket of `delta_power`.
+    pub delta_power_confidence_interval: ConfidenceInterval<Array1<f64>>,
+    /// The deltas of the forward curve of every additional asset, in the order of
+    /// `CalculateGreeksArgs::additional_curves`.
+    pub delta_additional: Vec<AssetDelta>,
+    /// Vega with respect to every bucket of the gas price volatility (`sigma_g`), or to
+    /// the short- and long-term volatilities of the Schwartz-Smith gas model.
+    pub vega_gas: Array1<f64>,
+    /// The standard error of every bucket of `vega_gas`.
+    pub vega_gas_standard_error: Array1<f64>,
+    /// The 95% confidence interval of every bucket of `vega_gas`.
+    pub vega_gas_confidence_interval: ConfidenceInterval<Array1<f64>>,
+    /// The name of every bucket of `vega_gas`, see `ModelParameters::gas_volatility_buckets`.
+    pub vega_gas_buckets: Vec<String>,
+    /// Vega with respect to every bucket of the power price volatility (`sigma_p`), or to
+    /// the volatility of every regime of the regime-switching power model.
+    pub vega_power: Array1<f64>,
+    /// The standard error of every bucket of `vega_power`.
+    pub vega_power_standard_error: Array1<f64>,
+    /// The 95% confidence interval of every bucket of `vega_power`.
+    pub vega_power_confidence_interval: ConfidenceInterval<Array1<f64>>,
+    /// The name of every bucket of `vega_power`, see
+    /// `ModelParameters::power_volatility_buckets`.
+    pub vega_power_buckets: Vec<String>,
+    /// The sensitivity to every parameter of `ModelParameters::gas_model_parameters`,
+    /// e.g. to the initial variance, mean variance and vol-of-vol of the Heston model.
+    pub gas_model_sensitivities: Vec<ParameterSensitivity>,
+}
+
+/// The delta with respect to every point of the forward curve of an additional asset.
+pub struct AssetDelta {
+    /// The asset of the forward curve.
+    pub asset: Asset,
+    /// Delta with respect to every point of the forward curve.
+    pub delta: Array1<f64>,
+    /// The standard error of every bucket of `delta`.
+    pub standard_error: Array1<f64>,
+    /// The 95% confidence interval of every bucket of `delta`.
+    pub confidence_interval: ConfidenceInterval<Array1<f64>>,
+}
+
+/// The sensitivity of the present value to a single model parameter.
+pub struct ParameterSensitivity {
+    /// The name of the parameter, see `ModelParameters::gas_model_parameter_names`.
+    pub name: String,
+    /// The derivative of the present value with respect to the parameter.
+    pub sensitivity: f64,
+    /// The standard error of `sensitivity`.
+    pub standard_error: f64,
+    /// The 95% confidence interval of `sensitivity`.
+    pub confidence_interval: ConfidenceInterval<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parameters::{GasModel, PowerModel, Volatility};
+    use crate::core::processes::heston_process_transformer::VarianceScheme;
+    use crate::core::processes::regime_switching_process_transformer::Regime;
+    use crate::core::services::calculate_profit::{calculate_daily_profits, CalculateProfitArgs};
+    use crate::core::services::calculate_pv::{calculate_pv, CalculatePvArgs};
+    use crate::core::simulator::simulation_config::NoiseScheme;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+
+    fn greeks_args(noise_source: NoiseSource) -> CalculateGreeksArgs {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        CalculateGreeksArgs {
+            gas_curve: Array1::from_elem(72, 3.5),
+            power_curve: Array1::from_elem(72, 45.0),
+            additional_curves: vec![],
+            time_grid: TimeGrid::hourly(start, start, 3, Tz::UTC).unwrap(),
+            model_params: ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6),
+            unit_params: vec![UnitParameter::new(1.67, 400.0, 15000.0)],
+            num_paths: 100,
+            risk_free_rate: 0.02,
+            noise_source,
+            simulation_config: SimulationConfig::new(true, true, NoiseScheme::PseudoRandom),
+        }
+    }
+
+    #[test]
+    fn test_pv_matches_profit_service_with_common_random_numbers() {
+        let noise_source = NoiseSource::new(7);
+        let args = greeks_args(noise_source);
+        let greeks = calculate_greeks(&args).unwrap();
+
+        let profits = calculate_daily_profits(CalculateProfitArgs {
+            gas_curve: args.gas_curve.clone(),
+            power_curve: args.power_curve.clone(),
+            additional_curves: args.additional_curves.clone(),
+            time_grid: args.time_grid.clone(),
+            model_params: args.model_params.clone(),
+            unit_params: args.unit_params.clone(),
+            num_paths: args.num_paths,
+            risk_free_rate: args.risk_free_rate,
+            noise_source,
+            simulation_config: args.simulation_config,
+        })
+        .unwrap();
+        let pv = profits.sum() / args.num_paths as f64;
+
+        assert!((greeks.pv - pv).abs() < 1e-6 * pv.abs());
+    }
+
+    #[test]
+    fn test_scrambled_sobol_agrees_with_pseudo_random() {
+        let pseudo_random = calculate_greeks(&greeks_args(NoiseSource::new(11))).unwrap();
+
+        let mut args = greeks_args(NoiseSource::new(11));
+        args.num_paths = 128;
+        args.simulation_config = SimulationConfig::new(
+            false,
+            false,
+            NoiseScheme::ScrambledSobol {
+                num_replicates: 8,
+                brownian_bridge: true,
+            },
+        );
+        let quasi_random = calculate_greeks(&args).unwrap();
+
+        let tolerance = 4.0 * (pseudo_random.pv_standard_error + quasi_random.pv_standard_error);
+        assert!(quasi_random.pv_standard_error.is_finite());
+        assert!((quasi_random.pv - pseudo_random.pv).abs() < tolerance);
+    }
+
+    #[test]
+    fn test_standard_errors_match_pv_service() {
+        let noise_source = NoiseSource::new(3);
+        let args = greeks_args(noise_source);
+        let greeks = calculate_greeks(&args).unwrap();
+
+        let pv = calculate_pv(&CalculatePvArgs {
+            gas_curve: args.gas_curve.clone(),
+            power_curve: args.power_curve.clone(),
+            additional_curves: args.additional_curves.clone(),
+            time_grid: args.time_grid.clone(),
+            model_params: args.model_params.clone(),
+            unit_params: args.unit_params.clone(),
+            num_paths: args.num_paths,
+            risk_free_rate: args.risk_free_rate,
+            noise_source,
+            simulation_config: args.simulation_config,
+            control_variates: vec![],
+        })
+        .unwrap();
+
+        // The streaming moments of the Greeks reduction agree with the standard error
+        // computed from the stored path values.
+        assert!((greeks.pv_standard_error - pv.standard_error).abs() < 1e-9 * pv.standard_error);
+        let interval = &greeks.pv_confidence_interval;
+        assert!(interval.lower < greeks.pv && greeks.pv < interval.upper);
+
+        let deltas = &greeks.delta_power_confidence_interval;
+        assert_eq!(greeks.delta_power_standard_error.len(), args.power_curve.len());
+        assert!(deltas.lower.iter().zip(&greeks.delta_power).all(|(lower, delta)| lower <= delta));
+        assert!(deltas.upper.iter().zip(&greeks.delta_power).all(|(upper, delta)| upper >= delta));
+        assert!(greeks.vega_power_standard_error[0] > 0.0);
+    }
+
+    #[test]
+    fn test_daily_gas_deltas_sum_the_hourly_deltas() {
+        let hourly = calculate_greeks(&greeks_args(NoiseSource::new(5))).unwrap();
+
+        let mut args = greeks_args(NoiseSource::new(5));
+        args.gas_curve = Array1::from_elem(3, 3.5);
+        let daily = calculate_greeks(&args).unwrap();
+
+        assert_eq!(daily.delta_gas.len(), 3);
+        assert!((daily.pv - hourly.pv).abs() < 1e-9 * hourly.pv.abs());
+        for (day, delta) in daily.delta_gas.iter().enumerate() {
+            let expected: f64 = hourly.delta_gas.slice(s![24 * day..24 * (day + 1)]).sum();
+            assert!((delta - expected).abs() < 1e-9 * expected.abs());
+        }
+        assert_eq!(daily.delta_power, hourly.delta_power);
+    }
+
+    #[test]
+    fn test_heston_gas_sensitivities_match_finite_differences() {
+        let heston = |theta_v: f64, xi: f64| GasModel::Heston {
+            v0: 0.16,
+            kappa_v: 3.0,
+            theta_v,
+            xi,
+            rho_sv: 0.3,
+            scheme: VarianceScheme::QuadraticExponential,
+        };
+        let greeks_for = |gas_model: GasModel<f64>| {
+            let mut args = greeks_args(NoiseSource::new(13));
+            args.model_params = args.model_params.with_gas_model(gas_model);
+            calculate_greeks(&args).unwrap()
+        };
+        let greeks = greeks_for(heston(0.2, 0.8));
+        assert!(greeks.vega_gas.is_empty());
+        let names: Vec<_> = greeks.gas_model_sensitivities.iter().map(|s| &s.name).collect();
+        assert_eq!(names, ["v0", "theta_v", "xi"]);
+
+        // Common random numbers make the bumped values pathwise comparable.
+        let h = 1e-6;
+        let mean_variance =
+            (greeks_for(heston(0.2 + h, 0.8)).pv - greeks_for(heston(0.2 - h, 0.8)).pv) / (2.0 * h);
+        let vol_of_vol =
+            (greeks_for(heston(0.2, 0.8 + h)).pv - greeks_for(heston(0.2, 0.8 - h)).pv) / (2.0 * h);
+        for (aad, finite_difference) in [
+            (greeks.gas_model_sensitivities[1].sensitivity, mean_variance),
+            (greeks.gas_model_sensitivities[2].sensitivity, vol_of_vol),
+        ] {
+            assert!(
+                (aad - finite_difference).abs() < 1e-4 * aad.abs().max(1.0),
+                "{aad} vs {finite_difference}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_daily_power_vegas_sum_to_the_constant_vega() {
+        let constant = calculate_greeks(&greeks_args(NoiseSource::new(9))).unwrap();
+
+        let mut args = greeks_args(NoiseSource::new(9));
+        args.model_params = args
+            .model_params
+            .with_power_volatility(Volatility::Curve(vec![0.5; 3]));
+        let daily = calculate_greeks(&args).unwrap();
+
+        assert_eq!(daily.vega_power_buckets, ["0", "1", "2"]);
+        assert_eq!(constant.vega_power_buckets, ["sigma"]);
+        assert!((daily.pv - constant.pv).abs() < 1e-9 * constant.pv.abs());
+        let total = daily.vega_power.sum();
+        assert!((total - constant.vega_power[0]).abs() < 1e-9 * total.abs());
+    }
+
+    #[test]
+    fn test_regime_switching_power_vegas_match_finite_differences() {
+        let regimes = |scarcity_sigma: f64| PowerModel::RegimeSwitching {
+            regimes: vec![
+                Regime {
+                    level: 0.0,
+                    sigma: 0.5,
+                },
+                Regime {
+                    level: 0.8,
+                    sigma: scarcity_sigma,
+                },
+            ],
+            transition_rates: vec![vec![-20.0, 20.0], vec![150.0, -150.0]],
+        };
+        let greeks_for = |power_model: PowerModel<f64>| {
+            let mut args = greeks_args(NoiseSource::new(11));
+            args.model_params = args.model_params.with_power_model(power_model);
+            calculate_greeks(&args).unwrap()
+        };
+        let greeks = greeks_for(regimes(1.5));
+        assert_eq!(greeks.vega_power_buckets, ["regime_0", "regime_1"]);
+
+        // Common random numbers make the bumped values pathwise comparable.
+        let h = 1e-6;
+        let scarcity =
+            (greeks_for(regimes(1.5 + h)).pv - greeks_for(regimes(1.5 - h)).pv) / (2.0 * h);
+        let aad = greeks.vega_power[1];
+        assert!(aad != 0.0);
+        assert!(
+            (aad - scarcity).abs() < 1e-4 * aad.abs().max(1.0),
+            "{aad} vs {scarcity}"
+        );
+    }
+
+    #[test]
+    fn test_carbon_deltas_match_finite_differences() {
+        let greeks_for = |carbon: f64| {
+            let mut args = greeks_args(NoiseSource::new(17));
+            args.model_params = args.model_params.with_asset(Asset::Carbon, 0.3);
+            args.additional_curves = vec![(Asset::Carbon, Array1::from_elem(72, carbon))];
+            args.unit_params =
+                vec![UnitParameter::new(1.67, 400.0, 15000.0).with_emission_factor(0.37)];
+            calculate_greeks(&args).unwrap()
+        };
+        let greeks = greeks_for(20.0);
+        assert_eq!(greeks.delta_additional.len(), 1);
+        let carbon = &greeks.delta_additional[0];
+        assert_eq!(carbon.asset, Asset::Carbon);
+        assert_eq!(carbon.delta.len(), 72);
+        assert_eq!(carbon.standard_error.len(), 72);
+
+        // Common random numbers make the bumped values pathwise comparable.
+        let h = 1e-4;
+        let finite_difference = (greeks_for(20.0 + h).pv - greeks_for(20.0 - h).pv) / (2.0 * h);
+        let total = carbon.delta.sum();
+        assert!(total < 0.0);
+        assert!(
+            (total - finite_difference).abs() < 1e-4 * total.abs(),
+            "{total} vs {finite_difference}"
+        );
+    }
 }
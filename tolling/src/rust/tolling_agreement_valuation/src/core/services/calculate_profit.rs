@@ -1,7 +1,12 @@
 use crate::core::valuation::profit_and_loss::ProfitCalculator;
 use crate::core::parameters::ModelParameters;
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
 use crate::core::parameters::UnitParameter;
 use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::common::random::NoiseSource;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::simulator::simulation_config::SimulationConfig;
 use anyhow::Result;
 use ndarray::{Array1, Array2};
 
@@ -9,16 +14,27 @@ use ndarray::{Array1, Array2};
 // Returns a (num_paths, num_days) matrix of non-discounted daily profits
 pub fn calculate_daily_profits(args: CalculateProfitArgs) -> Result<Array2<f64>> {
     // 1. Simulate prices
-    let prices = Simulator::simulate(
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
         &args.gas_curve,
         &args.power_curve,
-        &args.model_params,
+        &args.additional_curves,
+    )?;
+    let prices = Simulator::simulate(
+        &forward_curves,
+        &args.time_grid,
+        &price_model,
         args.num_paths,
+        &args.noise_source,
+        &args.simulation_config,
     )?;
 
     // 2. Calculate Daily Profits
     let daily_profits = ProfitCalculator::calculate_daily_profits(
         &prices,
+        &args.time_grid,
         &args.unit_params,
         args.risk_free_rate,
     )?;
@@ -29,8 +45,12 @@ pub fn calculate_daily_profits(args: CalculateProfitArgs) -> Result<Array2<f64>>
 pub struct CalculateProfitArgs {
     pub gas_curve: Array1<f64>,
     pub power_curve: Array1<f64>,
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    pub time_grid: TimeGrid,
     pub model_params: ModelParameters<f64>,
     pub unit_params: Vec<UnitParameter<f64>>,
     pub num_paths: usize,
     pub risk_free_rate: f64,
+    pub noise_source: NoiseSource,
+    pub simulation_config: SimulationConfig,
 }
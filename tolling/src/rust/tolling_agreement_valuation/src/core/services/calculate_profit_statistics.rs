@@ -0,0 +1,255 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, s};
+
+use crate::core::common::parallel::map_chunks_in_order;
+use crate::core::common::random::NoiseSource;
+use crate::core::common::statistics::{BatchMoments, ConfidenceInterval, StreamingQuantile};
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::{ModelParameters, UnitParameter};
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::simulator::simulation_config::SimulationConfig;
+use crate::core::valuation::profit_and_loss::ProfitCalculator;
+
+/// Number of consecutive paths simulated and valued by one parallel task.
+const PATH_CHUNK_SIZE: usize = 64;
+
+/// Calculates summary statistics of the profits without materialising the price paths.
+///
+/// Every path is simulated, valued and folded into running aggregates before the next
+/// path of the same task is simulated, so neither the `(num_paths, num_assets,
+/// num_points)` price cube nor the `(num_paths, num_days)` profit matrix is ever built.
+/// The aggregates are:
+///
+/// - the mean, standard error and 95% confidence interval of the PV and of every day's
+///   discounted profit, from the streaming moments of `BatchMoments`;
+/// - quantiles of the path PV distribution, from the P² estimator of `StreamingQuantile`.
+///
+/// Peak memory therefore depends on the number of points and days, not on the number
+/// of paths. Paths are processed in fixed chunks and folded in path order, so the result
+/// does not depend on the number of threads. The random numbers are those of
+/// `calculate_daily_profits` for the same noise source and configuration.
+///
+/// # Arguments
+///
+/// * `args`: A reference to `CalculateProfitStatisticsArgs` containing all necessary input parameters.
+///
+/// # Errors
+///
+/// Returns an error if a quantile level lies outside `(0, 1)`, or if the model, the
+/// forward curves and the units do not fit together.
+pub fn calculate_profit_statistics(
+    args: &CalculateProfitStatisticsArgs,
+) -> Result<ProfitStatistics> {
+    if let Some(level) = args
+        .quantile_levels
+        .iter()
+        .find(|&&level| !(level > 0.0 && level < 1.0))
+    {
+        bail!("Quantile levels must lie strictly between 0 and 1, got {level}.");
+    }
+
+    let num_paths = args.num_paths;
+    let n_days = args.time_grid.num_days();
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    ProfitCalculator::check_assets(&args.unit_params, price_model.assets())?;
+    let sampler = NoiseSampler::new(
+        args.noise_source,
+        args.simulation_config,
+        price_model.noise_layout(),
+        num_paths,
+        args.time_grid.len(),
+    )?;
+
+    // Path values are laid out as `[pv, daily_profit...]`.
+    let layout = sampler.batch_layout(num_paths);
+    let mut moments = BatchMoments::with_layout(1 + n_days, layout);
+    let mut quantiles: Vec<StreamingQuantile> = args
+        .quantile_levels
+        .iter()
+        .map(|&level| StreamingQuantile::new(level))
+        .collect();
+
+    map_chunks_in_order(
+        num_paths,
+        PATH_CHUNK_SIZE,
+        |paths| {
+            let mut chunk_moments = BatchMoments::with_layout(1 + n_days, layout);
+            let mut path_values = Vec::with_capacity(paths.len());
+            let mut values = Array1::zeros(1 + n_days);
+            for path_idx in paths {
+                // Simulate and value a single path.
+                let noise = sampler.path_noise(path_idx);
+                let assets = Simulator::simulate_single_path(
+                    &forward_curves,
+                    &args.time_grid,
+                    &price_model,
+                    &noise,
+                );
+                let daily_profits = ProfitCalculator::calculate_single_path(
+                    &assets.view(),
+                    price_model.assets(),
+                    &args.time_grid,
+                    &args.unit_params,
+                    args.risk_free_rate,
+                );
+
+                // Fold it into the aggregates of the chunk.
+                let pv: f64 = daily_profits.iter().sum();
+                values[0] = pv;
+                values
+                    .slice_mut(s![1..])
+                    .assign(&Array1::from(daily_profits));
+                chunk_moments.add(path_idx, values.view());
+                path_values.push(pv);
+            }
+            (chunk_moments, path_values)
+        },
+        |(chunk_moments, path_values)| {
+            moments.merge(chunk_moments);
+            for quantile in &mut quantiles {
+                path_values.iter().for_each(|&pv| quantile.add(pv));
+            }
+        },
+    );
+
+    let mean = moments.mean();
+    let standard_error = moments.standard_error();
+    let confidence_interval = moments.confidence_interval();
+    let days = s![1..];
+
+    Ok(ProfitStatistics {
+        num_paths,
+        pv: mean[0],
+        pv_standard_error: standard_error[0],
+        pv_confidence_interval: ConfidenceInterval {
+            lower: confidence_interval.lower[0],
+            upper: confidence_interval.upper[0],
+        },
+        pv_quantiles: quantiles
+            .iter()
+            .map(|quantile| (quantile.probability(), quantile.estimate()))
+            .collect(),
+        daily_profit_mean: mean.slice(days).to_owned(),
+        daily_profit_standard_error: standard_error.slice(days).to_owned(),
+        daily_profit_confidence_interval: ConfidenceInterval {
+            lower: confidence_interval.lower.slice(days).to_owned(),
+            upper: confidence_interval.upper.slice(days).to_owned(),
+        },
+    })
+}
+
+/// Arguments required for the `calculate_profit_statistics` function.
+pub struct CalculateProfitStatisticsArgs {
+    /// The forward curve for gas prices.
+    pub gas_curve: Array1<f64>,
+    /// The forward curve for power prices.
+    pub power_curve: Array1<f64>,
+    /// The forward curves of the additional assets of `model_params`, e.g. carbon.
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    /// The calendar time of every point of the forward curves.
+    pub time_grid: TimeGrid,
+    /// Parameters for the stochastic models.
+    pub model_params: ModelParameters<f64>,
+    /// Parameters defining the power generation units.
+    pub unit_params: Vec<UnitParameter<f64>>,
+    /// The number of Monte Carlo paths to simulate.
+    pub num_paths: usize,
+    /// The annual risk-free interest rate for discounting.
+    pub risk_free_rate: f64,
+    /// The source of the random numbers driving the simulation.
+    pub noise_source: NoiseSource,
+    /// The random number scheme and variance reduction techniques of the simulation.
+    pub simulation_config: SimulationConfig,
+    /// The probabilities at which quantiles of the path PV distribution are estimated.
+    pub quantile_levels: Vec<f64>,
+}
+
+/// Holds the streaming statistics of the profits.
+#[derive(Debug, Clone)]
+pub struct ProfitStatistics {
+    /// The number of paths the statistics are based on.
+    pub num_paths: usize,
+    /// The present value of the agreement, i.e. the mean of the path PVs.
+    pub pv: f64,
+    /// The standard error of `pv`.
+    pub pv_standard_error: f64,
+    /// The 95% confidence interval of `pv`.
+    pub pv_confidence_interval: ConfidenceInterval<f64>,
+    /// `(probability, quantile)` pairs of the distribution of the path PVs, in the order
+    /// of the requested levels. These describe the spread of outcomes, not the accuracy
+    /// of `pv`.
+    pub pv_quantiles: Vec<(f64, f64)>,
+    /// The mean discounted profit of every day.
+    pub daily_profit_mean: Array1<f64>,
+    /// The standard error of every element of `daily_profit_mean`.
+    pub daily_profit_standard_error: Array1<f64>,
+    /// The 95% confidence interval of every element of `daily_profit_mean`.
+    pub daily_profit_confidence_interval: ConfidenceInterval<Array1<f64>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::services::calculate_profit::{CalculateProfitArgs, calculate_daily_profits};
+    use crate::core::simulator::simulation_config::NoiseScheme;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::Axis;
+
+    #[test]
+    fn streaming_statistics_match_the_profit_matrix() {
+        let n_points = 24 * 4;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let args = CalculateProfitStatisticsArgs {
+            gas_curve: Array1::from_elem(n_points, 20.0),
+            power_curve: Array1::from_elem(n_points, 45.0),
+            additional_curves: vec![],
+            time_grid: TimeGrid::hourly(start, start, 4, Tz::UTC).unwrap(),
+            model_params: ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6),
+            unit_params: vec![UnitParameter::new(2.0, 100.0, 500.0)],
+            num_paths: 301,
+            risk_free_rate: 0.02,
+            noise_source: NoiseSource::new(9),
+            simulation_config: SimulationConfig::new(true, false, NoiseScheme::PseudoRandom),
+            quantile_levels: vec![0.5],
+        };
+        let statistics = calculate_profit_statistics(&args).unwrap();
+
+        let profits = calculate_daily_profits(CalculateProfitArgs {
+            gas_curve: args.gas_curve.clone(),
+            power_curve: args.power_curve.clone(),
+            additional_curves: args.additional_curves.clone(),
+            time_grid: args.time_grid.clone(),
+            model_params: args.model_params.clone(),
+            unit_params: args.unit_params.clone(),
+            num_paths: args.num_paths,
+            risk_free_rate: args.risk_free_rate,
+            noise_source: args.noise_source,
+            simulation_config: args.simulation_config,
+        })
+        .unwrap();
+
+        let daily_mean = profits.mean_axis(Axis(0)).unwrap();
+        let max_error = (&statistics.daily_profit_mean - &daily_mean)
+            .mapv(f64::abs)
+            .fold(0.0, |a: f64, &b| a.max(b));
+        assert!(max_error < 1e-8 * daily_mean.sum().abs());
+        assert!((statistics.pv - daily_mean.sum()).abs() < 1e-8 * daily_mean.sum().abs());
+
+        // The streaming median lies between the quartiles of the path PVs.
+        let mut path_pvs = profits.sum_axis(Axis(1)).to_vec();
+        path_pvs.sort_by(f64::total_cmp);
+        let median = statistics.pv_quantiles[0].1;
+        assert!(path_pvs[args.num_paths / 4] < median && median < path_pvs[3 * args.num_paths / 4]);
+    }
+}
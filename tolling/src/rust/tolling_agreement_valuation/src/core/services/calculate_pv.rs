@@ -0,0 +1,309 @@
+use ndarray::Array1;
+use rayon::prelude::*;
+
+use crate::core::common::random::NoiseSource;
+use crate::core::common::statistics::{batch_standard_error, ConfidenceInterval};
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::{ModelParameters, UnitParameter};
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::price_model::PriceModel;
+use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::simulator::simulation_config::SimulationConfig;
+use crate::core::valuation::control_variates::{ControlVariate, ControlVariates};
+use crate::core::valuation::profit_and_loss::ProfitCalculator;
+use anyhow::{Result, bail};
+
+/// Calculates the present value of the tolling agreement.
+///
+/// Every path is simulated and valued on its own, so only the path values (and the
+/// controls, if any) are kept in memory. If control variates are requested, the PV is
+/// corrected by the controls and the result reports how much they reduced the variance
+/// of the estimate.
+///
+/// The random numbers are the same as those of `calculate_daily_profits` and
+/// `calculate_greeks` for the same noise source and configuration, so without control
+/// variates the PV equals the sum of the mean daily profits.
+///
+/// # Arguments
+///
+/// * `args`: A reference to `CalculatePvArgs` containing all necessary input parameters.
+pub fn calculate_pv(args: &CalculatePvArgs) -> Result<PvResult> {
+    let price_model = args.model_params.price_model()?;
+    calculate_pv_under(args, &price_model)
+}
+
+/// Calculates the present value of the tolling agreement with the prices simulated by
+/// `price_model`, e.g. a model with a user-defined `PriceProcess`.
+///
+/// This equals `calculate_pv` except that `args.model_params` are ignored. The model must
+/// simulate gas and power and every further asset of `args.additional_curves`.
+///
+/// # Errors
+///
+/// Returns an error if control variates are requested, because their expectations are
+/// derived from `args.model_params`, or if the curves do not match the assets of
+/// `price_model`.
+pub fn calculate_pv_with_model(
+    args: &CalculatePvArgs,
+    price_model: &PriceModel<f64>,
+) -> Result<PvResult> {
+    if !args.control_variates.is_empty() {
+        bail!("Control variates need the price model of the model parameters.");
+    }
+    calculate_pv_under(args, price_model)
+}
+
+/// Calculates the present value of the tolling agreement with the prices simulated by
+/// `price_model`, which is that of `args.model_params` if control variates are used.
+fn calculate_pv_under(args: &CalculatePvArgs, price_model: &PriceModel<f64>) -> Result<PvResult> {
+    let num_paths = args.num_paths;
+    let forward_curves = forward_curves(
+        price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    ProfitCalculator::check_assets(&args.unit_params, price_model.assets())?;
+    let sampler = NoiseSampler::new(
+        args.noise_source,
+        args.simulation_config,
+        price_model.noise_layout(),
+        num_paths,
+        args.time_grid.len(),
+    )?;
+    // The curves were ordered for the model, which therefore contains gas and power.
+    let gas = price_model.index_of(Asset::Gas).unwrap();
+    let power = price_model.index_of(Asset::Power).unwrap();
+    let control_variates = ControlVariates::new(
+        &args.control_variates,
+        &forward_curves[gas],
+        &forward_curves[power],
+        &args.time_grid,
+        &args.model_params,
+        &args.unit_params,
+        args.risk_free_rate,
+    )?;
+
+    // Simulate and value the paths in parallel. `collect` keeps the path order, so the
+    // sums below do not depend on the number of threads.
+    let (path_values, path_controls): (Vec<f64>, Vec<Vec<f64>>) = (0..num_paths)
+        .into_par_iter()
+        .map(|path_idx| {
+            let noise = sampler.path_noise(path_idx);
+            let assets = Simulator::simulate_single_path(
+                &forward_curves,
+                &args.time_grid,
+                price_model,
+                &noise,
+            );
+            let value: f64 = ProfitCalculator::calculate_single_path(
+                &assets.view(),
+                price_model.assets(),
+                &args.time_grid,
+                &args.unit_params,
+                args.risk_free_rate,
+            )
+            .iter()
+            .sum();
+            let controls =
+                control_variates.path_controls(assets.row(gas), assets.row(power), &noise);
+            (value, controls)
+        })
+        .unzip();
+
+    let num_batches = sampler.num_batches(num_paths);
+    let standard_error = |values: &[f64]| {
+        batch_standard_error(values, |path_idx| sampler.batch_of(path_idx), num_batches)
+    };
+    let uncorrected_pv = path_values.iter().sum::<f64>() / num_paths as f64;
+    let uncorrected_standard_error = standard_error(&path_values);
+
+    if control_variates.is_empty() {
+        return Ok(PvResult {
+            pv: uncorrected_pv,
+            standard_error: uncorrected_standard_error,
+            confidence_interval: ConfidenceInterval::<f64>::from_standard_error(
+                uncorrected_pv,
+                uncorrected_standard_error,
+                num_batches,
+            ),
+            control_variates: None,
+        });
+    }
+
+    let estimate = control_variates.apply(&path_values, &path_controls);
+    let corrected_standard_error = standard_error(&estimate.adjusted_values);
+    let report = ControlVariateReport {
+        uncorrected_pv,
+        uncorrected_standard_error,
+        coefficients: estimate.coefficients,
+        variance_reduction: (uncorrected_standard_error / corrected_standard_error).powi(2),
+    };
+
+    Ok(PvResult {
+        pv: estimate.estimate,
+        standard_error: corrected_standard_error,
+        confidence_interval: ConfidenceInterval::<f64>::from_standard_error(
+            estimate.estimate,
+            corrected_standard_error,
+            num_batches,
+        ),
+        control_variates: Some(report),
+    })
+}
+
+/// Arguments required for the `calculate_pv` function.
+pub struct CalculatePvArgs {
+    /// The forward curve for gas prices.
+    pub gas_curve: Array1<f64>,
+    /// The forward curve for power prices.
+    pub power_curve: Array1<f64>,
+    /// The forward curves of the additional assets of `model_params`, e.g. carbon.
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    /// The calendar time of every point of the forward curves.
+    pub time_grid: TimeGrid,
+    /// Parameters for the stochastic models.
+    pub model_params: ModelParameters<f64>,
+    /// Parameters defining the power generation units.
+    pub unit_params: Vec<UnitParameter<f64>>,
+    /// The number of Monte Carlo paths to simulate.
+    pub num_paths: usize,
+    /// The annual risk-free interest rate for discounting.
+    pub risk_free_rate: f64,
+    /// The source of the random numbers driving the simulation.
+    pub noise_source: NoiseSource,
+    /// The random number scheme and variance reduction techniques of the simulation.
+    pub simulation_config: SimulationConfig,
+    /// The control variates applied to the PV. Empty for the plain Monte Carlo estimate.
+    pub control_variates: Vec<ControlVariate>,
+}
+
+/// Holds the results of the PV calculation.
+#[derive(Debug, Clone)]
+pub struct PvResult {
+    /// The present value of the agreement.
+    pub pv: f64,
+    /// The standard error of `pv`.
+    pub standard_error: f64,
+    /// The 95% confidence interval of `pv`.
+    pub confidence_interval: ConfidenceInterval<f64>,
+    /// Details of the control-variate correction, if control variates were used.
+    pub control_variates: Option<ControlVariateReport>,
+}
+
+/// Describes the effect of the control variates on the PV estimate.
+#[derive(Debug, Clone)]
+pub struct ControlVariateReport {
+    /// The plain Monte Carlo estimate of the PV, before the correction.
+    pub uncorrected_pv: f64,
+    /// The standard error of `uncorrected_pv`.
+    pub uncorrected_standard_error: f64,
+    /// The regression coefficient of every control, in the order they were requested.
+    pub coefficients: Vec<f64>,
+    /// The ratio of the variance of the plain estimate to that of the corrected one,
+    /// i.e. the factor by which the path count would have to grow to reach the same
+    /// accuracy without control variates.
+    pub variance_reduction: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::core::common::correlation::CorrelationRepair;
+    use crate::core::processes::geometric_brownian_motion_transformer::GeometricBrownianMotionTransformer;
+    use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+    use chrono_tz::Tz;
+    use ndarray::{ArrayViewMut1, array};
+
+    fn args(control_variates: Vec<ControlVariate>) -> CalculatePvArgs {
+        let n_points = 24 * 5;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        CalculatePvArgs {
+            gas_curve: Array1::linspace(20.0, 22.0, n_points),
+            power_curve: Array1::linspace(45.0, 50.0, n_points),
+            additional_curves: vec![],
+            time_grid: TimeGrid::hourly(start, start, 5, Tz::UTC).unwrap(),
+            model_params: ModelParameters::new(0.4, 0.6, 30.0, 20.0, 0.2, 0.3, 0.5),
+            unit_params: vec![
+                UnitParameter::new(2.0, 100.0, 500.0),
+                UnitParameter::new(2.5, 50.0, 200.0),
+            ],
+            num_paths: 2000,
+            risk_free_rate: 0.03,
+            noise_source: NoiseSource::new(11),
+            simulation_config: SimulationConfig::default(),
+            control_variates,
+        }
+    }
+
+    #[test]
+    fn control_variates_reduce_the_variance() {
+        let plain = calculate_pv(&args(vec![])).unwrap();
+        let corrected = calculate_pv(&args(vec![
+            ControlVariate::AverageGasPrice,
+            ControlVariate::AveragePowerPrice,
+            ControlVariate::SpreadOption,
+        ]))
+        .unwrap();
+
+        let report = corrected.control_variates.unwrap();
+        assert_eq!(report.uncorrected_pv, plain.pv);
+        assert!(report.variance_reduction > 2.0);
+        assert!((corrected.pv - plain.pv).abs() < 4.0 * plain.standard_error);
+    }
+
+    /// A power process that sells at the forward price plus its own uniform draw.
+    struct ForwardPlusUniform;
+
+    impl PriceProcess<f64> for ForwardPlusUniform {
+        fn num_uniforms(&self) -> usize {
+            1
+        }
+
+        fn transform_path(
+            &self,
+            forward_curve: &Array1<f64>,
+            _time_grid: &TimeGrid,
+            mut path: ArrayViewMut1<f64>,
+            noise: ProcessNoise<'_>,
+        ) {
+            path.assign(&(forward_curve + &noise.uniforms.row(0)));
+        }
+
+        fn expected_path(&self, forward_curve: &Array1<f64>, _time_grid: &TimeGrid) -> Array1<f64> {
+            forward_curve + 0.5
+        }
+    }
+
+    #[test]
+    fn values_custom_price_processes() {
+        let args = args(vec![]);
+        let model = args.model_params.price_model().unwrap();
+        let own_model = calculate_pv_with_model(&args, &model).unwrap();
+        assert_eq!(own_model.pv, calculate_pv(&args).unwrap().pv);
+
+        // Flat gas and power 0.5 above the forward on average.
+        let custom = PriceModel::new(
+            vec![
+                (Asset::Gas, Box::new(GeometricBrownianMotionTransformer::new(0.0))),
+                (Asset::Power, Box::new(ForwardPlusUniform)),
+            ],
+            &array![[1.0, 0.0], [0.0, 1.0]],
+            CorrelationRepair::Reject,
+        )
+        .unwrap();
+        let result = calculate_pv_with_model(&args, &custom).unwrap();
+        assert!(result.pv.is_finite() && result.standard_error > 0.0);
+        assert_ne!(result.pv, own_model.pv);
+
+        let with_controls = CalculatePvArgs {
+            control_variates: vec![ControlVariate::AverageGasPrice],
+            ..args
+        };
+        assert!(calculate_pv_with_model(&with_controls, &custom).is_err());
+    }
+}
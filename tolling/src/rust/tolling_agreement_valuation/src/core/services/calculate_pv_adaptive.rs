@@ -0,0 +1,377 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+use ndarray::{Array1, Axis};
+
+use crate::core::common::random::NoiseSource;
+use crate::core::common::statistics::{BatchMoments, ConfidenceInterval};
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::{ModelParameters, UnitParameter};
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::simulator::simulation_config::{NoiseScheme, SimulationConfig};
+use crate::core::valuation::profit_and_loss::ProfitCalculator;
+
+/// Calculates the present value of the tolling agreement with as many paths as needed.
+///
+/// Paths are simulated and valued in steps of `paths_per_step` paths, using
+/// `Simulator::simulate_paths` and `ProfitCalculator::calculate_daily_profits`. After
+/// every step the PV and its standard error are updated from streaming statistics, and
+/// the run stops as soon as the standard error is at most `tolerance`, the path budget
+/// `max_paths` is used up or the time budget `max_duration` has passed. Only the profits
+/// of one step are held in memory at a time.
+///
+/// The paths are the first paths of the same noise as a `calculate_pv` run with the same
+/// noise source and configuration, so the result equals the plain estimate of such a run
+/// with `num_paths` paths.
+///
+/// # Arguments
+///
+/// * `args`: A reference to `CalculatePvAdaptiveArgs` containing all necessary input parameters.
+///
+/// # Errors
+///
+/// Returns an error if moment matching is enabled, because it needs the noise of all
+/// paths before the first path can be simulated, if `max_paths` is smaller than one
+/// path per antithetic pair and quasi-Monte Carlo replicate, or if quasi-Monte Carlo
+/// needs more than 2^32 points per replicate for `max_paths` paths.
+pub fn calculate_pv_adaptive(args: &CalculatePvAdaptiveArgs) -> Result<AdaptivePvResult> {
+    let config = args.simulation_config;
+    if config.moment_matching {
+        bail!("Moment matching needs the final number of paths and cannot be used adaptively.");
+    }
+    if args.paths_per_step == 0 {
+        bail!("The number of paths per step must be positive.");
+    }
+    // Steps are rounded up to whole antithetic pairs and, under quasi-Monte Carlo, to the
+    // same number of points in every replicate.
+    let num_replicates = match config.noise_scheme {
+        NoiseScheme::PseudoRandom => None,
+        NoiseScheme::ScrambledSobol { num_replicates, .. } => Some(num_replicates.max(1)),
+    };
+    let granularity = if config.antithetic { 2 } else { 1 } * num_replicates.unwrap_or(1);
+    if args.max_paths < granularity {
+        bail!(
+            "The path budget of {} paths is smaller than one path per antithetic pair and \
+             replicate, {} paths.",
+            args.max_paths,
+            granularity
+        );
+    }
+
+    let start_time = Instant::now();
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    ProfitCalculator::check_assets(&args.unit_params, price_model.assets())?;
+    let sampler = NoiseSampler::new(
+        args.noise_source,
+        config,
+        price_model.noise_layout(),
+        args.max_paths,
+        args.time_grid.len(),
+    )?;
+
+    let paths_per_step = args.paths_per_step.next_multiple_of(granularity);
+
+    let mut statistics = match num_replicates {
+        None => PvStatistics::Independent(BatchMoments::new(1)),
+        Some(num_replicates) => PvStatistics::Replicates {
+            sums: vec![0.0; num_replicates],
+            counts: vec![0; num_replicates],
+        },
+    };
+
+    let mut history = Vec::new();
+    let mut num_paths = 0;
+    let stop_reason = loop {
+        let end = (num_paths + paths_per_step).min(args.max_paths);
+
+        // 1. Simulate and value the paths of this step.
+        let prices = Simulator::simulate_paths(
+            &forward_curves,
+            &args.time_grid,
+            &price_model,
+            &sampler,
+            num_paths..end,
+        )?;
+        let daily_profits = ProfitCalculator::calculate_daily_profits(
+            &prices,
+            &args.time_grid,
+            &args.unit_params,
+            args.risk_free_rate,
+        )?;
+        let path_values = daily_profits.sum_axis(Axis(1));
+
+        // 2. Update the streaming statistics.
+        statistics.add(&sampler, num_paths, &path_values);
+        num_paths = end;
+        let (pv, standard_error, _) = statistics.estimate();
+        history.push(ConvergencePoint {
+            num_paths,
+            pv,
+            standard_error,
+            elapsed: start_time.elapsed(),
+        });
+
+        // 3. Check the stopping criteria.
+        if standard_error <= args.tolerance {
+            break StopReason::ToleranceReached;
+        }
+        if num_paths >= args.max_paths {
+            break StopReason::PathBudgetExhausted;
+        }
+        if args
+            .max_duration
+            .is_some_and(|max_duration| start_time.elapsed() >= max_duration)
+        {
+            break StopReason::TimeBudgetExhausted;
+        }
+    };
+
+    let (pv, standard_error, num_batches) = statistics.estimate();
+    Ok(AdaptivePvResult {
+        pv,
+        standard_error,
+        confidence_interval: ConfidenceInterval::<f64>::from_standard_error(
+            pv,
+            standard_error,
+            num_batches,
+        ),
+        num_paths,
+        stop_reason,
+        history,
+    })
+}
+
+/// Streaming statistics of the path values.
+enum PvStatistics {
+    /// Batches are single paths or antithetic pairs. Steps hold whole pairs, so every
+    /// batch is complete at the end of the step it starts in.
+    Independent(BatchMoments),
+    /// Batches are quasi-random replicates, which receive paths in every step. Only the
+    /// running sum and count of every replicate are kept.
+    Replicates { sums: Vec<f64>, counts: Vec<usize> },
+}
+
+impl PvStatistics {
+    /// Adds the values of the paths `first_path..first_path + path_values.len()`.
+    fn add(&mut self, sampler: &NoiseSampler, first_path: usize, path_values: &Array1<f64>) {
+        match self {
+            PvStatistics::Independent(moments) => {
+                // The paths of a batch are consecutive.
+                let mut current: Option<(usize, f64, usize)> = None;
+                for (offset, &value) in path_values.iter().enumerate() {
+                    let batch = sampler.batch_of(first_path + offset);
+                    match &mut current {
+                        Some((current_batch, sum, count)) if *current_batch == batch => {
+                            *sum += value;
+                            *count += 1;
+                        }
+                        _ => {
+                            if let Some((_, sum, count)) = current {
+                                moments.add_batch(Array1::from_elem(1, sum).view(), count);
+                            }
+                            current = Some((batch, value, 1));
+                        }
+                    }
+                }
+                if let Some((_, sum, count)) = current {
+                    moments.add_batch(Array1::from_elem(1, sum).view(), count);
+                }
+            }
+            PvStatistics::Replicates { sums, counts } => {
+                for (offset, &value) in path_values.iter().enumerate() {
+                    let batch = sampler.batch_of(first_path + offset);
+                    sums[batch] += value;
+                    counts[batch] += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the current PV, its standard error and the number of batches.
+    fn estimate(&self) -> (f64, f64, usize) {
+        let replicate_moments;
+        let moments = match self {
+            PvStatistics::Independent(moments) => moments,
+            PvStatistics::Replicates { sums, counts } => {
+                let mut moments = BatchMoments::new(1);
+                for (&sum, &count) in sums.iter().zip(counts) {
+                    moments.add_batch(Array1::from_elem(1, sum).view(), count);
+                }
+                replicate_moments = moments;
+                &replicate_moments
+            }
+        };
+        (
+            moments.mean()[0],
+            moments.standard_error()[0],
+            moments.num_batches(),
+        )
+    }
+}
+
+/// Arguments required for the `calculate_pv_adaptive` function.
+pub struct CalculatePvAdaptiveArgs {
+    /// The forward curve for gas prices.
+    pub gas_curve: Array1<f64>,
+    /// The forward curve for power prices.
+    pub power_curve: Array1<f64>,
+    /// The forward curves of the additional assets of `model_params`, e.g. carbon.
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    /// The calendar time of every point of the forward curves.
+    pub time_grid: TimeGrid,
+    /// Parameters for the stochastic models.
+    pub model_params: ModelParameters<f64>,
+    /// Parameters defining the power generation units.
+    pub unit_params: Vec<UnitParameter<f64>>,
+    /// The annual risk-free interest rate for discounting.
+    pub risk_free_rate: f64,
+    /// The source of the random numbers driving the simulation.
+    pub noise_source: NoiseSource,
+    /// The random number scheme and variance reduction techniques of the simulation.
+    /// Moment matching is not supported.
+    pub simulation_config: SimulationConfig,
+    /// The target standard error of the PV, in the currency of the PV.
+    pub tolerance: f64,
+    /// The number of paths simulated between two checks of the standard error.
+    pub paths_per_step: usize,
+    /// The maximum number of paths to simulate, at least one per antithetic pair and
+    /// quasi-Monte Carlo replicate.
+    pub max_paths: usize,
+    /// The maximum wall-clock time to spend. The step running when the time is up is
+    /// completed. `None` for no time limit.
+    pub max_duration: Option<Duration>,
+}
+
+/// The reason an adaptive run stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The standard error reached the tolerance.
+    ToleranceReached,
+    /// The maximum number of paths was simulated.
+    PathBudgetExhausted,
+    /// The maximum duration passed.
+    TimeBudgetExhausted,
+}
+
+/// The state of an adaptive run after one step.
+#[derive(Debug, Clone)]
+pub struct ConvergencePoint {
+    /// The number of paths simulated so far.
+    pub num_paths: usize,
+    /// The PV estimate from these paths.
+    pub pv: f64,
+    /// The standard error of `pv`.
+    pub standard_error: f64,
+    /// The time elapsed since the start of the run.
+    pub elapsed: Duration,
+}
+
+/// Holds the results of the adaptive PV calculation.
+#[derive(Debug, Clone)]
+pub struct AdaptivePvResult {
+    /// The present value of the agreement.
+    pub pv: f64,
+    /// The standard error of `pv`.
+    pub standard_error: f64,
+    /// The 95% confidence interval of `pv`.
+    pub confidence_interval: ConfidenceInterval<f64>,
+    /// The number of paths used.
+    pub num_paths: usize,
+    /// Why the run stopped. Unless this is `ToleranceReached`, the standard error is
+    /// above the tolerance.
+    pub stop_reason: StopReason,
+    /// The estimate after every step, in order.
+    pub history: Vec<ConvergencePoint>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::services::calculate_pv::{CalculatePvArgs, calculate_pv};
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+
+    fn args(tolerance: f64, max_paths: usize) -> CalculatePvAdaptiveArgs {
+        let n_points = 24 * 3;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        CalculatePvAdaptiveArgs {
+            gas_curve: Array1::from_elem(n_points, 20.0),
+            power_curve: Array1::from_elem(n_points, 45.0),
+            additional_curves: vec![],
+            time_grid: TimeGrid::hourly(start, start, 3, Tz::UTC).unwrap(),
+            model_params: ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6),
+            unit_params: vec![UnitParameter::new(2.0, 100.0, 500.0)],
+            risk_free_rate: 0.02,
+            noise_source: NoiseSource::new(5),
+            simulation_config: SimulationConfig::new(true, false, NoiseScheme::PseudoRandom),
+            tolerance,
+            paths_per_step: 101,
+            max_paths,
+            max_duration: None,
+        }
+    }
+
+    #[test]
+    fn stops_at_tolerance_with_the_fixed_path_estimate() {
+        let budget = args(0.0, 400);
+        let full_run = calculate_pv_adaptive(&budget).unwrap();
+        assert_eq!(full_run.stop_reason, StopReason::PathBudgetExhausted);
+        assert_eq!(full_run.num_paths, 400);
+
+        // Ask for an error between those of the first and the last step.
+        let first_error = full_run.history[0].standard_error;
+        let tolerance = 0.5 * (first_error + full_run.standard_error);
+        let adaptive = calculate_pv_adaptive(&args(tolerance, 400)).unwrap();
+        assert_eq!(adaptive.stop_reason, StopReason::ToleranceReached);
+        assert!(adaptive.standard_error <= tolerance);
+        assert!(adaptive.num_paths < 400);
+        // Steps are rounded up to whole antithetic pairs.
+        assert_eq!(adaptive.history[0].num_paths, 102);
+
+        let fixed = calculate_pv(&CalculatePvArgs {
+            gas_curve: budget.gas_curve.clone(),
+            power_curve: budget.power_curve.clone(),
+            additional_curves: budget.additional_curves.clone(),
+            time_grid: budget.time_grid.clone(),
+            model_params: budget.model_params.clone(),
+            unit_params: budget.unit_params.clone(),
+            num_paths: adaptive.num_paths,
+            risk_free_rate: budget.risk_free_rate,
+            noise_source: budget.noise_source,
+            simulation_config: budget.simulation_config,
+            control_variates: vec![],
+        })
+        .unwrap();
+        assert!((adaptive.pv - fixed.pv).abs() < 1e-9 * fixed.pv.abs());
+        assert!(
+            (adaptive.standard_error - fixed.standard_error).abs() < 1e-9 * fixed.standard_error
+        );
+    }
+
+    #[test]
+    fn rejects_path_budgets_it_cannot_use() {
+        // Less than one antithetic pair.
+        assert!(calculate_pv_adaptive(&args(0.0, 0)).is_err());
+        assert!(calculate_pv_adaptive(&args(0.0, 1)).is_err());
+
+        // Less than one pair per replicate, and more points than the Sobol sequence has.
+        let mut quasi_random = args(0.0, 7);
+        quasi_random.simulation_config.noise_scheme = NoiseScheme::ScrambledSobol {
+            num_replicates: 4,
+            brownian_bridge: true,
+        };
+        assert!(calculate_pv_adaptive(&quasi_random).is_err());
+        quasi_random.max_paths = usize::MAX;
+        assert!(calculate_pv_adaptive(&quasi_random).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{Result, bail};
+use ndarray::Array1;
+
+use crate::core::calibration::historical::{HistoricalCalibration, HistoricalCalibrator};
+use crate::core::calibration::seasonal_shape::SeasonalShape;
+use crate::core::common::time_grid::TimeGrid;
+
+/// Estimates the parameters of `ModelParameters` from historical gas and power prices.
+///
+/// Every price series is turned into log prices less the log of its shape, e.g. the
+/// forward curve the prices were quoted against. Without a shape, power prices are
+/// taken relative to a seasonal shape of month, hour and weekend effects fitted to their
+/// log by `SeasonalShape`, while gas prices are taken as they are: gas is a random walk
+/// in the log, whose level has no stable seasonal pattern to fit. The deseasonalised
+/// series are then fitted by maximum likelihood, see `HistoricalCalibrator::calibrate`.
+///
+/// With a `power_shift`, the power prices and their shape are shifted by it before the
+/// logs are taken, as in `ModelParameters::power_shift`, so negative power prices can be
+/// fitted; the estimates then belong to parameters with the same shift.
+///
+/// # Errors
+///
+/// Returns an error if a series or shape has neither one point per point of the time
+/// grid nor one per local day, if the power shift is negative, if a price or shape plus
+/// its shift is not positive, or if the series are too short to fit.
+pub fn calibrate_historical(args: &CalibrateHistoricalArgs) -> Result<HistoricalCalibration> {
+    let gas = deseasonalised_log_prices(
+        "gas",
+        &args.gas_prices,
+        args.gas_shape.as_ref(),
+        false,
+        0.0,
+        &args.time_grid,
+    )?;
+    let power = deseasonalised_log_prices(
+        "power",
+        &args.power_prices,
+        args.power_shape.as_ref(),
+        true,
+        args.power_shift.unwrap_or(0.0),
+        &args.time_grid,
+    )?;
+    HistoricalCalibrator::calibrate(&gas, &power, &args.time_grid)
+}
+
+/// Returns the log prices of an asset less the log of their shape at every point of
+/// `time_grid`, both shifted by `shift`, fitting a seasonal shape if none is given and
+/// `fit_shape` is set.
+fn deseasonalised_log_prices(
+    name: &str,
+    prices: &Array1<f64>,
+    shape: Option<&Array1<f64>>,
+    fit_shape: bool,
+    shift: f64,
+    time_grid: &TimeGrid,
+) -> Result<Array1<f64>> {
+    if !(shift >= 0.0 && shift.is_finite()) {
+        bail!("The shift of {name} must not be negative.");
+    }
+    let log_curve = |curve: &Array1<f64>, kind: &str| -> Result<Array1<f64>> {
+        let curve = time_grid
+            .align_curve(curve)
+            .map_err(|e| anyhow::anyhow!("The {kind} of {name} does not fit: {}", e))?
+            + shift;
+        if curve.iter().any(|&x| !(x > 0.0 && x.is_finite())) {
+            if shift > 0.0 {
+                bail!(
+                    "The {kind} of {name} must exceed -{shift}, as the model is lognormal in \
+                     the {kind} plus the shift."
+                );
+            }
+            bail!("The {kind} of {name} must be positive, as the model is lognormal.");
+        }
+        Ok(curve.mapv(f64::ln))
+    };
+    let log_prices = log_curve(prices, "prices")?;
+    Ok(match shape {
+        Some(shape) => log_prices - log_curve(shape, "shape")?,
+        None if fit_shape => {
+            let log_shape = SeasonalShape::fit(&log_prices, time_grid)?.log_levels(time_grid);
+            log_prices - log_shape
+        }
+        None => log_prices,
+    })
+}
+
+/// Arguments required for the `calibrate_historical` function.
+pub struct CalibrateHistoricalArgs {
+    /// The historical gas prices.
+    pub gas_prices: Array1<f64>,
+    /// The historical power prices.
+    pub power_prices: Array1<f64>,
+    /// The time of every observation.
+    pub time_grid: TimeGrid,
+    /// The shape of the gas prices; none if omitted.
+    pub gas_shape: Option<Array1<f64>>,
+    /// The shape of the power prices; fitted to them if omitted.
+    pub power_shape: Option<Array1<f64>>,
+    /// The shift of the power prices (see `ModelParameters::power_shift`) that lets them
+    /// fall to `-power_shift`; none if omitted.
+    pub power_shift: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+
+    /// The parameters `sigma_g`, `sigma_p`, `kappa`, `lambda_j`, `mu_j`, `sigma_j` and
+    /// `rho` the prices are simulated with.
+    const TRUTH: [f64; 7] = [0.5, 1.5, 200.0, 200.0, 0.3, 0.3, 0.6];
+
+    /// Simulates gas and power prices on `time_grid` with the transitions of the model
+    /// around an hourly shape, the power prices shifted down by `power_shift`.
+    fn simulated_prices(time_grid: &TimeGrid, power_shift: f64) -> (Array1<f64>, Array1<f64>) {
+        let n = time_grid.len();
+        let [sigma_g, sigma_p, kappa, lambda_j, mu_j, sigma_j, rho] = TRUTH;
+        let noise = NoiseSource::new(7).path_noise(0, 4, 1, n);
+
+        let (mut x_g, mut x_p) = (0.0, 0.0);
+        let mut gas_prices = Array1::zeros(n);
+        let mut power_prices = Array1::zeros(n);
+        for i in 0..n {
+            if i > 0 {
+                let dt = time_grid.time_step(i);
+                let z_g = noise.normals[[0, i]];
+                let z_p = rho * z_g + (1.0 - rho * rho).sqrt() * noise.normals[[1, i]];
+                x_g += -0.5 * sigma_g * sigma_g * dt + sigma_g * dt.sqrt() * z_g;
+                let decay = (-kappa * dt).exp();
+                let noise_scale = ((1.0 - decay * decay) / (2.0 * kappa)).sqrt();
+                x_p = decay * x_p + sigma_p * noise_scale * z_p;
+                if noise.uniforms[[0, i]] < 1.0 - (-lambda_j * dt).exp() {
+                    x_p += mu_j + sigma_j * noise.normals[[2, i]];
+                }
+            }
+            let hour = time_grid.season(i).hour as f64;
+            let shape = 0.2 * (2.0 * std::f64::consts::PI * hour / 24.0).sin();
+            gas_prices[i] = 30.0 * f64::exp(x_g);
+            power_prices[i] = 60.0 * f64::exp(x_p + shape) - power_shift;
+        }
+        (gas_prices, power_prices)
+    }
+
+    /// Asserts that every estimate of `calibration` lies within four standard errors of
+    /// `TRUTH`.
+    fn assert_recovers_the_truth(calibration: &HistoricalCalibration) {
+        for ((name, estimate), value) in calibration.estimates().into_iter().zip(TRUTH) {
+            assert!(
+                (estimate.value - value).abs() < 4.0 * estimate.standard_error,
+                "{name}: {estimate:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_recovers_the_parameters_of_simulated_prices() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 120, Tz::UTC).unwrap();
+        let (gas_prices, power_prices) = simulated_prices(&time_grid, 0.0);
+
+        let calibration = calibrate_historical(&CalibrateHistoricalArgs {
+            gas_prices,
+            power_prices,
+            time_grid,
+            gas_shape: None,
+            power_shape: None,
+            power_shift: None,
+        })
+        .unwrap();
+
+        assert!(calibration.power_fit.converged);
+        assert!(!calibration.power_fit.stalled);
+        assert_recovers_the_truth(&calibration);
+        // The fitted mixture explains the fat tails of the power transitions.
+        assert!(calibration.power_fit.residual_excess_kurtosis.abs() < 0.5);
+        assert!(calibration.power_fit.residual_autocorrelation.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_fits_negative_power_prices_with_a_shift() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 120, Tz::UTC).unwrap();
+        let shift = 55.0;
+        let (gas_prices, power_prices) = simulated_prices(&time_grid, shift);
+        assert!(power_prices.iter().any(|&price| price < 0.0));
+        let args = |power_shift| CalibrateHistoricalArgs {
+            gas_prices: gas_prices.clone(),
+            power_prices: power_prices.clone(),
+            time_grid: time_grid.clone(),
+            gas_shape: None,
+            power_shape: None,
+            power_shift,
+        };
+
+        assert!(calibrate_historical(&args(None)).is_err());
+        let calibration = calibrate_historical(&args(Some(shift))).unwrap();
+        assert!(calibration.power_fit.converged);
+        assert_recovers_the_truth(&calibration);
+    }
+}
@@ -0,0 +1,397 @@
+use aad::{AADVar, backward, clear_tape, get_tape_len};
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2, s};
+
+use crate::core::calibration::implied::{
+    CalibratedParameter, ImpliedCalibration, OptionQuote, ParameterDomain, QuoteFit,
+};
+use crate::core::calibration::optimizer::Bfgs;
+use crate::core::common::parallel::map_chunks_in_order;
+use crate::core::common::random::NoiseSource;
+use crate::core::common::statistics::BatchMoments;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::ModelParameters;
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::simulate_prices::Simulator;
+use crate::core::simulator::simulation_config::SimulationConfig;
+
+/// Number of consecutive paths priced sequentially before the partial moments are
+/// combined, which keeps the result independent of the number of threads (see
+/// `calculate_greeks`).
+const REDUCTION_CHUNK_SIZE: usize = 64;
+
+/// Fits parameters of `ModelParameters` to the market prices of options by weighted
+/// least squares.
+///
+/// The options are priced by Monte Carlo on common random numbers: every evaluation of
+/// the objective simulates the same paths of `noise_source`, so the model prices change
+/// smoothly with the parameters and the optimiser is not misled by simulation noise. The
+/// objective `sum(weight * (model - market)^2) / 2` is minimised by BFGS over
+/// unconstrained coordinates of the parameters (see `ParameterDomain`), with the
+/// gradient of the model prices computed by one backward AAD pass per quote and path.
+///
+/// The fitted parameters reproduce the quotes on the simulated paths. Their model prices
+/// are reported with the Monte Carlo standard error, which bounds how closely they match
+/// the exact prices of the fitted model.
+///
+/// # Errors
+///
+/// Returns an error if no parameter or quote is given, if a parameter is given twice or
+/// is not used by the model (see `CalibratedParameter::check`), if a quote does not fit
+/// the time grid, or if the model or the curves are invalid.
+pub fn calibrate_implied(args: &CalibrateImpliedArgs) -> Result<ImpliedCalibration> {
+    if args.parameters.is_empty() {
+        bail!("At least one parameter must be calibrated.");
+    }
+    if args.quotes.is_empty() {
+        bail!("At least one option quote is needed for the calibration.");
+    }
+    for (i, parameter) in args.parameters.iter().enumerate() {
+        if args.parameters[..i].contains(parameter) {
+            bail!("The parameter '{}' is calibrated twice.", parameter.name());
+        }
+        parameter.check(&args.model_params)?;
+    }
+    for quote in &args.quotes {
+        quote.check(&args.time_grid)?;
+    }
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    let sampler = NoiseSampler::new(
+        args.noise_source,
+        args.simulation_config,
+        price_model.noise_layout(),
+        args.num_paths,
+        args.time_grid.len(),
+    )?;
+    let pricer = QuotePricer {
+        args,
+        forward_curves,
+        sampler,
+    };
+
+    let domains: Vec<ParameterDomain> = args
+        .parameters
+        .iter()
+        .flat_map(|parameter| {
+            let num_values = parameter.values(&args.model_params).len();
+            std::iter::repeat_n(parameter.domain(), num_values)
+        })
+        .collect();
+    let start: Array1<f64> = fitted_values(args, &args.model_params)
+        .iter()
+        .zip(&domains)
+        .map(|(&value, domain)| domain.to_coordinate(value))
+        .collect();
+    let market_prices: Array1<f64> = args.quotes.iter().map(|quote| quote.price).collect();
+    let weights: Array1<f64> = args.quotes.iter().map(|quote| quote.weight).collect();
+
+    let minimum = Bfgs::default().minimize(
+        |coordinates| {
+            let (params, derivatives) = parameters_at(args, &domains, coordinates);
+            if params.price_model().is_err() {
+                return (f64::INFINITY, Array1::zeros(coordinates.len()));
+            }
+            let (prices, jacobian, _) = pricer.price(&params);
+            let weighted_residuals = &weights * &(&prices - &market_prices);
+            let value = 0.5 * weighted_residuals.dot(&(&prices - &market_prices));
+            (value, jacobian.t().dot(&weighted_residuals) * &derivatives)
+        },
+        start,
+    );
+
+    let (model_params, _) = parameters_at(args, &domains, &minimum.point);
+    let (prices, _, standard_errors) = pricer.price(&model_params);
+    let quote_fits: Vec<QuoteFit> = args
+        .quotes
+        .iter()
+        .enumerate()
+        .map(|(q, quote)| QuoteFit {
+            market_price: quote.price,
+            model_price: prices[q],
+            model_price_standard_error: standard_errors[q],
+            residual: prices[q] - quote.price,
+        })
+        .collect();
+    let rmse = (quote_fits
+        .iter()
+        .map(|fit| fit.residual * fit.residual)
+        .sum::<f64>()
+        / quote_fits.len() as f64)
+        .sqrt();
+    let names = args
+        .parameters
+        .iter()
+        .flat_map(|parameter| parameter.value_names(&args.model_params));
+    Ok(ImpliedCalibration {
+        fitted_values: names.zip(fitted_values(args, &model_params)).collect(),
+        model_params,
+        quote_fits,
+        rmse,
+        iterations: minimum.iterations,
+        gradient_norm: minimum.gradient_norm,
+        converged: minimum.converged,
+        stalled: minimum.stalled,
+    })
+}
+
+/// Returns the values of the calibrated parameters in `params`, in the order of
+/// `CalibrateImpliedArgs::parameters`.
+fn fitted_values(args: &CalibrateImpliedArgs, params: &ModelParameters<f64>) -> Vec<f64> {
+    args.parameters
+        .iter()
+        .flat_map(|parameter| parameter.values(params).into_iter().copied())
+        .collect()
+}
+
+/// Returns the parameters at the unconstrained `coordinates` of the calibrated values,
+/// and the derivative of every value with respect to its coordinate.
+fn parameters_at(
+    args: &CalibrateImpliedArgs,
+    domains: &[ParameterDomain],
+    coordinates: &Array1<f64>,
+) -> (ModelParameters<f64>, Array1<f64>) {
+    let mut params = args.model_params.clone();
+    let mut derivatives = Array1::zeros(coordinates.len());
+    let mut i = 0;
+    for parameter in &args.parameters {
+        for value in parameter.values_mut(&mut params) {
+            let (fitted, derivative) = domains[i].value_at(coordinates[i]);
+            *value = fitted;
+            derivatives[i] = derivative;
+            i += 1;
+        }
+    }
+    (params, derivatives)
+}
+
+/// Prices the quotes of a calibration on its fixed set of paths.
+struct QuotePricer<'a> {
+    args: &'a CalibrateImpliedArgs,
+    /// The curves of all assets in the order of the price model.
+    forward_curves: Vec<Array1<f64>>,
+    sampler: NoiseSampler,
+}
+
+impl QuotePricer<'_> {
+    /// Returns the discounted price of every quote under `params`, the Jacobian of the
+    /// prices with respect to the calibrated values, with one row per quote, and the
+    /// standard errors of the prices.
+    ///
+    /// The parameters must define a valid price model.
+    fn price(&self, params: &ModelParameters<f64>) -> (Array1<f64>, Array2<f64>, Array1<f64>) {
+        let num_quotes = self.args.quotes.len();
+        let num_fitted = fitted_values(self.args, params).len();
+        let num_values = num_quotes * (1 + num_fitted);
+        let layout = self.sampler.batch_layout(self.args.num_paths);
+        let mut moments = BatchMoments::with_layout(num_values, layout);
+        map_chunks_in_order(
+            self.args.num_paths,
+            REDUCTION_CHUNK_SIZE,
+            |paths| {
+                let mut chunk_moments = BatchMoments::with_layout(num_values, layout);
+                for path_idx in paths {
+                    let values = self.price_path(params, path_idx, num_values);
+                    chunk_moments.add(path_idx, values.view());
+                }
+                chunk_moments
+            },
+            |chunk_moments| moments.merge(chunk_moments),
+        );
+
+        let mean = moments.mean();
+        let prices = mean.slice(s![..num_quotes]).to_owned();
+        let jacobian = mean
+            .slice(s![num_quotes..])
+            .to_owned()
+            .into_shape_with_order((num_quotes, num_fitted))
+            .expect("The Jacobian has one row per quote.");
+        let standard_errors = moments.standard_error().slice(s![..num_quotes]).to_owned();
+        (prices, jacobian, standard_errors)
+    }
+
+    /// Returns the discounted payoff of every quote on path `path_idx`, followed by its
+    /// gradient with respect to the calibrated values, quote by quote.
+    fn price_path(
+        &self,
+        params: &ModelParameters<f64>,
+        path_idx: usize,
+        num_values: usize,
+    ) -> Array1<f64> {
+        let args = self.args;
+        clear_tape();
+        let curves_aad: Vec<Array1<AADVar>> = self
+            .forward_curves
+            .iter()
+            .map(|curve| curve.mapv(AADVar::constant))
+            .collect();
+        let params_aad = params.map(|&x| AADVar::constant(x));
+        let price_model = params_aad
+            .price_model()
+            .expect("The parameters were validated by `calibrate_implied`.");
+        let noise = self.sampler.path_noise(path_idx);
+        let assets =
+            Simulator::simulate_single_path(&curves_aad, &args.time_grid, &price_model, &noise);
+
+        // Models built from `ModelParameters` always contain gas and power.
+        let gas = price_model.index_of(Asset::Gas).unwrap();
+        let power = price_model.index_of(Asset::Power).unwrap();
+        let prices: Vec<AADVar> = args
+            .quotes
+            .iter()
+            .map(|quote| {
+                let t = quote.payment_time(&args.time_grid);
+                let discount_factor = AADVar::constant((-args.risk_free_rate * t).exp());
+                quote.payoff(&assets.view(), gas, power) * discount_factor
+            })
+            .collect();
+
+        let fitted: Vec<&AADVar> = args
+            .parameters
+            .iter()
+            .flat_map(|parameter| parameter.values(&params_aad))
+            .collect();
+        let mut values = Array1::zeros(num_values);
+        let tape_len = get_tape_len();
+        for (q, price) in prices.iter().enumerate() {
+            values[q] = price.value;
+            let mut adjoints = vec![0.0; tape_len];
+            adjoints[price.index] = 1.0;
+            backward(&mut adjoints);
+            let start = prices.len() + q * fitted.len();
+            for (k, value) in fitted.iter().enumerate() {
+                values[start + k] = adjoints[value.index];
+            }
+        }
+        values
+    }
+}
+
+/// Arguments required for the `calibrate_implied` function.
+pub struct CalibrateImpliedArgs {
+    /// The forward curve for gas prices.
+    pub gas_curve: Array1<f64>,
+    /// The forward curve for power prices.
+    pub power_curve: Array1<f64>,
+    /// The forward curves of the additional assets of `model_params`, e.g. carbon.
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    /// The calendar time of every point of the forward curves.
+    pub time_grid: TimeGrid,
+    /// The starting values of the calibrated parameters and the values of all others.
+    pub model_params: ModelParameters<f64>,
+    /// The market prices of the options.
+    pub quotes: Vec<OptionQuote>,
+    /// The parameters fitted to the quotes.
+    pub parameters: Vec<CalibratedParameter>,
+    /// The number of Monte Carlo simulation paths the options are priced on.
+    pub num_paths: usize,
+    /// The annual risk-free rate for discounting.
+    pub risk_free_rate: f64,
+    /// The source of random numbers for the simulation paths.
+    pub noise_source: NoiseSource,
+    /// The variance reduction techniques applied to the random numbers.
+    pub simulation_config: SimulationConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::calibration::implied::{OptionKind, OptionUnderlying, Settlement};
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_recovers_the_volatilities_behind_option_prices() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::regular(start, start, 59, TimeDelta::days(1), Tz::UTC).unwrap();
+        let months = [0..31, 31..59];
+        let mut quotes = Vec::new();
+        for month in &months {
+            quotes.push(OptionQuote::new(
+                OptionUnderlying::Gas,
+                OptionKind::Call,
+                30.0,
+                month.clone(),
+                0.0,
+            ));
+            quotes.push(OptionQuote::new(
+                OptionUnderlying::Power,
+                OptionKind::Put,
+                80.0,
+                month.clone(),
+                0.0,
+            ));
+        }
+        quotes.push(
+            OptionQuote::new(
+                OptionUnderlying::SparkSpread { heat_rate: 2.0 },
+                OptionKind::Call,
+                15.0,
+                0..59,
+                0.0,
+            )
+            .with_settlement(Settlement::Strip),
+        );
+        let mut args = CalibrateImpliedArgs {
+            gas_curve: Array1::from_elem(59, 30.0),
+            power_curve: Array1::from_elem(59, 80.0),
+            additional_curves: Vec::new(),
+            time_grid,
+            model_params: ModelParameters::new(0.5, 0.9, 30.0, 4.0, 0.0, 0.2, 0.4),
+            quotes,
+            parameters: vec![
+                CalibratedParameter::SigmaG,
+                CalibratedParameter::SigmaP,
+                CalibratedParameter::Rho,
+            ],
+            num_paths: 128,
+            risk_free_rate: 0.03,
+            noise_source: NoiseSource::new(11),
+            simulation_config: SimulationConfig::default(),
+        };
+
+        // Quote the prices of the model itself on the same paths, then start away from
+        // the parameters behind them.
+        let price_model = args.model_params.price_model().unwrap();
+        let pricer = QuotePricer {
+            args: &args,
+            forward_curves: forward_curves(
+                &price_model,
+                &args.time_grid,
+                &args.gas_curve,
+                &args.power_curve,
+                &[],
+            )
+            .unwrap(),
+            sampler: NoiseSampler::new(
+                args.noise_source,
+                args.simulation_config,
+                price_model.noise_layout(),
+                args.num_paths,
+                args.time_grid.len(),
+            ).unwrap(),
+        };
+        let (market_prices, _, _) = pricer.price(&args.model_params);
+        for (quote, price) in args.quotes.iter_mut().zip(&market_prices) {
+            quote.price = *price;
+        }
+        args.model_params = ModelParameters::new(0.3, 0.6, 30.0, 4.0, 0.0, 0.2, 0.0);
+
+        let calibration = calibrate_implied(&args).unwrap();
+        assert!(calibration.converged);
+        assert!(!calibration.stalled);
+        assert!((calibration.model_params.sigma_g.parameters()[0] - 0.5).abs() < 1e-3);
+        assert!((calibration.model_params.sigma_p.parameters()[0] - 0.9).abs() < 1e-3);
+        assert!((calibration.model_params.rho - 0.4).abs() < 1e-2);
+        assert!(calibration.rmse < 1e-3);
+        assert_eq!(calibration.fitted_values[0].0, "sigma_g");
+    }
+}
@@ -1,3 +1,45 @@
 pub mod calculate_profit;
 pub mod sample_paths;
 pub mod calculate_greeks;
+pub mod calculate_pv;
+pub mod calculate_pv_adaptive;
+pub mod calculate_profit_statistics;
+pub mod calibrate_historical;
+pub mod calibrate_implied;
+
+use anyhow::Result;
+use ndarray::Array1;
+
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::price_model::PriceModel;
+
+/// Collects the gas and power curves and the curves of any further assets of a service's
+/// arguments into the asset order of `price_model`, aligned to the points of `time_grid`,
+/// and checks that the processes of the model can run on the grid.
+///
+/// Every curve may have one point per point of the grid or one point per local day, so
+/// e.g. a daily gas curve can be combined with a quarter-hourly power curve.
+pub(crate) fn forward_curves(
+    price_model: &PriceModel<f64>,
+    time_grid: &TimeGrid,
+    gas_curve: &Array1<f64>,
+    power_curve: &Array1<f64>,
+    additional_curves: &[(Asset, Array1<f64>)],
+) -> Result<Vec<Array1<f64>>> {
+    let aligned = [(Asset::Gas, gas_curve), (Asset::Power, power_curve)]
+        .into_iter()
+        .chain(additional_curves.iter().map(|(asset, curve)| (*asset, curve)))
+        .map(|(asset, curve)| {
+            let curve = time_grid.align_curve(curve).map_err(|e| {
+                anyhow::anyhow!("The forward curve of '{}' does not fit: {}", asset.name(), e)
+            })?;
+            Ok((asset, curve))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let curves: Vec<(Asset, &Array1<f64>)> =
+        aligned.iter().map(|(asset, curve)| (*asset, curve)).collect();
+    let ordered = price_model.order_forward_curves(&curves)?;
+    price_model.check_time_grid(time_grid)?;
+    Ok(ordered)
+}
@@ -1,24 +1,98 @@
-use crate::core::parameters::ModelParameters;
+use crate::core::parameters::{ModelParameters, PhysicalParameters};
+use crate::core::services::forward_curves;
+use crate::core::simulator::asset::Asset;
 use crate::core::simulator::simulate_prices::Simulator;
 use crate::core::simulator::simulation_result::SimulationResult;
+use crate::core::common::random::NoiseSource;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::simulator::simulation_config::SimulationConfig;
 use anyhow::Result;
-use ndarray::Array1;
+use ndarray::{Array1, Array2};
 
 pub fn sample_paths(args: SamplePathsArgs) -> Result<SimulationResult<f64>> {
     // 1. Simulate prices
-    let prices = Simulator::simulate(
+    let price_model = args.model_params.price_model()?;
+    let forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
         &args.gas_curve,
         &args.power_curve,
-        &args.model_params,
+        &args.additional_curves,
+    )?;
+    let prices = Simulator::simulate(
+        &forward_curves,
+        &args.time_grid,
+        &price_model,
         args.num_paths,
+        &args.noise_source,
+        &args.simulation_config,
     )?;
 
     Ok(prices)
 }
 
+/// Simulates the regimes of the power price, for a power model that switches between
+/// regimes. With the same arguments, path `i` holds the regimes behind path `i` of
+/// `sample_paths`.
+pub fn sample_regimes(args: SamplePathsArgs) -> Result<Array2<usize>> {
+    let price_model = args.model_params.price_model()?;
+    Simulator::simulate_regimes(
+        &args.time_grid,
+        &price_model,
+        Asset::Power,
+        args.num_paths,
+        &args.noise_source,
+        &args.simulation_config,
+    )
+}
+
+/// Simulates real-world price paths under the physical measure, e.g. for risk limits and
+/// budgeting. Gas grows from today's price, the first point of the gas curve, at its real
+/// drift, and power reverts to the LRMC from today's price, the first point of the power
+/// curve; further assets follow their forward curves.
+pub fn sample_physical_paths(args: SamplePhysicalPathsArgs) -> Result<SimulationResult<f64>> {
+    let price_model = args.physical_params.price_model(&args.time_grid)?;
+    let mut forward_curves = forward_curves(
+        &price_model,
+        &args.time_grid,
+        &args.gas_curve,
+        &args.power_curve,
+        &args.additional_curves,
+    )?;
+    // Models built from `PhysicalParameters` always contain gas.
+    let gas = price_model.index_of(Asset::Gas).unwrap();
+    forward_curves[gas] = args
+        .physical_params
+        .expected_gas_curve(&forward_curves[gas], &args.time_grid);
+    Simulator::simulate(
+        &forward_curves,
+        &args.time_grid,
+        &price_model,
+        args.num_paths,
+        &args.noise_source,
+        &args.simulation_config,
+    )
+}
+
 pub struct SamplePathsArgs {
     pub gas_curve: Array1<f64>,
     pub power_curve: Array1<f64>,
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    pub time_grid: TimeGrid,
     pub model_params: ModelParameters<f64>,
     pub num_paths: usize,
+    pub noise_source: NoiseSource,
+    pub simulation_config: SimulationConfig,
+}
+
+/// Arguments required for the `sample_physical_paths` function.
+pub struct SamplePhysicalPathsArgs {
+    pub gas_curve: Array1<f64>,
+    pub power_curve: Array1<f64>,
+    pub additional_curves: Vec<(Asset, Array1<f64>)>,
+    pub time_grid: TimeGrid,
+    pub physical_params: PhysicalParameters<f64>,
+    pub num_paths: usize,
+    pub noise_source: NoiseSource,
+    pub simulation_config: SimulationConfig,
 }
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+/// The commodities whose prices can be simulated.
+///
+/// Assets are identified by name rather than by position: a `PriceModel` and a
+/// `SimulationResult` store the order of their assets and resolve an asset to its row
+/// with `index_of`. The set of assets is closed, and a model simulates every asset at
+/// most once, so a model has at most these four correlated assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Asset {
+    /// Natural gas (€ / MMBtu).
+    Gas,
+    /// Electric power (€ / MWh).
+    Power,
+    /// Coal (€ / MMBtu).
+    Coal,
+    /// EU ETS carbon emission allowances (€ / tCO2).
+    Carbon,
+}
+
+impl Asset {
+    /// All assets, in declaration order.
+    pub const ALL: [Asset; 4] = [Asset::Gas, Asset::Power, Asset::Coal, Asset::Carbon];
+
+    /// Returns the lowercase name of the asset, e.g. `"carbon"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Asset::Gas => "gas",
+            Asset::Power => "power",
+            Asset::Coal => "coal",
+            Asset::Carbon => "carbon",
+        }
+    }
+
+    /// Returns the asset with the lowercase name `name`, if any.
+    pub fn from_name(name: &str) -> Option<Asset> {
+        Self::ALL.into_iter().find(|asset| asset.name() == name)
+    }
+}
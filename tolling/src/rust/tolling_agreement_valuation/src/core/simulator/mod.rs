@@ -1,2 +1,6 @@
+pub mod asset;
+pub mod noise_sampler;
+pub mod price_model;
 pub mod simulate_prices;
+pub mod simulation_config;
 pub mod simulation_result;
@@ -0,0 +1,371 @@
+use anyhow::Result;
+use ndarray::{Array2, Zip};
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::Open01;
+use rayon::prelude::*;
+
+use crate::core::common::brownian_bridge::BrownianBridge;
+use crate::core::common::distributions::normal_inverse_cdf;
+use crate::core::common::random::{NoiseSource, PathNoise, path_rng};
+use crate::core::common::sobol::{MAX_DIMENSIONS, SobolSequence};
+use crate::core::common::statistics::BatchLayout;
+use crate::core::simulator::simulation_config::{NoiseScheme, SimulationConfig};
+
+/// The shape of the noise consumed by a single path.
+///
+/// Rows `0..num_diffusions` of the normals drive the Brownian motions of the assets
+/// (before correlation). The remaining rows of normals and all rows of uniforms are
+/// further draws of the processes, e.g. for jumps. A layout is obtained from the price
+/// model with `PriceModel::noise_layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoiseLayout {
+    /// The number of leading rows of normals that drive Brownian motions. Only these
+    /// rows are built with the Brownian bridge under quasi-Monte Carlo.
+    pub num_diffusions: usize,
+    /// The total number of rows of standard normal draws.
+    pub num_normals: usize,
+    /// The total number of rows of uniform draws.
+    pub num_uniforms: usize,
+}
+
+/// Number of consecutive paths whose noise moments are accumulated sequentially before
+/// the partial sums are combined, keeping the result independent of the thread count.
+const MOMENT_CHUNK_SIZE: usize = 64;
+
+/// Mixed into the seed of the pseudo-random draws that pad the quasi-random points
+/// beyond `MAX_DIMENSIONS`, so that they are independent of the scrambles.
+const PADDING_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The state needed to generate quasi-random path noise.
+struct QuasiRandomNoise {
+    /// One independently scrambled Sobol sequence per replicate.
+    replicates: Vec<SobolSequence>,
+    /// The dimension of a full point, which may exceed that of the sequences.
+    num_dims: usize,
+    /// The Brownian bridge applied to the diffusion rows, if enabled.
+    bridge: Option<BrownianBridge>,
+}
+
+/// Produces the noise of every path of a Monte Carlo run.
+///
+/// The sampler wraps a `NoiseSource` and applies the noise scheme and variance
+/// reduction selected in a `SimulationConfig`. It is prepared once per run, because
+/// moment matching needs the per time step moments of the normal draws across all
+/// paths and quasi-Monte Carlo needs the scrambled sequences. The noise of a path is
+/// still regenerated on demand, so memory use does not grow with the number of paths.
+pub struct NoiseSampler {
+    noise_source: NoiseSource,
+    config: SimulationConfig,
+    layout: NoiseLayout,
+    n_points: usize,
+    quasi_random: Option<QuasiRandomNoise>,
+    /// Per row and time step `(mean, standard deviation)` of the normal draws across
+    /// all paths. Only present if moment matching is enabled.
+    normal_moments: Option<(Array2<f64>, Array2<f64>)>,
+}
+
+impl NoiseSampler {
+    /// Prepares a sampler for a run of `num_paths` paths with `n_points` time steps, each
+    /// consuming noise of the shape `layout`.
+    ///
+    /// If moment matching is enabled, this generates the noise of every path once to
+    /// compute the moments of the normal draws.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if quasi-Monte Carlo needs more than 2^32 points per replicate.
+    pub fn new(
+        noise_source: NoiseSource,
+        config: SimulationConfig,
+        layout: NoiseLayout,
+        num_paths: usize,
+        n_points: usize,
+    ) -> Result<Self> {
+        let quasi_random = match config.noise_scheme {
+            NoiseScheme::PseudoRandom => None,
+            NoiseScheme::ScrambledSobol {
+                num_replicates,
+                brownian_bridge,
+            } => {
+                let num_replicates = num_replicates.max(1);
+                let num_points = Self::num_streams(config, num_paths).div_ceil(num_replicates);
+                let num_dims = (layout.num_normals + layout.num_uniforms) * n_points;
+                let sequence = SobolSequence::new(num_dims.min(MAX_DIMENSIONS), num_points.max(1))
+                    .map_err(|e| anyhow::anyhow!("Cannot draw quasi-random noise: {}", e))?;
+                let replicates = (0..num_replicates)
+                    .map(|r| sequence.scrambled(&mut path_rng(noise_source.seed(), r)))
+                    .collect();
+                Some(QuasiRandomNoise {
+                    replicates,
+                    num_dims,
+                    bridge: brownian_bridge.then(|| BrownianBridge::new(n_points)),
+                })
+            }
+        };
+
+        let mut sampler = NoiseSampler {
+            noise_source,
+            config,
+            layout,
+            n_points,
+            quasi_random,
+            normal_moments: None,
+        };
+        // Moment matching needs at least two paths to estimate a standard deviation.
+        if config.moment_matching && num_paths > 1 {
+            sampler.normal_moments = Some(sampler.compute_normal_moments(num_paths));
+        }
+        Ok(sampler)
+    }
+
+    /// Returns the noise of the path with index `path_idx`.
+    ///
+    /// Every caller that uses a sampler built from the same `NoiseSource`, configuration
+    /// and number of paths receives identical draws for `path_idx`, which gives common
+    /// random numbers across valuations.
+    pub fn path_noise(&self, path_idx: usize) -> PathNoise {
+        let mut noise = self.raw_path_noise(path_idx);
+        if let Some((mean, std_dev)) = &self.normal_moments {
+            Zip::from(&mut noise.normals)
+                .and(mean)
+                .and(std_dev)
+                .for_each(|z, &m, &s| *z = (*z - m) / s);
+        }
+        noise
+    }
+
+    /// Returns the index of the independent batch that path `path_idx` belongs to.
+    ///
+    /// Paths in different batches are statistically independent, so standard errors
+    /// must be computed from batch totals: an antithetic pair forms one batch, and under
+    /// quasi-Monte Carlo all paths of one scrambled replicate form one batch.
+    pub fn batch_of(&self, path_idx: usize) -> usize {
+        self.batch_layout(path_idx + 1).batch_of(path_idx)
+    }
+
+    /// Returns the number of independent batches in a run of `num_paths` paths.
+    pub fn num_batches(&self, num_paths: usize) -> usize {
+        self.batch_layout(num_paths).num_batches()
+    }
+
+    /// Returns the grouping of the paths of a run of `num_paths` paths into independent
+    /// batches, see `batch_of`.
+    pub fn batch_layout(&self, num_paths: usize) -> BatchLayout {
+        let stream_len = if self.config.antithetic { 2 } else { 1 };
+        let num_replicates = self.quasi_random.as_ref().map(|q| q.replicates.len());
+        BatchLayout::new(num_paths, stream_len, num_replicates)
+    }
+
+    /// Returns the number of distinct noise streams needed for `num_paths` paths.
+    fn num_streams(config: SimulationConfig, num_paths: usize) -> usize {
+        if config.antithetic {
+            num_paths.div_ceil(2)
+        } else {
+            num_paths
+        }
+    }
+
+    /// Returns the noise stream that path `path_idx` is generated from.
+    fn stream_of(&self, path_idx: usize) -> usize {
+        if self.config.antithetic {
+            path_idx / 2
+        } else {
+            path_idx
+        }
+    }
+
+    /// Returns the noise of a path before moment matching.
+    ///
+    /// With antithetic sampling, the odd path of each pair mirrors the draws of the
+    /// preceding even path.
+    fn raw_path_noise(&self, path_idx: usize) -> PathNoise {
+        let mut noise = self.draw(self.stream_of(path_idx));
+        if self.config.antithetic && path_idx % 2 == 1 {
+            noise.normals.mapv_inplace(|z| -z);
+            noise.uniforms.mapv_inplace(|u| 1.0 - u);
+        }
+        noise
+    }
+
+    fn draw(&self, stream_idx: usize) -> PathNoise {
+        match &self.quasi_random {
+            Some(quasi_random) => self.draw_quasi_random(quasi_random, stream_idx),
+            None => self.noise_source.path_noise(
+                stream_idx,
+                self.layout.num_normals,
+                self.layout.num_uniforms,
+                self.n_points,
+            ),
+        }
+    }
+
+    /// Generates the noise of a stream from a point of the scrambled Sobol sequence.
+    ///
+    /// Streams are assigned to the replicates in turn. The dimensions of the point are
+    /// used in order of importance: first the diffusion draws, interleaved across the
+    /// assets and in Brownian bridge order if enabled, then the jump draws. All normal
+    /// draws are obtained by inverting the normal CDF, and the jump counts by inverting
+    /// the Poisson CDF in the process, so the jumps stay quasi-random as well.
+    ///
+    /// The sequence provides at most `MAX_DIMENSIONS` dimensions. Any further dimensions,
+    /// the least important ones, are padded with pseudo-random draws of the stream.
+    fn draw_quasi_random(&self, quasi_random: &QuasiRandomNoise, stream_idx: usize) -> PathNoise {
+        let n = self.n_points;
+        let layout = self.layout;
+        let num_replicates = quasi_random.replicates.len();
+        let sequence = &quasi_random.replicates[stream_idx % num_replicates];
+
+        let mut point = vec![0.0; quasi_random.num_dims];
+        sequence.point(stream_idx / num_replicates, &mut point);
+        if quasi_random.num_dims > sequence.num_dims() {
+            let mut rng = path_rng(self.noise_source.seed() ^ PADDING_SEED_SALT, stream_idx);
+            for x in &mut point[sequence.num_dims()..] {
+                *x = rng.sample(Open01);
+            }
+        }
+
+        let mut normals = Array2::zeros((layout.num_normals, n));
+        let mut diffusion = vec![0.0; n];
+        for row in 0..layout.num_diffusions {
+            for (k, z) in diffusion.iter_mut().enumerate() {
+                *z = normal_inverse_cdf(point[k * layout.num_diffusions + row]);
+            }
+            if let Some(bridge) = &quasi_random.bridge {
+                bridge.transform(&mut diffusion);
+            }
+            for (k, z) in diffusion.iter().enumerate() {
+                normals[[row, k]] = *z;
+            }
+        }
+
+        let mut offset = layout.num_diffusions * n;
+        for row in layout.num_diffusions..layout.num_normals {
+            for k in 0..n {
+                normals[[row, k]] = normal_inverse_cdf(point[offset + k]);
+            }
+            offset += n;
+        }
+
+        let mut uniforms = Array2::zeros((layout.num_uniforms, n));
+        for row in 0..layout.num_uniforms {
+            for k in 0..n {
+                uniforms[[row, k]] = point[offset + k];
+            }
+            offset += n;
+        }
+
+        PathNoise { normals, uniforms }
+    }
+
+    /// Computes the per row and time step mean and standard deviation of the normal
+    /// draws over all paths.
+    fn compute_normal_moments(&self, num_paths: usize) -> (Array2<f64>, Array2<f64>) {
+        let shape = (self.layout.num_normals, self.n_points);
+        let num_chunks = num_paths.div_ceil(MOMENT_CHUNK_SIZE);
+
+        let chunk_sums: Vec<(Array2<f64>, Array2<f64>)> = (0..num_chunks)
+            .into_par_iter()
+            .map(|chunk_idx| {
+                let start = chunk_idx * MOMENT_CHUNK_SIZE;
+                let end = (start + MOMENT_CHUNK_SIZE).min(num_paths);
+                let mut sum = Array2::<f64>::zeros(shape);
+                let mut sum_sq = Array2::<f64>::zeros(shape);
+                for path_idx in start..end {
+                    let noise = self.raw_path_noise(path_idx);
+                    sum += &noise.normals;
+                    sum_sq += &noise.normals.mapv(|z| z * z);
+                }
+                (sum, sum_sq)
+            })
+            .collect();
+
+        let (sum, sum_sq): (Array2<f64>, Array2<f64>) = chunk_sums.into_iter().fold(
+            (Array2::zeros(shape), Array2::zeros(shape)),
+            |(a, a_sq), (b, b_sq)| (a + b, a_sq + b_sq),
+        );
+
+        let n = num_paths as f64;
+        let mean = sum / n;
+        let std_dev = Zip::from(&sum_sq)
+            .and(&mean)
+            .map_collect(|&s_sq, &m| (s_sq / n - m * m).max(0.0).sqrt());
+        (mean, std_dev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Axis;
+
+    const LAYOUT: NoiseLayout = NoiseLayout {
+        num_diffusions: 2,
+        num_normals: 3,
+        num_uniforms: 1,
+    };
+
+    #[test]
+    fn test_antithetic_paths_mirror_their_partner() {
+        let sampler = NoiseSampler::new(
+            NoiseSource::new(3),
+            SimulationConfig::new(true, false, NoiseScheme::PseudoRandom),
+            LAYOUT,
+            4,
+            10,
+        )
+        .unwrap();
+        let even = sampler.path_noise(2);
+        let odd = sampler.path_noise(3);
+
+        assert_eq!(odd.normals, even.normals.mapv(|z| -z));
+        assert_eq!(odd.uniforms, even.uniforms.mapv(|u| 1.0 - u));
+    }
+
+    #[test]
+    fn test_moment_matching_standardises_each_time_step() {
+        let num_paths = 50;
+        let sampler = NoiseSampler::new(
+            NoiseSource::new(3),
+            SimulationConfig::new(false, true, NoiseScheme::PseudoRandom),
+            LAYOUT,
+            num_paths,
+            10,
+        )
+        .unwrap();
+        let normals: Vec<Array2<f64>> = (0..num_paths)
+            .map(|i| sampler.path_noise(i).normals)
+            .collect();
+        let views: Vec<_> = normals.iter().map(|n| n.view()).collect();
+        let stacked = ndarray::stack(Axis(0), &views).unwrap();
+
+        let mean = stacked.mean_axis(Axis(0)).unwrap();
+        let variance = stacked.var_axis(Axis(0), 0.0);
+        assert!(mean.iter().all(|m| m.abs() < 1e-12));
+        assert!(variance.iter().all(|v| (v - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_quasi_random_points_are_padded_beyond_the_sequence() {
+        // 2 * 8760 hourly points need far more dimensions than the sequence supports.
+        let layout = NoiseLayout {
+            num_diffusions: 2,
+            num_normals: 2,
+            num_uniforms: 0,
+        };
+        let config = SimulationConfig::new(
+            false,
+            false,
+            NoiseScheme::ScrambledSobol {
+                num_replicates: 2,
+                brownian_bridge: false,
+            },
+        );
+        let sampler = NoiseSampler::new(NoiseSource::new(5), config, layout, 8, 8760).unwrap();
+
+        let first = sampler.path_noise(0).normals;
+        let second = sampler.path_noise(1).normals;
+        assert!(first.iter().all(|z| z.is_finite()));
+        assert_ne!(first.column(8759), second.column(8759));
+        assert_eq!(first, sampler.path_noise(0).normals);
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2, s};
+use num_traits::{Float, FromPrimitive};
+
+use crate::core::common::correlation::{
+    CorrelationFactor, CorrelationRepair, factorise_correlation,
+};
+use crate::core::common::random::PathNoise;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseLayout;
+
+/// The joint price model of all simulated assets.
+///
+/// A model holds one `PriceProcess` per asset, in the order the assets are given, and
+/// the Cholesky factor of the correlation matrix of their Brownian shocks. Any process
+/// can be combined with any other, so new models plug into the simulator without
+/// changes to it.
+pub struct PriceModel<T: Float + FromPrimitive> {
+    assets: Vec<Asset>,
+    processes: Vec<Box<dyn PriceProcess<T>>>,
+    correlation: CorrelationFactor<T>,
+}
+
+impl<T: Float + FromPrimitive> PriceModel<T> {
+    /// Constructs a model from the process of every asset and the correlation matrix of
+    /// their Brownian shocks, whose rows and columns are in the order of `processes`.
+    ///
+    /// If `correlation` is not positive semi-definite, it is repaired as selected by
+    /// `repair`; `correlation_factor` reports how far it was moved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no assets, if an asset appears twice, or if
+    /// `correlation` is not a valid correlation matrix with one row per asset (see
+    /// `factorise_correlation`).
+    pub fn new(
+        processes: Vec<(Asset, Box<dyn PriceProcess<T>>)>,
+        correlation: &Array2<T>,
+        repair: CorrelationRepair,
+    ) -> Result<Self> {
+        let (assets, processes): (Vec<Asset>, Vec<_>) = processes.into_iter().unzip();
+        let n = assets.len();
+        if n == 0 {
+            bail!("A price model needs at least one asset.");
+        }
+        if let Some(asset) = assets
+            .iter()
+            .enumerate()
+            .find_map(|(i, asset)| assets[..i].contains(asset).then_some(asset))
+        {
+            bail!("The asset '{}' is modelled twice.", asset.name());
+        }
+        if correlation.dim() != (n, n) {
+            bail!(
+                "The correlation matrix of {n} assets must be {n} x {n}, got {:?}.",
+                correlation.dim()
+            );
+        }
+
+        Ok(Self {
+            assets,
+            processes,
+            correlation: factorise_correlation(correlation, repair)?,
+        })
+    }
+
+    /// Returns the number of assets.
+    pub fn num_assets(&self) -> usize {
+        self.processes.len()
+    }
+
+    /// Returns the assets of the model, in the order of their rows in the simulation.
+    pub fn assets(&self) -> &[Asset] {
+        &self.assets
+    }
+
+    /// Returns the row of `asset` in the simulation, if the model contains it.
+    pub fn index_of(&self, asset: Asset) -> Option<usize> {
+        self.assets.iter().position(|&a| a == asset)
+    }
+
+    /// Returns the process of the asset with index `asset_idx`.
+    pub fn process(&self, asset_idx: usize) -> &dyn PriceProcess<T> {
+        self.processes[asset_idx].as_ref()
+    }
+
+    /// Returns the Cholesky factor of the correlation matrix.
+    pub fn cholesky_l(&self) -> &Array2<T> {
+        &self.correlation.cholesky_l
+    }
+
+    /// Returns the factorised correlation matrix and the report of its repair, if any.
+    pub fn correlation_factor(&self) -> &CorrelationFactor<T> {
+        &self.correlation
+    }
+
+    /// Orders forward curves given by asset into the asset order of the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the curve of an asset of the model is missing, if a curve is
+    /// given for an asset the model does not contain, or if the curves differ in length.
+    pub fn order_forward_curves(&self, curves: &[(Asset, &Array1<T>)]) -> Result<Vec<Array1<T>>> {
+        if let Some((asset, _)) = curves
+            .iter()
+            .find(|(asset, _)| self.index_of(*asset).is_none())
+        {
+            bail!(
+                "A forward curve is given for '{}', which is not modelled.",
+                asset.name()
+            );
+        }
+        let ordered = self
+            .assets
+            .iter()
+            .map(|&asset| match curves.iter().find(|(a, _)| *a == asset) {
+                Some((_, curve)) => Ok((*curve).clone()),
+                None => bail!("The forward curve of '{}' is missing.", asset.name()),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if ordered.iter().any(|curve| curve.len() != ordered[0].len()) {
+            bail!("All forward curves must have the same number of points.");
+        }
+        Ok(ordered)
+    }
+
+    /// Checks that every process of the model can run on `time_grid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the asset whose process does not fit the grid.
+    pub fn check_time_grid(&self, time_grid: &TimeGrid) -> Result<()> {
+        for (asset, process) in self.assets.iter().zip(&self.processes) {
+            process.check_time_grid(time_grid).map_err(|e| {
+                anyhow::anyhow!("The process of '{}' does not fit: {}", asset.name(), e)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the shape of the noise a path of this model consumes.
+    ///
+    /// The first rows of normals drive the diffusions of the assets, one row per
+    /// asset. The further rows of normals and the rows of uniforms of the processes
+    /// follow in asset order.
+    pub fn noise_layout(&self) -> NoiseLayout {
+        NoiseLayout {
+            num_diffusions: self.num_assets(),
+            num_normals: self.num_assets()
+                + self
+                    .processes
+                    .iter()
+                    .map(|p| p.num_normals())
+                    .sum::<usize>(),
+            num_uniforms: self.processes.iter().map(|p| p.num_uniforms()).sum(),
+        }
+    }
+
+    /// Returns the further draws of the process of asset `asset_idx` from the noise of a
+    /// path, see `noise_layout`.
+    pub fn process_noise<'a>(&self, noise: &'a PathNoise, asset_idx: usize) -> ProcessNoise<'a> {
+        let preceding = &self.processes[..asset_idx];
+        let normals_start =
+            self.num_assets() + preceding.iter().map(|p| p.num_normals()).sum::<usize>();
+        let uniforms_start: usize = preceding.iter().map(|p| p.num_uniforms()).sum();
+        let process = &self.processes[asset_idx];
+        ProcessNoise {
+            normals: noise
+                .normals
+                .slice(s![normals_start..normals_start + process.num_normals(), ..]),
+            uniforms: noise.uniforms.slice(s![
+                uniforms_start..uniforms_start + process.num_uniforms(),
+                ..
+            ]),
+        }
+    }
+}
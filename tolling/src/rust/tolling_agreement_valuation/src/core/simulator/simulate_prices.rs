@@ -1,34 +1,18 @@
-use anyhow::Result;
-use ndarray::{Array1, Array2, Array3, Axis};
-use ndarray_rand::rand;
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+use ndarray::{Array1, Array2, Array3, ArrayViewMut2, Axis, Zip};
 use ndarray_rand::rand_distr::num_traits::{Float, FromPrimitive};
-use ndarray_rand::rand_distr::StandardNormal;
-use ndarray_rand::RandomExt;
 use rayon::prelude::*;
 
-use crate::core::common::cholesky::cholesky_2d;
-use crate::core::parameters::ModelParameters;
-use crate::core::processes::geometric_brownian_motion_transformer::GeometricBrownianMotionTransformer;
-use crate::core::processes::jump_diffusion_process_transformer::JumpDiffusionProcessTransformer;
+use crate::core::common::random::{NoiseSource, PathNoise};
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::noise_sampler::NoiseSampler;
+use crate::core::simulator::price_model::PriceModel;
+use crate::core::simulator::simulation_config::SimulationConfig;
 use crate::core::simulator::simulation_result::SimulationResult;
 
-/// An index enum to provide clear, type-safe access to asset data in arrays.
-#[repr(usize)]
-#[derive(Debug, Copy, Clone)]
-pub enum TollingAssetIndex {
-    /// The index corresponding to the Gas asset.
-    Gas = 0,
-    /// The index corresponding to the Power asset.
-    Power = 1,
-}
-
-impl TollingAssetIndex {
-    /// Returns the `usize` representation of the enum variant.
-    pub fn idx(self) -> usize {
-        self as usize
-    }
-}
-
 /// A stateless struct that serves as a namespace for simulation functions.
 pub struct Simulator;
 
@@ -37,117 +21,511 @@ impl Simulator {
     ///
     /// # Arguments
     ///
-    /// * `forward_curve_gas`: The forward curve for gas prices.
-    /// * `forward_curve_power`: The forward curve for power prices.
-    /// * `model_parameters`: The parameters for the stochastic models.
+    /// * `forward_curves`: The forward curve of every asset, in the asset order of
+    ///   `price_model` (see `PriceModel::order_forward_curves`).
+    /// * `time_grid`: The time of every point of the forward curves.
+    /// * `price_model`: The price process of every asset and their correlation, e.g.
+    ///   from `ModelParameters::price_model`.
     /// * `num_paths`: The total number of simulation paths to generate.
+    /// * `noise_source`: The source of random numbers. Each path draws from its own
+    ///   random stream derived from the source's seed and the path index, so the result
+    ///   is reproducible and does not depend on the number of threads.
+    /// * `config`: The variance reduction techniques applied to the random numbers.
     ///
     /// # Returns
     ///
     /// A `Result` containing a `SimulationResult`, which wraps the simulated price data
     /// in an `Array3` of shape `(num_paths, num_assets, num_points)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of forward curves differs from the number of
+    /// assets of the model, if a curve has not one point per point of `time_grid`, if a
+    /// process of the model does not fit `time_grid`, or if quasi-Monte Carlo needs more
+    /// points than the Sobol sequence supports.
     pub fn simulate<T>(
-        forward_curve_gas: &Array1<T>,
-        forward_curve_power: &Array1<T>,
-        model_parameters: &ModelParameters<T>,
+        forward_curves: &[Array1<T>],
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
         num_paths: usize,
+        noise_source: &NoiseSource,
+        config: &SimulationConfig,
     ) -> Result<SimulationResult<T>>
     where
         T: Float + FromPrimitive + Send + Sync + 'static,
     {
-        // Pre-calculate the Cholesky matrix once to be shared across all parallel tasks.
-        // This is a performance optimization.
-        let l = cholesky_2d(model_parameters.rho);
-
-        // Run the path simulations in parallel.
-        // The `map` operation creates a simulation for each path index.
-        // The `collect` operation gathers the results from all parallel tasks into a Vec.
-        let paths: Vec<Array2<T>> = (0..num_paths)
-            .into_par_iter()
-            .map(|_| {
-                Self::simulate_single_path(
-                    forward_curve_gas,
-                    forward_curve_power,
-                    model_parameters,
-                    &l,
-                )
-            })
-            .collect();
+        Self::check_forward_curves(forward_curves, time_grid, price_model)?;
+        let sampler = NoiseSampler::new(
+            *noise_source,
+            *config,
+            price_model.noise_layout(),
+            num_paths,
+            time_grid.len(),
+        )?;
 
-        // After collection, stack the individual 2D path arrays into a single 3D array.
-        // This is more memory-intensive than writing to a pre-allocated array but allows
-        // for a cleaner functional pattern (map-collect).
-        let n_assets = 2;
-        let n_points = forward_curve_gas.len();
-        let mut prices = Array3::<T>::zeros((num_paths, n_assets, n_points));
-        for (i, path) in paths.iter().enumerate() {
-            prices.index_axis_mut(Axis(0), i).assign(path);
-        }
+        Self::simulate_paths(
+            forward_curves,
+            time_grid,
+            price_model,
+            &sampler,
+            0..num_paths,
+        )
+    }
 
-        Ok(SimulationResult::new(prices))
+    /// Simulates the paths with indices in `paths` in parallel.
+    ///
+    /// This allows a run to be simulated in consecutive ranges of paths, e.g. to stop
+    /// once an estimate is accurate enough. The paths are identical to those of a single
+    /// `simulate` call with the same sampler, whatever the split into ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `forward_curves`: The forward curve of every asset, in the asset order of
+    ///   `price_model`.
+    /// * `time_grid`: The time of every point of the forward curves.
+    /// * `price_model`: The price process of every asset and their correlation.
+    /// * `sampler`: The sampler producing the noise of every path of the run. Its noise
+    ///   layout must be that of `price_model`.
+    /// * `paths`: The range of path indices to simulate.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `SimulationResult` of shape
+    /// `(paths.len(), num_assets, num_points)`, in path order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the forward curves do not match the model, see `simulate`.
+    pub fn simulate_paths<T>(
+        forward_curves: &[Array1<T>],
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
+        sampler: &NoiseSampler,
+        paths: Range<usize>,
+    ) -> Result<SimulationResult<T>>
+    where
+        T: Float + FromPrimitive + Send + Sync + 'static,
+    {
+        Self::check_forward_curves(forward_curves, time_grid, price_model)?;
+        let n_points = time_grid.len();
+        let n_assets = price_model.num_assets();
+
+        // Allocate the output once and let every parallel task write its path into its
+        // own disjoint slice. Each path is seeded from its path index, so the outcome is
+        // independent of scheduling.
+        let mut prices = Array3::<T>::zeros((paths.len(), n_assets, n_points));
+        prices
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .zip(paths)
+            .for_each(|(path_prices, path_idx)| {
+                let noise = sampler.path_noise(path_idx);
+                Self::simulate_single_path_into(
+                    forward_curves,
+                    time_grid,
+                    price_model,
+                    &noise,
+                    path_prices,
+                );
+            });
+
+        Ok(SimulationResult::new(prices, price_model.assets().to_vec()))
     }
 
-    /// Simulates a single price path for both gas and power.
+    /// Simulates a single price path for all assets.
     ///
-    /// This function performs the following steps:
-    /// 1. Generates standard normal random noise for both assets.
-    /// 2. Applies the Cholesky decomposition to correlate the noise.
-    /// 3. Transforms the correlated noise into a GBM process for gas.
-    /// 4. Transforms the correlated noise into a MRJD process for power.
+    /// Allocates the path and fills it with `simulate_single_path_into`.
     ///
     /// # Returns
     ///
     /// An `Array2` of shape `(num_assets, num_points)` containing the simulated prices.
     pub fn simulate_single_path<T>(
-        forward_curve_gas: &Array1<T>,
-        forward_curve_power: &Array1<T>,
-        model_parameters: &ModelParameters<T>,
-        cholesky_l: &Array2<T>,
+        forward_curves: &[Array1<T>],
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
+        noise: &PathNoise,
     ) -> Array2<T>
     where
         T: Float + FromPrimitive + 'static,
     {
-        let n_points = forward_curve_gas.len();
-        let mut rng = rand::thread_rng();
+        let mut assets = Array2::zeros((price_model.num_assets(), time_grid.len()));
+        Self::simulate_single_path_into(
+            forward_curves,
+            time_grid,
+            price_model,
+            noise,
+            assets.view_mut(),
+        );
+        assets
+    }
 
-        // 1. Generate independent standard normal noise.
+    /// Simulates a single price path for all assets into a pre-allocated buffer.
+    ///
+    /// This function performs the following steps:
+    /// 1. Reads the standard normal random noise of the diffusions from `noise`.
+    /// 2. Applies the Cholesky factor of the model to correlate the noise, writing the
+    ///    result directly into `assets`.
+    /// 3. Transforms the correlated noise of every asset into prices with the process
+    ///    of the asset, e.g. a GBM for gas and a MRJD for power.
+    ///
+    /// All random numbers are taken from `noise` (see `NoiseSampler::path_noise`), so the
+    /// path is fully determined by the noise passed in. No intermediate arrays are
+    /// allocated.
+    ///
+    /// # Arguments
+    ///
+    /// * `forward_curves`: The forward curve of every asset, in the asset order of
+    ///   `price_model`. Their number and lengths are not checked.
+    /// * `time_grid`: The time of every point of the forward curves.
+    /// * `assets`: The buffer of shape `(num_assets, num_points)` receiving the prices.
+    ///   Its previous contents are overwritten.
+    pub fn simulate_single_path_into<T>(
+        forward_curves: &[Array1<T>],
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
+        noise: &PathNoise,
+        mut assets: ArrayViewMut2<T>,
+    ) where
+        T: Float + FromPrimitive + 'static,
+    {
+        let cholesky_l = price_model.cholesky_l();
+
+        // 1. + 2. Correlate the independent standard normal noise of the diffusions
+        // row by row, using the lower triangle of the Cholesky matrix.
         // The source of randomness is always f64, which is then cast to type T.
-        let z_f64 = Array2::random_using((2, n_points), StandardNormal, &mut rng);
-        let z = z_f64.mapv(|x| T::from_f64(x).unwrap());
-
-        // 2. Correlate the noise using the pre-calculated Cholesky matrix.
-        let correlated = cholesky_l.dot(&z);
-
-        // Allocate a buffer for the path results.
-        let mut assets = Array2::zeros((2, n_points));
-        let (mut gas_part, mut power_part) = assets.view_mut().split_at(Axis(0), 1);
-        let mut gas_path = gas_part.index_axis_mut(Axis(0), 0);
-        let mut power_path = power_part.index_axis_mut(Axis(0), 0);
-
-        // Overwrite the asset buffer with the correlated noise before transformation.
-        gas_path.assign(&correlated.index_axis(Axis(0), 0));
-        power_path.assign(&correlated.index_axis(Axis(0), 1));
-
-        // 3. Transform the gas path noise into a GBM process.
-        GeometricBrownianMotionTransformer::transform_path_to_gbm(
-            forward_curve_gas,
-            model_parameters.sigma_g,
+        for (i, mut asset_path) in assets.outer_iter_mut().enumerate() {
+            Zip::from(&mut asset_path)
+                .and(noise.normals.row(0))
+                .for_each(|x, &z| *x = cholesky_l[[i, 0]] * T::from_f64(z).unwrap());
+            for k in 1..=i {
+                Zip::from(&mut asset_path)
+                    .and(noise.normals.row(k))
+                    .for_each(|x, &z| *x = *x + cholesky_l[[i, k]] * T::from_f64(z).unwrap());
+            }
+        }
+
+        // 3. Transform the noise of every asset into its price process.
+        for (asset_idx, (asset_path, forward_curve)) in
+            assets.outer_iter_mut().zip(forward_curves).enumerate()
+        {
+            price_model.process(asset_idx).transform_path(
+                forward_curve,
+                time_grid,
+                asset_path,
+                price_model.process_noise(noise, asset_idx),
+            );
+        }
+    }
+
+    /// Simulates the regime paths of the process of `asset` in parallel.
+    ///
+    /// The regimes are drawn from the same noise as the prices of `simulate`, so with the
+    /// same `noise_source` and `config` path `i` of the result holds the regimes behind
+    /// path `i` of the prices.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an `Array2` of shape `(num_paths, num_points)` with the
+    /// index of the regime at every point of every path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `asset` is not simulated by `price_model`, if its process does
+    /// not fit `time_grid`, or if it does not switch between regimes (see
+    /// `PriceProcess::regime_path`).
+    pub fn simulate_regimes<T>(
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
+        asset: Asset,
+        num_paths: usize,
+        noise_source: &NoiseSource,
+        config: &SimulationConfig,
+    ) -> Result<Array2<usize>>
+    where
+        T: Float + FromPrimitive + Send + Sync + 'static,
+    {
+        let Some(asset_idx) = price_model.index_of(asset) else {
+            bail!("The price of '{}' is not simulated.", asset.name());
+        };
+        price_model.check_time_grid(time_grid)?;
+        let n_points = time_grid.len();
+        let process = price_model.process(asset_idx);
+        let sampler = NoiseSampler::new(
+            *noise_source,
+            *config,
+            price_model.noise_layout(),
+            num_paths,
             n_points,
-            gas_path.view_mut(),
+        )?;
+
+        let paths = (0..num_paths)
+            .into_par_iter()
+            .map(|path_idx| {
+                let noise = sampler.path_noise(path_idx);
+                process.regime_path(time_grid, price_model.process_noise(&noise, asset_idx))
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(paths) = paths else {
+            bail!(
+                "The process of '{}' does not switch between regimes.",
+                asset.name()
+            );
+        };
+        Ok(Array2::from_shape_fn((num_paths, n_points), |(i, t)| {
+            paths[i][t]
+        }))
+    }
+
+    /// Checks that there is one forward curve per asset of `price_model`, that every
+    /// curve has one point per point of `time_grid`, and that every process of the model
+    /// fits `time_grid`.
+    fn check_forward_curves<T>(
+        forward_curves: &[Array1<T>],
+        time_grid: &TimeGrid,
+        price_model: &PriceModel<T>,
+    ) -> Result<()>
+    where
+        T: Float + FromPrimitive,
+    {
+        if forward_curves.len() != price_model.num_assets() {
+            bail!(
+                "Expected {} forward curves, one per asset, got {}.",
+                price_model.num_assets(),
+                forward_curves.len()
+            );
+        }
+        if let Some(curve) = forward_curves
+            .iter()
+            .find(|curve| curve.len() != time_grid.len())
+        {
+            bail!(
+                "A forward curve has {} points, but the time grid has {}.",
+                curve.len(),
+                time_grid.len()
+            );
+        }
+        price_model.check_time_grid(time_grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::correlation::CorrelationRepair;
+    use crate::core::parameters::{ModelParameters, Volatility};
+    use crate::core::processes::geometric_brownian_motion_transformer::GeometricBrownianMotionTransformer;
+    use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::{ArrayViewMut1, array};
+    use rayon::ThreadPoolBuilder;
+
+    /// An hourly UTC grid of `num_days` days, valued at its start.
+    fn hourly_grid(num_days: usize) -> TimeGrid {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        TimeGrid::hourly(start, start, num_days, Tz::UTC).unwrap()
+    }
+
+    fn simulate_with_threads(num_threads: usize, seed: u64) -> SimulationResult<f64> {
+        let forward_curves = [Array1::from_elem(48, 3.5), Array1::from_elem(48, 45.0)];
+        let params = ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6);
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            Simulator::simulate(
+                &forward_curves,
+                &hourly_grid(2),
+                &params.price_model().unwrap(),
+                16,
+                &NoiseSource::new(seed),
+                &SimulationConfig::default(),
+            )
+            .unwrap()
+        })
+    }
+
+    #[test]
+    fn test_simulate_is_independent_of_thread_count() {
+        let single = simulate_with_threads(1, 42);
+        let multi = simulate_with_threads(4, 42);
+
+        for asset in [Asset::Gas, Asset::Power] {
+            assert_eq!(single.asset_data(asset), multi.asset_data(asset));
+        }
+    }
+
+    #[test]
+    fn test_simulate_differs_between_seeds() {
+        let a = simulate_with_threads(2, 1);
+        let b = simulate_with_threads(2, 2);
+
+        assert_ne!(a.asset_data(Asset::Power), b.asset_data(Asset::Power));
+    }
+
+    /// A process that adds its own uniform draw to the forward curve.
+    struct ShiftedForward;
+
+    impl PriceProcess<f64> for ShiftedForward {
+        fn num_uniforms(&self) -> usize {
+            1
+        }
+
+        fn transform_path(
+            &self,
+            forward_curve: &Array1<f64>,
+            _time_grid: &TimeGrid,
+            mut path: ArrayViewMut1<f64>,
+            noise: ProcessNoise<'_>,
+        ) {
+            path.assign(&(forward_curve + &noise.uniforms.row(0)));
+        }
+
+        fn expected_path(&self, forward_curve: &Array1<f64>, _time_grid: &TimeGrid) -> Array1<f64> {
+            forward_curve + 0.5
+        }
+    }
+
+    #[test]
+    fn test_simulate_accepts_custom_processes() {
+        let power = Array1::from_elem(24, 45.0);
+        let forward_curves = [Array1::from_elem(24, 3.5), power.clone()];
+        let model = PriceModel::new(
+            vec![
+                (
+                    Asset::Gas,
+                    Box::new(GeometricBrownianMotionTransformer::new(0.4)),
+                ),
+                (Asset::Power, Box::new(ShiftedForward)),
+            ],
+            &array![[1.0, 0.6], [0.6, 1.0]],
+            CorrelationRepair::Reject,
+        )
+        .unwrap();
+        let noise_source = NoiseSource::new(7);
+        let config = SimulationConfig::default();
+        let result = Simulator::simulate(
+            &forward_curves,
+            &hourly_grid(1),
+            &model,
+            8,
+            &noise_source,
+            &config,
+        )
+        .unwrap();
+
+        // The custom power process receives the only row of uniforms.
+        let sampler =
+            NoiseSampler::new(noise_source, config, model.noise_layout(), 8, 24).unwrap();
+        let power_prices = result.asset_data(Asset::Power).unwrap();
+        for path_idx in 0..8 {
+            let uniforms = sampler.path_noise(path_idx).uniforms;
+            assert_eq!(uniforms.nrows(), 1);
+            assert_eq!(power_prices.row(path_idx), &power + &uniforms.row(0));
+        }
+    }
+
+    #[test]
+    fn test_simulate_correlates_any_number_of_assets() {
+        let params = ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6)
+            .with_asset(Asset::Coal, 0.3)
+            .with_asset(Asset::Carbon, 0.5)
+            .with_correlation(Asset::Gas, Asset::Carbon, 0.7)
+            .with_correlation(Asset::Coal, Asset::Carbon, -0.4);
+        let model = params.price_model().unwrap();
+        let n_points = 48;
+        let forward_curves: Vec<Array1<f64>> = [3.5, 45.0, 2.0, 80.0]
+            .into_iter()
+            .map(|level| Array1::from_elem(n_points, level))
+            .collect();
+        let result = Simulator::simulate(
+            &forward_curves,
+            &hourly_grid(2),
+            &model,
+            500,
+            &NoiseSource::new(5),
+            &SimulationConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            result.assets(),
+            &[Asset::Gas, Asset::Power, Asset::Coal, Asset::Carbon]
         );
 
-        // 4. Transform the power path noise into a Mean-Reverting Jump Diffusion process.
-        JumpDiffusionProcessTransformer::transform_path_to_jdp(
-            forward_curve_power,
-            model_parameters.sigma_p,
-            model_parameters.kappa,
-            model_parameters.lambda_j,
-            model_parameters.mu_j,
-            model_parameters.sigma_j,
-            power_path.view_mut(),
-            &mut rng,
+        // The log returns of two GBMs are correlated like their Brownian shocks.
+        let log_returns = |asset| {
+            let prices = result.asset_data(asset).unwrap();
+            let log_prices = prices.mapv(f64::ln);
+            let returns = &log_prices.slice(ndarray::s![.., 1..])
+                - &log_prices.slice(ndarray::s![.., ..n_points - 1]);
+            returns.iter().copied().collect::<Array1<f64>>()
+        };
+        let correlation = |a: &Array1<f64>, b: &Array1<f64>| {
+            let (da, db) = (a - a.mean().unwrap(), b - b.mean().unwrap());
+            da.dot(&db) / (da.dot(&da) * db.dot(&db)).sqrt()
+        };
+        let (gas, coal, carbon) = (
+            log_returns(Asset::Gas),
+            log_returns(Asset::Coal),
+            log_returns(Asset::Carbon),
         );
+        assert!((correlation(&gas, &carbon) - 0.7).abs() < 0.03);
+        assert!((correlation(&coal, &carbon) + 0.4).abs() < 0.03);
+        assert!(correlation(&gas, &coal).abs() < 0.03);
+    }
 
-        assets
+    #[test]
+    fn test_simulate_rejects_missing_forward_curves() {
+        let model = ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6)
+            .with_asset(Asset::Carbon, 0.5)
+            .price_model()
+            .unwrap();
+        let forward_curves = [Array1::from_elem(24, 3.5), Array1::from_elem(24, 45.0)];
+        let result = Simulator::simulate(
+            &forward_curves,
+            &hourly_grid(1),
+            &model,
+            4,
+            &NoiseSource::new(1),
+            &SimulationConfig::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_simulate_paths_rejects_volatility_curves_that_do_not_fit_the_grid() {
+        let model = ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6)
+            .with_power_volatility(Volatility::Curve(vec![0.5; 12]))
+            .price_model()
+            .unwrap();
+        let forward_curves = [Array1::from_elem(24, 3.5), Array1::from_elem(24, 45.0)];
+        let sampler = NoiseSampler::new(
+            NoiseSource::new(1),
+            SimulationConfig::default(),
+            model.noise_layout(),
+            4,
+            24,
+        )
+        .unwrap();
+        let result =
+            Simulator::simulate_paths(&forward_curves, &hourly_grid(1), &model, &sampler, 0..4);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_simulate_regimes_rejects_assets_that_are_not_simulated() {
+        let model = ModelParameters::new(0.4, 0.5, 50.0, 5.0, 0.5, 0.3, 0.6)
+            .price_model()
+            .unwrap();
+        let result = Simulator::simulate_regimes(
+            &hourly_grid(1),
+            &model,
+            Asset::Carbon,
+            4,
+            &NoiseSource::new(1),
+            &SimulationConfig::default(),
+        );
+        assert!(result.is_err());
     }
 }
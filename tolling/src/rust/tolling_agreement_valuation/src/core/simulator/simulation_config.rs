@@ -0,0 +1,53 @@
+/// Selects the generator of the random numbers driving the simulation.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum NoiseScheme {
+    /// Independent pseudo-random numbers from a counter-based generator.
+    #[default]
+    PseudoRandom,
+    /// Randomised quasi-Monte Carlo with a scrambled Sobol sequence.
+    ///
+    /// The paths are spread over `num_replicates` independently scrambled copies of the
+    /// sequence. Each copy gives an unbiased estimate, and the spread between the copies
+    /// yields the standard error of the result. A path draws its leading
+    /// `sobol::MAX_DIMENSIONS` numbers, the diffusion draws of the coarsest steps, from
+    /// the sequence and any further numbers pseudo-randomly.
+    ScrambledSobol {
+        /// The number of independent scrambles. At least two are needed for a
+        /// standard error.
+        num_replicates: usize,
+        /// If `true`, the diffusion draws are assigned in Brownian bridge order, so
+        /// that the first Sobol dimensions decide the coarse shape of the path.
+        brownian_bridge: bool,
+    },
+}
+
+/// Selects the variance reduction techniques applied to the random numbers of a
+/// Monte Carlo run.
+///
+/// The techniques only change how the noise is generated, so they apply equally to the
+/// `f64` valuation and the `AADVar` greeks calculation without any change to the
+/// valuation code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimulationConfig {
+    /// If `true`, paths are generated in antithetic pairs: path `2k + 1` uses the
+    /// negated normal draws `-Z` and the reflected uniform draws `1 - U` of path `2k`.
+    /// This applies to both the Brownian and the jump components.
+    pub antithetic: bool,
+    /// If `true`, the normal draws of every time step are shifted and scaled across
+    /// all paths so that their sample mean is exactly zero and their sample variance
+    /// exactly one.
+    pub moment_matching: bool,
+    /// The generator of the underlying random numbers.
+    pub noise_scheme: NoiseScheme,
+}
+
+impl SimulationConfig {
+    /// Constructs a new `SimulationConfig` instance.
+    pub fn new(antithetic: bool, moment_matching: bool, noise_scheme: NoiseScheme) -> Self {
+        SimulationConfig {
+            antithetic,
+            moment_matching,
+            noise_scheme,
+        }
+    }
+}
@@ -1,25 +1,40 @@
 #[cfg(feature = "python")]
 use ndarray::Ix3;
-use ndarray::{Array3, ArrayView2, Axis};
+use ndarray::{Array3, ArrayView2, ArrayView3, Axis};
 #[cfg(feature = "python")]
 use numpy::{IntoPyArray, PyArray};
 #[cfg(feature = "python")]
 use pyo3::{Bound, Python};
 
+use crate::core::simulator::asset::Asset;
+
 /// A wrapper struct for the results of a Monte Carlo simulation.
 ///
 /// This struct owns the simulation data, which is stored in a 3D array. It provides
 /// convenient methods to access dimensions and slices of the data.
 ///
-/// The internal data is laid out as `(num_paths, num_assets, num_points)`.
+/// The internal data is laid out as `(num_paths, num_assets, num_points)`, with the
+/// assets in the order of `assets`.
 pub struct SimulationResult<T> {
     data: Array3<T>,
+    assets: Vec<Asset>,
 }
 
 impl<T> SimulationResult<T> {
-    /// Constructs a new `SimulationResult` from a 3D array of data.
-    pub fn new(data: Array3<T>) -> Self {
-        Self { data }
+    /// Constructs a new `SimulationResult` from a 3D array of data and the asset of
+    /// every index along its second axis.
+    pub fn new(data: Array3<T>, assets: Vec<Asset>) -> Self {
+        assert_eq!(
+            data.len_of(Axis(1)),
+            assets.len(),
+            "The simulation data must have one row per asset."
+        );
+        Self { data, assets }
+    }
+
+    /// Returns the simulated assets, in the order of their indices.
+    pub fn assets(&self) -> &[Asset] {
+        &self.assets
     }
 
     /// Returns a 2D view of the data for a single asset.
@@ -28,11 +43,24 @@ impl<T> SimulationResult<T> {
     ///
     /// # Arguments
     ///
-    /// * `asset_idx`: The index of the asset to retrieve (e.g., from `TollingAssetIndex`).
+    /// * `asset_idx`: The index of the asset to retrieve, i.e. its position in `assets`.
     pub fn get_asset_data(&self, asset_idx: usize) -> ArrayView2<'_, T> {
         self.data.index_axis(Axis(1), asset_idx)
     }
 
+    /// Returns a 2D view of the data of `asset`, or `None` if it was not simulated.
+    ///
+    /// The returned array view has the shape `(num_paths, num_points)`.
+    pub fn asset_data(&self, asset: Asset) -> Option<ArrayView2<'_, T>> {
+        let asset_idx = self.assets.iter().position(|&a| a == asset)?;
+        Some(self.get_asset_data(asset_idx))
+    }
+
+    /// Returns a 3D view of all data, of shape `(num_paths, num_assets, num_points)`.
+    pub fn data(&self) -> ArrayView3<'_, T> {
+        self.data.view()
+    }
+
     /// Returns the number of simulation paths.
     pub fn num_paths(&self) -> usize {
         self.data.len_of(Axis(0))
@@ -0,0 +1,467 @@
+use anyhow::{Result, bail};
+use ndarray::{Array1, ArrayView1};
+
+use crate::core::common::distributions::normal_cdf;
+use crate::core::common::random::PathNoise;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::{GasModel, ModelParameters, PowerModel, UnitParameter};
+use crate::core::processes::price_process::PriceProcess;
+use crate::core::simulator::asset::Asset;
+use crate::core::simulator::price_model::PriceModel;
+use crate::core::valuation::profit_and_loss::ProfitCalculator;
+
+/// A path quantity whose expectation is known analytically and which can therefore be
+/// used as a control variate for the value of the agreement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlVariate {
+    /// The discounted average of the simulated gas prices. Its expectation follows from
+    /// the gas forward curve, since `E[G_t] = F_gas(t)`.
+    AverageGasPrice,
+    /// The discounted average of the simulated power prices. Its expectation follows
+    /// from the power forward curve and the moments of the simulated process.
+    AveragePowerPrice,
+    /// The discounted payoff of spread options `max(P~_t - HR * G_t, 0)` on the energy of
+    /// every unit and delivery period, where `P~_t` is the power price of the same path
+    /// without jumps. Gas and the jump-free power price are jointly lognormal, so the
+    /// expectation of every option is given by Margrabe's formula. The options are
+    /// written on gas whatever the fuel of a unit, which keeps the control exact but
+    /// makes it less effective for units that do not burn gas. Only available for a GBM
+    /// gas price, and not for a shifted power price or the regime-switching power model,
+    /// whose prices are not lognormal without jumps.
+    SpreadOption,
+}
+
+/// The result of applying control variates to a sample of path values.
+#[derive(Debug, Clone)]
+pub struct ControlVariateEstimate {
+    /// The control-variate estimate of the mean of the path values.
+    pub estimate: f64,
+    /// The regression coefficient of every control, in the order of the controls.
+    pub coefficients: Vec<f64>,
+    /// The path values with the fitted controls subtracted, `Y_i - beta * (X_i - E[X])`.
+    /// Their mean is `estimate`; their spread measures the remaining noise.
+    pub adjusted_values: Vec<f64>,
+}
+
+/// Calculates control variates for simulated paths and applies them to path values.
+///
+/// The controls of a path are computed from its simulated prices (and, for the spread
+/// option, from the noise of the path), while their expectations are computed once
+/// from the forward curves and the model parameters. The correction uses the regression
+/// coefficients that minimise the variance of the adjusted values:
+///
+/// `estimate = mean(Y) - beta . (mean(X) - E[X])`,  `beta = Cov(X)^-1 Cov(X, Y)`
+///
+/// Estimating `beta` from the same paths introduces a bias of order `1 / num_paths`,
+/// which is negligible against the standard error for any practical path count.
+pub struct ControlVariates {
+    kinds: Vec<ControlVariate>,
+    expectations: Vec<f64>,
+    /// The discount factor of every point, i.e. that of the day the point belongs to.
+    discount_factors: Array1<f64>,
+    power_curve: Array1<f64>,
+    time_grid: TimeGrid,
+    model_params: ModelParameters<f64>,
+    price_model: PriceModel<f64>,
+    /// The index of the power price in the price model.
+    power: usize,
+    /// The power process of the model with the jump intensity set to zero.
+    jump_free_power: Box<dyn PriceProcess<f64>>,
+    unit_params: Vec<UnitParameter<f64>>,
+}
+
+impl ControlVariates {
+    /// Prepares the controls `kinds` and computes their expectations.
+    ///
+    /// # Arguments
+    ///
+    /// * `kinds`: The controls to use. May be empty, in which case no correction is applied.
+    /// * `gas_curve`: The forward curve for gas prices, aligned to `time_grid`.
+    /// * `power_curve`: The forward curve for power prices, aligned to `time_grid`.
+    /// * `time_grid`: The time of every point of the forward curves.
+    /// * `model_params`: The parameters the paths are simulated with.
+    /// * `unit_params`: The parameters of the power generation units.
+    /// * `risk_free_rate`: The annual risk-free rate used to discount the daily profits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `model_params` do not define a valid price model, or if the
+    /// `SpreadOption` control is used with the Schwartz-Smith gas model, a shifted power
+    /// price or the regime-switching power model.
+    pub fn new(
+        kinds: &[ControlVariate],
+        gas_curve: &Array1<f64>,
+        power_curve: &Array1<f64>,
+        time_grid: &TimeGrid,
+        model_params: &ModelParameters<f64>,
+        unit_params: &[UnitParameter<f64>],
+        risk_free_rate: f64,
+    ) -> Result<Self> {
+        let discount_factors = Array1::from_shape_fn(time_grid.len(), |i| {
+            ProfitCalculator::discount_factor(time_grid, time_grid.day_of(i), risk_free_rate)
+        });
+
+        if kinds.contains(&ControlVariate::SpreadOption)
+            && (matches!(model_params.power_model, PowerModel::RegimeSwitching { .. })
+                || model_params.power_shift.is_some())
+        {
+            bail!(
+                "The spread option control variate needs a power price that is lognormal \
+                 without jumps, which a shifted or regime-switching one is not."
+            );
+        }
+        if kinds.contains(&ControlVariate::SpreadOption) && model_params.gas_model != GasModel::Gbm
+        {
+            bail!("The spread option control variate needs a GBM gas price.");
+        }
+
+        // Models built from `ModelParameters` always contain gas and power.
+        let price_model = model_params.price_model()?;
+        let gas = price_model.index_of(Asset::Gas).unwrap();
+        let power = price_model.index_of(Asset::Power).unwrap();
+        let mut control_variates = ControlVariates {
+            kinds: kinds.to_vec(),
+            expectations: Vec::with_capacity(kinds.len()),
+            discount_factors,
+            power_curve: power_curve.clone(),
+            time_grid: time_grid.clone(),
+            model_params: model_params.clone(),
+            price_model,
+            power,
+            jump_free_power: ModelParameters {
+                lambda_j: 0.0,
+                ..model_params.clone()
+            }
+            .power_process()?,
+            unit_params: unit_params.to_vec(),
+        };
+        control_variates.expectations = kinds
+            .iter()
+            .map(|kind| match kind {
+                ControlVariate::AverageGasPrice => control_variates.average_price(
+                    control_variates
+                        .price_model
+                        .process(gas)
+                        .expected_path(gas_curve, time_grid)
+                        .view(),
+                ),
+                ControlVariate::AveragePowerPrice => control_variates.average_price(
+                    control_variates
+                        .price_model
+                        .process(power)
+                        .expected_path(power_curve, time_grid)
+                        .view(),
+                ),
+                ControlVariate::SpreadOption => control_variates.expected_spread_options(gas_curve),
+            })
+            .collect();
+        Ok(control_variates)
+    }
+
+    /// Returns `true` if no controls are used.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Calculates the controls of a single simulated path.
+    ///
+    /// # Arguments
+    ///
+    /// * `gas_prices`: The simulated gas prices of the path.
+    /// * `power_prices`: The simulated power prices of the path.
+    /// * `noise`: The noise the path was simulated from.
+    pub fn path_controls(
+        &self,
+        gas_prices: ArrayView1<f64>,
+        power_prices: ArrayView1<f64>,
+        noise: &PathNoise,
+    ) -> Vec<f64> {
+        self.kinds
+            .iter()
+            .map(|kind| match kind {
+                ControlVariate::AverageGasPrice => self.average_price(gas_prices),
+                ControlVariate::AveragePowerPrice => self.average_price(power_prices),
+                ControlVariate::SpreadOption => {
+                    let jump_free_power = self.jump_free_power_path(noise);
+                    self.spread_options(|unit, h| {
+                        (jump_free_power[h] - unit.heat_rate * gas_prices[h]).max(0.0)
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Applies the controls to the path values.
+    ///
+    /// # Arguments
+    ///
+    /// * `values`: The value of every path.
+    /// * `controls`: The controls of every path, as returned by `path_controls`.
+    pub fn apply(&self, values: &[f64], controls: &[Vec<f64>]) -> ControlVariateEstimate {
+        let num_controls = self.kinds.len();
+        let n = values.len() as f64;
+        let mean_value = values.iter().sum::<f64>() / n;
+        let mut mean_controls = vec![0.0; num_controls];
+        for path_controls in controls {
+            for (mean, x) in mean_controls.iter_mut().zip(path_controls) {
+                *mean += x / n;
+            }
+        }
+
+        // Covariance matrix of the controls and their covariance with the values.
+        let mut cov_xx = vec![vec![0.0; num_controls]; num_controls];
+        let mut cov_xy = vec![0.0; num_controls];
+        for (value, path_controls) in values.iter().zip(controls) {
+            let dx: Vec<f64> = path_controls
+                .iter()
+                .zip(&mean_controls)
+                .map(|(x, mean)| x - mean)
+                .collect();
+            for (j, dx_j) in dx.iter().enumerate() {
+                cov_xy[j] += dx_j * (value - mean_value);
+                for (k, dx_k) in dx.iter().enumerate() {
+                    cov_xx[j][k] += dx_j * dx_k;
+                }
+            }
+        }
+        let coefficients = solve_normal_equations(cov_xx, cov_xy);
+
+        let adjusted_values: Vec<f64> = values
+            .iter()
+            .zip(controls)
+            .map(|(value, path_controls)| {
+                let correction: f64 = coefficients
+                    .iter()
+                    .zip(path_controls.iter().zip(&self.expectations))
+                    .map(|(beta, (x, expectation))| beta * (x - expectation))
+                    .sum();
+                value - correction
+            })
+            .collect();
+        let estimate = adjusted_values.iter().sum::<f64>() / n;
+
+        ControlVariateEstimate {
+            estimate,
+            coefficients,
+            adjusted_values,
+        }
+    }
+
+    /// The discounted average of a price path.
+    fn average_price(&self, prices: ArrayView1<f64>) -> f64 {
+        let n_points = self.discount_factors.len();
+        prices
+            .iter()
+            .zip(self.discount_factors.iter())
+            .map(|(price, discount_factor)| price * discount_factor)
+            .sum::<f64>()
+            / n_points as f64
+    }
+
+    /// Sums the option values `option_value(unit, h)` over all points and units,
+    /// discounted and scaled by the energy of the units, their capacity times the length
+    /// of the delivery period.
+    fn spread_options(&self, option_value: impl Fn(&UnitParameter<f64>, usize) -> f64) -> f64 {
+        let mut total = 0.0;
+        for (h, discount_factor) in self.discount_factors.iter().enumerate() {
+            for unit in &self.unit_params {
+                let energy = unit.capacity * self.time_grid.duration(h);
+                total += discount_factor * energy * option_value(unit, h);
+            }
+        }
+        total
+    }
+
+    /// Simulates the power price of a path from its diffusion noise only.
+    ///
+    /// The path is generated by the same process as the simulated power price, with the
+    /// jump intensity set to zero, so it shares the diffusion of the simulated path.
+    fn jump_free_power_path(&self, noise: &PathNoise) -> Array1<f64> {
+        let power = self.power;
+        let l = self.price_model.cholesky_l();
+        let mut path = Array1::zeros(noise.normals.ncols());
+        for k in 0..=power {
+            path.scaled_add(l[[power, k]], &noise.normals.row(k));
+        }
+        self.jump_free_power.transform_path(
+            &self.power_curve,
+            &self.time_grid,
+            path.view_mut(),
+            self.price_model.process_noise(noise, power),
+        );
+        path
+    }
+
+    /// The expectation of the `SpreadOption` control.
+    ///
+    /// At every point `t` the log gas price is Gaussian with variance `v_g(t)`, the log
+    /// jump-free power price is Gaussian with variance `v_p(t)` and the two have
+    /// covariance `c(t)`. These follow from the discretisations of the two transformers
+    /// on the steps `dt_k` of the time grid:
+    ///
+    /// - `v_g(t) = sum_{k <= t} sigma_g,k^2 * dt_k`
+    /// - `v_p(t) = sum_{k <= t} b_k^2 * sigma_p,k^2 * s_k^2`
+    /// - `c(t) = rho * sum_{k <= t} b_k * sigma_g,k * sigma_p,k * s_k * sqrt(dt_k)`
+    ///
+    /// where `sigma_g,k` and `sigma_p,k` are the volatilities of step `k`, `s_k` is the
+    /// shock scale of step `k` of the OU scheme and `b_k` is the
+    /// product of the decays of the steps `m` after `k` up to `t` (see `OuStep`). The
+    /// mean of the jump-free power price is the expected path of its process, which
+    /// depends on the convexity correction of the power model. The option
+    /// `max(P~_t - HR * G_t, 0)` is then valued with Margrabe's formula for the exchange
+    /// of two lognormal assets.
+    fn expected_spread_options(&self, gas_curve: &Array1<f64>) -> f64 {
+        let params = &self.model_params;
+        let time_grid = &self.time_grid;
+
+        let mut variances = Vec::with_capacity(self.discount_factors.len());
+        let (mut v_g, mut v_p, mut c) = (0.0, 0.0, 0.0);
+        for t in 0..time_grid.len() {
+            let dt = time_grid.time_step(t);
+            let step = params.ou_scheme.step(params.kappa, dt);
+            let sigma_g = params.sigma_g.at(time_grid, t);
+            let sigma_p = params.sigma_p.at(time_grid, t) * step.noise_scale;
+            v_g += sigma_g.powi(2) * dt;
+            v_p = step.decay.powi(2) * v_p + sigma_p.powi(2);
+            c = step.decay * c + params.rho * sigma_g * sigma_p * dt.sqrt();
+            variances.push(v_g + v_p - 2.0 * c);
+        }
+
+        let mean_power = self
+            .jump_free_power
+            .expected_path(&self.power_curve, time_grid);
+        self.spread_options(|unit, t| {
+            margrabe(mean_power[t], unit.heat_rate * gas_curve[t], variances[t])
+        })
+    }
+}
+
+/// The value `E[max(X - Y, 0)]` of exchanging two jointly lognormal assets with means
+/// `mean_x` and `mean_y` whose log ratio has variance `variance`.
+fn margrabe(mean_x: f64, mean_y: f64, variance: f64) -> f64 {
+    if mean_y <= 0.0 {
+        // The option is always exercised.
+        return mean_x - mean_y;
+    }
+    let std_dev = variance.max(0.0).sqrt();
+    if std_dev < 1e-12 {
+        return (mean_x - mean_y).max(0.0);
+    }
+    let d1 = ((mean_x / mean_y).ln() + 0.5 * variance) / std_dev;
+    let d2 = d1 - std_dev;
+    mean_x * normal_cdf(d1) - mean_y * normal_cdf(d2)
+}
+
+/// Solves the normal equations `cov_xx * beta = cov_xy` of the control regression.
+///
+/// The covariance matrix is symmetric positive semi-definite. Gaussian elimination in
+/// the given order is stable for such matrices; a control whose remaining variance is
+/// negligible (e.g. the gas control with zero gas volatility, or a control that is a
+/// linear combination of the previous ones) gets a coefficient of zero.
+fn solve_normal_equations(mut cov_xx: Vec<Vec<f64>>, mut cov_xy: Vec<f64>) -> Vec<f64> {
+    let num_controls = cov_xy.len();
+    let scale = (0..num_controls).map(|j| cov_xx[j][j]).fold(0.0, f64::max);
+    let tolerance = 1e-12 * scale;
+
+    let mut active = vec![false; num_controls];
+    for pivot in 0..num_controls {
+        if cov_xx[pivot][pivot] <= tolerance {
+            continue;
+        }
+        active[pivot] = true;
+        let pivot_row = cov_xx[pivot].clone();
+        for row in pivot + 1..num_controls {
+            let factor = cov_xx[row][pivot] / pivot_row[pivot];
+            for (entry, pivot_entry) in cov_xx[row].iter_mut().zip(&pivot_row).skip(pivot) {
+                *entry -= factor * pivot_entry;
+            }
+            cov_xy[row] -= factor * cov_xy[pivot];
+        }
+    }
+
+    let mut coefficients = vec![0.0; num_controls];
+    for j in (0..num_controls).rev() {
+        if active[j] {
+            let known: f64 = (j + 1..num_controls)
+                .map(|k| cov_xx[j][k] * coefficients[k])
+                .sum();
+            coefficients[j] = (cov_xy[j] - known) / cov_xx[j][j];
+        }
+    }
+    coefficients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::common::random::NoiseSource;
+    use crate::core::simulator::noise_sampler::NoiseSampler;
+    use crate::core::simulator::simulate_prices::Simulator;
+    use crate::core::simulator::simulation_config::SimulationConfig;
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use ndarray::Array2;
+
+    #[test]
+    fn controls_average_to_their_expectations() {
+        let n_points = 24 * 5;
+        let num_paths = 2000;
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let time_grid = TimeGrid::hourly(start, start, 5, Tz::UTC).unwrap();
+        let gas_curve = Array1::linspace(20.0, 22.0, n_points);
+        let power_curve = Array1::linspace(45.0, 50.0, n_points);
+        let model_params = ModelParameters::new(0.4, 0.6, 30.0, 20.0, 0.2, 0.3, 0.5);
+        let unit_params = [
+            UnitParameter::new(2.0, 100.0, 500.0),
+            UnitParameter::new(2.5, 50.0, 200.0),
+        ];
+        let kinds = [
+            ControlVariate::AverageGasPrice,
+            ControlVariate::AveragePowerPrice,
+            ControlVariate::SpreadOption,
+        ];
+        let control_variates = ControlVariates::new(
+            &kinds,
+            &gas_curve,
+            &power_curve,
+            &time_grid,
+            &model_params,
+            &unit_params,
+            0.03,
+        )
+        .unwrap();
+        let price_model = model_params.price_model().unwrap();
+        let forward_curves = [gas_curve, power_curve];
+        let sampler = NoiseSampler::new(
+            NoiseSource::new(11),
+            SimulationConfig::default(),
+            price_model.noise_layout(),
+            num_paths,
+            n_points,
+        )
+        .unwrap();
+
+        let mut controls = Array2::<f64>::zeros((num_paths, kinds.len()));
+        for path_idx in 0..num_paths {
+            let noise = sampler.path_noise(path_idx);
+            let assets =
+                Simulator::simulate_single_path(&forward_curves, &time_grid, &price_model, &noise);
+            let path_controls =
+                control_variates.path_controls(assets.row(0), assets.row(1), &noise);
+            controls
+                .row_mut(path_idx)
+                .assign(&Array1::from(path_controls));
+        }
+
+        for (j, expectation) in control_variates.expectations.iter().enumerate() {
+            let column = controls.column(j);
+            let mean = column.mean().unwrap();
+            let standard_error = column.std(1.0) / (num_paths as f64).sqrt();
+            assert!(
+                (mean - expectation).abs() < 4.0 * standard_error,
+                "control {j}: mean {mean}, expectation {expectation}, standard error \
+                 {standard_error}"
+            );
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod profit_and_loss;
+pub mod control_variates;
@@ -1,8 +1,9 @@
-use crate::core::parameters::UnitParameter;
-use crate::core::simulator::simulate_prices::TollingAssetIndex;
+use crate::core::common::time_grid::TimeGrid;
+use crate::core::parameters::{Dispatch, UnitParameter};
+use crate::core::simulator::asset::Asset;
 use crate::core::simulator::simulation_result::SimulationResult;
-use anyhow::Result;
-use ndarray::{Array2, ArrayView1};
+use anyhow::{Result, bail};
+use ndarray::{Array2, ArrayView2, Axis};
 use num_traits::{Float, FromPrimitive};
 use rayon::prelude::*;
 
@@ -17,39 +18,51 @@ impl ProfitCalculator {
     /// # Arguments
     ///
     /// * `simulation_result`: The result of a Monte Carlo simulation.
+    /// * `time_grid`: The time grid the prices were simulated on.
     /// * `unit_parameters`: A slice of `UnitParameter` structs defining the power units.
     /// * `risk_free_rate`: The annual risk-free rate for discounting.
     ///
     /// # Returns
     ///
     /// A `Result` containing a 2D array of shape `(num_paths, num_days)` with the
-    /// discounted daily profits for each path and each day.
+    /// discounted daily profits for each path and each local day of `time_grid`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the simulation lacks an asset the units need, see
+    /// `check_assets`, or if it does not have one point per point of `time_grid`.
     pub fn calculate_daily_profits<T: Float + FromPrimitive + Send + Sync + 'static>(
         simulation_result: &SimulationResult<T>,
+        time_grid: &TimeGrid,
         unit_parameters: &[UnitParameter<T>],
         risk_free_rate: T,
     ) -> Result<Array2<T>> {
-        let num_paths = simulation_result.num_paths();
-        let num_hours = simulation_result.num_points();
-        let n_days = num_hours / 24;
+        let assets = simulation_result.assets();
+        Self::check_assets(unit_parameters, assets)?;
 
-        let gas_prices = simulation_result.get_asset_data(TollingAssetIndex::Gas.idx());
-        let power_prices = simulation_result.get_asset_data(TollingAssetIndex::Power.idx());
+        if simulation_result.num_points() != time_grid.len() {
+            bail!(
+                "The simulation has {} points, but the time grid has {}.",
+                simulation_result.num_points(),
+                time_grid.len()
+            );
+        }
+
+        let num_paths = simulation_result.num_paths();
+        let n_days = time_grid.num_days();
+        let prices = simulation_result.data();
 
         // Process each path in parallel using `flat_map` to collect all daily profits
         // into a single Vec.
         let daily_profits: Vec<T> = (0..num_paths)
             .into_par_iter()
             .flat_map(|path_idx| {
-                let path_gas = gas_prices.row(path_idx);
-                let path_power = power_prices.row(path_idx);
-
                 Self::calculate_single_path(
-                    &path_gas,
-                    &path_power,
+                    &prices.index_axis(Axis(0), path_idx),
+                    assets,
+                    time_grid,
                     unit_parameters,
                     risk_free_rate,
-                    n_days,
                 )
             })
             .collect();
@@ -59,46 +72,115 @@ impl ProfitCalculator {
             .map_err(|e| anyhow::anyhow!("Failed to reshape daily profits: {}", e))
     }
 
+    /// Checks that `assets` contains every price the units need: power, the fuel of
+    /// every unit and, if a unit emits carbon, carbon.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of these assets is missing or if a unit burns power.
+    pub fn check_assets<T: Float>(
+        unit_parameters: &[UnitParameter<T>],
+        assets: &[Asset],
+    ) -> Result<()> {
+        let missing = |asset: Asset| !assets.contains(&asset);
+        if missing(Asset::Power) {
+            bail!("The power price must be simulated to value the units.");
+        }
+        for unit in unit_parameters {
+            if unit.fuel == Asset::Power {
+                bail!("A unit cannot burn power.");
+            }
+            if missing(unit.fuel) {
+                bail!(
+                    "A unit burns '{}', whose price is not simulated.",
+                    unit.fuel.name()
+                );
+            }
+            if unit.emission_factor != T::zero() && missing(Asset::Carbon) {
+                bail!("A unit emits carbon, but the carbon price is not simulated.");
+            }
+        }
+        Ok(())
+    }
+
     /// Calculates the discounted daily profits for a single simulation path.
     ///
-    /// For each day, this function calculates the total profit from all generation units
+    /// For each local day of the time grid, this function calculates the total profit from all generation units
     /// based on an optimal dispatch decision (i.e., only run a unit if it's profitable
     /// for that day). The daily profit is then discounted to present value.
     ///
+    /// Every point a unit earns the clean spread
+    /// `power - heat_rate * fuel - emission_factor * carbon` on the energy it produces,
+    /// its capacity in MW times the length of the delivery period in hours: the clean
+    /// spark spread for a gas-fired unit and the clean dark spread for a coal-fired one.
+    /// A unit that is started for the day runs through all of its points, even those
+    /// with a negative spread, unless its `dispatch` is `Dispatch::Hourly`, in which case
+    /// it stands idle in the points whose spread is negative.
+    /// The grid may be hourly, half-hourly, quarter-hourly or irregular, and a day has as
+    /// many points as it has delivery periods, e.g. 23 or 25 hours on the days the clocks
+    /// change. Its profit is paid, and discounted from, the end of the day.
+    ///
     /// # Arguments
     ///
-    /// * `gas_prices`: A 1D view of the simulated hourly gas prices for the path.
-    /// * `power_prices`: A 1D view of the simulated hourly power prices for the path.
+    /// * `prices`: A 2D view of shape `(num_assets, num_points)` of the simulated prices
+    ///   of the path.
+    /// * `assets`: The asset of every row of `prices`.
+    /// * `time_grid`: The time grid of the prices.
     /// * `unit_parameters`: A slice of `UnitParameter` structs defining the power units.
     /// * `risk_free_rate`: The annual risk-free rate for discounting.
-    /// * `n_days`: The number of days in the simulation path.
     ///
     /// # Returns
     ///
     /// A `Vec<T>` where each element is the discounted profit for a single day.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `assets` lacks an asset the units need. Call `check_assets` first to
+    /// report this as an error instead.
     pub fn calculate_single_path<T: Float + FromPrimitive>(
-        gas_prices: &ArrayView1<T>,
-        power_prices: &ArrayView1<T>,
+        prices: &ArrayView2<T>,
+        assets: &[Asset],
+        time_grid: &TimeGrid,
         unit_parameters: &[UnitParameter<T>],
         risk_free_rate: T,
-        n_days: usize,
     ) -> Vec<T> {
-        let mut path_daily_profits = Vec::with_capacity(n_days);
+        let prices_of = |asset: Asset| {
+            assets
+                .iter()
+                .position(|&a| a == asset)
+                .map(|asset_idx| prices.row(asset_idx))
+        };
+        let power_prices = prices_of(Asset::Power).expect("The power price is not simulated.");
+        // Without a carbon price no unit may emit carbon, so its cost is zero.
+        let carbon_prices = prices_of(Asset::Carbon);
+        let fuel_prices: Vec<_> = unit_parameters
+            .iter()
+            .map(|unit| prices_of(unit.fuel).expect("The fuel price of a unit is not simulated."))
+            .collect();
+
+        let mut path_daily_profits = Vec::with_capacity(time_grid.num_days());
 
-        for day in 0..n_days {
+        for day in 0..time_grid.num_days() {
             let mut daily_profit = T::zero();
-            let day_offset = day * 24;
 
             // Calculate the profit for each generation unit for the current day.
-            for unit in unit_parameters {
+            for (unit, fuel_prices) in unit_parameters.iter().zip(&fuel_prices) {
                 let mut unit_day_gross = T::zero();
-                for hour_of_day in 0..24 {
-                    let h = day_offset + hour_of_day;
+                for h in time_grid.day_points(day) {
                     let p = power_prices[h];
-                    let g = gas_prices[h];
-                    // Profit for one hour = (Power Price - Gas Cost) * Capacity
-                    let hourly_profit = (p - (unit.heat_rate * g)) * unit.capacity;
-                    unit_day_gross = unit_day_gross + hourly_profit;
+                    let f = fuel_prices[h];
+                    // Profit for one period = (Power Price - Fuel Cost - Carbon Cost) * Energy
+                    let mut spread = p - (unit.heat_rate * f);
+                    if let Some(carbon_prices) = &carbon_prices {
+                        spread = spread - unit.emission_factor * carbon_prices[h];
+                    }
+                    // Under hourly dispatch a negative spread, e.g. from a negative power
+                    // price, is avoided by standing idle for the period.
+                    if unit.dispatch == Dispatch::Hourly && spread <= T::zero() {
+                        continue;
+                    }
+                    let energy = unit.capacity * T::from_f64(time_grid.duration(h)).unwrap();
+                    unit_day_gross = unit_day_gross + spread * energy;
                 }
 
                 // The net profit for the unit is the gross profit minus startup costs.
@@ -111,13 +193,228 @@ impl ProfitCalculator {
             }
 
             // Discount the total daily profit to its present value.
-            // The time `t` is represented in years.
-            // We use `day + 1` because the first day's cash flow occurs at the end of day 1.
-            let t = T::from_usize(day + 1).unwrap() / T::from_f64(365.0).unwrap();
-            let discount_factor = (-risk_free_rate * t).exp();
+            let discount_factor = Self::discount_factor(time_grid, day, risk_free_rate);
 
             path_daily_profits.push(daily_profit * discount_factor);
         }
         path_daily_profits
     }
+
+    /// Returns the discount factor of the cash flow of day `day` (zero-based) of
+    /// `time_grid`.
+    ///
+    /// The cash flow of a day occurs at its end, the following local midnight, whose
+    /// time `t` in years from the valuation time is given by the grid.
+    pub fn discount_factor<T: Float + FromPrimitive>(
+        time_grid: &TimeGrid,
+        day: usize,
+        risk_free_rate: T,
+    ) -> T {
+        let t = T::from_f64(time_grid.day_end_time(day)).unwrap();
+        (-risk_free_rate * t).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+    use chrono_tz::Tz;
+    use ndarray::{Array1, s, stack};
+
+    /// An hourly grid of `num_days` days from `start`, valued at `start`.
+    fn grid(start: NaiveDate, num_days: usize, time_zone: Tz) -> TimeGrid {
+        TimeGrid::hourly(start, start, num_days, time_zone).unwrap()
+    }
+
+    fn one_day() -> TimeGrid {
+        grid(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), 1, Tz::UTC)
+    }
+
+    /// One day of constant hourly prices for gas, power, coal and carbon.
+    fn prices(gas: f64, power: f64, coal: f64, carbon: f64) -> Array2<f64> {
+        hourly_prices(24, [gas, power, coal, carbon])
+    }
+
+    /// `n_points` constant hourly prices for gas, power, coal and carbon.
+    fn hourly_prices(n_points: usize, levels: [f64; 4]) -> Array2<f64> {
+        let rows: Vec<Array1<f64>> = levels
+            .into_iter()
+            .map(|price| Array1::from_elem(n_points, price))
+            .collect();
+        let views: Vec<_> = rows.iter().map(|row| row.view()).collect();
+        stack(Axis(0), &views).unwrap()
+    }
+
+    const ASSETS: [Asset; 4] = [Asset::Gas, Asset::Power, Asset::Coal, Asset::Carbon];
+
+    #[test]
+    fn units_earn_clean_spark_and_dark_spreads() {
+        let prices = prices(30.0, 120.0, 12.0, 80.0);
+        let gas_unit = UnitParameter::new(2.0, 100.0, 1000.0).with_emission_factor(0.37);
+        let coal_unit = UnitParameter::new(2.5, 50.0, 500.0)
+            .with_fuel(Asset::Coal)
+            .with_emission_factor(0.9);
+        let units = [gas_unit, coal_unit];
+        ProfitCalculator::check_assets(&units, &ASSETS).unwrap();
+
+        let profits = ProfitCalculator::calculate_single_path(
+            &prices.view(),
+            &ASSETS,
+            &one_day(),
+            &units,
+            0.0,
+        );
+
+        let clean_spark = 120.0 - 2.0 * 30.0 - 0.37 * 80.0;
+        let clean_dark = 120.0 - 2.5 * 12.0 - 0.9 * 80.0;
+        let expected = (24.0 * clean_spark * 100.0 - 1000.0) + (24.0 * clean_dark * 50.0 - 500.0);
+        assert!((profits[0] - expected).abs() < 1e-9 * expected);
+    }
+
+    #[test]
+    fn carbon_costs_can_make_a_unit_stay_off() {
+        let prices = prices(30.0, 120.0, 12.0, 80.0);
+        let units = [UnitParameter::new(2.5, 50.0, 500.0)
+            .with_fuel(Asset::Coal)
+            .with_emission_factor(1.2)];
+
+        // Without carbon costs the clean dark spread is positive, with them it is not.
+        let dirty = ProfitCalculator::calculate_single_path(
+            &prices.view(),
+            &ASSETS,
+            &one_day(),
+            &[units[0].clone().with_emission_factor(0.0)],
+            0.0,
+        );
+        let clean = ProfitCalculator::calculate_single_path(
+            &prices.view(),
+            &ASSETS,
+            &one_day(),
+            &units,
+            0.0,
+        );
+        assert!(dirty[0] > 0.0);
+        assert_eq!(clean[0], 0.0);
+    }
+
+    #[test]
+    fn units_run_all_day_once_started() {
+        let mut weekend = prices(30.0, 120.0, 12.0, 80.0);
+        // Power turns negative around noon, as on a sunny, windy weekend.
+        weekend.slice_mut(s![1, 10..16]).fill(-40.0);
+        let units = [UnitParameter::new(2.0, 100.0, 1000.0)];
+
+        let profits = ProfitCalculator::calculate_single_path(
+            &weekend.view(),
+            &ASSETS,
+            &one_day(),
+            &units,
+            0.0,
+        );
+
+        // The unit sells at a loss in the six hours of negative power prices.
+        let expected =
+            (18.0 * (120.0 - 2.0 * 30.0) + 6.0 * (-40.0 - 2.0 * 30.0)) * 100.0 - 1000.0;
+        assert!((profits[0] - expected).abs() < 1e-9 * expected);
+    }
+
+    #[test]
+    fn hourly_dispatch_stands_idle_at_negative_spreads() {
+        let mut weekend = prices(30.0, 120.0, 12.0, 80.0);
+        weekend.slice_mut(s![1, 10..16]).fill(-40.0);
+        let units = [UnitParameter::new(2.0, 100.0, 1000.0).with_dispatch(Dispatch::Hourly)];
+
+        let profits = ProfitCalculator::calculate_single_path(
+            &weekend.view(),
+            &ASSETS,
+            &one_day(),
+            &units,
+            0.0,
+        );
+
+        let expected = 18.0 * (120.0 - 2.0 * 30.0) * 100.0 - 1000.0;
+        assert!((profits[0] - expected).abs() < 1e-9 * expected);
+
+        // A day of negative power prices leaves the unit off.
+        let negative = ProfitCalculator::calculate_single_path(
+            &prices(30.0, -5.0, 12.0, 80.0).view(),
+            &ASSETS,
+            &one_day(),
+            &units,
+            0.0,
+        );
+        assert_eq!(negative[0], 0.0);
+    }
+
+    #[test]
+    fn check_assets_requires_the_prices_of_fuel_and_carbon() {
+        let gas_and_power = [Asset::Gas, Asset::Power];
+        let coal_unit = UnitParameter::new(2.5, 50.0, 500.0).with_fuel(Asset::Coal);
+        let emitting_units = [UnitParameter::new(2.0, 100.0, 1000.0).with_emission_factor(0.37)];
+
+        assert!(ProfitCalculator::check_assets(&[coal_unit], &gas_and_power).is_err());
+        assert!(ProfitCalculator::check_assets(&emitting_units, &gas_and_power).is_err());
+        assert!(ProfitCalculator::check_assets(&emitting_units, &ASSETS).is_ok());
+    }
+
+    #[test]
+    fn local_days_follow_daylight_saving_time() {
+        // The clocks go back in Berlin on 26 October 2025, giving that day 25 hours.
+        let time_grid = grid(
+            NaiveDate::from_ymd_opt(2025, 10, 25).unwrap(),
+            3,
+            chrono_tz::Europe::Berlin,
+        );
+        let prices = hourly_prices(time_grid.len(), [30.0, 100.0, 12.0, 80.0]);
+        let units = [UnitParameter::new(2.0, 10.0, 0.0)];
+        let rate = 0.05;
+
+        let profits = ProfitCalculator::calculate_single_path(
+            &prices.view(),
+            &ASSETS,
+            &time_grid,
+            &units,
+            rate,
+        );
+
+        let hourly_profit = (100.0 - 2.0 * 30.0) * 10.0;
+        for (day, hours) in [24.0, 25.0, 24.0].into_iter().enumerate() {
+            let t = time_grid.day_end_time(day);
+            let expected = hours * hourly_profit * (-rate * t).exp();
+            assert!((profits[day] - expected).abs() < 1e-9 * expected);
+        }
+        assert!((time_grid.day_end_time(2) - 73.0 / 8760.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn energy_is_capacity_times_period_length() {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 29).unwrap();
+        let berlin = chrono_tz::Europe::Berlin;
+        let hourly = grid(start, 2, berlin);
+        let quarter_hourly =
+            TimeGrid::regular(start, start, 2, TimeDelta::minutes(15), berlin).unwrap();
+        let units = [UnitParameter::new(2.0, 10.0, 50.0)];
+        let levels = [30.0, 100.0, 12.0, 80.0];
+
+        let profits = |time_grid: &TimeGrid| {
+            let prices = hourly_prices(time_grid.len(), levels);
+            ProfitCalculator::calculate_single_path(
+                &prices.view(),
+                &ASSETS,
+                time_grid,
+                &units,
+                0.03,
+            )
+        };
+
+        // The second day has 23 hours, or 92 quarter-hours, in either resolution.
+        let expected = profits(&hourly);
+        let actual = profits(&quarter_hourly);
+        assert_eq!(quarter_hourly.len(), 4 * hourly.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!((actual - expected).abs() < 1e-9 * expected);
+        }
+        assert!((expected[1] - (23.0 * 400.0 - 50.0) * (-0.03 * 47.0 / 8760.0).exp()).abs() < 1e-6);
+    }
 }
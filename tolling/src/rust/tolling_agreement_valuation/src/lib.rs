@@ -9,20 +9,100 @@
 
 // Conditionally compile the Python bindings module when the "python" feature is enabled.
 #[cfg(feature = "python")]
-use crate::python::parameters::{PyModelParameters, PyUnitParameter};
+use crate::python::parameters::{
+    PyJumpSize, PyModelParameters, PyPhysicalParameters, PyUnitParameter, PyVolatility,
+};
 #[cfg(feature = "python")]
 use crate::python::calculate_profit::calculate_daily_profits_py;
 #[cfg(feature = "python")]
 use crate::python::calculate_greeks::{calculate_greeks_py, PyGreeksResult};
 #[cfg(feature = "python")]
-use crate::python::sample_paths::sample_prices_py;
+use crate::python::calculate_pv::{calculate_pv_py, PyPvResult};
+#[cfg(feature = "python")]
+use crate::python::calculate_pv_adaptive::{calculate_pv_adaptive_py, PyAdaptivePvResult};
+#[cfg(feature = "python")]
+use crate::python::calculate_profit_statistics::{
+    calculate_profit_statistics_py, PyProfitStatistics,
+};
+#[cfg(feature = "python")]
+use crate::python::calibrate_historical::{
+    calibrate_historical_py, PyFitDiagnostics, PyHistoricalCalibration,
+};
+#[cfg(feature = "python")]
+use crate::python::calibrate_implied::{
+    calibrate_implied_py, PyImpliedCalibration, PyOptionQuote,
+};
+#[cfg(feature = "python")]
+use crate::python::sample_paths::{
+    sample_physical_prices_py, sample_prices_py, sample_regimes_py,
+};
+#[cfg(feature = "python")]
+use crate::python::simulation_config::PySimulationConfig;
+#[cfg(feature = "python")]
+use crate::python::time_grid::PyTimeGrid;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 
 /// The core business logic of the simulation and valuation models.
-/// This module is private to the crate.
+///
+/// This module is private to the crate; Rust users reach it through the re-exports below.
 mod core;
 
+// The simulation API, also used by the benchmarks.
+pub use crate::core::common::random::{NoiseSource, entropy_seed};
+pub use crate::core::common::time_grid::TimeGrid;
+pub use crate::core::parameters::ModelParameters;
+pub use crate::core::simulator::noise_sampler::NoiseSampler;
+pub use crate::core::simulator::price_model::PriceModel;
+pub use crate::core::simulator::simulate_prices::Simulator;
+pub use crate::core::simulator::simulation_config::SimulationConfig;
+
+// The parameters of the models and of the units.
+pub use crate::core::common::correlation::CorrelationRepair;
+pub use crate::core::parameters::{
+    Dispatch, GasModel, PhysicalParameters, PowerModel, SeasonalVolatility, UnitParameter,
+    Volatility,
+};
+pub use crate::core::processes::heston_process_transformer::VarianceScheme;
+pub use crate::core::processes::jump_diffusion_process_transformer::OuScheme;
+pub use crate::core::processes::jump_size::JumpSize;
+pub use crate::core::processes::regime_switching_process_transformer::Regime;
+pub use crate::core::simulator::asset::Asset;
+pub use crate::core::simulator::simulation_result::SimulationResult;
+pub use crate::core::valuation::control_variates::ControlVariate;
+
+// The valuation of user-defined price processes, see `calculate_pv_with_model`.
+pub use crate::core::processes::price_process::{PriceProcess, ProcessNoise};
+
+// The services: simulation, valuation, risk and calibration.
+pub use crate::core::calibration::historical::{Estimate, FitDiagnostics, HistoricalCalibration};
+pub use crate::core::calibration::implied::{
+    CalibratedParameter, ImpliedCalibration, OptionKind, OptionQuote, OptionUnderlying,
+    QuoteFit, Settlement,
+};
+pub use crate::core::services::calculate_greeks::{
+    AssetDelta, CalculateGreeksArgs, GreeksResult, ParameterSensitivity, calculate_greeks,
+};
+pub use crate::core::services::calculate_profit::{CalculateProfitArgs, calculate_daily_profits};
+pub use crate::core::services::calculate_profit_statistics::{
+    CalculateProfitStatisticsArgs, ProfitStatistics, calculate_profit_statistics,
+};
+pub use crate::core::services::calculate_pv::{
+    CalculatePvArgs, ControlVariateReport, PvResult, calculate_pv, calculate_pv_with_model,
+};
+pub use crate::core::services::calculate_pv_adaptive::{
+    AdaptivePvResult, CalculatePvAdaptiveArgs, ConvergencePoint, StopReason,
+    calculate_pv_adaptive,
+};
+pub use crate::core::services::calibrate_historical::{
+    CalibrateHistoricalArgs, calibrate_historical,
+};
+pub use crate::core::services::calibrate_implied::{CalibrateImpliedArgs, calibrate_implied};
+pub use crate::core::services::sample_paths::{
+    SamplePathsArgs, SamplePhysicalPathsArgs, sample_paths, sample_physical_paths,
+    sample_regimes,
+};
+
 /// Public module containing Python bindings for the core logic.
 ///
 /// This module is only compiled when the `python` feature is enabled. It uses `pyo3` to
@@ -39,9 +119,28 @@ pub mod python;
 fn tolling_agreement_valuation(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(calculate_daily_profits_py, m)?)?;
     m.add_function(wrap_pyfunction!(calculate_greeks_py, m)?)?;
+    m.add_function(wrap_pyfunction!(calculate_pv_py, m)?)?;
+    m.add_function(wrap_pyfunction!(calculate_pv_adaptive_py, m)?)?;
+    m.add_function(wrap_pyfunction!(calculate_profit_statistics_py, m)?)?;
+    m.add_function(wrap_pyfunction!(calibrate_historical_py, m)?)?;
+    m.add_function(wrap_pyfunction!(calibrate_implied_py, m)?)?;
     m.add_function(wrap_pyfunction!(sample_prices_py, m)?)?;
+    m.add_function(wrap_pyfunction!(sample_regimes_py, m)?)?;
+    m.add_function(wrap_pyfunction!(sample_physical_prices_py, m)?)?;
     m.add_class::<PyModelParameters>()?;
+    m.add_class::<PyPhysicalParameters>()?;
     m.add_class::<PyUnitParameter>()?;
+    m.add_class::<PyVolatility>()?;
+    m.add_class::<PyJumpSize>()?;
     m.add_class::<PyGreeksResult>()?;
+    m.add_class::<PyPvResult>()?;
+    m.add_class::<PyAdaptivePvResult>()?;
+    m.add_class::<PyProfitStatistics>()?;
+    m.add_class::<PyHistoricalCalibration>()?;
+    m.add_class::<PyFitDiagnostics>()?;
+    m.add_class::<PyOptionQuote>()?;
+    m.add_class::<PyImpliedCalibration>()?;
+    m.add_class::<PySimulationConfig>()?;
+    m.add_class::<PyTimeGrid>()?;
     Ok(())
 }
@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::common::statistics::ConfidenceInterval;
 use crate::core::services::calculate_greeks::{calculate_greeks, CalculateGreeksArgs, GreeksResult};
-use crate::python::parameters::{PyModelParameters, PyUnitParameter};
+use crate::python::parameters::{PyModelParameters, PyUnitParameter, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use ndarray::Array1;
 use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
 use pyo3::{pyclass, pyfunction, Py, PyErr, PyResult, Python};
 
@@ -13,7 +20,8 @@ use pyo3::{pyclass, pyfunction, Py, PyErr, PyResult, Python};
 /// Parameters
 /// ----------
 /// gas_curve : numpy.ndarray
-///     A 1D NumPy array representing the forward curve for gas prices.
+///     A 1D NumPy array representing the forward curve for gas prices, with one point
+///     per point of the time grid or one per local day.
 /// power_curve : numpy.ndarray
 ///     A 1D NumPy array representing the forward curve for power prices.
 /// model_params : ModelParameters
@@ -25,6 +33,22 @@ use pyo3::{pyclass, pyfunction, Py, PyErr, PyResult, Python};
 ///     The number of Monte Carlo simulation paths to run for the calculation.
 /// risk_free_rate : float
 ///     The annual risk-free rate for discounting profits.
+/// seed : int, optional
+///     The master seed of the random number streams. Runs with the same seed produce
+///     identical results and share their random numbers with `calculate_daily_profits`,
+///     so bumped revaluations differ only by the parameter change. If omitted, a random
+///     seed is drawn.
+/// config : SimulationConfig, optional
+///     The random number scheme (pseudo-random or scrambled Sobol) and the variance
+///     reduction techniques applied to it. Defaults to plain pseudo-random sampling.
+/// additional_curves : dict[str, numpy.ndarray], optional
+///     The forward curves of the additional assets of `model_params`, keyed by asset
+///     name, e.g. ``{"carbon": carbon_curve}``.
+/// time_grid : TimeGrid, optional
+///     The calendar time of every point of the curves, which sets the time steps of the
+///     price processes, the length of the delivery periods and the local days that are
+///     valued and discounted. Defaults to hourly points in UTC starting at the valuation
+///     time, and is required for a seasonal volatility.
 ///
 /// Returns
 /// -------
@@ -32,7 +56,11 @@ use pyo3::{pyclass, pyfunction, Py, PyErr, PyResult, Python};
 ///     An object containing the calculated Greeks as NumPy arrays and floats.
 #[cfg(feature = "python")]
 #[pyfunction]
-#[pyo3(name = "calculate_greeks")]
+#[pyo3(
+    name = "calculate_greeks",
+    signature = (gas_curve, power_curve, model_params, unit_params, num_paths, risk_free_rate, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_greeks_py<'py>(
     py: Python<'py>,
     gas_curve: PyReadonlyArray1<f64>,
@@ -41,15 +69,28 @@ pub fn calculate_greeks_py<'py>(
     unit_params: Vec<PyUnitParameter>,
     num_paths: usize,
     risk_free_rate: f64,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
 ) -> PyResult<PyGreeksResult> {
     // 1. Convert Python inputs to the core Rust domain types.
+    let model_params = model_params.to_domain();
     let args = CalculateGreeksArgs {
         gas_curve: gas_curve.as_array().to_owned(),
         power_curve: power_curve.as_array().to_owned(),
-        model_params: model_params.to_domain(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
         unit_params: unit_params.iter().map(|p| p.to_domain()).collect(),
         num_paths,
         risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
     };
 
     // 2. Call the core Rust function.
@@ -61,38 +102,179 @@ pub fn calculate_greeks_py<'py>(
     Ok(py_result)
 }
 
+/// The ``(lower, upper)`` bounds of a confidence interval per bucket.
+type ArrayInterval = (Py<PyArray1<f64>>, Py<PyArray1<f64>>);
+
 /// A Python class to hold the results of the greeks calculation.
 ///
+/// Every estimate comes with its standard error and a 95% confidence interval, given as
+/// a ``(lower, upper)`` tuple. Under quasi-Monte Carlo both are estimated from the spread
+/// between the independently scrambled replicates. Delta and vega intervals are per bucket.
+///
 /// Attributes
 /// ----------
+/// pv : float
+///     The present value of the agreement, estimated from the same paths as the Greeks.
+/// pv_standard_error : float
+///     The standard error of `pv`.
+/// pv_confidence_interval : tuple[float, float]
+///     The 95% confidence interval of `pv`.
 /// delta_power : numpy.ndarray
 ///     The sensitivity of the portfolio value to changes in the power forward curve.
+/// delta_power_standard_error : numpy.ndarray
+///     The standard error of every bucket of `delta_power`.
+/// delta_power_confidence_interval : tuple[numpy.ndarray, numpy.ndarray]
+///     The 95% confidence interval of every bucket of `delta_power`.
 /// delta_gas : numpy.ndarray
 ///     The sensitivity of the portfolio value to changes in the gas forward curve.
-/// vega_power : float
-///     The sensitivity of the portfolio value to changes in the power price volatility.
-/// vega_gas : float
-///     The sensitivity of the portfolio value to changes in the gas price volatility.
+/// delta_gas_standard_error : numpy.ndarray
+///     The standard error of every bucket of `delta_gas`.
+/// delta_gas_confidence_interval : tuple[numpy.ndarray, numpy.ndarray]
+///     The 95% confidence interval of every bucket of `delta_gas`.
+/// delta_additional : dict[str, numpy.ndarray]
+///     The sensitivity of the portfolio value to changes in the forward curve of every
+///     additional asset, keyed by asset name, e.g. ``"carbon"``.
+/// delta_additional_standard_error : dict[str, numpy.ndarray]
+///     The standard error of every bucket of `delta_additional`, keyed by asset name.
+/// delta_additional_confidence_interval : dict[str, tuple[numpy.ndarray, numpy.ndarray]]
+///     The 95% confidence interval of every bucket of `delta_additional`, keyed by asset
+///     name.
+/// vega_power : numpy.ndarray
+///     The sensitivity of the portfolio value to every bucket of the power price
+///     volatility, or to the volatility of every regime of the regime-switching power
+///     model. A constant volatility has a single bucket.
+/// vega_power_standard_error : numpy.ndarray
+///     The standard error of every bucket of `vega_power`.
+/// vega_power_confidence_interval : tuple[numpy.ndarray, numpy.ndarray]
+///     The 95% confidence interval of every bucket of `vega_power`.
+/// vega_power_buckets : list[str]
+///     The name of every bucket of `vega_power`, e.g. `"regime_0"` for the first regime.
+/// vega_gas : numpy.ndarray
+///     The sensitivity of the portfolio value to every bucket of the gas price volatility,
+///     or to the short- and long-term volatilities of the Schwartz-Smith gas model.
+/// vega_gas_standard_error : numpy.ndarray
+///     The standard error of every bucket of `vega_gas`.
+/// vega_gas_confidence_interval : tuple[numpy.ndarray, numpy.ndarray]
+///     The 95% confidence interval of every bucket of `vega_gas`.
+/// vega_gas_buckets : list[str]
+///     The name of every bucket of `vega_gas`, see `Volatility.bucket_names`, or
+///     `short_term` and `long_term` for the Schwartz-Smith gas model.
+/// gas_model_sensitivities : dict[str, float]
+///     The sensitivity of the portfolio value to every parameter of the gas model
+///     reported besides the vegas, keyed by parameter name: ``"v0"``, ``"theta_v"`` and
+///     ``"xi"`` for the Heston gas model, and empty otherwise.
+/// gas_model_sensitivities_standard_error : dict[str, float]
+///     The standard error of every entry of `gas_model_sensitivities`.
+/// gas_model_sensitivities_confidence_interval : dict[str, tuple[float, float]]
+///     The 95% confidence interval of every entry of `gas_model_sensitivities`.
 #[pyclass(name = "GreeksResult")]
 pub struct PyGreeksResult {
+    #[pyo3(get)]
+    pub pv: f64,
+    #[pyo3(get)]
+    pub pv_standard_error: f64,
+    #[pyo3(get)]
+    pub pv_confidence_interval: (f64, f64),
     #[pyo3(get)]
     pub delta_power: Py<PyArray1<f64>>,
     #[pyo3(get)]
+    pub delta_power_standard_error: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub delta_power_confidence_interval: (Py<PyArray1<f64>>, Py<PyArray1<f64>>),
+    #[pyo3(get)]
     pub delta_gas: Py<PyArray1<f64>>,
     #[pyo3(get)]
-    pub vega_power: f64,
+    pub delta_gas_standard_error: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub delta_gas_confidence_interval: (Py<PyArray1<f64>>, Py<PyArray1<f64>>),
+    #[pyo3(get)]
+    pub delta_additional: HashMap<String, Py<PyArray1<f64>>>,
+    #[pyo3(get)]
+    pub delta_additional_standard_error: HashMap<String, Py<PyArray1<f64>>>,
+    #[pyo3(get)]
+    pub delta_additional_confidence_interval: HashMap<String, ArrayInterval>,
+    #[pyo3(get)]
+    pub vega_power: Py<PyArray1<f64>>,
     #[pyo3(get)]
-    pub vega_gas: f64,
+    pub vega_power_standard_error: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub vega_power_confidence_interval: (Py<PyArray1<f64>>, Py<PyArray1<f64>>),
+    #[pyo3(get)]
+    pub vega_power_buckets: Vec<String>,
+    #[pyo3(get)]
+    pub vega_gas: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub vega_gas_standard_error: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub vega_gas_confidence_interval: (Py<PyArray1<f64>>, Py<PyArray1<f64>>),
+    #[pyo3(get)]
+    pub vega_gas_buckets: Vec<String>,
+    #[pyo3(get)]
+    pub gas_model_sensitivities: HashMap<String, f64>,
+    #[pyo3(get)]
+    pub gas_model_sensitivities_standard_error: HashMap<String, f64>,
+    #[pyo3(get)]
+    pub gas_model_sensitivities_confidence_interval: HashMap<String, (f64, f64)>,
 }
 
 impl PyGreeksResult {
     /// Creates a `PyGreeksResult` from a core `GreeksResult`.
     fn from_domain(domain: GreeksResult, py: Python) -> Self {
+        let array_interval = |interval: ConfidenceInterval<Array1<f64>>| {
+            (
+                interval.lower.into_pyarray(py).unbind(),
+                interval.upper.into_pyarray(py).unbind(),
+            )
+        };
+        let scalar_interval = |interval: ConfidenceInterval<f64>| (interval.lower, interval.upper);
+        let mut delta_additional = HashMap::new();
+        let mut delta_additional_standard_error = HashMap::new();
+        let mut delta_additional_confidence_interval = HashMap::new();
+        for asset_delta in domain.delta_additional {
+            let name = asset_delta.asset.name().to_string();
+            delta_additional.insert(name.clone(), asset_delta.delta.into_pyarray(py).unbind());
+            delta_additional_standard_error.insert(
+                name.clone(),
+                asset_delta.standard_error.into_pyarray(py).unbind(),
+            );
+            delta_additional_confidence_interval
+                .insert(name, array_interval(asset_delta.confidence_interval));
+        }
+        let mut gas_model_sensitivities = HashMap::new();
+        let mut gas_model_sensitivities_standard_error = HashMap::new();
+        let mut gas_model_sensitivities_confidence_interval = HashMap::new();
+        for sensitivity in domain.gas_model_sensitivities {
+            let name = sensitivity.name;
+            gas_model_sensitivities.insert(name.clone(), sensitivity.sensitivity);
+            gas_model_sensitivities_standard_error.insert(name.clone(), sensitivity.standard_error);
+            gas_model_sensitivities_confidence_interval
+                .insert(name, scalar_interval(sensitivity.confidence_interval));
+        }
+
         Self {
+            pv: domain.pv,
+            pv_standard_error: domain.pv_standard_error,
+            pv_confidence_interval: scalar_interval(domain.pv_confidence_interval),
             delta_power: domain.delta_power.into_pyarray(py).unbind(),
+            delta_power_standard_error: domain.delta_power_standard_error.into_pyarray(py).unbind(),
+            delta_power_confidence_interval: array_interval(domain.delta_power_confidence_interval),
             delta_gas: domain.delta_gas.into_pyarray(py).unbind(),
-            vega_power: domain.vega_power,
-            vega_gas: domain.vega_gas,
+            delta_gas_standard_error: domain.delta_gas_standard_error.into_pyarray(py).unbind(),
+            delta_gas_confidence_interval: array_interval(domain.delta_gas_confidence_interval),
+            delta_additional,
+            delta_additional_standard_error,
+            delta_additional_confidence_interval,
+            vega_power: domain.vega_power.into_pyarray(py).unbind(),
+            vega_power_standard_error: domain.vega_power_standard_error.into_pyarray(py).unbind(),
+            vega_power_confidence_interval: array_interval(domain.vega_power_confidence_interval),
+            vega_power_buckets: domain.vega_power_buckets,
+            vega_gas: domain.vega_gas.into_pyarray(py).unbind(),
+            vega_gas_standard_error: domain.vega_gas_standard_error.into_pyarray(py).unbind(),
+            vega_gas_confidence_interval: array_interval(domain.vega_gas_confidence_interval),
+            vega_gas_buckets: domain.vega_gas_buckets,
+            gas_model_sensitivities,
+            gas_model_sensitivities_standard_error,
+            gas_model_sensitivities_confidence_interval,
         }
     }
 }
@@ -5,16 +5,29 @@
 //! `valuation_tutorial.ipynb` notebook. This file remains for historical purposes
 //! but should be deleted.
 
+use std::collections::HashMap;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
 use crate::core::services::calculate_profit::{calculate_daily_profits, CalculateProfitArgs};
-use crate::python::parameters::{PyModelParameters, PyUnitParameter};
+use crate::python::parameters::{PyModelParameters, PyUnitParameter, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
 use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1};
 use pyo3::{pyfunction, Bound, PyErr, PyResult, Python};
 
 /// Python Wrapper for Calculate Daily Profits
 /// Returns a (num_paths, num_days) matrix of non-discounted daily profits
+/// Paths with the same `seed` share their random numbers with `calculate_greeks`
+/// `additional_curves` maps the additional assets of `model_params` to their forward curves
+/// `time_grid` gives the calendar time of the curve points; hourly in UTC if omitted
+/// Each curve has one point per grid point or one per local day, e.g. a daily gas curve
 #[cfg(feature = "python")]
 #[pyfunction]
-#[pyo3(name = "calculate_daily_profits")]
+#[pyo3(
+    name = "calculate_daily_profits",
+    signature = (gas_curve, power_curve, model_params, unit_params, num_paths, risk_free_rate, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_daily_profits_py<'py>(
     py: Python<'py>,
     gas_curve: PyReadonlyArray1<f64>,
@@ -23,14 +36,27 @@ pub fn calculate_daily_profits_py<'py>(
     unit_params: Vec<PyUnitParameter>,
     num_paths: usize,
     risk_free_rate: f64,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
 ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let model_params = model_params.to_domain();
     let args = CalculateProfitArgs {
         gas_curve: gas_curve.as_array().to_owned(),
         power_curve: power_curve.as_array().to_owned(),
-        model_params: model_params.to_domain(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
         unit_params: unit_params.iter().map(|p| p.to_domain()).collect(),
         num_paths,
         risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
     };
 
     let result = calculate_daily_profits(args)
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::services::calculate_profit_statistics::{
+    calculate_profit_statistics, CalculateProfitStatisticsArgs, ProfitStatistics,
+};
+use crate::python::parameters::{PyModelParameters, PyUnitParameter, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::{pyclass, pyfunction, Py, PyErr, PyResult, Python};
+
+/// Calculates summary statistics of the profits without storing the price paths.
+///
+/// This function is a Python wrapper around the core Rust `calculate_profit_statistics`
+/// implementation. Every path is simulated, valued and folded into running statistics
+/// on its own, so memory does not grow with `num_paths` and runs with many more paths
+/// than `calculate_daily_profits` can hold are possible.
+///
+/// Parameters
+/// ----------
+/// gas_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for gas prices, with one point
+///     per point of the time grid or one per local day.
+/// power_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for power prices.
+/// model_params : ModelParameters
+///     An instance of the `ModelParameters` class containing parameters for the
+///     stochastic models.
+/// unit_params : list[UnitParameter]
+///     A list of `UnitParameter` objects defining the power generation units.
+/// num_paths : int
+///     The number of Monte Carlo simulation paths to run for the calculation.
+/// risk_free_rate : float
+///     The annual risk-free rate for discounting profits.
+/// seed : int, optional
+///     The master seed of the random number streams. Runs with the same seed share
+///     their random numbers with `calculate_daily_profits`. If omitted, a random seed
+///     is drawn.
+/// config : SimulationConfig, optional
+///     The random number scheme (pseudo-random or scrambled Sobol) and the variance
+///     reduction techniques applied to it. Defaults to plain pseudo-random sampling.
+/// quantile_levels : list[float], optional
+///     The probabilities at which quantiles of the path PV distribution are estimated.
+///     Defaults to ``[0.05, 0.5, 0.95]``.
+/// additional_curves : dict[str, numpy.ndarray], optional
+///     The forward curves of the additional assets of `model_params`, keyed by asset
+///     name, e.g. ``{"carbon": carbon_curve}``.
+/// time_grid : TimeGrid, optional
+///     The calendar time of every point of the curves, which sets the time steps of the
+///     price processes, the length of the delivery periods and the local days that are
+///     valued and discounted. Defaults to hourly points in UTC starting at the valuation
+///     time, and is required for a seasonal volatility.
+///
+/// Returns
+/// -------
+/// ProfitStatistics
+///     An object containing the PV, the mean daily profits and their uncertainty, and
+///     the requested PV quantiles.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "calculate_profit_statistics",
+    signature = (gas_curve, power_curve, model_params, unit_params, num_paths, risk_free_rate, seed=None, config=None, quantile_levels=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_profit_statistics_py<'py>(
+    py: Python<'py>,
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    model_params: PyModelParameters,
+    unit_params: Vec<PyUnitParameter>,
+    num_paths: usize,
+    risk_free_rate: f64,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    quantile_levels: Option<Vec<f64>>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<PyProfitStatistics> {
+    // 1. Convert Python inputs to the core Rust domain types.
+    let model_params = model_params.to_domain();
+    let args = CalculateProfitStatisticsArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
+        unit_params: unit_params.iter().map(|p| p.to_domain()).collect(),
+        num_paths,
+        risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+        quantile_levels: quantile_levels.unwrap_or_else(|| vec![0.05, 0.5, 0.95]),
+    };
+
+    // 2. Call the core Rust function.
+    let statistics = calculate_profit_statistics(&args)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+    // 3. Convert the Rust result back to a Python-compatible type.
+    Ok(PyProfitStatistics::from_domain(statistics, py))
+}
+
+/// A Python class to hold the streaming statistics of the profits.
+///
+/// Attributes
+/// ----------
+/// num_paths : int
+///     The number of paths the statistics are based on.
+/// pv : float
+///     The present value of the agreement.
+/// pv_standard_error : float
+///     The standard error of `pv`.
+/// pv_confidence_interval : tuple[float, float]
+///     The 95% confidence interval of `pv`.
+/// pv_quantiles : list[tuple[float, float]]
+///     ``(probability, quantile)`` pairs of the distribution of the path PVs, estimated
+///     with the streaming P² algorithm.
+/// daily_profit_mean : numpy.ndarray
+///     The mean discounted profit of every day.
+/// daily_profit_standard_error : numpy.ndarray
+///     The standard error of every element of `daily_profit_mean`.
+/// daily_profit_confidence_interval : tuple[numpy.ndarray, numpy.ndarray]
+///     The 95% confidence interval of every element of `daily_profit_mean`.
+#[pyclass(name = "ProfitStatistics")]
+pub struct PyProfitStatistics {
+    #[pyo3(get)]
+    pub num_paths: usize,
+    #[pyo3(get)]
+    pub pv: f64,
+    #[pyo3(get)]
+    pub pv_standard_error: f64,
+    #[pyo3(get)]
+    pub pv_confidence_interval: (f64, f64),
+    #[pyo3(get)]
+    pub pv_quantiles: Vec<(f64, f64)>,
+    #[pyo3(get)]
+    pub daily_profit_mean: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub daily_profit_standard_error: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub daily_profit_confidence_interval: (Py<PyArray1<f64>>, Py<PyArray1<f64>>),
+}
+
+impl PyProfitStatistics {
+    /// Creates a `PyProfitStatistics` from a core `ProfitStatistics`.
+    fn from_domain(domain: ProfitStatistics, py: Python) -> Self {
+        Self {
+            num_paths: domain.num_paths,
+            pv: domain.pv,
+            pv_standard_error: domain.pv_standard_error,
+            pv_confidence_interval: (
+                domain.pv_confidence_interval.lower,
+                domain.pv_confidence_interval.upper,
+            ),
+            pv_quantiles: domain.pv_quantiles,
+            daily_profit_mean: domain.daily_profit_mean.into_pyarray(py).unbind(),
+            daily_profit_standard_error: domain.daily_profit_standard_error.into_pyarray(py).unbind(),
+            daily_profit_confidence_interval: (
+                domain.daily_profit_confidence_interval.lower.into_pyarray(py).unbind(),
+                domain.daily_profit_confidence_interval.upper.into_pyarray(py).unbind(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::services::calculate_pv::{calculate_pv, CalculatePvArgs, PvResult};
+use crate::core::valuation::control_variates::ControlVariate;
+use crate::python::parameters::{PyModelParameters, PyUnitParameter, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use numpy::PyReadonlyArray1;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::{pyclass, pyfunction, PyErr, PyResult};
+
+/// Calculates the present value of the tolling agreement.
+///
+/// This function is a Python wrapper around the core Rust `calculate_pv` implementation.
+/// Control variates with analytically known expectations can be used to tighten the
+/// estimate at the same number of paths.
+///
+/// Parameters
+/// ----------
+/// gas_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for gas prices, with one point
+///     per point of the time grid or one per local day.
+/// power_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for power prices.
+/// model_params : ModelParameters
+///     An instance of the `ModelParameters` class containing parameters for the
+///     stochastic models.
+/// unit_params : list[UnitParameter]
+///     A list of `UnitParameter` objects defining the power generation units.
+/// num_paths : int
+///     The number of Monte Carlo simulation paths to run for the calculation.
+/// risk_free_rate : float
+///     The annual risk-free rate for discounting profits.
+/// seed : int, optional
+///     The master seed of the random number streams. Runs with the same seed share
+///     their random numbers with `calculate_daily_profits` and `calculate_greeks`.
+///     If omitted, a random seed is drawn.
+/// config : SimulationConfig, optional
+///     The random number scheme (pseudo-random or scrambled Sobol) and the variance
+///     reduction techniques applied to it. Defaults to plain pseudo-random sampling.
+/// control_variates : list[str], optional
+///     The control variates to apply. Any of ``"average_gas_price"``,
+///     ``"average_power_price"`` and ``"spread_option"``. Defaults to none.
+/// additional_curves : dict[str, numpy.ndarray], optional
+///     The forward curves of the additional assets of `model_params`, keyed by asset
+///     name, e.g. ``{"carbon": carbon_curve}``.
+/// time_grid : TimeGrid, optional
+///     The calendar time of every point of the curves, which sets the time steps of the
+///     price processes, the length of the delivery periods and the local days that are
+///     valued and discounted. Defaults to hourly points in UTC starting at the valuation
+///     time, and is required for a seasonal volatility.
+///
+/// Returns
+/// -------
+/// PvResult
+///     An object containing the PV, its standard error and the effect of the control
+///     variates.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "calculate_pv",
+    signature = (gas_curve, power_curve, model_params, unit_params, num_paths, risk_free_rate, seed=None, config=None, control_variates=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_pv_py(
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    model_params: PyModelParameters,
+    unit_params: Vec<PyUnitParameter>,
+    num_paths: usize,
+    risk_free_rate: f64,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    control_variates: Option<Vec<String>>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<PyPvResult> {
+    // 1. Convert Python inputs to the core Rust domain types.
+    let control_variates = control_variates
+        .unwrap_or_default()
+        .iter()
+        .map(|name| parse_control_variate(name))
+        .collect::<PyResult<Vec<_>>>()?;
+    let model_params = model_params.to_domain();
+    let args = CalculatePvArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
+        unit_params: unit_params.iter().map(|p| p.to_domain()).collect(),
+        num_paths,
+        risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+        control_variates,
+    };
+
+    // 2. Call the core Rust function.
+    let pv_result =
+        calculate_pv(&args).map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+    // 3. Convert the Rust result back to a Python-compatible type.
+    Ok(PyPvResult::from_domain(pv_result))
+}
+
+/// Maps the Python name of a control variate to the core `ControlVariate`.
+fn parse_control_variate(name: &str) -> PyResult<ControlVariate> {
+    match name {
+        "average_gas_price" => Ok(ControlVariate::AverageGasPrice),
+        "average_power_price" => Ok(ControlVariate::AveragePowerPrice),
+        "spread_option" => Ok(ControlVariate::SpreadOption),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown control variate '{name}'. Expected one of 'average_gas_price', \
+             'average_power_price' or 'spread_option'."
+        ))),
+    }
+}
+
+/// A Python class to hold the results of the PV calculation.
+///
+/// Attributes
+/// ----------
+/// pv : float
+///     The present value of the agreement, corrected by the control variates if any
+///     were requested.
+/// pv_standard_error : float
+///     The standard error of `pv`.
+/// pv_confidence_interval : tuple[float, float]
+///     The 95% confidence interval of `pv`.
+/// uncorrected_pv : float
+///     The plain Monte Carlo estimate of the PV from the same paths.
+/// uncorrected_standard_error : float
+///     The standard error of `uncorrected_pv`.
+/// control_variate_coefficients : list[float]
+///     The regression coefficient of every control variate, in the order requested.
+/// variance_reduction : float
+///     The ratio of the variance of the plain estimate to that of the corrected one.
+///     1.0 if no control variates were used.
+#[pyclass(name = "PvResult")]
+pub struct PyPvResult {
+    #[pyo3(get)]
+    pub pv: f64,
+    #[pyo3(get)]
+    pub pv_standard_error: f64,
+    #[pyo3(get)]
+    pub pv_confidence_interval: (f64, f64),
+    #[pyo3(get)]
+    pub uncorrected_pv: f64,
+    #[pyo3(get)]
+    pub uncorrected_standard_error: f64,
+    #[pyo3(get)]
+    pub control_variate_coefficients: Vec<f64>,
+    #[pyo3(get)]
+    pub variance_reduction: f64,
+}
+
+impl PyPvResult {
+    /// Creates a `PyPvResult` from a core `PvResult`.
+    fn from_domain(domain: PvResult) -> Self {
+        let pv_confidence_interval = (
+            domain.confidence_interval.lower,
+            domain.confidence_interval.upper,
+        );
+        match domain.control_variates {
+            Some(report) => Self {
+                pv: domain.pv,
+                pv_standard_error: domain.standard_error,
+                pv_confidence_interval,
+                uncorrected_pv: report.uncorrected_pv,
+                uncorrected_standard_error: report.uncorrected_standard_error,
+                control_variate_coefficients: report.coefficients,
+                variance_reduction: report.variance_reduction,
+            },
+            None => Self {
+                pv: domain.pv,
+                pv_standard_error: domain.standard_error,
+                pv_confidence_interval,
+                uncorrected_pv: domain.pv,
+                uncorrected_standard_error: domain.standard_error,
+                control_variate_coefficients: Vec::new(),
+                variance_reduction: 1.0,
+            },
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::services::calculate_pv_adaptive::{
+    calculate_pv_adaptive, AdaptivePvResult, CalculatePvAdaptiveArgs, StopReason,
+};
+use crate::python::parameters::{PyModelParameters, PyUnitParameter, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use numpy::PyReadonlyArray1;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::{pyclass, pyfunction, PyErr, PyResult};
+
+/// Calculates the present value of the tolling agreement with as many paths as needed.
+///
+/// Paths are simulated in steps until the standard error of the PV is at most
+/// `tolerance`, or until the path or time budget is used up.
+///
+/// Parameters
+/// ----------
+/// gas_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for gas prices, with one point
+///     per point of the time grid or one per local day.
+/// power_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for power prices.
+/// model_params : ModelParameters
+///     An instance of the `ModelParameters` class containing parameters for the
+///     stochastic models.
+/// unit_params : list[UnitParameter]
+///     A list of `UnitParameter` objects defining the power generation units.
+/// tolerance : float
+///     The target standard error of the PV, in the currency of the PV.
+/// max_paths : int
+///     The maximum number of paths to simulate, at least one per antithetic pair and
+///     quasi-Monte Carlo replicate.
+/// risk_free_rate : float
+///     The annual risk-free rate for discounting profits.
+/// paths_per_step : int, optional
+///     The number of paths simulated between two checks of the standard error.
+///     Defaults to 1000.
+/// max_seconds : float, optional
+///     The maximum wall-clock time to spend, in seconds. Defaults to no limit.
+/// seed : int, optional
+///     The master seed of the random number streams. If omitted, a random seed is drawn.
+/// config : SimulationConfig, optional
+///     The random number scheme and variance reduction techniques. Moment matching is
+///     not supported.
+/// additional_curves : dict[str, numpy.ndarray], optional
+///     The forward curves of the additional assets of `model_params`, keyed by asset
+///     name, e.g. ``{"carbon": carbon_curve}``.
+/// time_grid : TimeGrid, optional
+///     The calendar time of every point of the curves, which sets the time steps of the
+///     price processes, the length of the delivery periods and the local days that are
+///     valued and discounted. Defaults to hourly points in UTC starting at the valuation
+///     time, and is required for a seasonal volatility.
+///
+/// Returns
+/// -------
+/// AdaptivePvResult
+///     An object containing the PV, its standard error, the number of paths used and the
+///     convergence history.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "calculate_pv_adaptive",
+    signature = (gas_curve, power_curve, model_params, unit_params, tolerance, max_paths, risk_free_rate, paths_per_step=1000, max_seconds=None, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_pv_adaptive_py(
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    model_params: PyModelParameters,
+    unit_params: Vec<PyUnitParameter>,
+    tolerance: f64,
+    max_paths: usize,
+    risk_free_rate: f64,
+    paths_per_step: usize,
+    max_seconds: Option<f64>,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<PyAdaptivePvResult> {
+    // 1. Convert Python inputs to the core Rust domain types.
+    let model_params = model_params.to_domain();
+    let args = CalculatePvAdaptiveArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
+        unit_params: unit_params.iter().map(|p| p.to_domain()).collect(),
+        risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+        tolerance,
+        paths_per_step,
+        max_paths,
+        max_duration: max_seconds.map(Duration::from_secs_f64),
+    };
+
+    // 2. Call the core Rust function.
+    let result = calculate_pv_adaptive(&args)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+    // 3. Convert the Rust result back to a Python-compatible type.
+    Ok(PyAdaptivePvResult::from_domain(result))
+}
+
+/// A Python class to hold the results of the adaptive PV calculation.
+///
+/// Attributes
+/// ----------
+/// pv : float
+///     The present value of the agreement.
+/// pv_standard_error : float
+///     The standard error of `pv`.
+/// pv_confidence_interval : tuple[float, float]
+///     The 95% confidence interval of `pv`.
+/// num_paths : int
+///     The number of paths used.
+/// stop_reason : str
+///     Why the run stopped: ``"tolerance_reached"``, ``"path_budget_exhausted"`` or
+///     ``"time_budget_exhausted"``.
+/// history_num_paths : list[int]
+///     The number of paths simulated after every step.
+/// history_pv : list[float]
+///     The PV estimate after every step.
+/// history_standard_error : list[float]
+///     The standard error after every step.
+/// history_elapsed_seconds : list[float]
+///     The time elapsed since the start of the run after every step.
+#[pyclass(name = "AdaptivePvResult")]
+pub struct PyAdaptivePvResult {
+    #[pyo3(get)]
+    pub pv: f64,
+    #[pyo3(get)]
+    pub pv_standard_error: f64,
+    #[pyo3(get)]
+    pub pv_confidence_interval: (f64, f64),
+    #[pyo3(get)]
+    pub num_paths: usize,
+    #[pyo3(get)]
+    pub stop_reason: String,
+    #[pyo3(get)]
+    pub history_num_paths: Vec<usize>,
+    #[pyo3(get)]
+    pub history_pv: Vec<f64>,
+    #[pyo3(get)]
+    pub history_standard_error: Vec<f64>,
+    #[pyo3(get)]
+    pub history_elapsed_seconds: Vec<f64>,
+}
+
+impl PyAdaptivePvResult {
+    /// Creates a `PyAdaptivePvResult` from a core `AdaptivePvResult`.
+    fn from_domain(domain: AdaptivePvResult) -> Self {
+        let stop_reason = match domain.stop_reason {
+            StopReason::ToleranceReached => "tolerance_reached",
+            StopReason::PathBudgetExhausted => "path_budget_exhausted",
+            StopReason::TimeBudgetExhausted => "time_budget_exhausted",
+        };
+        Self {
+            pv: domain.pv,
+            pv_standard_error: domain.standard_error,
+            pv_confidence_interval: (
+                domain.confidence_interval.lower,
+                domain.confidence_interval.upper,
+            ),
+            num_paths: domain.num_paths,
+            stop_reason: stop_reason.to_string(),
+            history_num_paths: domain.history.iter().map(|p| p.num_paths).collect(),
+            history_pv: domain.history.iter().map(|p| p.pv).collect(),
+            history_standard_error: domain.history.iter().map(|p| p.standard_error).collect(),
+            history_elapsed_seconds: domain
+                .history
+                .iter()
+                .map(|p| p.elapsed.as_secs_f64())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use crate::core::calibration::historical::{FitDiagnostics, HistoricalCalibration};
+use crate::core::services::calibrate_historical::{calibrate_historical, CalibrateHistoricalArgs};
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use numpy::PyReadonlyArray1;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::{pyclass, pyfunction, PyErr, PyResult};
+
+/// Estimates the model parameters from historical gas and power prices.
+///
+/// This function is a Python wrapper around the core Rust `calibrate_historical`
+/// implementation. The prices are deseasonalised by their shape, and the jump-diffusion
+/// model is fitted to them by maximum likelihood.
+///
+/// Parameters
+/// ----------
+/// gas_prices : numpy.ndarray
+///     The historical gas prices, with one point per point of the time grid or one per
+///     local day.
+/// power_prices : numpy.ndarray
+///     The historical power prices, e.g. hourly. Must be positive, or above
+///     ``-power_shift`` with a shift.
+/// time_grid : TimeGrid, optional
+///     The time of every observation, e.g. from ``TimeGrid.from_timestamps``. Gaps
+///     between observations enter through the time steps. Defaults to consecutive hours
+///     in UTC.
+/// gas_shape : numpy.ndarray, optional
+///     The shape the gas prices are measured against, e.g. the forward curve they were
+///     quoted under. The gas prices are used as they are if omitted.
+/// power_shape : numpy.ndarray, optional
+///     The shape the power prices are measured against. A seasonal shape of month, hour
+///     and weekend effects is fitted to the power prices if omitted.
+/// power_shift : float, optional
+///     The shift (€ / MWh) added to the power prices and their shape before their logs
+///     are taken, so negative power prices can be fitted. The estimates then belong to
+///     ``ModelParameters`` with the same ``power_shift``.
+///
+/// Returns
+/// -------
+/// HistoricalCalibration
+///     An object containing the estimates, their standard errors and the diagnostics of
+///     the fits.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "calibrate_historical",
+    signature = (
+        gas_prices,
+        power_prices,
+        time_grid=None,
+        gas_shape=None,
+        power_shape=None,
+        power_shift=None
+    )
+)]
+pub fn calibrate_historical_py(
+    gas_prices: PyReadonlyArray1<f64>,
+    power_prices: PyReadonlyArray1<f64>,
+    time_grid: Option<PyTimeGrid>,
+    gas_shape: Option<PyReadonlyArray1<f64>>,
+    power_shape: Option<PyReadonlyArray1<f64>>,
+    power_shift: Option<f64>,
+) -> PyResult<PyHistoricalCalibration> {
+    // 1. Convert Python inputs to the core Rust domain types.
+    let args = CalibrateHistoricalArgs {
+        gas_prices: gas_prices.as_array().to_owned(),
+        power_prices: power_prices.as_array().to_owned(),
+        time_grid: time_grid_to_domain(time_grid, power_prices.as_array().len(), false)?,
+        gas_shape: gas_shape.map(|shape| shape.as_array().to_owned()),
+        power_shape: power_shape.map(|shape| shape.as_array().to_owned()),
+        power_shift,
+    };
+
+    // 2. Call the core Rust function.
+    let calibration = calibrate_historical(&args)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+    // 3. Convert the Rust result back to a Python-compatible type.
+    Ok(PyHistoricalCalibration::from_domain(calibration))
+}
+
+/// A Python class to hold the result of a historical calibration.
+///
+/// Attributes
+/// ----------
+/// estimates : dict[str, float]
+///     The estimates of `sigma_g`, `sigma_p`, `kappa`, `lambda_j`, `mu_j`, `sigma_j` and
+///     `rho`, so ``ModelParameters(**calibration.estimates)`` builds the fitted model.
+/// standard_errors : dict[str, float]
+///     The standard error of every estimate, NaN if it cannot be determined, e.g. when
+///     no jumps are found.
+/// gas_drift : tuple[float, float]
+///     The real drift of gas per year and its standard error, e.g. for
+///     `PhysicalParameters`.
+/// power_level : tuple[float, float]
+///     The long-run level of the log power price relative to its shape and its standard
+///     error.
+/// expected_num_jumps : float
+///     The expected number of jumps in the observed power prices.
+/// gas_fit : FitDiagnostics
+///     The goodness of fit of the gas model.
+/// power_fit : FitDiagnostics
+///     The goodness of fit of the power model.
+#[pyclass(name = "HistoricalCalibration")]
+pub struct PyHistoricalCalibration {
+    #[pyo3(get)]
+    pub estimates: HashMap<String, f64>,
+    #[pyo3(get)]
+    pub standard_errors: HashMap<String, f64>,
+    #[pyo3(get)]
+    pub gas_drift: (f64, f64),
+    #[pyo3(get)]
+    pub power_level: (f64, f64),
+    #[pyo3(get)]
+    pub expected_num_jumps: f64,
+    #[pyo3(get)]
+    pub gas_fit: PyFitDiagnostics,
+    #[pyo3(get)]
+    pub power_fit: PyFitDiagnostics,
+}
+
+impl PyHistoricalCalibration {
+    /// Creates a `PyHistoricalCalibration` from a core `HistoricalCalibration`.
+    fn from_domain(domain: HistoricalCalibration) -> Self {
+        let estimates = domain.estimates();
+        Self {
+            estimates: estimates
+                .iter()
+                .map(|(name, estimate)| (name.to_string(), estimate.value))
+                .collect(),
+            standard_errors: estimates
+                .iter()
+                .map(|(name, estimate)| (name.to_string(), estimate.standard_error))
+                .collect(),
+            gas_drift: (domain.gas_drift.value, domain.gas_drift.standard_error),
+            power_level: (domain.power_level.value, domain.power_level.standard_error),
+            expected_num_jumps: domain.expected_num_jumps,
+            gas_fit: PyFitDiagnostics::from_domain(domain.gas_fit),
+            power_fit: PyFitDiagnostics::from_domain(domain.power_fit),
+        }
+    }
+}
+
+/// A Python class to hold the goodness-of-fit diagnostics of a fitted price model.
+///
+/// The residuals are the normal scores of the observed transitions under the fitted
+/// transition law, which are independent standard normal if the model is right.
+///
+/// Attributes
+/// ----------
+/// num_observations : int
+///     The number of observed transitions.
+/// log_likelihood : float
+///     The maximised log-likelihood.
+/// aic : float
+///     Akaike's information criterion.
+/// bic : float
+///     The Bayesian information criterion.
+/// residual_mean : float
+///     The mean of the residuals.
+/// residual_std : float
+///     The standard deviation of the residuals.
+/// residual_skewness : float
+///     The skewness of the residuals.
+/// residual_excess_kurtosis : float
+///     The excess kurtosis of the residuals.
+/// jarque_bera : float
+///     The Jarque-Bera statistic of the residuals; values above 5.99 reject normality
+///     at the 5% level.
+/// residual_autocorrelation : float
+///     The autocorrelation of consecutive residuals.
+/// converged : bool
+///     Whether the maximisation of the likelihood converged, i.e. its gradient vanished.
+/// stalled : bool
+///     Whether the maximisation of the likelihood stopped because the likelihood no
+///     longer increased while its gradient had not vanished yet.
+#[pyclass(name = "FitDiagnostics")]
+#[derive(Clone)]
+pub struct PyFitDiagnostics {
+    #[pyo3(get)]
+    pub num_observations: usize,
+    #[pyo3(get)]
+    pub log_likelihood: f64,
+    #[pyo3(get)]
+    pub aic: f64,
+    #[pyo3(get)]
+    pub bic: f64,
+    #[pyo3(get)]
+    pub residual_mean: f64,
+    #[pyo3(get)]
+    pub residual_std: f64,
+    #[pyo3(get)]
+    pub residual_skewness: f64,
+    #[pyo3(get)]
+    pub residual_excess_kurtosis: f64,
+    #[pyo3(get)]
+    pub jarque_bera: f64,
+    #[pyo3(get)]
+    pub residual_autocorrelation: f64,
+    #[pyo3(get)]
+    pub converged: bool,
+    #[pyo3(get)]
+    pub stalled: bool,
+}
+
+impl PyFitDiagnostics {
+    /// Creates a `PyFitDiagnostics` from core `FitDiagnostics`.
+    fn from_domain(domain: FitDiagnostics) -> Self {
+        Self {
+            num_observations: domain.num_observations,
+            log_likelihood: domain.log_likelihood,
+            aic: domain.aic,
+            bic: domain.bic,
+            residual_mean: domain.residual_mean,
+            residual_std: domain.residual_std,
+            residual_skewness: domain.residual_skewness,
+            residual_excess_kurtosis: domain.residual_excess_kurtosis,
+            jarque_bera: domain.jarque_bera,
+            residual_autocorrelation: domain.residual_autocorrelation,
+            converged: domain.converged,
+            stalled: domain.stalled,
+        }
+    }
+}
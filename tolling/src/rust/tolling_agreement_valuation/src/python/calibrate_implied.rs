@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use crate::core::calibration::implied::{
+    CalibratedParameter, ImpliedCalibration, OptionKind, OptionQuote, OptionUnderlying,
+    QuoteFit, Settlement,
+};
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::services::calibrate_implied::{calibrate_implied, CalibrateImpliedArgs};
+use crate::python::parameters::{PyModelParameters, additional_curves_to_domain};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use ndarray::Array1;
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+/// Fits model parameters to the market prices of gas, power and spark-spread options.
+///
+/// This function is a Python wrapper around the core Rust `calibrate_implied`
+/// implementation. The options are priced by Monte Carlo on a fixed set of paths, and
+/// the parameters are fitted by weighted least squares, driven by AAD gradients of the
+/// model prices.
+///
+/// Parameters
+/// ----------
+/// gas_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for gas prices, with one point
+///     per point of the time grid or one per local day.
+/// power_curve : numpy.ndarray
+///     A 1D NumPy array representing the forward curve for power prices.
+/// model_params : ModelParameters
+///     The starting values of the fitted parameters and the values of all others.
+/// quotes : list[OptionQuote]
+///     The market prices of the options.
+/// parameters : list[str]
+///     The parameters to fit, out of ``"sigma_g"``, ``"sigma_p"``, ``"kappa"``,
+///     ``"mu_j"``, ``"sigma_j"`` and ``"rho"``. Every bucket of a time-dependent
+///     volatility is fitted.
+/// num_paths : int
+///     The number of Monte Carlo simulation paths the options are priced on.
+/// risk_free_rate : float
+///     The annual risk-free rate for discounting the option payoffs.
+/// seed : int, optional
+///     The master seed of the random number streams. If omitted, a random seed is drawn.
+/// config : SimulationConfig, optional
+///     The random number scheme and the variance reduction techniques applied to it.
+///     Defaults to plain pseudo-random sampling.
+/// additional_curves : dict[str, numpy.ndarray], optional
+///     The forward curves of the additional assets of `model_params`, keyed by asset
+///     name.
+/// time_grid : TimeGrid, optional
+///     The calendar time of every point of the curves. Defaults to hourly points in UTC
+///     starting at the valuation time, and is required for a seasonal volatility.
+///
+/// Returns
+/// -------
+/// ImpliedCalibration
+///     An object containing the fitted parameters and the fitted and market price of
+///     every quote.
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "calibrate_implied",
+    signature = (gas_curve, power_curve, model_params, quotes, parameters, num_paths, risk_free_rate, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn calibrate_implied_py<'py>(
+    py: Python<'py>,
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    model_params: PyModelParameters,
+    quotes: Vec<PyOptionQuote>,
+    parameters: Vec<String>,
+    num_paths: usize,
+    risk_free_rate: f64,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<PyImpliedCalibration> {
+    // 1. Convert Python inputs to the core Rust domain types.
+    let args = CalibrateImpliedArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.to_domain::<f64>().is_seasonal(),
+        )?,
+        model_params: model_params.to_domain(),
+        quotes: quotes
+            .iter()
+            .map(|quote| quote.to_domain())
+            .collect::<PyResult<_>>()?,
+        parameters: parameters
+            .iter()
+            .map(|name| parse_calibrated_parameter(name))
+            .collect::<PyResult<_>>()?,
+        num_paths,
+        risk_free_rate,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+    };
+
+    // 2. Call the core Rust function.
+    let calibration = calibrate_implied(&args)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+    // 3. Convert the Rust result back to a Python-compatible type.
+    Ok(PyImpliedCalibration::from_domain(calibration, &model_params, py))
+}
+
+/// Maps the name of a parameter to the core `CalibratedParameter`.
+fn parse_calibrated_parameter(name: &str) -> PyResult<CalibratedParameter> {
+    match name {
+        "sigma_g" => Ok(CalibratedParameter::SigmaG),
+        "sigma_p" => Ok(CalibratedParameter::SigmaP),
+        "kappa" => Ok(CalibratedParameter::Kappa),
+        "mu_j" => Ok(CalibratedParameter::MuJ),
+        "sigma_j" => Ok(CalibratedParameter::SigmaJ),
+        "rho" => Ok(CalibratedParameter::Rho),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown or non-calibrated parameter '{name}'. Expected 'sigma_g', 'sigma_p', \
+             'kappa', 'mu_j', 'sigma_j' or 'rho'."
+        ))),
+    }
+}
+
+/// A Python-compatible wrapper for the market price of an option.
+///
+/// This struct is exposed to Python as the `OptionQuote` class and is passed to
+/// `calibrate_implied`. Prices and strikes are per MWh of the underlying.
+#[pyclass(name = "OptionQuote")]
+#[derive(Clone, Debug)]
+pub struct PyOptionQuote {
+    /// The price the option is written on: `"gas"`, `"power"` or `"spark_spread"`.
+    #[pyo3(get)]
+    pub underlying: String,
+    /// Whether the option is a `"call"` or a `"put"`.
+    #[pyo3(get)]
+    pub kind: String,
+    /// The strike (€ / MWh).
+    #[pyo3(get)]
+    pub strike: f64,
+    /// The first and one past the last point of the time grid the option is exercised
+    /// against.
+    #[pyo3(get)]
+    pub delivery: (usize, usize),
+    /// The market price (€ / MWh).
+    #[pyo3(get)]
+    pub price: f64,
+    /// How the option settles: `"average"` or `"strip"`.
+    #[pyo3(get)]
+    pub settlement: String,
+    /// The heat rate of the spark spread (MMBtu/MWh), if any.
+    #[pyo3(get)]
+    pub heat_rate: Option<f64>,
+    /// The weight of the squared residual of the quote.
+    #[pyo3(get)]
+    pub weight: f64,
+}
+
+#[pymethods]
+impl PyOptionQuote {
+    /// Creates the market price of an option.
+    ///
+    /// Parameters
+    /// ----------
+    /// underlying : str
+    ///     The price the option is written on: ``"gas"``, ``"power"`` or
+    ///     ``"spark_spread"``, the spark spread ``power - heat_rate * gas``.
+    /// kind : str
+    ///     ``"call"`` or ``"put"``.
+    /// strike : float
+    ///     The strike (€ / MWh).
+    /// delivery_start : int
+    ///     The first point of the time grid the option is exercised against, e.g. the
+    ///     first hour of the delivery month.
+    /// delivery_end : int
+    ///     One past the last point of the delivery period.
+    /// price : float
+    ///     The market price (€ / MWh), paid at the end of the last delivery day.
+    /// settlement : str, optional
+    ///     ``"average"`` for a single option on the average price of the delivery period,
+    ///     e.g. a monthly option, or ``"strip"`` for one option per point, quoted as the
+    ///     average payoff, e.g. daily spark-spread options. Defaults to ``"average"``.
+    /// heat_rate : float, optional
+    ///     The heat rate of the spark spread (MMBtu/MWh), for gas prices in € / MMBtu.
+    ///     Required for ``"spark_spread"`` only.
+    /// weight : float, optional
+    ///     The weight of the squared residual of the quote, e.g. the inverse of its
+    ///     squared bid-ask spread. Defaults to 1.
+    #[new]
+    #[pyo3(signature = (underlying, kind, strike, delivery_start, delivery_end, price, settlement="average".to_string(), heat_rate=None, weight=1.0))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        underlying: String,
+        kind: String,
+        strike: f64,
+        delivery_start: usize,
+        delivery_end: usize,
+        price: f64,
+        settlement: String,
+        heat_rate: Option<f64>,
+        weight: f64,
+    ) -> PyResult<Self> {
+        let quote = Self {
+            underlying,
+            kind,
+            strike,
+            delivery: (delivery_start, delivery_end),
+            price,
+            settlement,
+            heat_rate,
+            weight,
+        };
+        quote.to_domain()?;
+        Ok(quote)
+    }
+}
+
+impl PyOptionQuote {
+    /// Converts the Python-facing quote to the core `OptionQuote`.
+    pub fn to_domain(&self) -> PyResult<OptionQuote> {
+        let underlying = match (self.underlying.as_str(), self.heat_rate) {
+            ("gas", None) => OptionUnderlying::Gas,
+            ("power", None) => OptionUnderlying::Power,
+            ("spark_spread", Some(heat_rate)) => OptionUnderlying::SparkSpread { heat_rate },
+            ("spark_spread", None) => {
+                return Err(PyErr::new::<PyValueError, _>(
+                    "A 'spark_spread' option needs 'heat_rate'.",
+                ));
+            }
+            ("gas" | "power", Some(_)) => {
+                return Err(PyErr::new::<PyValueError, _>(
+                    "'heat_rate' is only used by 'spark_spread' options.",
+                ));
+            }
+            (name, _) => {
+                return Err(PyErr::new::<PyValueError, _>(format!(
+                    "Unknown underlying '{name}'. Expected 'gas', 'power' or 'spark_spread'."
+                )));
+            }
+        };
+        let kind = match self.kind.as_str() {
+            "call" => OptionKind::Call,
+            "put" => OptionKind::Put,
+            name => {
+                return Err(PyErr::new::<PyValueError, _>(format!(
+                    "Unknown option kind '{name}'. Expected 'call' or 'put'."
+                )));
+            }
+        };
+        let settlement = match self.settlement.as_str() {
+            "average" => Settlement::Average,
+            "strip" => Settlement::Strip,
+            name => {
+                return Err(PyErr::new::<PyValueError, _>(format!(
+                    "Unknown settlement '{name}'. Expected 'average' or 'strip'."
+                )));
+            }
+        };
+        let (start, end) = self.delivery;
+        Ok(
+            OptionQuote::new(underlying, kind, self.strike, start..end, self.price)
+                .with_settlement(settlement)
+                .with_weight(self.weight),
+        )
+    }
+}
+
+/// A Python class to hold the result of an implied calibration.
+///
+/// Attributes
+/// ----------
+/// model_params : ModelParameters
+///     The given parameters with the fitted values, ready for the valuation functions.
+/// fitted_values : dict[str, float]
+///     The fitted values, keyed by parameter name, or by parameter and bucket, e.g.
+///     ``"sigma_p[jan]"``, for a time-dependent volatility.
+/// market_prices : numpy.ndarray
+///     The market price of every quote.
+/// model_prices : numpy.ndarray
+///     The price of every quote under the fitted parameters.
+/// model_price_standard_errors : numpy.ndarray
+///     The Monte Carlo standard error of every model price.
+/// residuals : numpy.ndarray
+///     The model less the market price of every quote.
+/// rmse : float
+///     The root mean square of the residuals.
+/// iterations : int
+///     The number of iterations of the optimiser.
+/// gradient_norm : float
+///     The largest absolute component of the gradient of the objective at the fit.
+/// converged : bool
+///     Whether the optimiser converged, i.e. the gradient fell below its tolerance.
+/// stalled : bool
+///     Whether the optimiser stopped because the objective no longer decreased while
+///     the gradient was still above its tolerance.
+#[pyclass(name = "ImpliedCalibration")]
+pub struct PyImpliedCalibration {
+    #[pyo3(get)]
+    pub model_params: PyModelParameters,
+    #[pyo3(get)]
+    pub fitted_values: HashMap<String, f64>,
+    #[pyo3(get)]
+    pub market_prices: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub model_prices: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub model_price_standard_errors: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub residuals: Py<PyArray1<f64>>,
+    #[pyo3(get)]
+    pub rmse: f64,
+    #[pyo3(get)]
+    pub iterations: usize,
+    #[pyo3(get)]
+    pub gradient_norm: f64,
+    #[pyo3(get)]
+    pub converged: bool,
+    #[pyo3(get)]
+    pub stalled: bool,
+}
+
+impl PyImpliedCalibration {
+    /// Creates a `PyImpliedCalibration` from a core `ImpliedCalibration`, with the fitted
+    /// values set on a copy of the given `model_params`.
+    fn from_domain(
+        domain: ImpliedCalibration,
+        model_params: &PyModelParameters,
+        py: Python,
+    ) -> Self {
+        let column = |value: fn(&QuoteFit) -> f64| {
+            domain
+                .quote_fits
+                .iter()
+                .map(value)
+                .collect::<Array1<f64>>()
+                .into_pyarray(py)
+                .unbind()
+        };
+        Self {
+            model_params: model_params.with_calibrated(&domain.model_params),
+            fitted_values: domain.fitted_values.iter().cloned().collect(),
+            market_prices: column(|fit| fit.market_price),
+            model_prices: column(|fit| fit.model_price),
+            model_price_standard_errors: column(|fit| fit.model_price_standard_error),
+            residuals: column(|fit| fit.residual),
+            rmse: domain.rmse,
+            iterations: domain.iterations,
+            gradient_norm: domain.gradient_norm,
+            converged: domain.converged,
+            stalled: domain.stalled,
+        }
+    }
+}
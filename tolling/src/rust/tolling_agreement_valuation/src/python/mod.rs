@@ -7,10 +7,24 @@
 //! The submodules are organized to mirror the `core` services, providing wrappers for:
 //! - Parameter structs (`parameters.rs`)
 //! - Greeks calculation (`calculate_greeks.rs`)
+//! - PV calculation with control variates (`calculate_pv.rs`)
+//! - Adaptive PV calculation to a target standard error (`calculate_pv_adaptive.rs`)
 //! - Profit calculation (`calculate_profit.rs`)
+//! - Streaming profit statistics (`calculate_profit_statistics.rs`)
+//! - Historical calibration of the model parameters (`calibrate_historical.rs`)
+//! - Implied calibration of the model parameters to option prices (`calibrate_implied.rs`)
 //! - Path sampling (`sample_paths.rs`)
+//! - Simulation configuration (`simulation_config.rs`)
+//! - Calendar time grids (`time_grid.rs`)
 
 pub mod calculate_profit;
 pub mod parameters;
 pub mod sample_paths;
+pub mod simulation_config;
+pub mod time_grid;
 pub mod calculate_greeks;
+pub mod calculate_pv;
+pub mod calculate_pv_adaptive;
+pub mod calculate_profit_statistics;
+pub mod calibrate_historical;
+pub mod calibrate_implied;
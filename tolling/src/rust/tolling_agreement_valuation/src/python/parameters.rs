@@ -1,5 +1,19 @@
-use crate::core::parameters::{ModelParameters, UnitParameter};
+use std::collections::HashMap;
+
+use crate::core::common::correlation::CorrelationRepair;
+use crate::core::parameters::{
+    Dispatch, GasModel, ModelParameters, PhysicalParameters, PowerModel, SeasonalVolatility,
+    UnitParameter, Volatility,
+};
+use crate::core::processes::heston_process_transformer::VarianceScheme;
+use crate::core::processes::jump_diffusion_process_transformer::OuScheme;
+use crate::core::processes::jump_size::JumpSize;
+use crate::core::processes::regime_switching_process_transformer::Regime;
+use crate::core::simulator::asset::Asset;
+use ndarray::Array1;
 use num_traits::{Float, FromPrimitive};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 /// A Python-compatible wrapper for the stochastic model parameters.
@@ -16,10 +30,10 @@ pub struct PyModelParameters {
     /// Volatility of the power price process (in percent).
     #[pyo3(get, set)]
     pub sigma_p: f64,
-    /// Mean reversion speed of the power price process (unitless).
+    /// Mean reversion speed of the power price process (1 / year).
     #[pyo3(get, set)]
     pub kappa: f64,
-    /// The average number of jumps per day in the power price (jumps / day).
+    /// The average number of jumps per year in the power price (jumps / year).
     #[pyo3(get, set)]
     pub lambda_j: f64,
     /// The mean size of a jump in the power price (€ / MWh).
@@ -31,14 +45,105 @@ pub struct PyModelParameters {
     /// The correlation between the gas and power price processes.
     #[pyo3(get, set)]
     pub rho: f64,
+    /// `(asset, volatility)` pairs of the assets simulated besides gas and power.
+    #[pyo3(get)]
+    pub additional_assets: Vec<(String, f64)>,
+    /// `(asset, asset, correlation)` triples of the correlated pairs besides gas and power.
+    #[pyo3(get)]
+    pub correlations: Vec<(String, String, f64)>,
+    /// How inconsistent correlations are repaired: `"reject"`, `"higham"` or
+    /// `"eigenvalue_clipping"`.
+    #[pyo3(get)]
+    pub correlation_repair: String,
+    /// The discretisation of the mean-reverting power factor: `"exact"` or `"euler"`.
+    #[pyo3(get)]
+    pub ou_scheme: String,
+    /// A time-dependent gas volatility replacing `sigma_g`, if any.
+    #[pyo3(get)]
+    pub gas_volatility: Option<PyVolatility>,
+    /// A time-dependent power volatility replacing `sigma_p`, if any.
+    #[pyo3(get)]
+    pub power_volatility: Option<PyVolatility>,
+    /// The model of the power price: `"jump_diffusion"`, `"two_factor_spike"` or
+    /// `"regime_switching"`.
+    #[pyo3(get)]
+    pub power_model: String,
+    /// Mean reversion speed of the spike factor of the two-factor spike model (1 / year).
+    #[pyo3(get)]
+    pub kappa_spike: Option<f64>,
+    /// `(level, volatility)` pairs of the regimes of the regime-switching model, the
+    /// first of which holds at the valuation time.
+    #[pyo3(get)]
+    pub regimes: Option<Vec<(f64, f64)>>,
+    /// The rates per year of switching between the regimes of the regime-switching
+    /// model, from the regime of the row to the regime of the column.
+    #[pyo3(get)]
+    pub transition_rates: Option<Vec<Vec<f64>>>,
+    /// The law of the power price jumps replacing the normal law of `mu_j` and `sigma_j`,
+    /// if any.
+    #[pyo3(get)]
+    pub jump_size: Option<PyJumpSize>,
+    /// The shift of the power price (€ / MWh) that lets it fall to `-power_shift`, if any.
+    #[pyo3(get)]
+    pub power_shift: Option<f64>,
+    /// The model of the gas price: `"gbm"`, `"schwartz_smith"` or `"heston"`.
+    #[pyo3(get)]
+    pub gas_model: String,
+    /// Mean reversion speed of the short-term gas factor of the Schwartz-Smith model
+    /// (1 / year).
+    #[pyo3(get)]
+    pub kappa_short: Option<f64>,
+    /// Volatility of the short-term gas factor of the Schwartz-Smith model.
+    #[pyo3(get)]
+    pub sigma_short: Option<f64>,
+    /// Volatility of the long-term gas factor of the Schwartz-Smith model.
+    #[pyo3(get)]
+    pub sigma_long: Option<f64>,
+    /// The correlation between the short- and long-term gas factors of the
+    /// Schwartz-Smith model. Zero if omitted.
+    #[pyo3(get)]
+    pub rho_short_long: Option<f64>,
+    /// `(v0, kappa_v, theta_v, xi, rho_sv)` of the Heston gas model: the initial
+    /// variance, its mean-reversion speed, mean and vol-of-vol, and the correlation of
+    /// the price and its variance.
+    #[pyo3(get)]
+    pub heston: Option<(f64, f64, f64, f64, f64)>,
+    /// The discretisation of the Heston variance: `"full_truncation"` or
+    /// `"quadratic_exponential"`.
+    #[pyo3(get)]
+    pub variance_scheme: String,
 }
 
 #[pymethods]
 impl PyModelParameters {
     /// Creates a new instance of the ModelParameters class.
     ///
-    /// This is the constructor (`__init__` in Python) for the class.
+    /// This is the constructor (`__init__` in Python) for the class. Further assets are
+    /// given by name (`"coal"` or `"carbon"`) with their volatility, and correlated by
+    /// `(asset, asset, correlation)` triples. Pairs that are not listed are uncorrelated.
+    /// If the correlations are not jointly consistent, they are rejected unless
+    /// `correlation_repair` is `"higham"` (nearest correlation matrix) or
+    /// `"eigenvalue_clipping"`. The power factor is simulated with its exact Gaussian
+    /// transition unless `ou_scheme` is `"euler"`. A `Volatility` given as
+    /// `gas_volatility` or `power_volatility` replaces the constant `sigma_g` or `sigma_p`.
+    /// With `power_model="two_factor_spike"` the jumps of power enter a separate spike
+    /// factor reverting at `kappa_spike`, while the diffusion reverts at `kappa`. With
+    /// `power_model="regime_switching"` power reverts at `kappa` without jumps, and its
+    /// level and volatility switch between the `(level, volatility)` pairs of `regimes`
+    /// at the `transition_rates`; `sigma_p` and the jump parameters are then unused. A
+    /// `JumpSize` given as `jump_size` replaces the normal jumps of `mu_j` and `sigma_j`.
+    /// With `gas_model="schwartz_smith"` gas follows a short-term factor reverting at
+    /// `kappa_short` with volatility `sigma_short` and a long-term factor with volatility
+    /// `sigma_long`, correlated by `rho_short_long`, in place of `sigma_g`; the gas vegas
+    /// of `calculate_greeks` are then split into a short- and a long-term vega. With
+    /// `gas_model="heston"` the gas variance follows a CIR process with the
+    /// `(v0, kappa_v, theta_v, xi, rho_sv)` of `heston`, discretised by
+    /// `variance_scheme`, and the gas vegas become the sensitivities to the initial
+    /// variance, the mean variance and the vol-of-vol. A `power_shift` shifts the power
+    /// model by a constant, e.g. the lognormal jump diffusion to a shifted lognormal one,
+    /// so power prices can turn negative while their mean stays the forward curve.
     #[new]
+    #[pyo3(signature = (sigma_g, sigma_p, kappa, lambda_j, mu_j, sigma_j, rho, additional_assets=None, correlations=None, correlation_repair="reject".to_string(), ou_scheme="exact".to_string(), gas_volatility=None, power_volatility=None, power_model="jump_diffusion".to_string(), kappa_spike=None, jump_size=None, regimes=None, transition_rates=None, gas_model="gbm".to_string(), kappa_short=None, sigma_short=None, sigma_long=None, rho_short_long=None, power_shift=None, heston=None, variance_scheme="full_truncation".to_string()))]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         sigma_g: f64,
@@ -48,8 +153,27 @@ impl PyModelParameters {
         mu_j: f64,
         sigma_j: f64,
         rho: f64,
-    ) -> Self {
-        Self {
+        additional_assets: Option<Vec<(String, f64)>>,
+        correlations: Option<Vec<(String, String, f64)>>,
+        correlation_repair: String,
+        ou_scheme: String,
+        gas_volatility: Option<PyVolatility>,
+        power_volatility: Option<PyVolatility>,
+        power_model: String,
+        kappa_spike: Option<f64>,
+        jump_size: Option<PyJumpSize>,
+        regimes: Option<Vec<(f64, f64)>>,
+        transition_rates: Option<Vec<Vec<f64>>>,
+        gas_model: String,
+        kappa_short: Option<f64>,
+        sigma_short: Option<f64>,
+        sigma_long: Option<f64>,
+        rho_short_long: Option<f64>,
+        power_shift: Option<f64>,
+        heston: Option<(f64, f64, f64, f64, f64)>,
+        variance_scheme: String,
+    ) -> PyResult<Self> {
+        let params = Self {
             sigma_g,
             sigma_p,
             kappa,
@@ -57,26 +181,482 @@ impl PyModelParameters {
             mu_j,
             sigma_j,
             rho,
-        }
+            additional_assets: additional_assets.unwrap_or_default(),
+            correlations: correlations.unwrap_or_default(),
+            correlation_repair,
+            ou_scheme,
+            gas_volatility,
+            power_volatility,
+            power_model,
+            kappa_spike,
+            regimes,
+            transition_rates,
+            jump_size,
+            gas_model,
+            kappa_short,
+            sigma_short,
+            sigma_long,
+            rho_short_long,
+            power_shift,
+            heston,
+            variance_scheme,
+        };
+        // Reject unknown names and inconsistent correlations at construction time.
+        params
+            .try_to_domain::<f64>()?
+            .price_model()
+            .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        Ok(params)
+    }
+
+    /// Returns the correlation matrix the simulation uses and how far it was repaired.
+    ///
+    /// Returns
+    /// -------
+    /// tuple[numpy.ndarray, float, float]
+    ///     The correlation matrix of gas, power and the additional assets, in that
+    ///     order, after any repair; the Frobenius norm of the repair; and the largest
+    ///     change of a single correlation. Both are zero if no repair was needed.
+    pub fn repaired_correlation<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyArray2<f64>>, f64, f64)> {
+        let price_model = self
+            .to_domain::<f64>()
+            .price_model()
+            .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        let factor = price_model.correlation_factor();
+        Ok((
+            factor.correlation.clone().into_pyarray(py),
+            factor.frobenius_distance,
+            factor.max_abs_change,
+        ))
     }
 }
 
 impl PyModelParameters {
+    /// Maps the name of the power model and its parameters to the core `PowerModel`.
+    fn parse_power_model(&self) -> PyResult<PowerModel<f64>> {
+        let name = self.power_model.as_str();
+        if self.kappa_spike.is_some() && name != "two_factor_spike" {
+            return Err(PyErr::new::<PyValueError, _>(
+                "'kappa_spike' is only used by the 'two_factor_spike' power model.",
+            ));
+        }
+        if (self.regimes.is_some() || self.transition_rates.is_some())
+            && name != "regime_switching"
+        {
+            return Err(PyErr::new::<PyValueError, _>(
+                "'regimes' and 'transition_rates' are only used by the 'regime_switching' \
+                 power model.",
+            ));
+        }
+        match name {
+            "jump_diffusion" => Ok(PowerModel::JumpDiffusion),
+            "two_factor_spike" => match self.kappa_spike {
+                Some(kappa_spike) => Ok(PowerModel::TwoFactorSpike { kappa_spike }),
+                None => Err(PyErr::new::<PyValueError, _>(
+                    "The 'two_factor_spike' power model needs 'kappa_spike'.",
+                )),
+            },
+            "regime_switching" => match (&self.regimes, &self.transition_rates) {
+                (Some(regimes), Some(transition_rates)) => Ok(PowerModel::RegimeSwitching {
+                    regimes: regimes
+                        .iter()
+                        .map(|&(level, sigma)| Regime { level, sigma })
+                        .collect(),
+                    transition_rates: transition_rates.clone(),
+                }),
+                _ => Err(PyErr::new::<PyValueError, _>(
+                    "The 'regime_switching' power model needs 'regimes' and \
+                     'transition_rates'.",
+                )),
+            },
+            _ => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown power model '{name}'. Expected 'jump_diffusion', \
+                 'two_factor_spike' or 'regime_switching'."
+            ))),
+        }
+    }
+
+    /// Maps the name of the gas model and its parameters to the core `GasModel`.
+    fn parse_gas_model(&self) -> PyResult<GasModel<f64>> {
+        let name = self.gas_model.as_str();
+        let factors = (self.kappa_short, self.sigma_short, self.sigma_long);
+        let has_factors = factors != (None, None, None) || self.rho_short_long.is_some();
+        if has_factors && name != "schwartz_smith" {
+            return Err(PyErr::new::<PyValueError, _>(
+                "'kappa_short', 'sigma_short', 'sigma_long' and 'rho_short_long' are only \
+                 used by the 'schwartz_smith' gas model.",
+            ));
+        }
+        if self.heston.is_some() && name != "heston" {
+            return Err(PyErr::new::<PyValueError, _>(
+                "'heston' is only used by the 'heston' gas model.",
+            ));
+        }
+        match (name, factors) {
+            ("gbm", _) => Ok(GasModel::Gbm),
+            ("schwartz_smith", (Some(kappa_short), Some(sigma_short), Some(sigma_long))) => {
+                Ok(GasModel::SchwartzSmith {
+                    kappa_short,
+                    sigma_short,
+                    sigma_long,
+                    rho_short_long: self.rho_short_long.unwrap_or(0.0),
+                })
+            }
+            ("schwartz_smith", _) => Err(PyErr::new::<PyValueError, _>(
+                "The 'schwartz_smith' gas model needs 'kappa_short', 'sigma_short' and \
+                 'sigma_long'.",
+            )),
+            ("heston", _) => match self.heston {
+                Some((v0, kappa_v, theta_v, xi, rho_sv)) => Ok(GasModel::Heston {
+                    v0,
+                    kappa_v,
+                    theta_v,
+                    xi,
+                    rho_sv,
+                    scheme: parse_variance_scheme(&self.variance_scheme)?,
+                }),
+                None => Err(PyErr::new::<PyValueError, _>(
+                    "The 'heston' gas model needs 'heston'.",
+                )),
+            },
+            (name, _) => Err(PyErr::new::<PyValueError, _>(format!(
+                "Unknown gas model '{name}'. Expected 'gbm', 'schwartz_smith' or 'heston'."
+            ))),
+        }
+    }
+
     /// Converts the Python-facing `f64` parameters to the generic `ModelParameters<T>`.
     ///
     /// This is a crucial step to allow the core Rust functions to be generic over the
     /// number type `T`, which can be `f64` for simulation or `AADVar` for differentiation.
     pub fn to_domain<T: Float + FromPrimitive>(&self) -> ModelParameters<T> {
-        ModelParameters {
-            sigma_g: T::from_f64(self.sigma_g).unwrap(),
-            sigma_p: T::from_f64(self.sigma_p).unwrap(),
-            kappa: T::from_f64(self.kappa).unwrap(),
-            lambda_j: T::from_f64(self.lambda_j).unwrap(),
-            mu_j: T::from_f64(self.mu_j).unwrap(),
-            sigma_j: T::from_f64(self.sigma_j).unwrap(),
-            rho: T::from_f64(self.rho).unwrap(),
+        self.try_to_domain()
+            .expect("The asset names were validated on construction.")
+    }
+
+    /// Returns a copy of the parameters with the volatilities, the mean reversion, the
+    /// normal jump sizes and the correlation of `params`, e.g. after a calibration.
+    pub fn with_calibrated(&self, params: &ModelParameters<f64>) -> Self {
+        let mut updated = self.clone();
+        match (&mut updated.gas_volatility, &params.sigma_g) {
+            (None, Volatility::Constant(sigma)) => updated.sigma_g = *sigma,
+            (volatility, sigma_g) => {
+                *volatility = Some(PyVolatility {
+                    inner: sigma_g.clone(),
+                })
+            }
+        }
+        match (&mut updated.power_volatility, &params.sigma_p) {
+            (None, Volatility::Constant(sigma)) => updated.sigma_p = *sigma,
+            (volatility, sigma_p) => {
+                *volatility = Some(PyVolatility {
+                    inner: sigma_p.clone(),
+                })
+            }
+        }
+        updated.kappa = params.kappa;
+        updated.mu_j = params.mu_j;
+        updated.sigma_j = params.sigma_j;
+        updated.rho = params.rho;
+        updated
+    }
+
+    /// Converts the parameters like `to_domain`, failing on unknown asset names.
+    fn try_to_domain<T: Float + FromPrimitive>(&self) -> PyResult<ModelParameters<T>> {
+        let convert = |x: f64| T::from_f64(x).unwrap();
+        Ok(ModelParameters {
+            sigma_g: match &self.gas_volatility {
+                Some(volatility) => volatility.to_domain(),
+                None => Volatility::Constant(convert(self.sigma_g)),
+            },
+            sigma_p: match &self.power_volatility {
+                Some(volatility) => volatility.to_domain(),
+                None => Volatility::Constant(convert(self.sigma_p)),
+            },
+            kappa: convert(self.kappa),
+            lambda_j: convert(self.lambda_j),
+            mu_j: convert(self.mu_j),
+            sigma_j: convert(self.sigma_j),
+            rho: convert(self.rho),
+            additional_assets: self
+                .additional_assets
+                .iter()
+                .map(|(name, sigma)| Ok((parse_asset(name)?, convert(*sigma))))
+                .collect::<PyResult<_>>()?,
+            correlations: self
+                .correlations
+                .iter()
+                .map(|(first, second, rho)| {
+                    Ok((parse_asset(first)?, parse_asset(second)?, convert(*rho)))
+                })
+                .collect::<PyResult<_>>()?,
+            correlation_repair: parse_correlation_repair(&self.correlation_repair)?,
+            ou_scheme: parse_ou_scheme(&self.ou_scheme)?,
+            power_model: self.parse_power_model()?.map(|x| convert(*x)),
+            gas_model: self.parse_gas_model()?.map(|x| convert(*x)),
+            power_shift: self.power_shift.map(convert),
+            jump_size: self
+                .jump_size
+                .as_ref()
+                .map(|jump_size| jump_size.inner.map(|x| convert(*x))),
+        })
+    }
+}
+
+/// A Python-compatible wrapper for the real-world parameters of the physical measure.
+///
+/// This struct is exposed to Python as the `PhysicalParameters` class and is passed to
+/// `sample_physical_prices`. It is a distinct class from `ModelParameters`, so real-world
+/// dynamics cannot be passed to the valuation functions by mistake.
+#[pyclass(name = "PhysicalParameters")]
+#[derive(Clone, Debug)]
+pub struct PyPhysicalParameters {
+    /// The risk-neutral parameters whose volatilities, jumps and correlations are used.
+    #[pyo3(get)]
+    pub model_params: PyModelParameters,
+    /// The real drift of the gas price (1 / year).
+    #[pyo3(get)]
+    pub gas_drift: f64,
+    /// The Long Run Marginal Cost of power (€ / MWh).
+    pub lrmc_curve: Vec<f64>,
+}
+
+#[pymethods]
+impl PyPhysicalParameters {
+    /// Creates the real-world parameters.
+    ///
+    /// Parameters
+    /// ----------
+    /// model_params : ModelParameters
+    ///     The parameters of the volatilities, jumps and correlations. Power must follow
+    ///     the jump diffusion without a shift.
+    /// gas_drift : float
+    ///     The real drift of the gas price per year.
+    /// lrmc_curve : numpy.ndarray
+    ///     The Long Run Marginal Cost to which the log power price reverts, with one
+    ///     point per grid point or one per local day.
+    #[new]
+    pub fn new(
+        model_params: PyModelParameters,
+        gas_drift: f64,
+        lrmc_curve: PyReadonlyArray1<f64>,
+    ) -> PyResult<Self> {
+        let params = Self {
+            model_params,
+            gas_drift,
+            lrmc_curve: lrmc_curve.as_array().to_vec(),
+        };
+        params
+            .to_domain()
+            .check()
+            .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        Ok(params)
+    }
+
+    /// The Long Run Marginal Cost of power.
+    #[getter]
+    pub fn lrmc_curve<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.lrmc_curve.clone().into_pyarray(py)
+    }
+}
+
+impl PyPhysicalParameters {
+    /// Converts the Python-facing parameters to the core `PhysicalParameters<f64>`.
+    pub fn to_domain(&self) -> PhysicalParameters<f64> {
+        self.model_params
+            .to_domain()
+            .into_physical(self.gas_drift, Array1::from_vec(self.lrmc_curve.clone()))
+    }
+}
+
+/// A Python-compatible wrapper for a time-dependent volatility.
+///
+/// This struct is exposed to Python as the `Volatility` class and is passed to
+/// `ModelParameters` as `gas_volatility` or `power_volatility`. The vegas of
+/// `calculate_greeks` are reported per bucket of the volatility.
+#[pyclass(name = "Volatility")]
+#[derive(Clone, Debug)]
+pub struct PyVolatility {
+    inner: Volatility<f64>,
+}
+
+#[pymethods]
+impl PyVolatility {
+    /// Creates a volatility that is the same at every point.
+    ///
+    /// Parameters
+    /// ----------
+    /// sigma : float
+    ///     The annualised volatility.
+    #[staticmethod]
+    pub fn constant(sigma: f64) -> Self {
+        Self {
+            inner: Volatility::Constant(sigma),
+        }
+    }
+
+    /// Creates a volatility curve.
+    ///
+    /// Parameters
+    /// ----------
+    /// sigma : list[float]
+    ///     The annualised volatility of every point of the time grid, or of every local
+    ///     day. The volatility of a point applies to the time step reaching it.
+    #[staticmethod]
+    pub fn curve(sigma: Vec<f64>) -> Self {
+        Self {
+            inner: Volatility::Curve(sigma),
+        }
+    }
+
+    /// Creates a seasonal volatility shape.
+    ///
+    /// The volatility at a point is ``monthly[month] * hourly[hour] * weekend``, taken in
+    /// the local time of the time grid.
+    ///
+    /// Parameters
+    /// ----------
+    /// monthly : list[float]
+    ///     The annualised volatility of every calendar month, January first.
+    /// hourly : list[float], optional
+    ///     The factor of every local hour of the day, midnight first. Must have 24 entries.
+    /// weekend : float, optional
+    ///     The factor applied on Saturdays and Sundays.
+    #[staticmethod]
+    #[pyo3(signature = (monthly, hourly=None, weekend=None))]
+    pub fn seasonal(monthly: Vec<f64>, hourly: Option<Vec<f64>>, weekend: Option<f64>) -> Self {
+        Self {
+            inner: Volatility::Seasonal(SeasonalVolatility {
+                monthly,
+                hourly: hourly.unwrap_or_default(),
+                weekend,
+            }),
+        }
+    }
+
+    /// The name of every bucket, in the order of the vegas of `GreeksResult`.
+    #[getter]
+    pub fn bucket_names(&self) -> Vec<String> {
+        self.inner.bucket_names()
+    }
+}
+
+impl PyVolatility {
+    /// Converts the volatility to the generic `Volatility<T>`.
+    pub fn to_domain<T: Float + FromPrimitive>(&self) -> Volatility<T> {
+        self.inner.map(|x| T::from_f64(*x).unwrap())
+    }
+}
+
+/// A Python-compatible wrapper for the law of the power price jumps.
+///
+/// This struct is exposed to Python as the `JumpSize` class and is passed to
+/// `ModelParameters` as `jump_size`. Jump sizes are changes of the log price.
+#[pyclass(name = "JumpSize")]
+#[derive(Clone, Debug)]
+pub struct PyJumpSize {
+    inner: JumpSize<f64>,
+}
+
+#[pymethods]
+impl PyJumpSize {
+    /// Creates normal jumps, the law used when no `JumpSize` is given.
+    ///
+    /// Parameters
+    /// ----------
+    /// mu : float
+    ///     The mean of a jump.
+    /// sigma : float
+    ///     The standard deviation of a jump.
+    #[staticmethod]
+    pub fn normal(mu: f64, sigma: f64) -> Self {
+        Self {
+            inner: JumpSize::Normal { mu, sigma },
+        }
+    }
+
+    /// Creates double-exponential (Kou) jumps, for asymmetric up and down spikes.
+    ///
+    /// Parameters
+    /// ----------
+    /// p_up : float
+    ///     The probability that a jump is up.
+    /// eta_up : float
+    ///     The rate of the exponential up jumps, whose mean is ``1 / eta_up``. Must
+    ///     exceed 1.
+    /// eta_down : float
+    ///     The rate of the exponential down jumps, whose mean is ``1 / eta_down``.
+    #[staticmethod]
+    pub fn double_exponential(p_up: f64, eta_up: f64, eta_down: f64) -> Self {
+        Self {
+            inner: JumpSize::DoubleExponential {
+                p_up,
+                eta_up,
+                eta_down,
+            },
+        }
+    }
+
+    /// Creates up-only exponential spikes.
+    ///
+    /// Parameters
+    /// ----------
+    /// eta : float
+    ///     The rate of the jumps, whose mean is ``1 / eta``. Must exceed 1.
+    #[staticmethod]
+    pub fn exponential(eta: f64) -> Self {
+        Self {
+            inner: JumpSize::Exponential { eta },
         }
     }
+
+    /// The expected value ``E[exp(J)]`` of the price factor of a single jump.
+    #[getter]
+    pub fn mean_price_factor(&self) -> f64 {
+        self.inner.mgf(1.0)
+    }
+}
+
+/// Maps the Python name of a correlation repair to the core `CorrelationRepair`.
+fn parse_correlation_repair(name: &str) -> PyResult<CorrelationRepair> {
+    match name {
+        "reject" => Ok(CorrelationRepair::Reject),
+        "higham" => Ok(CorrelationRepair::higham()),
+        "eigenvalue_clipping" => Ok(CorrelationRepair::EigenvalueClipping {
+            min_eigenvalue: 0.0,
+        }),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown correlation repair '{name}'. Expected one of 'reject', 'higham' or \
+             'eigenvalue_clipping'."
+        ))),
+    }
+}
+
+/// Maps the Python name of an OU discretisation to the core `OuScheme`.
+fn parse_ou_scheme(name: &str) -> PyResult<OuScheme> {
+    match name {
+        "exact" => Ok(OuScheme::Exact),
+        "euler" => Ok(OuScheme::Euler),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown OU scheme '{name}'. Expected 'exact' or 'euler'."
+        ))),
+    }
+}
+
+
+/// Maps the Python name of a variance discretisation to the core `VarianceScheme`.
+fn parse_variance_scheme(name: &str) -> PyResult<VarianceScheme> {
+    match name {
+        "full_truncation" => Ok(VarianceScheme::FullTruncation),
+        "quadratic_exponential" => Ok(VarianceScheme::QuadraticExponential),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown variance scheme '{name}'. Expected 'full_truncation' or \
+             'quadratic_exponential'."
+        ))),
+    }
 }
 
 /// A Python-compatible wrapper for the power generation unit parameters.
@@ -88,26 +668,51 @@ pub struct PyUnitParameter {
     /// The efficiency of the unit (MWh / MMBtu).
     #[pyo3(get, set)]
     pub heat_rate: f64,
-    /// The maximum power output (MWh).
+    /// The maximum power output (MW).
     #[pyo3(get, set)]
     pub capacity: f64,
     /// The fixed cost to start the unit (€).
     #[pyo3(get, set)]
     pub start_up_costs: f64,
+    /// The fuel the unit burns, `"gas"` or `"coal"`.
+    #[pyo3(get)]
+    pub fuel: String,
+    /// The carbon emitted per MWh of power (tCO2 / MWh).
+    #[pyo3(get, set)]
+    pub emission_factor: f64,
+    /// How the unit is dispatched on a day it is started, `"full_day"` or `"hourly"`.
+    #[pyo3(get)]
+    pub dispatch: String,
 }
 
 #[pymethods]
 impl PyUnitParameter {
     /// Creates a new instance of the UnitParameter class.
     ///
-    /// This is the constructor (`__init__` in Python) for the class.
+    /// This is the constructor (`__init__` in Python) for the class. A unit with a
+    /// positive `emission_factor` pays for carbon, so the carbon price must be simulated.
+    /// A unit started for a day runs through all of it with `dispatch="full_day"`, and
+    /// stands idle in the hours of negative spread with `dispatch="hourly"`.
     #[new]
-    pub fn new(heat_rate: f64, capacity: f64, start_up_costs: f64) -> Self {
-        Self {
+    #[pyo3(signature = (heat_rate, capacity, start_up_costs, fuel="gas".to_string(), emission_factor=0.0, dispatch="full_day".to_string()))]
+    pub fn new(
+        heat_rate: f64,
+        capacity: f64,
+        start_up_costs: f64,
+        fuel: String,
+        emission_factor: f64,
+        dispatch: String,
+    ) -> PyResult<Self> {
+        parse_asset(&fuel)?;
+        parse_dispatch(&dispatch)?;
+        Ok(Self {
             heat_rate,
             capacity,
             start_up_costs,
-        }
+            fuel,
+            emission_factor,
+            dispatch,
+        })
     }
 }
 
@@ -118,6 +723,42 @@ impl PyUnitParameter {
             heat_rate: T::from_f64(self.heat_rate).unwrap(),
             capacity: T::from_f64(self.capacity).unwrap(),
             start_up_costs: T::from_f64(self.start_up_costs).unwrap(),
+            fuel: parse_asset(&self.fuel).expect("The fuel was validated on construction."),
+            emission_factor: T::from_f64(self.emission_factor).unwrap(),
+            dispatch: parse_dispatch(&self.dispatch)
+                .expect("The dispatch was validated on construction."),
         }
     }
 }
+
+/// Maps the Python name of a dispatch to the core `Dispatch`.
+fn parse_dispatch(name: &str) -> PyResult<Dispatch> {
+    match name {
+        "full_day" => Ok(Dispatch::FullDay),
+        "hourly" => Ok(Dispatch::Hourly),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Unknown dispatch '{name}'. Expected 'full_day' or 'hourly'."
+        ))),
+    }
+}
+
+/// Maps the Python name of an asset to the core `Asset`.
+pub fn parse_asset(name: &str) -> PyResult<Asset> {
+    Asset::from_name(name).ok_or_else(|| {
+        PyErr::new::<PyValueError, _>(format!(
+            "Unknown asset '{name}'. Expected one of 'gas', 'power', 'coal' or 'carbon'."
+        ))
+    })
+}
+
+/// Converts the forward curves of additional assets, given by asset name, to the core
+/// representation.
+pub fn additional_curves_to_domain(
+    curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+) -> PyResult<Vec<(Asset, Array1<f64>)>> {
+    curves
+        .unwrap_or_default()
+        .iter()
+        .map(|(name, curve)| Ok((parse_asset(name)?, curve.as_array().to_owned())))
+        .collect()
+}
@@ -4,28 +4,58 @@
 //! method, which is called from the `valuation_tutorial.ipynb` notebook. This file
 //! remains for historical purposes but should be deleted.
 
-use crate::core::services::sample_paths::{sample_paths, SamplePathsArgs};
-use crate::python::parameters::PyModelParameters;
-use numpy::{PyArray3, PyReadonlyArray1};
+use std::collections::HashMap;
+
+use crate::core::common::random::{entropy_seed, NoiseSource};
+use crate::core::services::sample_paths::{
+    sample_paths, sample_physical_paths, sample_regimes, SamplePathsArgs, SamplePhysicalPathsArgs,
+};
+use crate::python::parameters::{
+    PyModelParameters, PyPhysicalParameters, additional_curves_to_domain,
+};
+use crate::python::simulation_config::PySimulationConfig;
+use crate::python::time_grid::{PyTimeGrid, time_grid_to_domain};
+use numpy::{IntoPyArray, PyArray2, PyArray3, PyReadonlyArray1};
 use pyo3::{pyfunction, Bound, PyErr, PyResult, Python};
 
 /// Python Wrapper for Sample Prices
+/// Returns a (num_paths, num_assets, num_points) array; the assets are gas, power and
+/// then the additional assets of `model_params`, in their order
+/// `time_grid` gives the calendar time of the curve points; hourly in UTC if omitted
+/// Each curve has one point per grid point or one per local day, e.g. a daily gas curve
 #[cfg(feature = "python")]
 #[pyfunction]
-#[pyo3(name = "sample_prices")]
+#[pyo3(
+    name = "sample_prices",
+    signature = (gas_curve, power_curve, model_params, num_paths, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
 pub fn sample_prices_py<'py>(
     py: Python<'py>,
     gas_curve: PyReadonlyArray1<f64>,
     power_curve: PyReadonlyArray1<f64>,
     model_params: PyModelParameters,
     num_paths: usize,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
 ) -> PyResult<Bound<'py, PyArray3<f64>>> {
     // 1. Convert Python args to your Rust Struct
+    let model_params = model_params.to_domain();
     let args = SamplePathsArgs {
         gas_curve: gas_curve.as_array().to_owned(),
         power_curve: power_curve.as_array().to_owned(),
-        model_params: model_params.to_domain(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
         num_paths,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
     };
 
     // Run your logic
@@ -38,3 +68,93 @@ pub fn sample_prices_py<'py>(
 
     Ok(np_array)
 }
+
+/// Python Wrapper for Sample Regimes
+/// Returns a (num_paths, num_points) array with the index of the regime of power at
+/// every point, for `model_params` with `power_model="regime_switching"`
+/// With the same arguments and seed, path i holds the regimes behind path i of
+/// `sample_prices`
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "sample_regimes",
+    signature = (gas_curve, power_curve, model_params, num_paths, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn sample_regimes_py<'py>(
+    py: Python<'py>,
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    model_params: PyModelParameters,
+    num_paths: usize,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<Bound<'py, PyArray2<usize>>> {
+    let model_params = model_params.to_domain();
+    let args = SamplePathsArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            model_params.is_seasonal(),
+        )?,
+        model_params,
+        num_paths,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+    };
+
+    let regimes = sample_regimes(args)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+    Ok(regimes.into_pyarray(py))
+}
+
+/// Python Wrapper for Sample Physical Prices
+/// Returns a (num_paths, num_assets, num_points) array of real-world prices under the
+/// physical measure, in the asset order of `sample_prices`
+/// Gas grows from the first point of `gas_curve` at the real drift and power reverts to
+/// the LRMC from the first point of `power_curve`; further assets follow their curves
+#[cfg(feature = "python")]
+#[pyfunction]
+#[pyo3(
+    name = "sample_physical_prices",
+    signature = (gas_curve, power_curve, physical_params, num_paths, seed=None, config=None, additional_curves=None, time_grid=None)
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn sample_physical_prices_py<'py>(
+    py: Python<'py>,
+    gas_curve: PyReadonlyArray1<f64>,
+    power_curve: PyReadonlyArray1<f64>,
+    physical_params: PyPhysicalParameters,
+    num_paths: usize,
+    seed: Option<u64>,
+    config: Option<PySimulationConfig>,
+    additional_curves: Option<HashMap<String, PyReadonlyArray1<f64>>>,
+    time_grid: Option<PyTimeGrid>,
+) -> PyResult<Bound<'py, PyArray3<f64>>> {
+    let physical_params = physical_params.to_domain();
+    let args = SamplePhysicalPathsArgs {
+        gas_curve: gas_curve.as_array().to_owned(),
+        power_curve: power_curve.as_array().to_owned(),
+        additional_curves: additional_curves_to_domain(additional_curves)?,
+        time_grid: time_grid_to_domain(
+            time_grid,
+            power_curve.as_array().len(),
+            physical_params.is_seasonal(),
+        )?,
+        physical_params,
+        num_paths,
+        noise_source: NoiseSource::new(seed.unwrap_or_else(entropy_seed)),
+        simulation_config: config.unwrap_or_default().to_domain(),
+    };
+
+    let result = sample_physical_paths(args)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+    Ok(result.into_pyarray(py))
+}
@@ -0,0 +1,59 @@
+use crate::core::simulator::simulation_config::{NoiseScheme, SimulationConfig};
+use pyo3::prelude::*;
+
+/// A Python-compatible wrapper for the Monte Carlo simulation configuration.
+///
+/// This struct is exposed to Python as the `SimulationConfig` class. It selects the
+/// variance reduction techniques applied to the random numbers of a run.
+#[pyclass(name = "SimulationConfig")]
+#[derive(Clone, Debug, Default)]
+pub struct PySimulationConfig {
+    /// Generate paths in antithetic pairs `(Z, -Z)`, for both diffusion and jumps.
+    #[pyo3(get, set)]
+    pub antithetic: bool,
+    /// Match the first two moments of the normal draws at every time step.
+    #[pyo3(get, set)]
+    pub moment_matching: bool,
+    /// Number of independently scrambled Sobol sequences. `None` uses pseudo-random numbers.
+    #[pyo3(get, set)]
+    pub sobol_replicates: Option<usize>,
+    /// Build the diffusion of quasi-random paths in Brownian bridge order.
+    #[pyo3(get, set)]
+    pub brownian_bridge: bool,
+}
+
+#[pymethods]
+impl PySimulationConfig {
+    /// Creates a new instance of the SimulationConfig class.
+    ///
+    /// This is the constructor (`__init__` in Python) for the class.
+    #[new]
+    #[pyo3(signature = (antithetic=false, moment_matching=false, sobol_replicates=None, brownian_bridge=true))]
+    pub fn new(
+        antithetic: bool,
+        moment_matching: bool,
+        sobol_replicates: Option<usize>,
+        brownian_bridge: bool,
+    ) -> Self {
+        Self {
+            antithetic,
+            moment_matching,
+            sobol_replicates,
+            brownian_bridge,
+        }
+    }
+}
+
+impl PySimulationConfig {
+    /// Converts the Python-facing configuration to the core `SimulationConfig`.
+    pub fn to_domain(&self) -> SimulationConfig {
+        let noise_scheme = match self.sobol_replicates {
+            None => NoiseScheme::PseudoRandom,
+            Some(num_replicates) => NoiseScheme::ScrambledSobol {
+                num_replicates,
+                brownian_bridge: self.brownian_bridge,
+            },
+        };
+        SimulationConfig::new(self.antithetic, self.moment_matching, noise_scheme)
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+use ndarray::Array1;
+use numpy::{IntoPyArray, PyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::core::common::time_grid::TimeGrid;
+
+/// A Python-compatible wrapper for the calendar time grid of a valuation.
+///
+/// This struct is exposed to Python as the `TimeGrid` class. It holds the start of every
+/// delivery period of the forward curves, the valuation time the year fractions are
+/// measured from and the time zone whose local days are valued.
+#[pyclass(name = "TimeGrid")]
+#[derive(Clone, Debug)]
+pub struct PyTimeGrid {
+    inner: TimeGrid,
+}
+
+#[pymethods]
+impl PyTimeGrid {
+    /// Creates a grid covering `num_days` whole local days from `start_date`.
+    ///
+    /// This is the constructor (`__init__` in Python) for the class. The delivery periods
+    /// are `resolution_minutes` long, e.g. 15 for quarter-hourly or 60 for hourly
+    /// products. The valuation time is the local midnight starting `valuation_date`. Days
+    /// with a daylight saving time change in `time_zone` (an IANA name such as
+    /// ``"Europe/Berlin"``) have 23 or 25 hours.
+    #[new]
+    #[pyo3(signature = (valuation_date, start_date, num_days, time_zone="UTC", resolution_minutes=60))]
+    pub fn new(
+        valuation_date: NaiveDate,
+        start_date: NaiveDate,
+        num_days: usize,
+        time_zone: &str,
+        resolution_minutes: i64,
+    ) -> PyResult<Self> {
+        let time_zone = parse_time_zone(time_zone)?;
+        let inner = TimeGrid::regular(
+            valuation_date,
+            start_date,
+            num_days,
+            TimeDelta::minutes(resolution_minutes),
+            time_zone,
+        )
+        .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Creates a grid from the start of every delivery period.
+    ///
+    /// All times must be timezone-aware datetimes, e.g. the index of a localised pandas
+    /// series via ``index.to_pydatetime()``. The last period ends at `end`.
+    #[staticmethod]
+    #[pyo3(signature = (valuation_time, timestamps, end, time_zone="UTC"))]
+    pub fn from_timestamps(
+        valuation_time: DateTime<FixedOffset>,
+        timestamps: Vec<DateTime<FixedOffset>>,
+        end: DateTime<FixedOffset>,
+        time_zone: &str,
+    ) -> PyResult<Self> {
+        let inner = TimeGrid::new(
+            valuation_time.with_timezone(&Utc),
+            timestamps.iter().map(|t| t.with_timezone(&Utc)).collect(),
+            end.with_timezone(&Utc),
+            parse_time_zone(time_zone)?,
+        )
+        .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// The year fraction (ACT/365) of every point from the valuation time.
+    #[getter]
+    pub fn times<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.inner.times().clone().into_pyarray(py)
+    }
+
+    /// The local date of every day of the grid.
+    #[getter]
+    pub fn days(&self) -> Vec<NaiveDate> {
+        self.inner.days().to_vec()
+    }
+
+    /// The length of every delivery period in hours.
+    #[getter]
+    pub fn durations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        Array1::from_shape_fn(self.inner.len(), |i| self.inner.duration(i)).into_pyarray(py)
+    }
+
+    /// The number of points of every day, e.g. 23 or 25 hours on the days the clocks
+    /// change.
+    #[getter]
+    pub fn points_per_day(&self) -> Vec<usize> {
+        (0..self.inner.num_days())
+            .map(|day| self.inner.day_points(day).len())
+            .collect()
+    }
+
+    /// The number of points of the grid.
+    pub fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl PyTimeGrid {
+    /// Returns the core `TimeGrid`.
+    pub fn to_domain(&self) -> TimeGrid {
+        self.inner.clone()
+    }
+}
+
+/// Maps the IANA name of a time zone to the core `Tz`.
+fn parse_time_zone(name: &str) -> PyResult<Tz> {
+    name.parse()
+        .map_err(|_| PyErr::new::<PyValueError, _>(format!("Unknown time zone '{name}'.")))
+}
+
+/// Converts an optional Python time grid to the core `TimeGrid` of forward curves with
+/// `n_points` points.
+///
+/// Without a grid, the curves are taken to be hourly in UTC, starting at the valuation
+/// time. Their calendar dates are then unknown, so a `seasonal` volatility requires a grid.
+pub fn time_grid_to_domain(
+    time_grid: Option<PyTimeGrid>,
+    n_points: usize,
+    seasonal: bool,
+) -> PyResult<TimeGrid> {
+    match time_grid {
+        Some(time_grid) => Ok(time_grid.to_domain()),
+        None if seasonal => Err(PyErr::new::<PyValueError, _>(
+            "A seasonal volatility requires a time grid giving the dates of the curves.",
+        )),
+        None => {
+            let start = DateTime::<Utc>::UNIX_EPOCH;
+            let hours = n_points as i64;
+            TimeGrid::new(
+                start,
+                (0..hours).map(|hour| start + TimeDelta::hours(hour)).collect(),
+                start + TimeDelta::hours(hours),
+                Tz::UTC,
+            )
+            .map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))
+        }
+    }
+}